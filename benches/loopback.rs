@@ -0,0 +1,190 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use crossbeam_channel::{self as chan, select};
+
+use datachannel::{
+    DataChannelHandler, DataChannelInfo, IceCandidate, PeerConnectionHandler, RtcConfig,
+    RtcDataChannel, RtcPeerConnection, SessionDescription,
+};
+
+enum ConnectionMsg {
+    RemoteDescription { sess_desc: Box<SessionDescription> },
+    RemoteCandidate { cand: IceCandidate },
+}
+
+/// Data channel handler counting received messages, it does no other work so that only the
+/// wrapper and libdatachannel overhead is measured.
+#[derive(Clone)]
+struct Counter {
+    received: Arc<AtomicUsize>,
+    ready: Option<chan::Sender<()>>,
+}
+
+impl DataChannelHandler for Counter {
+    fn on_open(&mut self) {
+        if let Some(ready) = &self.ready {
+            ready.send(()).ok();
+        }
+    }
+
+    fn on_message(&mut self, _msg: &[u8]) {
+        self.received.fetch_add(1, Ordering::Release);
+    }
+}
+
+struct LocalConn {
+    signaling: chan::Sender<ConnectionMsg>,
+    counter: Counter,
+    dc: Option<Box<RtcDataChannel<Counter>>>,
+}
+
+impl PeerConnectionHandler for LocalConn {
+    type DCH = Counter;
+
+    fn data_channel_handler(&mut self, _info: DataChannelInfo) -> Counter {
+        self.counter.clone()
+    }
+
+    fn on_description(&mut self, sess_desc: SessionDescription) {
+        self.signaling
+            .send(ConnectionMsg::RemoteDescription {
+                sess_desc: Box::new(sess_desc),
+            })
+            .ok();
+    }
+
+    fn on_candidate(&mut self, cand: IceCandidate) {
+        self.signaling
+            .send(ConnectionMsg::RemoteCandidate { cand })
+            .ok();
+    }
+
+    fn on_data_channel(&mut self, dc: Box<RtcDataChannel<Counter>>) {
+        self.dc.replace(dc);
+    }
+}
+
+/// Two peer connections linked in-process with an open data channel from the first to the
+/// second, `received` counts the messages delivered to the second one.
+struct Loopback {
+    dc: Box<RtcDataChannel<Counter>>,
+    received: Arc<AtomicUsize>,
+    _pcs: (
+        Box<RtcPeerConnection<LocalConn>>,
+        Box<RtcPeerConnection<LocalConn>>,
+    ),
+}
+
+impl Loopback {
+    fn new() -> Self {
+        let (tx_peer1, rx_peer1) = chan::unbounded::<ConnectionMsg>();
+        let (tx_peer2, rx_peer2) = chan::unbounded::<ConnectionMsg>();
+        let (tx_ready, rx_ready) = chan::unbounded();
+        let received = Arc::new(AtomicUsize::new(0));
+
+        let conn = |signaling| LocalConn {
+            signaling,
+            counter: Counter {
+                received: Arc::clone(&received),
+                ready: None,
+            },
+            dc: None,
+        };
+
+        let conf = RtcConfig::new::<&str>(&[]);
+        let mut pc1 = RtcPeerConnection::new(&conf, conn(tx_peer2)).unwrap();
+        let mut pc2 = RtcPeerConnection::new(&conf, conn(tx_peer1)).unwrap();
+
+        let sender = Counter {
+            received: Arc::new(AtomicUsize::new(0)),
+            ready: Some(tx_ready),
+        };
+        let dc = pc1.create_data_channel("bench", sender).unwrap();
+
+        loop {
+            select! {
+                recv(rx_peer1) -> msg => match msg.unwrap() {
+                    ConnectionMsg::RemoteDescription { sess_desc } => {
+                        pc1.set_remote_description(&sess_desc).unwrap();
+                    }
+                    ConnectionMsg::RemoteCandidate { cand } => {
+                        pc1.add_remote_candidate(&cand).unwrap();
+                    }
+                },
+                recv(rx_peer2) -> msg => match msg.unwrap() {
+                    ConnectionMsg::RemoteDescription { sess_desc } => {
+                        pc2.set_remote_description(&sess_desc).unwrap();
+                    }
+                    ConnectionMsg::RemoteCandidate { cand } => {
+                        pc2.add_remote_candidate(&cand).unwrap();
+                    }
+                },
+                recv(rx_ready) -> _ => break,
+                default(Duration::from_secs(10)) => panic!("Loopback connection timed out"),
+            }
+        }
+
+        Self {
+            dc,
+            received,
+            _pcs: (pc1, pc2),
+        }
+    }
+
+    /// Sends `count` messages and waits until they are all received.
+    fn transfer(&mut self, msg: &[u8], count: usize) {
+        let target = self.received.load(Ordering::Acquire) + count;
+        for _ in 0..count {
+            self.dc.send(msg).unwrap();
+        }
+        while self.received.load(Ordering::Acquire) < target {
+            thread::yield_now();
+        }
+    }
+}
+
+fn throughput(c: &mut Criterion) {
+    const BATCH: usize = 100;
+
+    let mut loopback = Loopback::new();
+    let mut group = c.benchmark_group("throughput");
+    for size in [64, 1024, 16 * 1024, 64 * 1024] {
+        let msg = vec![0u8; size];
+        group.throughput(Throughput::Bytes((size * BATCH) as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &msg, |b, msg| {
+            b.iter(|| loopback.transfer(msg, BATCH))
+        });
+    }
+    group.finish();
+}
+
+fn latency(c: &mut Criterion) {
+    let mut loopback = Loopback::new();
+    let mut group = c.benchmark_group("latency");
+    for size in [64, 16 * 1024] {
+        let msg = vec![0u8; size];
+        group.bench_with_input(BenchmarkId::from_parameter(size), &msg, |b, msg| {
+            b.iter(|| loopback.transfer(msg, 1))
+        });
+    }
+    group.finish();
+}
+
+fn callback(c: &mut Criterion) {
+    const BATCH: usize = 1000;
+
+    let mut loopback = Loopback::new();
+    let mut group = c.benchmark_group("callback");
+    group.throughput(Throughput::Elements(BATCH as u64));
+    group.bench_function("tiny_messages", |b| {
+        b.iter(|| loopback.transfer(&[0], BATCH))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, throughput, latency, callback);
+criterion_main!(benches);
@@ -0,0 +1,805 @@
+pub const RTC_VERSION_MAJOR: u32 = 0;
+pub const RTC_VERSION_MINOR: u32 = 22;
+pub const RTC_VERSION_PATCH: u32 = 2;
+pub const RTC_VERSION: &[u8; 7] = b"0.22.2\0";
+pub const RTC_ENABLE_WEBSOCKET: u32 = 1;
+pub const RTC_ENABLE_MEDIA: u32 = 1;
+pub const RTC_DEFAULT_MTU: u32 = 1280;
+pub const RTC_DEFAULT_MAX_STORED_PACKET_COUNT: u32 = 512;
+pub const RTC_DEFAULT_MAXIMUM_PACKET_COUNT_FOR_NACK_CACHE: u32 = 512;
+pub const RTC_ERR_SUCCESS: u32 = 0;
+pub const RTC_ERR_INVALID: i32 = -1;
+pub const RTC_ERR_FAILURE: i32 = -2;
+pub const RTC_ERR_NOT_AVAIL: i32 = -3;
+pub const RTC_ERR_TOO_SMALL: i32 = -4;
+pub type uint16_t = ::std::os::raw::c_ushort;
+pub type uint32_t = ::std::os::raw::c_uint;
+pub type uint8_t = ::std::os::raw::c_uchar;
+pub const rtcState_RTC_NEW: rtcState = 0;
+pub const rtcState_RTC_CONNECTING: rtcState = 1;
+pub const rtcState_RTC_CONNECTED: rtcState = 2;
+pub const rtcState_RTC_DISCONNECTED: rtcState = 3;
+pub const rtcState_RTC_FAILED: rtcState = 4;
+pub const rtcState_RTC_CLOSED: rtcState = 5;
+pub type rtcState = ::std::os::raw::c_int;
+pub const rtcIceState_RTC_ICE_NEW: rtcIceState = 0;
+pub const rtcIceState_RTC_ICE_CHECKING: rtcIceState = 1;
+pub const rtcIceState_RTC_ICE_CONNECTED: rtcIceState = 2;
+pub const rtcIceState_RTC_ICE_COMPLETED: rtcIceState = 3;
+pub const rtcIceState_RTC_ICE_FAILED: rtcIceState = 4;
+pub const rtcIceState_RTC_ICE_DISCONNECTED: rtcIceState = 5;
+pub const rtcIceState_RTC_ICE_CLOSED: rtcIceState = 6;
+pub type rtcIceState = ::std::os::raw::c_int;
+pub const rtcGatheringState_RTC_GATHERING_NEW: rtcGatheringState = 0;
+pub const rtcGatheringState_RTC_GATHERING_INPROGRESS: rtcGatheringState = 1;
+pub const rtcGatheringState_RTC_GATHERING_COMPLETE: rtcGatheringState = 2;
+pub type rtcGatheringState = ::std::os::raw::c_int;
+pub const rtcSignalingState_RTC_SIGNALING_STABLE: rtcSignalingState = 0;
+pub const rtcSignalingState_RTC_SIGNALING_HAVE_LOCAL_OFFER: rtcSignalingState = 1;
+pub const rtcSignalingState_RTC_SIGNALING_HAVE_REMOTE_OFFER: rtcSignalingState = 2;
+pub const rtcSignalingState_RTC_SIGNALING_HAVE_LOCAL_PRANSWER: rtcSignalingState = 3;
+pub const rtcSignalingState_RTC_SIGNALING_HAVE_REMOTE_PRANSWER: rtcSignalingState = 4;
+pub type rtcSignalingState = ::std::os::raw::c_int;
+pub const rtcLogLevel_RTC_LOG_NONE: rtcLogLevel = 0;
+pub const rtcLogLevel_RTC_LOG_FATAL: rtcLogLevel = 1;
+pub const rtcLogLevel_RTC_LOG_ERROR: rtcLogLevel = 2;
+pub const rtcLogLevel_RTC_LOG_WARNING: rtcLogLevel = 3;
+pub const rtcLogLevel_RTC_LOG_INFO: rtcLogLevel = 4;
+pub const rtcLogLevel_RTC_LOG_DEBUG: rtcLogLevel = 5;
+pub const rtcLogLevel_RTC_LOG_VERBOSE: rtcLogLevel = 6;
+pub type rtcLogLevel = ::std::os::raw::c_int;
+pub const rtcCertificateType_RTC_CERTIFICATE_DEFAULT: rtcCertificateType = 0;
+pub const rtcCertificateType_RTC_CERTIFICATE_ECDSA: rtcCertificateType = 1;
+pub const rtcCertificateType_RTC_CERTIFICATE_RSA: rtcCertificateType = 2;
+pub type rtcCertificateType = ::std::os::raw::c_int;
+pub const rtcCodec_RTC_CODEC_H264: rtcCodec = 0;
+pub const rtcCodec_RTC_CODEC_VP8: rtcCodec = 1;
+pub const rtcCodec_RTC_CODEC_VP9: rtcCodec = 2;
+pub const rtcCodec_RTC_CODEC_H265: rtcCodec = 3;
+pub const rtcCodec_RTC_CODEC_AV1: rtcCodec = 4;
+pub const rtcCodec_RTC_CODEC_OPUS: rtcCodec = 128;
+pub const rtcCodec_RTC_CODEC_PCMU: rtcCodec = 129;
+pub const rtcCodec_RTC_CODEC_PCMA: rtcCodec = 130;
+pub const rtcCodec_RTC_CODEC_AAC: rtcCodec = 131;
+pub type rtcCodec = ::std::os::raw::c_int;
+pub const rtcDirection_RTC_DIRECTION_UNKNOWN: rtcDirection = 0;
+pub const rtcDirection_RTC_DIRECTION_SENDONLY: rtcDirection = 1;
+pub const rtcDirection_RTC_DIRECTION_RECVONLY: rtcDirection = 2;
+pub const rtcDirection_RTC_DIRECTION_SENDRECV: rtcDirection = 3;
+pub const rtcDirection_RTC_DIRECTION_INACTIVE: rtcDirection = 4;
+pub type rtcDirection = ::std::os::raw::c_int;
+pub const rtcTransportPolicy_RTC_TRANSPORT_POLICY_ALL: rtcTransportPolicy = 0;
+pub const rtcTransportPolicy_RTC_TRANSPORT_POLICY_RELAY: rtcTransportPolicy = 1;
+pub type rtcTransportPolicy = ::std::os::raw::c_int;
+pub type rtcLogCallbackFunc = ::std::option::Option<
+    unsafe extern "C" fn(level: rtcLogLevel, message: *const ::std::os::raw::c_char),
+>;
+pub type rtcDescriptionCallbackFunc = ::std::option::Option<
+    unsafe extern "C" fn(
+        pc: ::std::os::raw::c_int,
+        sdp: *const ::std::os::raw::c_char,
+        type_: *const ::std::os::raw::c_char,
+        ptr: *mut ::std::os::raw::c_void,
+    ),
+>;
+pub type rtcCandidateCallbackFunc = ::std::option::Option<
+    unsafe extern "C" fn(
+        pc: ::std::os::raw::c_int,
+        cand: *const ::std::os::raw::c_char,
+        mid: *const ::std::os::raw::c_char,
+        ptr: *mut ::std::os::raw::c_void,
+    ),
+>;
+pub type rtcStateChangeCallbackFunc = ::std::option::Option<
+    unsafe extern "C" fn(
+        pc: ::std::os::raw::c_int,
+        state: rtcState,
+        ptr: *mut ::std::os::raw::c_void,
+    ),
+>;
+pub type rtcIceStateChangeCallbackFunc = ::std::option::Option<
+    unsafe extern "C" fn(
+        pc: ::std::os::raw::c_int,
+        state: rtcIceState,
+        ptr: *mut ::std::os::raw::c_void,
+    ),
+>;
+pub type rtcGatheringStateCallbackFunc = ::std::option::Option<
+    unsafe extern "C" fn(
+        pc: ::std::os::raw::c_int,
+        state: rtcGatheringState,
+        ptr: *mut ::std::os::raw::c_void,
+    ),
+>;
+pub type rtcSignalingStateCallbackFunc = ::std::option::Option<
+    unsafe extern "C" fn(
+        pc: ::std::os::raw::c_int,
+        state: rtcSignalingState,
+        ptr: *mut ::std::os::raw::c_void,
+    ),
+>;
+pub type rtcDataChannelCallbackFunc = ::std::option::Option<
+    unsafe extern "C" fn(
+        pc: ::std::os::raw::c_int,
+        dc: ::std::os::raw::c_int,
+        ptr: *mut ::std::os::raw::c_void,
+    ),
+>;
+pub type rtcTrackCallbackFunc = ::std::option::Option<
+    unsafe extern "C" fn(
+        pc: ::std::os::raw::c_int,
+        tr: ::std::os::raw::c_int,
+        ptr: *mut ::std::os::raw::c_void,
+    ),
+>;
+pub type rtcOpenCallbackFunc = ::std::option::Option<
+    unsafe extern "C" fn(id: ::std::os::raw::c_int, ptr: *mut ::std::os::raw::c_void),
+>;
+pub type rtcClosedCallbackFunc = ::std::option::Option<
+    unsafe extern "C" fn(id: ::std::os::raw::c_int, ptr: *mut ::std::os::raw::c_void),
+>;
+pub type rtcErrorCallbackFunc = ::std::option::Option<
+    unsafe extern "C" fn(
+        id: ::std::os::raw::c_int,
+        error: *const ::std::os::raw::c_char,
+        ptr: *mut ::std::os::raw::c_void,
+    ),
+>;
+pub type rtcMessageCallbackFunc = ::std::option::Option<
+    unsafe extern "C" fn(
+        id: ::std::os::raw::c_int,
+        message: *const ::std::os::raw::c_char,
+        size: ::std::os::raw::c_int,
+        ptr: *mut ::std::os::raw::c_void,
+    ),
+>;
+pub type rtcInterceptorCallbackFunc = ::std::option::Option<
+    unsafe extern "C" fn(
+        pc: ::std::os::raw::c_int,
+        message: *const ::std::os::raw::c_char,
+        size: ::std::os::raw::c_int,
+        ptr: *mut ::std::os::raw::c_void,
+    ) -> *mut ::std::os::raw::c_void,
+>;
+pub type rtcBufferedAmountLowCallbackFunc = ::std::option::Option<
+    unsafe extern "C" fn(id: ::std::os::raw::c_int, ptr: *mut ::std::os::raw::c_void),
+>;
+pub type rtcAvailableCallbackFunc = ::std::option::Option<
+    unsafe extern "C" fn(id: ::std::os::raw::c_int, ptr: *mut ::std::os::raw::c_void),
+>;
+pub type rtcPliHandlerCallbackFunc = ::std::option::Option<
+    unsafe extern "C" fn(tr: ::std::os::raw::c_int, ptr: *mut ::std::os::raw::c_void),
+>;
+pub type rtcRembHandlerCallbackFunc = ::std::option::Option<
+    unsafe extern "C" fn(
+        tr: ::std::os::raw::c_int,
+        bitrate: ::std::os::raw::c_uint,
+        ptr: *mut ::std::os::raw::c_void,
+    ),
+>;
+extern "C" {
+    pub fn rtcInitLogger(level: rtcLogLevel, cb: rtcLogCallbackFunc);
+}
+extern "C" {
+    pub fn rtcSetUserPointer(id: ::std::os::raw::c_int, ptr: *mut ::std::os::raw::c_void);
+}
+extern "C" {
+    pub fn rtcGetUserPointer(i: ::std::os::raw::c_int) -> *mut ::std::os::raw::c_void;
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct rtcConfiguration {
+    pub iceServers: *mut *const ::std::os::raw::c_char,
+    pub iceServersCount: ::std::os::raw::c_int,
+    pub proxyServer: *const ::std::os::raw::c_char,
+    pub bindAddress: *const ::std::os::raw::c_char,
+    pub certificateType: rtcCertificateType,
+    pub iceTransportPolicy: rtcTransportPolicy,
+    pub enableIceTcp: bool,
+    pub enableIceUdpMux: bool,
+    pub disableAutoNegotiation: bool,
+    pub forceMediaTransport: bool,
+    pub portRangeBegin: uint16_t,
+    pub portRangeEnd: uint16_t,
+    pub mtu: ::std::os::raw::c_int,
+    pub maxMessageSize: ::std::os::raw::c_int,
+}
+extern "C" {
+    pub fn rtcCreatePeerConnection(config: *const rtcConfiguration) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcClosePeerConnection(pc: ::std::os::raw::c_int) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcDeletePeerConnection(pc: ::std::os::raw::c_int) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcSetLocalDescriptionCallback(
+        pc: ::std::os::raw::c_int,
+        cb: rtcDescriptionCallbackFunc,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcSetLocalCandidateCallback(
+        pc: ::std::os::raw::c_int,
+        cb: rtcCandidateCallbackFunc,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcSetStateChangeCallback(
+        pc: ::std::os::raw::c_int,
+        cb: rtcStateChangeCallbackFunc,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcSetIceStateChangeCallback(
+        pc: ::std::os::raw::c_int,
+        cb: rtcIceStateChangeCallbackFunc,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcSetGatheringStateChangeCallback(
+        pc: ::std::os::raw::c_int,
+        cb: rtcGatheringStateCallbackFunc,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcSetSignalingStateChangeCallback(
+        pc: ::std::os::raw::c_int,
+        cb: rtcSignalingStateCallbackFunc,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcSetLocalDescription(
+        pc: ::std::os::raw::c_int,
+        type_: *const ::std::os::raw::c_char,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcSetRemoteDescription(
+        pc: ::std::os::raw::c_int,
+        sdp: *const ::std::os::raw::c_char,
+        type_: *const ::std::os::raw::c_char,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcAddRemoteCandidate(
+        pc: ::std::os::raw::c_int,
+        cand: *const ::std::os::raw::c_char,
+        mid: *const ::std::os::raw::c_char,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcGetLocalDescription(
+        pc: ::std::os::raw::c_int,
+        buffer: *mut ::std::os::raw::c_char,
+        size: ::std::os::raw::c_int,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcGetRemoteDescription(
+        pc: ::std::os::raw::c_int,
+        buffer: *mut ::std::os::raw::c_char,
+        size: ::std::os::raw::c_int,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcGetLocalDescriptionType(
+        pc: ::std::os::raw::c_int,
+        buffer: *mut ::std::os::raw::c_char,
+        size: ::std::os::raw::c_int,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcGetRemoteDescriptionType(
+        pc: ::std::os::raw::c_int,
+        buffer: *mut ::std::os::raw::c_char,
+        size: ::std::os::raw::c_int,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcGetLocalAddress(
+        pc: ::std::os::raw::c_int,
+        buffer: *mut ::std::os::raw::c_char,
+        size: ::std::os::raw::c_int,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcGetRemoteAddress(
+        pc: ::std::os::raw::c_int,
+        buffer: *mut ::std::os::raw::c_char,
+        size: ::std::os::raw::c_int,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcGetSelectedCandidatePair(
+        pc: ::std::os::raw::c_int,
+        local: *mut ::std::os::raw::c_char,
+        localSize: ::std::os::raw::c_int,
+        remote: *mut ::std::os::raw::c_char,
+        remoteSize: ::std::os::raw::c_int,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcIsNegotiationNeeded(pc: ::std::os::raw::c_int) -> bool;
+}
+extern "C" {
+    pub fn rtcGetMaxDataChannelStream(pc: ::std::os::raw::c_int) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcGetRemoteMaxMessageSize(pc: ::std::os::raw::c_int) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcSetOpenCallback(
+        id: ::std::os::raw::c_int,
+        cb: rtcOpenCallbackFunc,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcSetClosedCallback(
+        id: ::std::os::raw::c_int,
+        cb: rtcClosedCallbackFunc,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcSetErrorCallback(
+        id: ::std::os::raw::c_int,
+        cb: rtcErrorCallbackFunc,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcSetMessageCallback(
+        id: ::std::os::raw::c_int,
+        cb: rtcMessageCallbackFunc,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcSendMessage(
+        id: ::std::os::raw::c_int,
+        data: *const ::std::os::raw::c_char,
+        size: ::std::os::raw::c_int,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcClose(id: ::std::os::raw::c_int) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcDelete(id: ::std::os::raw::c_int) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcIsOpen(id: ::std::os::raw::c_int) -> bool;
+}
+extern "C" {
+    pub fn rtcIsClosed(id: ::std::os::raw::c_int) -> bool;
+}
+extern "C" {
+    pub fn rtcMaxMessageSize(id: ::std::os::raw::c_int) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcGetBufferedAmount(id: ::std::os::raw::c_int) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcSetBufferedAmountLowThreshold(
+        id: ::std::os::raw::c_int,
+        amount: ::std::os::raw::c_int,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcSetBufferedAmountLowCallback(
+        id: ::std::os::raw::c_int,
+        cb: rtcBufferedAmountLowCallbackFunc,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcGetAvailableAmount(id: ::std::os::raw::c_int) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcSetAvailableCallback(
+        id: ::std::os::raw::c_int,
+        cb: rtcAvailableCallbackFunc,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcReceiveMessage(
+        id: ::std::os::raw::c_int,
+        buffer: *mut ::std::os::raw::c_char,
+        size: *mut ::std::os::raw::c_int,
+    ) -> ::std::os::raw::c_int;
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct rtcReliability {
+    pub unordered: bool,
+    pub unreliable: bool,
+    pub maxPacketLifeTime: ::std::os::raw::c_uint,
+    pub maxRetransmits: ::std::os::raw::c_uint,
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct rtcDataChannelInit {
+    pub reliability: rtcReliability,
+    pub protocol: *const ::std::os::raw::c_char,
+    pub negotiated: bool,
+    pub manualStream: bool,
+    pub stream: uint16_t,
+}
+extern "C" {
+    pub fn rtcSetDataChannelCallback(
+        pc: ::std::os::raw::c_int,
+        cb: rtcDataChannelCallbackFunc,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcCreateDataChannel(
+        pc: ::std::os::raw::c_int,
+        label: *const ::std::os::raw::c_char,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcCreateDataChannelEx(
+        pc: ::std::os::raw::c_int,
+        label: *const ::std::os::raw::c_char,
+        init: *const rtcDataChannelInit,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcDeleteDataChannel(dc: ::std::os::raw::c_int) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcGetDataChannelStream(dc: ::std::os::raw::c_int) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcGetDataChannelLabel(
+        dc: ::std::os::raw::c_int,
+        buffer: *mut ::std::os::raw::c_char,
+        size: ::std::os::raw::c_int,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcGetDataChannelProtocol(
+        dc: ::std::os::raw::c_int,
+        buffer: *mut ::std::os::raw::c_char,
+        size: ::std::os::raw::c_int,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcGetDataChannelReliability(
+        dc: ::std::os::raw::c_int,
+        reliability: *mut rtcReliability,
+    ) -> ::std::os::raw::c_int;
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct rtcTrackInit {
+    pub direction: rtcDirection,
+    pub codec: rtcCodec,
+    pub payloadType: ::std::os::raw::c_int,
+    pub ssrc: uint32_t,
+    pub mid: *const ::std::os::raw::c_char,
+    pub name: *const ::std::os::raw::c_char,
+    pub msid: *const ::std::os::raw::c_char,
+    pub trackId: *const ::std::os::raw::c_char,
+    pub profile: *const ::std::os::raw::c_char,
+}
+extern "C" {
+    pub fn rtcSetTrackCallback(
+        pc: ::std::os::raw::c_int,
+        cb: rtcTrackCallbackFunc,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcAddTrack(
+        pc: ::std::os::raw::c_int,
+        mediaDescriptionSdp: *const ::std::os::raw::c_char,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcAddTrackEx(
+        pc: ::std::os::raw::c_int,
+        init: *const rtcTrackInit,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcDeleteTrack(tr: ::std::os::raw::c_int) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcGetTrackDescription(
+        tr: ::std::os::raw::c_int,
+        buffer: *mut ::std::os::raw::c_char,
+        size: ::std::os::raw::c_int,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcGetTrackMid(
+        tr: ::std::os::raw::c_int,
+        buffer: *mut ::std::os::raw::c_char,
+        size: ::std::os::raw::c_int,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcGetTrackDirection(
+        tr: ::std::os::raw::c_int,
+        direction: *mut rtcDirection,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcRequestKeyframe(tr: ::std::os::raw::c_int) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcRequestBitrate(
+        tr: ::std::os::raw::c_int,
+        bitrate: ::std::os::raw::c_uint,
+    ) -> ::std::os::raw::c_int;
+}
+pub const rtcObuPacketization_RTC_OBU_PACKETIZED_OBU: rtcObuPacketization = 0;
+pub const rtcObuPacketization_RTC_OBU_PACKETIZED_TEMPORAL_UNIT: rtcObuPacketization = 1;
+pub type rtcObuPacketization = ::std::os::raw::c_int;
+pub const rtcNalUnitSeparator_RTC_NAL_SEPARATOR_LENGTH: rtcNalUnitSeparator = 0;
+pub const rtcNalUnitSeparator_RTC_NAL_SEPARATOR_LONG_START_SEQUENCE: rtcNalUnitSeparator = 1;
+pub const rtcNalUnitSeparator_RTC_NAL_SEPARATOR_SHORT_START_SEQUENCE: rtcNalUnitSeparator = 2;
+pub const rtcNalUnitSeparator_RTC_NAL_SEPARATOR_START_SEQUENCE: rtcNalUnitSeparator = 3;
+pub type rtcNalUnitSeparator = ::std::os::raw::c_int;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct rtcPacketizerInit {
+    pub ssrc: uint32_t,
+    pub cname: *const ::std::os::raw::c_char,
+    pub payloadType: uint8_t,
+    pub clockRate: uint32_t,
+    pub sequenceNumber: uint16_t,
+    pub timestamp: uint32_t,
+    pub maxFragmentSize: uint16_t,
+    pub nalSeparator: rtcNalUnitSeparator,
+    pub obuPacketization: rtcObuPacketization,
+    pub playoutDelayId: uint8_t,
+    pub playoutDelayMin: uint16_t,
+    pub playoutDelayMax: uint16_t,
+}
+pub type rtcPacketizationHandlerInit = rtcPacketizerInit;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct rtcSsrcForTypeInit {
+    pub ssrc: uint32_t,
+    pub name: *const ::std::os::raw::c_char,
+    pub msid: *const ::std::os::raw::c_char,
+    pub trackId: *const ::std::os::raw::c_char,
+}
+pub type rtcMessage = *mut ::std::os::raw::c_void;
+extern "C" {
+    pub fn rtcCreateOpaqueMessage(
+        data: *mut ::std::os::raw::c_void,
+        size: ::std::os::raw::c_int,
+    ) -> *mut rtcMessage;
+}
+extern "C" {
+    pub fn rtcDeleteOpaqueMessage(msg: *mut rtcMessage);
+}
+extern "C" {
+    pub fn rtcSetMediaInterceptorCallback(
+        id: ::std::os::raw::c_int,
+        cb: rtcInterceptorCallbackFunc,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcSetH264Packetizer(
+        tr: ::std::os::raw::c_int,
+        init: *const rtcPacketizerInit,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcSetH265Packetizer(
+        tr: ::std::os::raw::c_int,
+        init: *const rtcPacketizerInit,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcSetAV1Packetizer(
+        tr: ::std::os::raw::c_int,
+        init: *const rtcPacketizerInit,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcSetOpusPacketizer(
+        tr: ::std::os::raw::c_int,
+        init: *const rtcPacketizerInit,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcSetAACPacketizer(
+        tr: ::std::os::raw::c_int,
+        init: *const rtcPacketizerInit,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcChainRtcpReceivingSession(tr: ::std::os::raw::c_int) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcChainRtcpSrReporter(tr: ::std::os::raw::c_int) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcChainRtcpNackResponder(
+        tr: ::std::os::raw::c_int,
+        maxStoredPacketsCount: ::std::os::raw::c_uint,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcChainPliHandler(
+        tr: ::std::os::raw::c_int,
+        cb: rtcPliHandlerCallbackFunc,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcChainRembHandler(
+        tr: ::std::os::raw::c_int,
+        cb: rtcRembHandlerCallbackFunc,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcTransformSecondsToTimestamp(
+        id: ::std::os::raw::c_int,
+        seconds: f64,
+        timestamp: *mut uint32_t,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcTransformTimestampToSeconds(
+        id: ::std::os::raw::c_int,
+        timestamp: uint32_t,
+        seconds: *mut f64,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcGetCurrentTrackTimestamp(
+        id: ::std::os::raw::c_int,
+        timestamp: *mut uint32_t,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcSetTrackRtpTimestamp(
+        id: ::std::os::raw::c_int,
+        timestamp: uint32_t,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcGetLastTrackSenderReportTimestamp(
+        id: ::std::os::raw::c_int,
+        timestamp: *mut uint32_t,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcSetNeedsToSendRtcpSr(id: ::std::os::raw::c_int) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcGetTrackPayloadTypesForCodec(
+        tr: ::std::os::raw::c_int,
+        ccodec: *const ::std::os::raw::c_char,
+        buffer: *mut ::std::os::raw::c_int,
+        size: ::std::os::raw::c_int,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcGetSsrcsForTrack(
+        tr: ::std::os::raw::c_int,
+        buffer: *mut uint32_t,
+        count: ::std::os::raw::c_int,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcGetCNameForSsrc(
+        tr: ::std::os::raw::c_int,
+        ssrc: uint32_t,
+        cname: *mut ::std::os::raw::c_char,
+        cnameSize: ::std::os::raw::c_int,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcGetSsrcsForType(
+        mediaType: *const ::std::os::raw::c_char,
+        sdp: *const ::std::os::raw::c_char,
+        buffer: *mut uint32_t,
+        bufferSize: ::std::os::raw::c_int,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcSetSsrcForType(
+        mediaType: *const ::std::os::raw::c_char,
+        sdp: *const ::std::os::raw::c_char,
+        buffer: *mut ::std::os::raw::c_char,
+        bufferSize: ::std::os::raw::c_int,
+        init: *mut rtcSsrcForTypeInit,
+    ) -> ::std::os::raw::c_int;
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct rtcWsConfiguration {
+    pub disableTlsVerification: bool,
+    pub proxyServer: *const ::std::os::raw::c_char,
+    pub protocols: *mut *const ::std::os::raw::c_char,
+    pub protocolsCount: ::std::os::raw::c_int,
+    pub connectionTimeoutMs: ::std::os::raw::c_int,
+    pub pingIntervalMs: ::std::os::raw::c_int,
+    pub maxOutstandingPings: ::std::os::raw::c_int,
+    pub maxMessageSize: ::std::os::raw::c_int,
+}
+extern "C" {
+    pub fn rtcCreateWebSocket(url: *const ::std::os::raw::c_char) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcCreateWebSocketEx(
+        url: *const ::std::os::raw::c_char,
+        config: *const rtcWsConfiguration,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcDeleteWebSocket(ws: ::std::os::raw::c_int) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcGetWebSocketRemoteAddress(
+        ws: ::std::os::raw::c_int,
+        buffer: *mut ::std::os::raw::c_char,
+        size: ::std::os::raw::c_int,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcGetWebSocketPath(
+        ws: ::std::os::raw::c_int,
+        buffer: *mut ::std::os::raw::c_char,
+        size: ::std::os::raw::c_int,
+    ) -> ::std::os::raw::c_int;
+}
+pub type rtcWebSocketClientCallbackFunc = ::std::option::Option<
+    unsafe extern "C" fn(
+        wsserver: ::std::os::raw::c_int,
+        ws: ::std::os::raw::c_int,
+        ptr: *mut ::std::os::raw::c_void,
+    ),
+>;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct rtcWsServerConfiguration {
+    pub port: uint16_t,
+    pub enableTls: bool,
+    pub certificatePemFile: *const ::std::os::raw::c_char,
+    pub keyPemFile: *const ::std::os::raw::c_char,
+    pub keyPemPass: *const ::std::os::raw::c_char,
+    pub bindAddress: *const ::std::os::raw::c_char,
+    pub connectionTimeoutMs: ::std::os::raw::c_int,
+    pub maxMessageSize: ::std::os::raw::c_int,
+}
+extern "C" {
+    pub fn rtcCreateWebSocketServer(
+        config: *const rtcWsServerConfiguration,
+        cb: rtcWebSocketClientCallbackFunc,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcDeleteWebSocketServer(wsserver: ::std::os::raw::c_int) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcGetWebSocketServerPort(wsserver: ::std::os::raw::c_int) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn rtcPreload();
+}
+extern "C" {
+    pub fn rtcCleanup();
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct rtcSctpSettings {
+    pub recvBufferSize: ::std::os::raw::c_int,
+    pub sendBufferSize: ::std::os::raw::c_int,
+    pub maxChunksOnQueue: ::std::os::raw::c_int,
+    pub initialCongestionWindow: ::std::os::raw::c_int,
+    pub maxBurst: ::std::os::raw::c_int,
+    pub congestionControlModule: ::std::os::raw::c_int,
+    pub delayedSackTimeMs: ::std::os::raw::c_int,
+    pub minRetransmitTimeoutMs: ::std::os::raw::c_int,
+    pub maxRetransmitTimeoutMs: ::std::os::raw::c_int,
+    pub initialRetransmitTimeoutMs: ::std::os::raw::c_int,
+    pub maxRetransmitAttempts: ::std::os::raw::c_int,
+    pub heartbeatIntervalMs: ::std::os::raw::c_int,
+}
+extern "C" {
+    pub fn rtcSetSctpSettings(settings: *const rtcSctpSettings) -> ::std::os::raw::c_int;
+}
@@ -1,4 +1,7 @@
 use std::env;
+use std::fs;
+#[cfg(feature = "bindgen")]
+use std::path::Path;
 use std::path::PathBuf;
 
 #[cfg(feature = "vendored")]
@@ -16,8 +19,155 @@ pub fn openssl_artifacts() -> &'static openssl_src::Artifacts {
     INSTANCE.get_or_init(|| openssl_src::Build::new().build())
 }
 
+#[cfg(all(feature = "vendored", feature = "system"))]
+compile_error!("features `vendored` and `system` are mutually exclusive");
+
+#[cfg(all(feature = "gnutls", feature = "mbedtls"))]
+compile_error!("features `gnutls` and `mbedtls` are mutually exclusive");
+
+/// Builds libdatachannel against the TLS backend selected by features, OpenSSL by default.
+#[allow(dead_code)]
+fn define_tls_backend(cmake_conf: &mut cmake::Config) {
+    if cfg!(feature = "gnutls") {
+        cmake_conf.define("USE_GNUTLS", "ON");
+    } else if cfg!(feature = "mbedtls") {
+        cmake_conf.define("USE_MBEDTLS", "ON");
+    }
+}
+
+/// Compiles the C and C++ dependencies with the MSVC runtime library matching the Rust target,
+/// `/MT` with `crt-static` and `/MD` otherwise, which `DATACHANNEL_SYS_STATIC_CRT` overrides.
+#[allow(dead_code)]
+fn define_msvc_runtime(cmake_conf: &mut cmake::Config) {
+    if env::var("CARGO_CFG_TARGET_ENV").as_deref() != Ok("msvc") {
+        return;
+    }
+    let static_crt = match env_var_rerun("DATACHANNEL_SYS_STATIC_CRT").as_deref() {
+        Ok("1") | Ok("true") => true,
+        Ok("0") | Ok("false") => false,
+        _ => env::var("CARGO_CFG_TARGET_FEATURE")
+            .unwrap_or_default()
+            .split(',')
+            .any(|feature| feature == "crt-static"),
+    };
+
+    // Rust always links the release runtime, even in debug builds
+    cmake_conf.static_crt(static_crt);
+    cmake_conf.define("CMAKE_POLICY_DEFAULT_CMP0091", "NEW");
+    cmake_conf.define(
+        "CMAKE_MSVC_RUNTIME_LIBRARY",
+        if static_crt {
+            "MultiThreaded"
+        } else {
+            "MultiThreadedDLL"
+        },
+    );
+}
+
+/// Builds for size with the `min-size` feature: `MinSizeRel` profile, no TURN server in
+/// libjuice, sections that the final link can discard and stripped shared libraries.
+#[allow(dead_code)]
+fn define_min_size(cmake_conf: &mut cmake::Config) {
+    if !cfg!(feature = "min-size") {
+        return;
+    }
+    cmake_conf.profile("MinSizeRel");
+    cmake_conf.define("NO_TESTS", "ON");
+    cmake_conf.define("NO_SERVER", "ON");
+
+    if env::var("CARGO_CFG_TARGET_ENV").as_deref() == Ok("msvc") {
+        cmake_conf.cflag("/Gy").cxxflag("/Gy");
+    } else {
+        cmake_conf
+            .cflag("-ffunction-sections -fdata-sections")
+            .cxxflag("-ffunction-sections -fdata-sections");
+        if env::var("CARGO_CFG_TARGET_VENDOR").as_deref() != Ok("apple") {
+            cmake_conf.define("CMAKE_SHARED_LINKER_FLAGS", "-s");
+        }
+    }
+}
+
+/// Configures the CMake cross-compilation toolchain of mobile targets, unless a toolchain file
+/// is already provided through `CMAKE_TOOLCHAIN_FILE`.
+#[allow(dead_code)]
+fn define_target_preset(cmake_conf: &mut cmake::Config) {
+    let target = env::var("TARGET").unwrap();
+    if env_var_rerun("CMAKE_TOOLCHAIN_FILE").is_ok() {
+        return;
+    }
+
+    if target.contains("-linux-android") {
+        let ndk = env_var_rerun("ANDROID_NDK_HOME")
+            .or_else(|_| env_var_rerun("ANDROID_NDK_ROOT"))
+            .or_else(|_| env_var_rerun("NDK_HOME"))
+            .expect("Set ANDROID_NDK_HOME to cross-compile for Android");
+        let abi = match target.split('-').next().unwrap() {
+            "aarch64" => "arm64-v8a",
+            "armv7" | "thumbv7neon" => "armeabi-v7a",
+            "i686" => "x86",
+            "x86_64" => "x86_64",
+            arch => panic!("Unsupported Android architecture: {}", arch),
+        };
+        let platform = env_var_rerun("ANDROID_PLATFORM").unwrap_or_else(|_| "android-21".into());
+
+        // OpenSSL's Configure looks the NDK up from there when vendoring
+        env::set_var("ANDROID_NDK_ROOT", &ndk);
+
+        cmake_conf.define(
+            "CMAKE_TOOLCHAIN_FILE",
+            format!("{}/build/cmake/android.toolchain.cmake", ndk),
+        );
+        cmake_conf.define("ANDROID_ABI", abi);
+        cmake_conf.define("ANDROID_PLATFORM", platform);
+    } else if target.ends_with("-apple-ios") || target.ends_with("-apple-ios-sim") {
+        let arch = match target.split('-').next().unwrap() {
+            "aarch64" => "arm64",
+            "x86_64" => "x86_64",
+            arch => panic!("Unsupported iOS architecture: {}", arch),
+        };
+        let simulator = target.ends_with("-sim") || arch == "x86_64";
+        let deployment_target =
+            env_var_rerun("IPHONEOS_DEPLOYMENT_TARGET").unwrap_or_else(|_| "13.0".into());
+
+        cmake_conf.define("CMAKE_SYSTEM_NAME", "iOS");
+        cmake_conf.define("CMAKE_OSX_ARCHITECTURES", arch);
+        cmake_conf.define("CMAKE_OSX_DEPLOYMENT_TARGET", deployment_target);
+        if simulator {
+            cmake_conf.define("CMAKE_OSX_SYSROOT", "iphonesimulator");
+        }
+    }
+}
+
+/// Links the system OpenSSL, from `OPENSSL_DIR` when set or found through pkg-config.
+#[cfg(feature = "vendored")]
+fn link_system_openssl() {
+    #[cfg(feature = "system-openssl")]
+    {
+        if let Ok(openssl_dir) = env_var_rerun("OPENSSL_DIR") {
+            println!("cargo:rustc-link-search=native={}/lib", openssl_dir);
+            if cfg!(target_env = "msvc") {
+                println!("cargo:rustc-link-lib=dylib=libcrypto");
+                println!("cargo:rustc-link-lib=dylib=libssl");
+            } else {
+                println!("cargo:rustc-link-lib=dylib=crypto");
+                println!("cargo:rustc-link-lib=dylib=ssl");
+            }
+        } else {
+            pkg_config::Config::new()
+                .probe("openssl")
+                .expect("Unable to find a system OpenSSL, set OPENSSL_DIR");
+        }
+    }
+}
+
+/// Oldest libdatachannel release whose C API matches these bindings.
+#[cfg(feature = "system")]
+const MIN_SYSTEM_VERSION: &str = "0.22";
+
 fn main() {
     let out_dir = env::var("OUT_DIR").unwrap();
+    #[allow(unused_mut)]
+    let mut header = PathBuf::from("libdatachannel/include/rtc/rtc.h");
 
     #[cfg(feature = "vendored")]
     {
@@ -27,13 +177,27 @@ fn main() {
 
         cmake_conf.define("NO_WEBSOCKET", "ON");
         cmake_conf.define("NO_EXAMPLES", "ON");
+        define_min_size(&mut cmake_conf);
         if !cfg!(feature = "media") {
             cmake_conf.define("NO_MEDIA", "ON");
         }
 
-        let openssl_root_dir = openssl_artifacts().lib_dir().parent().unwrap();
-        cmake_conf.define("OPENSSL_ROOT_DIR", openssl_root_dir.to_path_buf());
-        cmake_conf.define("OPENSSL_USE_STATIC_LIBS", "TRUE");
+        define_target_preset(&mut cmake_conf);
+        define_msvc_runtime(&mut cmake_conf);
+        define_tls_backend(&mut cmake_conf);
+        if cfg!(feature = "nice") {
+            cmake_conf.define("USE_NICE", "ON");
+        }
+        let openssl = !cfg!(any(feature = "gnutls", feature = "mbedtls"));
+        if openssl && cfg!(feature = "system-openssl") {
+            if let Ok(openssl_dir) = env_var_rerun("OPENSSL_DIR") {
+                cmake_conf.define("OPENSSL_ROOT_DIR", openssl_dir);
+            }
+        } else if openssl {
+            let openssl_root_dir = openssl_artifacts().lib_dir().parent().unwrap();
+            cmake_conf.define("OPENSSL_ROOT_DIR", openssl_root_dir.to_path_buf());
+            cmake_conf.define("OPENSSL_USE_STATIC_LIBS", "TRUE");
+        }
 
         cmake_conf.build();
 
@@ -44,32 +208,53 @@ fn main() {
             .include(format!("{}/lib", out_dir))
             .build("src/lib.rs");
 
-        // Link static openssl
-        println!(
-            "cargo:rustc-link-search=native={}",
-            openssl_artifacts().lib_dir().to_path_buf().display()
-        );
-        if cfg!(target_env = "msvc") {
-            println!("cargo:rustc-link-lib=static=libcrypto");
-            println!("cargo:rustc-link-lib=static=libssl");
+        if cfg!(feature = "gnutls") {
+            // Link system gnutls
+            println!("cargo:rustc-link-lib=gnutls");
+        } else if cfg!(feature = "mbedtls") {
+            // Link system mbedtls
+            println!("cargo:rustc-link-lib=mbedtls");
+            println!("cargo:rustc-link-lib=mbedx509");
+            println!("cargo:rustc-link-lib=mbedcrypto");
+        } else if cfg!(feature = "system-openssl") {
+            // Link dynamic system openssl
+            link_system_openssl();
         } else {
-            println!("cargo:rustc-link-lib=static=crypto");
-            println!("cargo:rustc-link-lib=static=ssl");
-        }
-
-        // Link static libjuice
-        if cfg!(target_env = "msvc") {
+            // Link static openssl
             println!(
-                "cargo:rustc-link-search=native={}/build/deps/libjuice/{}",
-                out_dir, profile
+                "cargo:rustc-link-search=native={}",
+                openssl_artifacts().lib_dir().to_path_buf().display()
             );
+            if cfg!(target_env = "msvc") {
+                println!("cargo:rustc-link-lib=static=libcrypto");
+                println!("cargo:rustc-link-lib=static=libssl");
+            } else {
+                println!("cargo:rustc-link-lib=static=crypto");
+                println!("cargo:rustc-link-lib=static=ssl");
+            }
+        }
+
+        if cfg!(feature = "nice") {
+            // Link system libnice and its glib dependencies
+            println!("cargo:rustc-link-lib=nice");
+            println!("cargo:rustc-link-lib=gio-2.0");
+            println!("cargo:rustc-link-lib=gobject-2.0");
+            println!("cargo:rustc-link-lib=glib-2.0");
         } else {
-            println!(
-                "cargo:rustc-link-search=native={}/build/deps/libjuice",
-                out_dir
-            );
+            // Link static libjuice
+            if cfg!(target_env = "msvc") {
+                println!(
+                    "cargo:rustc-link-search=native={}/build/deps/libjuice/{}",
+                    out_dir, profile
+                );
+            } else {
+                println!(
+                    "cargo:rustc-link-search=native={}/build/deps/libjuice",
+                    out_dir
+                );
+            }
+            println!("cargo:rustc-link-lib=static=juice-static");
         }
-        println!("cargo:rustc-link-lib=static=juice-static");
 
         // Link static usrsctplib
         if cfg!(target_env = "msvc") {
@@ -113,17 +298,48 @@ fn main() {
         println!("cargo:rustc-link-lib=static=datachannel-static");
     }
 
-    #[cfg(not(feature = "vendored"))]
+    #[cfg(feature = "system")]
+    {
+        // Link the libdatachannel installed on the system, pkg-config emits the link lines
+        let library = pkg_config::Config::new()
+            .atleast_version(MIN_SYSTEM_VERSION)
+            .probe("libdatachannel")
+            .unwrap_or_else(|err| {
+                panic!(
+                    "Unable to find a system libdatachannel >= {}: {}",
+                    MIN_SYSTEM_VERSION, err
+                )
+            });
+
+        // Generate the bindings from the installed header
+        if let Some(installed) = library
+            .include_paths
+            .iter()
+            .map(|path| path.join("rtc/rtc.h"))
+            .find(|path| path.exists())
+        {
+            header = installed;
+        }
+    }
+
+    #[cfg(not(any(feature = "vendored", feature = "system")))]
     {
         let mut cmake_conf = cmake::Config::new("libdatachannel");
         cmake_conf.out_dir(&out_dir);
 
         cmake_conf.define("NO_WEBSOCKET", "ON");
         cmake_conf.define("NO_EXAMPLES", "ON");
+        define_min_size(&mut cmake_conf);
         if !cfg!(feature = "media") {
             cmake_conf.define("NO_MEDIA", "ON");
         }
 
+        define_target_preset(&mut cmake_conf);
+        define_msvc_runtime(&mut cmake_conf);
+        define_tls_backend(&mut cmake_conf);
+        if cfg!(feature = "nice") {
+            cmake_conf.define("USE_NICE", "ON");
+        }
         if let Ok(openssl_root_dir) = env_var_rerun("OPENSSL_ROOT_DIR") {
             cmake_conf.define("OPENSSL_ROOT_DIR", openssl_root_dir);
         }
@@ -138,13 +354,53 @@ fn main() {
         println!("cargo:rustc-link-lib=dylib=datachannel");
     }
 
+    // Pre-generated bindings are used unless bindgen is explicitly requested, so that libclang
+    // is only needed when regenerating them. Enums are signed with MSVC only.
+    let out_path = PathBuf::from(out_dir).join("bindings.rs");
+    let family = env::var("CARGO_CFG_TARGET_FAMILY").unwrap();
+    let family = family.split(',').next().unwrap_or_default();
+    let target_env = env::var("CARGO_CFG_TARGET_ENV").unwrap_or_default();
+    let pregenerated = match family {
+        "windows" if target_env == "msvc" => PathBuf::from("bindings/windows-msvc.rs"),
+        "windows" => PathBuf::from("bindings/windows-gnu.rs"),
+        family => PathBuf::from(format!("bindings/{}.rs", family)),
+    };
+    println!("cargo:rerun-if-changed={}", pregenerated.display());
+
+    #[cfg(feature = "bindgen")]
+    {
+        generate_bindings(&header, &out_path);
+        if env_var_rerun("DATACHANNEL_SYS_UPDATE_BINDINGS").is_ok() {
+            fs::copy(&out_path, &pregenerated).expect("Couldn't update pre-generated bindings");
+        }
+    }
+
+    #[cfg(not(feature = "bindgen"))]
+    {
+        let _ = header;
+        if let Err(err) = fs::copy(&pregenerated, &out_path) {
+            panic!(
+                "No pre-generated bindings at {} ({}), enable the `bindgen` feature",
+                pregenerated.display(),
+                err
+            );
+        }
+    }
+}
+
+#[cfg(feature = "bindgen")]
+fn generate_bindings(header: &Path, out_path: &Path) {
     let bindings = bindgen::Builder::default()
-        .header("libdatachannel/include/rtc/rtc.h")
+        .header(header.to_string_lossy())
+        .allowlist_function("rtc.*")
+        .allowlist_type("rtc.*")
+        .allowlist_var("RTC_.*")
+        // Layouts depend on the pointer width, the pre-generated bindings must not
+        .layout_tests(false)
         .generate()
         .expect("Unable to generate bindings");
 
-    let out_path = PathBuf::from(out_dir);
     bindings
-        .write_to_file(out_path.join("bindings.rs"))
+        .write_to_file(out_path)
         .expect("Couldn't write bindings");
 }
@@ -0,0 +1,20 @@
+//! Decodes arbitrary signaling messages with the serde deserializers and the compact
+//! encoding.
+
+#![no_main]
+
+use datachannel::{IceCandidate, SessionDescription};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(sess_desc) = serde_json::from_slice::<SessionDescription>(data) {
+        // What was parsed must be serializable back
+        serde_json::to_string(&sess_desc).unwrap();
+    }
+    let _ = serde_json::from_slice::<IceCandidate>(data);
+    if let Ok(compact) = std::str::from_utf8(data) {
+        if let Ok(sess_desc) = SessionDescription::from_compact(compact) {
+            SessionDescription::from_compact(&sess_desc.to_compact()).unwrap();
+        }
+    }
+});
@@ -0,0 +1,56 @@
+//! Adds arbitrary candidates to a peer connection with a valid remote description.
+
+#![no_main]
+
+use datachannel::{
+    DataChannelHandler, DataChannelInfo, IceCandidate, PeerConnectionHandler, RtcConfig,
+    RtcPeerConnection, SdpType, SessionDescription,
+};
+use libfuzzer_sys::fuzz_target;
+
+const OFFER: &str = "v=0\r\n\
+o=- 1 1 IN IP4 127.0.0.1\r\n\
+s=-\r\n\
+t=0 0\r\n\
+a=group:BUNDLE 0\r\n\
+m=application 9 UDP/DTLS/SCTP webrtc-datachannel\r\n\
+c=IN IP4 0.0.0.0\r\n\
+a=mid:0\r\n\
+a=ice-ufrag:fuzz\r\n\
+a=ice-pwd:fuzzfuzzfuzzfuzzfuzzfuzz\r\n\
+a=fingerprint:sha-256 \
+00:00:00:00:00:00:00:00:00:00:00:00:00:00:00:00:\
+00:00:00:00:00:00:00:00:00:00:00:00:00:00:00:00\r\n\
+a=setup:actpass\r\n\
+a=sctp-port:5000\r\n";
+
+struct Noop;
+
+impl DataChannelHandler for Noop {}
+
+impl PeerConnectionHandler for Noop {
+    type DCH = Noop;
+
+    fn data_channel_handler(&mut self, _info: DataChannelInfo) -> Noop {
+        Noop
+    }
+}
+
+fuzz_target!(|data: (&str, &str)| {
+    let (candidate, mid) = data;
+    let cand = IceCandidate {
+        candidate: candidate.to_string(),
+        mid: mid.to_string(),
+    };
+
+    // Also the parser behind `LocalCandidate::attribute`
+    let _ = webrtc_sdp::parse_sdp_line(&format!("a={}", candidate.trim_start_matches("a=")), 0);
+
+    let mut pc = RtcPeerConnection::new(&RtcConfig::new::<&str>(&[]), Noop).unwrap();
+    let sess_desc = SessionDescription {
+        sdp: webrtc_sdp::parse_sdp(OFFER, false).unwrap(),
+        sdp_type: SdpType::Offer,
+    };
+    pc.set_remote_description(&sess_desc).unwrap();
+    let _ = pc.add_remote_candidate(&cand);
+});
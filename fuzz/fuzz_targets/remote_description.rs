@@ -0,0 +1,44 @@
+//! Parses arbitrary SDP and applies it as remote description.
+
+#![no_main]
+
+use datachannel::{
+    DataChannelHandler, DataChannelInfo, PeerConnectionHandler, RtcConfig, RtcPeerConnection,
+    SdpType, SessionDescription,
+};
+use libfuzzer_sys::fuzz_target;
+
+struct Noop;
+
+impl DataChannelHandler for Noop {}
+
+impl PeerConnectionHandler for Noop {
+    type DCH = Noop;
+
+    fn data_channel_handler(&mut self, _info: DataChannelInfo) -> Noop {
+        Noop
+    }
+}
+
+fuzz_target!(|data: (bool, &str)| {
+    let (offer, sdp) = data;
+    let Ok(sdp) = webrtc_sdp::parse_sdp(sdp, false) else {
+        return;
+    };
+    let sdp_type = if offer {
+        SdpType::Offer
+    } else {
+        SdpType::Answer
+    };
+    let sess_desc = SessionDescription { sdp, sdp_type };
+
+    let mut pc = RtcPeerConnection::new(&RtcConfig::new::<&str>(&[]), Noop).unwrap();
+    if offer {
+        let _ = pc.set_remote_description(&sess_desc);
+    } else {
+        // An answer is only accepted once an offer was made
+        let _dc = pc.create_data_channel("fuzz", Noop).unwrap();
+        let _ = pc.set_remote_description(&sess_desc);
+    }
+    let _ = pc.remote_description();
+});
@@ -0,0 +1,285 @@
+//! A minimal text chat between two peers, signaling over WebSocket.
+//!
+//! ```sh
+//! datachannel-chat server 127.0.0.1:8989
+//! datachannel-chat join ws://127.0.0.1:8989 alice
+//! datachannel-chat join ws://127.0.0.1:8989 bob alice
+//! ```
+//!
+//! The server relays signaling messages between the peers named in the WebSocket path. A
+//! peer given the name of another one offers it a `chat` data channel, lines read from stdin
+//! are then sent through it and received ones are printed.
+
+use std::collections::HashMap;
+use std::env;
+use std::process;
+use std::sync::{Arc, Mutex};
+
+use async_tungstenite::tokio::{accept_hdr_async, connect_async};
+use async_tungstenite::tungstenite::handshake::server::{Request, Response};
+use async_tungstenite::tungstenite::protocol::Message;
+use futures_util::{future, pin_mut, SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{mpsc, oneshot};
+
+use datachannel::{
+    ConnectionState, DataChannelHandler, DataChannelInfo, IceCandidate, PeerConnectionHandler,
+    RtcConfig, RtcDataChannel, RtcPeerConnection, SessionDescription,
+};
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SignalingMsg {
+    /// The recipient when sent to the server, the sender when received from it
+    peer: String,
+    kind: MsgKind,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+enum MsgKind {
+    Description(Box<SessionDescription>),
+    Candidate(IceCandidate),
+}
+
+// Server
+
+type PeerMap = Arc<Mutex<HashMap<String, mpsc::UnboundedSender<Message>>>>;
+
+async fn serve(addr: &str) {
+    let listener = TcpListener::bind(addr).await.unwrap_or_else(|err| {
+        eprintln!("Couldn't listen on {}: {}", addr, err);
+        process::exit(1);
+    });
+    eprintln!("Listening on {}", addr);
+
+    let peers = PeerMap::default();
+    while let Ok((stream, _)) = listener.accept().await {
+        tokio::spawn(relay(peers.clone(), stream));
+    }
+}
+
+// The handshake callback has to return tungstenite's large error response
+#[allow(clippy::result_large_err)]
+async fn relay(peers: PeerMap, stream: TcpStream) {
+    let mut name = String::new();
+    let callback = |req: &Request, resp: Response| {
+        name = req.uri().path().trim_start_matches('/').to_string();
+        Ok(resp)
+    };
+    let Ok(websocket) = accept_hdr_async(stream, callback).await else {
+        return;
+    };
+    if name.is_empty() {
+        return;
+    }
+    eprintln!("{} joined", name);
+
+    let (mut outgoing, mut incoming) = websocket.split();
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    peers.lock().unwrap().insert(name.clone(), tx);
+
+    let forward = async {
+        while let Some(msg) = rx.recv().await {
+            if outgoing.send(msg).await.is_err() {
+                break;
+            }
+        }
+    };
+    let dispatch = async {
+        while let Some(Ok(msg)) = incoming.next().await {
+            let Ok(mut signaling) = serde_json::from_slice::<SignalingMsg>(&msg.into_data()) else {
+                continue;
+            };
+            let dest = std::mem::replace(&mut signaling.peer, name.clone());
+            match peers.lock().unwrap().get(&dest) {
+                Some(peer) => {
+                    let msg = serde_json::to_vec(&signaling).unwrap();
+                    peer.send(Message::binary(msg)).ok();
+                }
+                None => eprintln!("{} not found", dest),
+            }
+        }
+    };
+    pin_mut!(forward, dispatch);
+    future::select(forward, dispatch).await;
+
+    eprintln!("{} left", name);
+    peers.lock().unwrap().remove(&name);
+}
+
+// Peer
+
+struct Chat {
+    /// Signaled once the channel is open
+    open: Option<oneshot::Sender<()>>,
+}
+
+impl DataChannelHandler for Chat {
+    fn on_open(&mut self) {
+        if let Some(open) = self.open.take() {
+            open.send(()).ok();
+        }
+    }
+
+    fn on_closed(&mut self) {
+        eprintln!("Channel closed");
+    }
+
+    fn on_message(&mut self, msg: &[u8]) {
+        println!("> {}", String::from_utf8_lossy(msg));
+    }
+}
+
+enum PeerEvent {
+    Signaling(SignalingMsg),
+    Channel(Box<RtcDataChannel<Chat>>),
+}
+
+struct Peer {
+    remote: Arc<Mutex<Option<String>>>,
+    events: mpsc::UnboundedSender<PeerEvent>,
+}
+
+impl Peer {
+    fn signal(&self, kind: MsgKind) {
+        if let Some(peer) = self.remote.lock().unwrap().clone() {
+            let msg = SignalingMsg { peer, kind };
+            self.events.send(PeerEvent::Signaling(msg)).ok();
+        }
+    }
+}
+
+impl PeerConnectionHandler for Peer {
+    type DCH = Chat;
+
+    fn data_channel_handler(&mut self, _info: DataChannelInfo) -> Chat {
+        Chat { open: None }
+    }
+
+    fn on_description(&mut self, sess_desc: SessionDescription) {
+        self.signal(MsgKind::Description(Box::new(sess_desc)));
+    }
+
+    fn on_candidate(&mut self, cand: IceCandidate) {
+        self.signal(MsgKind::Candidate(cand));
+    }
+
+    fn on_connection_state_change(&mut self, state: ConnectionState) {
+        eprintln!("Connection {:?}", state);
+    }
+
+    fn on_data_channel(&mut self, data_channel: Box<RtcDataChannel<Chat>>) {
+        self.events.send(PeerEvent::Channel(data_channel)).ok();
+    }
+}
+
+async fn join(url: &str, name: &str, remote: Option<String>) {
+    let (websocket, _) = connect_async(format!("{}/{}", url.trim_end_matches('/'), name))
+        .await
+        .unwrap_or_else(|err| {
+            eprintln!("Couldn't connect to {}: {}", url, err);
+            process::exit(1);
+        });
+    let (mut outgoing, mut incoming) = websocket.split();
+
+    let (events, mut rx_events) = mpsc::unbounded_channel();
+    let (open, opened) = oneshot::channel();
+    let remote = Arc::new(Mutex::new(remote));
+    let peer = Peer {
+        remote: Arc::clone(&remote),
+        events,
+    };
+    let config = RtcConfig::new(&["stun:stun.l.google.com:19302"]);
+    let mut pc = RtcPeerConnection::new(&config, peer).unwrap_or_else(|err| {
+        eprintln!("Couldn't create the peer connection: {}", err);
+        process::exit(1);
+    });
+
+    // The offering side creates the channel, the other one receives it from the handler
+    let mut channel = None;
+    let mut opened = Some(opened);
+    let mut waiting_open = None;
+    if remote.lock().unwrap().is_some() {
+        let dc = pc.create_data_channel("chat", Chat { open: Some(open) });
+        channel = Some(dc.unwrap_or_else(|err| {
+            eprintln!("Couldn't create the data channel: {}", err);
+            process::exit(1);
+        }));
+    } else {
+        eprintln!("Waiting for a peer to join");
+        waiting_open = Some(open);
+    }
+
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    loop {
+        tokio::select! {
+            event = rx_events.recv() => match event {
+                Some(PeerEvent::Signaling(msg)) => {
+                    let msg = serde_json::to_vec(&msg).unwrap();
+                    if outgoing.send(Message::binary(msg)).await.is_err() {
+                        break;
+                    }
+                }
+                Some(PeerEvent::Channel(dc)) => {
+                    // Already open when received
+                    if let Some(open) = waiting_open.take() {
+                        open.send(()).ok();
+                    }
+                    channel = Some(dc);
+                }
+                None => break,
+            },
+            msg = incoming.next() => {
+                let Some(Ok(msg)) = msg else {
+                    break;
+                };
+                let Ok(signaling) = serde_json::from_slice::<SignalingMsg>(&msg.into_data())
+                else {
+                    continue;
+                };
+                remote.lock().unwrap().get_or_insert(signaling.peer);
+                let res = match &signaling.kind {
+                    MsgKind::Description(sess_desc) => pc.set_remote_description(sess_desc),
+                    MsgKind::Candidate(cand) => pc.add_remote_candidate(cand),
+                };
+                if let Err(err) = res {
+                    eprintln!("Signaling failed: {}", err);
+                }
+            },
+            _ = async { opened.as_mut().unwrap().await }, if opened.is_some() => {
+                opened = None;
+                eprintln!("Chat open, type away");
+            },
+            line = lines.next_line(), if opened.is_none() => match line {
+                Ok(Some(line)) => {
+                    if let Some(dc) = &mut channel {
+                        if let Err(err) = dc.send(line.as_bytes()) {
+                            eprintln!("Couldn't send: {}", err);
+                        }
+                    }
+                }
+                _ => break,
+            },
+        }
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("Usage: datachannel-chat server <addr>");
+    eprintln!("       datachannel-chat join <url> <name> [peer]");
+    process::exit(2);
+}
+
+#[tokio::main]
+async fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+    match args.as_slice() {
+        ["server"] => serve("127.0.0.1:8989").await,
+        ["server", addr] => serve(addr).await,
+        ["join", url, name] => join(url, name, None).await,
+        ["join", url, name, peer] => join(url, name, Some(peer.to_string())).await,
+        _ => usage(),
+    }
+}
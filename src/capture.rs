@@ -0,0 +1,134 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::net::Ipv4Addr;
+use std::path::Path;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use crate::track;
+
+const LOCAL_ADDR: Ipv4Addr = Ipv4Addr::new(10, 0, 0, 1);
+const REMOTE_ADDR: Ipv4Addr = Ipv4Addr::new(10, 0, 0, 2);
+const PORT: u16 = 5004;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureFormat {
+    /// Raw IPv4 pcap, packets are wrapped in UDP between 10.0.0.1 (local) and 10.0.0.2
+    /// (remote) on port 5004, use "Decode As... RTP" in Wireshark.
+    Pcap,
+    /// rtpdump as written by `rtpdump -F dump`, directions are not recorded.
+    RtpDump,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CaptureDirection {
+    Sent,
+    Received,
+}
+
+/// A writer recording RTP and RTCP packets of a track.
+pub(crate) struct RtpCapture {
+    format: CaptureFormat,
+    out: BufWriter<File>,
+    start: Instant,
+}
+
+impl RtpCapture {
+    pub(crate) fn create(path: &Path, format: CaptureFormat) -> io::Result<Self> {
+        let mut out = BufWriter::new(File::create(path)?);
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+
+        match format {
+            CaptureFormat::Pcap => {
+                out.write_all(&0xa1b2c3d4u32.to_le_bytes())?;
+                out.write_all(&2u16.to_le_bytes())?;
+                out.write_all(&4u16.to_le_bytes())?;
+                out.write_all(&0i32.to_le_bytes())?; // thiszone
+                out.write_all(&0u32.to_le_bytes())?; // sigfigs
+                out.write_all(&65535u32.to_le_bytes())?; // snaplen
+                out.write_all(&228u32.to_le_bytes())?; // LINKTYPE_IPV4
+            }
+            CaptureFormat::RtpDump => {
+                writeln!(out, "#!rtpplay1.0 {}/{}", REMOTE_ADDR, PORT)?;
+                out.write_all(&(now.as_secs() as u32).to_be_bytes())?;
+                out.write_all(&now.subsec_micros().to_be_bytes())?;
+                out.write_all(&REMOTE_ADDR.octets())?;
+                out.write_all(&PORT.to_be_bytes())?;
+                out.write_all(&0u16.to_be_bytes())?; // padding
+            }
+        }
+
+        Ok(Self {
+            format,
+            out,
+            start: Instant::now(),
+        })
+    }
+
+    pub(crate) fn record(&mut self, direction: CaptureDirection, packet: &[u8]) -> io::Result<()> {
+        let packet = &packet[..packet.len().min(u16::MAX as usize - 28)];
+        let elapsed = self.start.elapsed();
+        let is_rtcp = track::is_rtcp(packet);
+
+        match self.format {
+            CaptureFormat::Pcap => {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default();
+                let (src, dst) = match direction {
+                    CaptureDirection::Sent => (LOCAL_ADDR, REMOTE_ADDR),
+                    CaptureDirection::Received => (REMOTE_ADDR, LOCAL_ADDR),
+                };
+                let len = packet.len() + 28;
+
+                self.out.write_all(&(now.as_secs() as u32).to_le_bytes())?;
+                self.out.write_all(&now.subsec_micros().to_le_bytes())?;
+                self.out.write_all(&(len as u32).to_le_bytes())?;
+                self.out.write_all(&(len as u32).to_le_bytes())?;
+
+                let mut ip = [0u8; 20];
+                ip[0] = 0x45;
+                ip[2..4].copy_from_slice(&(len as u16).to_be_bytes());
+                ip[8] = 64; // TTL
+                ip[9] = 17; // UDP
+                ip[12..16].copy_from_slice(&src.octets());
+                ip[16..20].copy_from_slice(&dst.octets());
+                let checksum = ip_checksum(&ip);
+                ip[10..12].copy_from_slice(&checksum.to_be_bytes());
+                self.out.write_all(&ip)?;
+
+                self.out.write_all(&PORT.to_be_bytes())?;
+                self.out.write_all(&PORT.to_be_bytes())?;
+                self.out
+                    .write_all(&((packet.len() + 8) as u16).to_be_bytes())?;
+                self.out.write_all(&0u16.to_be_bytes())?; // no UDP checksum
+            }
+            CaptureFormat::RtpDump => {
+                let plen = if is_rtcp { 0 } else { packet.len() as u16 };
+                self.out
+                    .write_all(&((packet.len() + 8) as u16).to_be_bytes())?;
+                self.out.write_all(&plen.to_be_bytes())?;
+                self.out
+                    .write_all(&(elapsed.as_millis() as u32).to_be_bytes())?;
+            }
+        }
+
+        self.out.write_all(packet)
+    }
+
+    pub(crate) fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+fn ip_checksum(header: &[u8]) -> u16 {
+    let mut sum = header
+        .chunks(2)
+        .map(|word| u16::from_be_bytes([word[0], word[1]]) as u32)
+        .sum::<u32>();
+    while sum > 0xffff {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
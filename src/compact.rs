@@ -0,0 +1,68 @@
+use std::io::{Read, Write};
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use webrtc_sdp::parse_sdp;
+
+use crate::error::{Error, Result};
+use crate::signaling::{SdpType, SessionDescription};
+
+/// Largest description accepted once inflated, way above any meant for copy-paste or QR codes.
+const MAX_SDP_SIZE: usize = 1 << 20;
+
+impl SessionDescription {
+    /// Encodes the description as a short URL-safe string (deflate and base64), suited for
+    /// copy-paste or QR code signaling.
+    ///
+    /// Candidates must be part of the description (gathering complete) as trickle candidates
+    /// are not supported by such flows.
+    pub fn to_compact(&self) -> String {
+        let prefix = match self.sdp_type {
+            SdpType::Answer => 'a',
+            SdpType::Offer => 'o',
+            SdpType::Pranswer => 'p',
+            SdpType::Rollback => 'r',
+        };
+        let mut encoder = DeflateEncoder::new(vec![], Compression::best());
+        encoder
+            .write_all(self.sdp.to_string().as_bytes())
+            .and_then(|_| encoder.finish())
+            .map(|deflated| format!("{}{}", prefix, URL_SAFE_NO_PAD.encode(deflated)))
+            .expect("Couldn't deflate in memory")
+    }
+
+    /// Decodes a description encoded with [`to_compact`](Self::to_compact).
+    ///
+    /// Fails with [`Error::BadString`] when it inflates beyond 1 MiB.
+    pub fn from_compact(compact: &str) -> Result<Self> {
+        let mut chars = compact.trim().chars();
+        let sdp_type = match chars.next() {
+            Some('a') => SdpType::Answer,
+            Some('o') => SdpType::Offer,
+            Some('p') => SdpType::Pranswer,
+            Some('r') => SdpType::Rollback,
+            _ => return Err(Error::InvalidArg),
+        };
+        let deflated = URL_SAFE_NO_PAD
+            .decode(chars.as_str())
+            .map_err(|err| Error::BadString(err.to_string()))?;
+        // Coming from the remote peer, it must not inflate without bounds
+        let limit = MAX_SDP_SIZE as u64 + 1;
+        let mut sdp = String::new();
+        let len = DeflateDecoder::new(deflated.as_slice())
+            .take(limit)
+            .read_to_string(&mut sdp)
+            .map_err(|err| Error::BadString(err.to_string()))?;
+        if len as u64 == limit {
+            return Err(Error::BadString(format!(
+                "description exceeds {} bytes",
+                MAX_SDP_SIZE
+            )));
+        }
+        let sdp = parse_sdp(&sdp, false).map_err(|err| Error::BadString(err.to_string()))?;
+        Ok(SessionDescription { sdp, sdp_type })
+    }
+}
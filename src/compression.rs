@@ -0,0 +1,224 @@
+use std::io::{Read, Write};
+
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+
+use crate::datachannel::DataChannelHandler;
+use crate::error::{ChannelError, ChannelErrorKind};
+
+/// Header byte of a message sent as is, below the threshold or not worth compressing.
+const RAW: u8 = 0;
+const DEFLATE: u8 = 1;
+#[cfg(feature = "zstd")]
+const ZSTD: u8 = 2;
+
+/// Messages of [`Compression::new`] smaller than this are sent as is.
+const DEFAULT_THRESHOLD: usize = 1024;
+
+/// Largest message accepted by [`Decompress::new`] once decompressed.
+const DEFAULT_MAX_MESSAGE_SIZE: usize = 16 << 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CompressionAlgorithm {
+    Deflate,
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+impl CompressionAlgorithm {
+    /// The data channel protocol announcing this algorithm, see
+    /// [`DataChannelInit::compression`](crate::DataChannelInit::compression).
+    pub fn protocol(self) -> &'static str {
+        match self {
+            Self::Deflate => "deflate",
+            #[cfg(feature = "zstd")]
+            Self::Zstd => "zstd",
+        }
+    }
+
+    pub fn from_protocol(protocol: &str) -> Option<Self> {
+        match protocol {
+            "deflate" => Some(Self::Deflate),
+            #[cfg(feature = "zstd")]
+            "zstd" => Some(Self::Zstd),
+            _ => None,
+        }
+    }
+}
+
+/// Compression of the messages sent through [`RtcDataChannel::send`](crate::RtcDataChannel::send),
+/// see [`RtcDataChannel::set_compression`](crate::RtcDataChannel::set_compression).
+///
+/// Each message starts with a header byte telling whether and how it is compressed, the
+/// receiving side has to restore them with [`Decompress`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Compression {
+    pub algorithm: CompressionAlgorithm,
+    /// Messages smaller than this are sent as is
+    pub threshold: usize,
+    /// Compression level of the algorithm, its default one when not set
+    pub level: Option<i32>,
+}
+
+impl Compression {
+    /// Compresses messages of at least 1 KiB.
+    pub fn new(algorithm: CompressionAlgorithm) -> Self {
+        Self {
+            algorithm,
+            threshold: DEFAULT_THRESHOLD,
+            level: None,
+        }
+    }
+
+    /// The compression announced by the protocol of a data channel, if any.
+    pub fn from_protocol(protocol: Option<&str>) -> Option<Self> {
+        protocol
+            .and_then(CompressionAlgorithm::from_protocol)
+            .map(Self::new)
+    }
+
+    pub fn threshold(mut self, threshold: usize) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    pub fn level(mut self, level: i32) -> Self {
+        self.level.replace(level);
+        self
+    }
+
+    /// Prefixes `msg` with its header, compressed if large enough and if it makes it smaller.
+    pub(crate) fn encode(&self, msg: &[u8]) -> Vec<u8> {
+        if msg.len() >= self.threshold {
+            if let Some(compressed) = self.compress(msg).filter(|c| c.len() < msg.len()) {
+                return compressed;
+            }
+        }
+        let mut raw = Vec::with_capacity(1 + msg.len());
+        raw.push(RAW);
+        raw.extend_from_slice(msg);
+        raw
+    }
+
+    fn compress(&self, msg: &[u8]) -> Option<Vec<u8>> {
+        match self.algorithm {
+            CompressionAlgorithm::Deflate => {
+                let level = match self.level {
+                    Some(level) => flate2::Compression::new(level.clamp(0, 9) as u32),
+                    None => flate2::Compression::default(),
+                };
+                let mut encoder = DeflateEncoder::new(vec![DEFLATE], level);
+                encoder.write_all(msg).and_then(|_| encoder.finish()).ok()
+            }
+            #[cfg(feature = "zstd")]
+            CompressionAlgorithm::Zstd => {
+                let mut encoder = zstd::Encoder::new(vec![ZSTD], self.level.unwrap_or(0)).ok()?;
+                encoder.write_all(msg).and_then(|_| encoder.finish()).ok()
+            }
+        }
+    }
+}
+
+/// A [`DataChannelHandler`] restoring the messages sent by a peer with [`Compression`]
+/// enabled.
+///
+/// Each message is handed decompressed to the inner handler, whatever the algorithm used. A
+/// message that can't be decompressed, or larger than the maximum size once decompressed, is
+/// reported through [`on_error`](DataChannelHandler::on_error) and dropped.
+pub struct Decompress<H> {
+    handler: H,
+    max_message_size: usize,
+    buf: Vec<u8>,
+}
+
+impl<H> Decompress<H> {
+    /// Accepts messages of at most 16 MiB once decompressed.
+    pub fn new(handler: H) -> Self {
+        Self {
+            handler,
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+            buf: Vec::new(),
+        }
+    }
+
+    pub fn max_message_size(mut self, max_message_size: usize) -> Self {
+        self.max_message_size = max_message_size;
+        self
+    }
+
+    pub fn handler(&self) -> &H {
+        &self.handler
+    }
+
+    pub fn handler_mut(&mut self) -> &mut H {
+        &mut self.handler
+    }
+
+    pub fn into_inner(self) -> H {
+        self.handler
+    }
+
+    /// Decompresses into the reused buffer, failing beyond the maximum size.
+    fn decompress(&mut self, decoder: impl Read) -> Result<(), &'static str> {
+        self.buf.clear();
+        let limit = self.max_message_size as u64 + 1;
+        match decoder.take(limit).read_to_end(&mut self.buf) {
+            Ok(len) if len as u64 == limit => Err("Decompressed message too large"),
+            Ok(_) => Ok(()),
+            Err(_) => Err("Malformed compressed message"),
+        }
+    }
+}
+
+impl<H> DataChannelHandler for Decompress<H>
+where
+    H: DataChannelHandler,
+{
+    fn on_open(&mut self) {
+        self.handler.on_open()
+    }
+
+    fn on_closed(&mut self) {
+        self.handler.on_closed()
+    }
+
+    fn on_error(&mut self, err: &ChannelError) {
+        self.handler.on_error(err)
+    }
+
+    fn on_message(&mut self, msg: &[u8]) {
+        let res = match msg.split_first() {
+            Some((&RAW, raw)) => return self.handler.on_message(raw),
+            Some((&DEFLATE, data)) => self.decompress(DeflateDecoder::new(data)),
+            #[cfg(feature = "zstd")]
+            Some((&ZSTD, data)) => match zstd::Decoder::new(data) {
+                Ok(decoder) => self.decompress(decoder),
+                Err(_) => Err("Malformed compressed message"),
+            },
+            _ => Err("Unknown compression header"),
+        };
+        match res {
+            Ok(()) => {
+                let buf = std::mem::take(&mut self.buf);
+                self.handler.on_message(&buf);
+                self.buf = buf;
+            }
+            Err(reason) => self
+                .handler
+                .on_error(&ChannelError::new(ChannelErrorKind::Protocol, reason)),
+        }
+    }
+
+    fn on_buffered_amount_low(&mut self) {
+        self.handler.on_buffered_amount_low()
+    }
+
+    fn on_available(&mut self) {
+        self.handler.on_available()
+    }
+
+    fn on_keepalive_timeout(&mut self) {
+        self.handler.on_keepalive_timeout()
+    }
+}
@@ -1,13 +1,22 @@
 use std::convert::TryFrom;
 use std::ffi::{c_void, CStr, CString};
+use std::io::IoSlice;
+use std::ops::ControlFlow;
 use std::os::raw::c_char;
 use std::ptr;
-use std::slice;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use std::{slice, thread};
 
 use datachannel_sys as sys;
+use parking_lot::{Condvar, Mutex};
 
-use crate::error::{check, Error, Result};
+#[cfg(feature = "compression")]
+use crate::compression::Compression;
+use crate::error::{check, ChannelError, ChannelErrorKind, Error, Result};
+use crate::keepalive::{Keepalive, Pulse, RoundTripTime};
 use crate::logger;
+use crate::stats::{ChannelCounters, DataChannelStats, Traffic};
 
 #[derive(Debug, Clone, Default)]
 pub struct Reliability {
@@ -64,6 +73,8 @@ pub struct DataChannelInit {
     negotiated: bool,
     manual_stream: bool,
     stream: u16,
+    #[cfg(feature = "compression")]
+    compression: Option<Compression>,
 }
 
 impl DataChannelInit {
@@ -77,6 +88,27 @@ impl DataChannelInit {
         self
     }
 
+    /// Announces [`SHUTDOWN_PROTOCOL`] as the protocol of the channel, so that both peers
+    /// exchange a close marker on [`RtcDataChannel::shutdown`].
+    ///
+    /// Replaces the protocol, compression can't be announced along.
+    pub fn graceful_shutdown(self) -> Self {
+        self.protocol(SHUTDOWN_PROTOCOL)
+    }
+
+    /// Announces `compression` as the protocol of the channel and enables it on the channel
+    /// created, the remote peer picks it up with [`Compression::from_protocol`].
+    #[cfg(feature = "compression")]
+    pub fn compression(mut self, compression: Compression) -> Self {
+        self.compression.replace(compression);
+        self.protocol(compression.algorithm.protocol())
+    }
+
+    #[cfg(feature = "compression")]
+    pub(crate) fn compression_enabled(&self) -> Option<Compression> {
+        self.compression
+    }
+
     pub fn negotiated(mut self) -> Self {
         self.negotiated = true;
         self
@@ -88,11 +120,41 @@ impl DataChannelInit {
     }
 
     /// numeric ID 0-65534, ignored if `manual_stream` is false
+    ///
+    /// Unless the channel is `negotiated`, the ID must be even when the local peer is the DTLS
+    /// client and odd when it is the server (RFC 8832). This is checked once the DTLS role is
+    /// known from the descriptions.
     pub fn stream(mut self, stream: u16) -> Self {
         self.stream = stream;
         self
     }
 
+    /// Checks the manual stream ID, `dtls_client` is the DTLS role of the local peer if known.
+    pub(crate) fn validate(&self, dtls_client: Option<bool>) -> Result<()> {
+        if !self.manual_stream {
+            return Ok(());
+        }
+        if self.stream > 65534 {
+            logger::error!(
+                "Invalid DataChannel stream {}, must be between 0 and 65534",
+                self.stream
+            );
+            return Err(Error::InvalidArg);
+        }
+        if let (false, Some(client)) = (self.negotiated, dtls_client) {
+            if self.stream.is_multiple_of(2) != client {
+                logger::error!(
+                    "Invalid DataChannel stream {}, the DTLS {} must use {} streams",
+                    self.stream,
+                    if client { "client" } else { "server" },
+                    if client { "even" } else { "odd" }
+                );
+                return Err(Error::InvalidArg);
+            }
+        }
+        Ok(())
+    }
+
     pub(crate) fn as_raw(&self) -> Result<sys::rtcDataChannelInit> {
         Ok(sys::rtcDataChannelInit {
             reliability: self.reliability.as_raw(),
@@ -111,27 +173,351 @@ pub struct DataChannelId(pub(crate) i32);
 pub trait DataChannelHandler {
     fn on_open(&mut self) {}
     fn on_closed(&mut self) {}
-    fn on_error(&mut self, err: &str) {}
+    fn on_error(&mut self, err: &ChannelError) {}
     fn on_message(&mut self, msg: &[u8]) {}
     fn on_buffered_amount_low(&mut self) {}
     fn on_available(&mut self) {}
+    /// Called when nothing was received within the timeout of [`Keepalive`], never called
+    /// otherwise. It is called again if the peer comes back and times out anew.
+    fn on_keepalive_timeout(&mut self) {}
+}
+
+/// Coalescing of the messages sent through [`RtcDataChannel::send`], see
+/// [`RtcDataChannel::set_batching`].
+///
+/// Each message is framed with a 4 bytes big endian length prefix, the receiving side has to
+/// split the batches with [`Unbatch`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Batching {
+    /// Size in bytes of the pending batch triggering a flush
+    pub max_size: usize,
+    /// Delay after which pending messages are flushed, from a timer thread
+    pub max_delay: Option<Duration>,
+    /// Messages of at least this size are sent right away (after the pending ones) instead of
+    /// being coalesced
+    pub coalesce_below: Option<usize>,
+}
+
+impl Batching {
+    pub fn new(max_size: usize) -> Self {
+        Self {
+            max_size,
+            max_delay: None,
+            coalesce_below: None,
+        }
+    }
+
+    /// Nagle-like coalescing: messages smaller than `threshold` are held for at most `delay`
+    /// waiting for others to share their SCTP message, larger ones aren't delayed.
+    pub fn coalescing(threshold: usize, delay: Duration) -> Self {
+        Self::new(DEFAULT_BATCH_SIZE)
+            .coalesce_below(threshold)
+            .max_delay(delay)
+    }
+
+    pub fn max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay.replace(max_delay);
+        self
+    }
+
+    pub fn coalesce_below(mut self, threshold: usize) -> Self {
+        self.coalesce_below.replace(threshold);
+        self
+    }
+}
+
+/// Batch size of [`Batching::coalescing`], fits in the default maximum message size.
+const DEFAULT_BATCH_SIZE: usize = 64 * 1024;
+
+struct Pending {
+    buf: Vec<u8>,
+    oldest: Option<Instant>,
+    closed: bool,
+}
+
+impl Pending {
+    /// Sends the pending messages, they are kept for a later flush on error.
+    fn flush(&mut self, id: DataChannelId) -> Result<()> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+        send_message(id, &self.buf)?;
+        self.buf.clear();
+        self.oldest = None;
+        Ok(())
+    }
+
+    fn append(&mut self, msg: &[u8]) {
+        self.buf
+            .extend_from_slice(&(msg.len() as u32).to_be_bytes());
+        self.buf.extend_from_slice(msg);
+    }
+}
+
+struct Batcher {
+    batching: Batching,
+    pending: Arc<Mutex<Pending>>,
+    timer: Arc<Condvar>,
+}
+
+impl Batcher {
+    fn new(id: DataChannelId, batching: Batching) -> Self {
+        let pending = Arc::new(Mutex::new(Pending {
+            buf: Vec::with_capacity(batching.max_size),
+            oldest: None,
+            closed: false,
+        }));
+        let timer = Arc::new(Condvar::new());
+
+        if let Some(max_delay) = batching.max_delay {
+            let pending = Arc::clone(&pending);
+            let timer = Arc::clone(&timer);
+            thread::spawn(move || Self::run_timer(id, max_delay, &pending, &timer));
+        }
+
+        Self {
+            batching,
+            pending,
+            timer,
+        }
+    }
+
+    fn run_timer(
+        id: DataChannelId,
+        max_delay: Duration,
+        pending: &Mutex<Pending>,
+        timer: &Condvar,
+    ) {
+        let mut pending = pending.lock();
+        while !pending.closed {
+            match pending.oldest {
+                None => {
+                    timer.wait(&mut pending);
+                }
+                Some(oldest) if oldest + max_delay > Instant::now() => {
+                    timer.wait_until(&mut pending, oldest + max_delay);
+                }
+                Some(_) => {
+                    if let Err(err) = pending.flush(id) {
+                        logger::warn!(
+                            "Couldn't flush pending batch of RtcDataChannel id={:?}, {}",
+                            id,
+                            err
+                        );
+                        pending.buf.clear();
+                        pending.oldest = None;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Appends a message, flushing before if it doesn't fit and after if the batch is full.
+    fn push(&self, id: DataChannelId, msg: &[u8]) -> Result<()> {
+        let mut pending = self.pending.lock();
+
+        let bypass = self
+            .batching
+            .coalesce_below
+            .is_some_and(|threshold| msg.len() >= threshold);
+        if bypass {
+            pending.flush(id)?;
+            let mut single = Vec::with_capacity(4 + msg.len());
+            single.extend_from_slice(&(msg.len() as u32).to_be_bytes());
+            single.extend_from_slice(msg);
+            return send_message(id, &single);
+        }
+
+        if !pending.buf.is_empty() && pending.buf.len() + 4 + msg.len() > self.batching.max_size {
+            pending.flush(id)?;
+        }
+        pending.append(msg);
+        if pending.oldest.is_none() {
+            pending.oldest = Some(Instant::now());
+            self.timer.notify_one();
+        }
+
+        if pending.buf.len() >= self.batching.max_size {
+            pending.flush(id)?;
+        }
+        Ok(())
+    }
+
+    fn flush(&self, id: DataChannelId) -> Result<()> {
+        self.pending.lock().flush(id)
+    }
+}
+
+impl Drop for Batcher {
+    fn drop(&mut self) {
+        self.pending.lock().closed = true;
+        self.timer.notify_one();
+    }
+}
+
+/// Unsets the callbacks and user pointer of a data channel or track.
+///
+/// libdatachannel waits for a callback being run to return before replacing it, so the Rust
+/// object can be freed safely once this returns.
+pub(crate) fn detach_callbacks(id: i32) {
+    unsafe {
+        // Callbacks starting from now get a null pointer and return right away
+        sys::rtcSetUserPointer(id, ptr::null_mut());
+        // Those being run are waited for
+        sys::rtcSetOpenCallback(id, None);
+        sys::rtcSetClosedCallback(id, None);
+        sys::rtcSetErrorCallback(id, None);
+        sys::rtcSetMessageCallback(id, None);
+        sys::rtcSetBufferedAmountLowCallback(id, None);
+        sys::rtcSetAvailableCallback(id, None);
+    }
+}
+
+pub(crate) fn send_message(id: DataChannelId, msg: &[u8]) -> Result<()> {
+    check_send(id.0, unsafe {
+        sys::rtcSendMessage(id.0, msg.as_ptr() as *const c_char, msg.len() as i32)
+    })
+}
+
+/// Checks the result of sending on a data channel or track, telling apart a closed one.
+pub(crate) fn check_send(id: i32, code: i32) -> Result<()> {
+    match check(code) {
+        Ok(_) => Ok(()),
+        Err(_) if !unsafe { sys::rtcIsOpen(id) } => Err(Error::NotOpen),
+        Err(err) => Err(err),
+    }
+}
+
+/// Time [`RtcDataChannel`] waits for the closed event when dropped, by default.
+const DEFAULT_CLOSE_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Protocol of the data channels exchanging a close marker on [`RtcDataChannel::shutdown`],
+/// see [`DataChannelInit::graceful_shutdown`].
+pub const SHUTDOWN_PROTOCOL: &str = "datachannel-shutdown";
+
+/// Marker exchanged by [`RtcDataChannel::shutdown`] over channels using [`SHUTDOWN_PROTOCOL`],
+/// never handed to the handler.
+const SHUTDOWN_FIN: &[u8] = b"\0datachannel-shutdown:fin";
+
+/// How often [`RtcDataChannel::shutdown`] checks whether the buffered amount is drained.
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Progress of the close marker exchange, see [`RtcDataChannel::shutdown`].
+#[derive(Default)]
+struct Fin {
+    sent: bool,
+    received: bool,
+}
+
+/// State reached from the callbacks, registered as user pointer.
+///
+/// It lives in its own allocation so that moving a [`RtcDataChannel`] out of its box doesn't
+/// invalidate the pointer held by libdatachannel.
+///
+/// libdatachannel may run different callbacks of a channel concurrently, hence the lock
+/// around the handler.
+struct DataChannelState<D> {
+    dc_handler: Mutex<D>,
+    /// Set from the closed callback
+    closed: Mutex<bool>,
+    closed_cvar: Condvar,
+    /// Shared with the peer connection
+    traffic: Arc<Traffic>,
+    counters: Arc<ChannelCounters>,
+    span: logger::Span,
+    /// Set while keepalive is enabled
+    pulse: Mutex<Option<Arc<Pulse>>>,
+    /// Whether the channel uses [`SHUTDOWN_PROTOCOL`]
+    graceful: bool,
+    fin: Mutex<Fin>,
+    fin_cvar: Condvar,
+}
+
+impl<D> DataChannelState<D> {
+    fn received(&self, len: usize) {
+        self.traffic.received(len);
+        self.counters.traffic.received(len);
+        crate::telemetry::received("data_channel", len);
+    }
+
+    /// The remote peer sent its close marker, answered unless this side sent one already.
+    fn fin_received(&self, id: DataChannelId) {
+        let mut fin = self.fin.lock();
+        fin.received = true;
+        let reply = !std::mem::replace(&mut fin.sent, true);
+        drop(fin);
+        if reply {
+            if let Err(err) = send_message(id, SHUTDOWN_FIN) {
+                logger::warn!("Couldn't answer shutdown of RtcDataChannel id={:?}: {}", id, err);
+            }
+        }
+        self.fin_cvar.notify_all();
+    }
+
+    /// Handles the in-band shutdown and keepalive markers, returns whether `msg` was one.
+    fn intercept(&self, id: DataChannelId, msg: &[u8]) -> bool {
+        if self.graceful && msg == SHUTDOWN_FIN {
+            self.fin_received(id);
+            return true;
+        }
+        let pulse = self.pulse.lock().clone();
+        pulse.is_some_and(|pulse| pulse.received(id, msg))
+    }
 }
 
 pub struct RtcDataChannel<D> {
     id: DataChannelId,
-    dc_handler: D,
+    state: Arc<DataChannelState<D>>,
+    batcher: Option<Batcher>,
+    close_timeout: Option<Duration>,
+    send_buffer_limit: Option<usize>,
+    #[cfg(feature = "compression")]
+    compression: Option<Compression>,
+    /// Set once deleted, the id may then be reused by libdatachannel
+    deleted: bool,
 }
 
 impl<D> RtcDataChannel<D>
 where
     D: DataChannelHandler + Send,
 {
-    pub(crate) fn new(id: DataChannelId, dc_handler: D) -> Result<Box<Self>> {
+    pub(crate) fn new(
+        id: DataChannelId,
+        dc_handler: D,
+        traffic: Arc<Traffic>,
+        span: logger::Span,
+    ) -> Result<Box<Self>> {
+        crate::runtime::register();
+        crate::telemetry::data_channel_created();
+        let graceful = matches!(
+            DataChannelInfo::protocol(id),
+            Ok(Some(protocol)) if protocol == SHUTDOWN_PROTOCOL
+        );
         unsafe {
-            let mut rtc_dc = Box::new(RtcDataChannel { id, dc_handler });
-            let ptr = &mut *rtc_dc;
+            let rtc_dc = Box::new(RtcDataChannel {
+                id,
+                state: Arc::new(DataChannelState {
+                    dc_handler: Mutex::new(dc_handler),
+                    closed: Mutex::new(false),
+                    closed_cvar: Condvar::new(),
+                    traffic,
+                    counters: Arc::default(),
+                    span,
+                    pulse: Mutex::new(None),
+                    graceful,
+                    fin: Mutex::default(),
+                    fin_cvar: Condvar::new(),
+                }),
+                batcher: None,
+                close_timeout: Some(DEFAULT_CLOSE_TIMEOUT),
+                send_buffer_limit: None,
+                #[cfg(feature = "compression")]
+                compression: None,
+                deleted: false,
+            });
+            let ptr = Arc::as_ptr(&rtc_dc.state);
 
-            sys::rtcSetUserPointer(id.0, ptr as *mut _ as *mut c_void);
+            sys::rtcSetUserPointer(id.0, ptr as *mut c_void);
 
             check(sys::rtcSetOpenCallback(
                 id.0,
@@ -168,65 +554,221 @@ where
     }
 
     unsafe extern "C" fn open_cb(_: i32, ptr: *mut c_void) {
-        let rtc_dc = &mut *(ptr as *mut RtcDataChannel<D>);
-        rtc_dc.dc_handler.on_open()
+        crate::panic::catch("RtcDataChannel::open_cb", || {
+            let Some(state) = (ptr as *const DataChannelState<D>).as_ref() else {
+                return;
+            };
+            let _span = state.span.enter();
+            state.dc_handler.lock().on_open()
+        })
     }
 
     unsafe extern "C" fn closed_cb(_: i32, ptr: *mut c_void) {
-        let rtc_dc = &mut *(ptr as *mut RtcDataChannel<D>);
-        rtc_dc.dc_handler.on_closed()
+        crate::panic::catch("RtcDataChannel::closed_cb", || {
+            let Some(state) = (ptr as *const DataChannelState<D>).as_ref() else {
+                return;
+            };
+            let _span = state.span.enter();
+            state.dc_handler.lock().on_closed();
+            *state.closed.lock() = true;
+            state.closed_cvar.notify_all();
+        })
     }
 
     unsafe extern "C" fn error_cb(_: i32, err: *const c_char, ptr: *mut c_void) {
-        let rtc_dc = &mut *(ptr as *mut RtcDataChannel<D>);
-        let err = CStr::from_ptr(err).to_string_lossy();
-        rtc_dc.dc_handler.on_error(&err)
+        crate::panic::catch("RtcDataChannel::error_cb", || {
+            let Some(state) = (ptr as *const DataChannelState<D>).as_ref() else {
+                return;
+            };
+            let _span = state.span.enter();
+            let err = ChannelError::from_message(&CStr::from_ptr(err).to_string_lossy());
+            state.dc_handler.lock().on_error(&err)
+        })
     }
 
-    unsafe extern "C" fn message_cb(_: i32, msg: *const c_char, size: i32, ptr: *mut c_void) {
-        let rtc_dc = &mut *(ptr as *mut RtcDataChannel<D>);
-        let msg = if size < 0 {
-            CStr::from_ptr(msg).to_bytes()
-        } else {
-            slice::from_raw_parts(msg as *const u8, size as usize)
-        };
-        rtc_dc.dc_handler.on_message(msg)
+    unsafe extern "C" fn message_cb(id: i32, msg: *const c_char, size: i32, ptr: *mut c_void) {
+        crate::panic::catch("RtcDataChannel::message_cb", || {
+            let Some(state) = (ptr as *const DataChannelState<D>).as_ref() else {
+                return;
+            };
+            let _span = state.span.enter();
+            let msg = if size < 0 {
+                CStr::from_ptr(msg).to_bytes()
+            } else {
+                slice::from_raw_parts(msg as *const u8, size as usize)
+            };
+            state.received(msg.len());
+            if !state.intercept(DataChannelId(id), msg) {
+                state.dc_handler.lock().on_message(msg)
+            }
+        })
     }
 
     unsafe extern "C" fn buffered_amount_low_cb(_: i32, ptr: *mut c_void) {
-        let rtc_dc = &mut *(ptr as *mut RtcDataChannel<D>);
-        rtc_dc.dc_handler.on_buffered_amount_low()
+        crate::panic::catch("RtcDataChannel::buffered_amount_low_cb", || {
+            let Some(state) = (ptr as *const DataChannelState<D>).as_ref() else {
+                return;
+            };
+            let _span = state.span.enter();
+            state.dc_handler.lock().on_buffered_amount_low()
+        })
     }
 
     unsafe extern "C" fn available_cb(_: i32, ptr: *mut c_void) {
-        let rtc_dc = &mut *(ptr as *mut RtcDataChannel<D>);
-        rtc_dc.dc_handler.on_available()
+        crate::panic::catch("RtcDataChannel::available_cb", || {
+            let Some(state) = (ptr as *const DataChannelState<D>).as_ref() else {
+                return;
+            };
+            let _span = state.span.enter();
+            state.dc_handler.lock().on_available()
+        })
     }
 
     pub fn id(&self) -> DataChannelId {
         self.id
     }
 
+    /// Sends a message, or appends it to the pending batch when batching is enabled.
+    ///
+    /// Fails with [`Error::NotOpen`] when the data channel isn't open, and with
+    /// [`Error::WouldBlock`] when the send buffer limit is reached.
     pub fn send(&mut self, msg: &[u8]) -> Result<()> {
-        check(unsafe {
-            sys::rtcSendMessage(self.id.0, msg.as_ptr() as *const c_char, msg.len() as i32)
-        })
-        .map(|_| ())
+        let start = Instant::now();
+        let res = self.try_send(msg);
+        let counters = &self.state.counters;
+        match res {
+            Ok(()) => {
+                let buffered_amount = self.buffered_amount();
+                self.state.traffic.sent(msg.len());
+                counters.traffic.sent(msg.len());
+                counters.buffered(buffered_amount);
+                crate::telemetry::sent("data_channel", msg.len());
+                crate::telemetry::data_channel_send(start.elapsed(), buffered_amount);
+            }
+            Err(_) => counters.send_failed(),
+        }
+        res
     }
 
-    pub fn label(&self) -> String {
+    fn try_send(&self, msg: &[u8]) -> Result<()> {
+        if let Some(limit) = self.send_buffer_limit {
+            if self.buffered_amount() >= limit {
+                return Err(Error::WouldBlock);
+            }
+        }
+        #[cfg(feature = "compression")]
+        let encoded = self.compression.map(|compression| compression.encode(msg));
+        #[cfg(feature = "compression")]
+        let msg = encoded.as_deref().unwrap_or(msg);
+        match &self.batcher {
+            Some(batcher) => batcher.push(self.id, msg),
+            None => send_message(self.id, msg),
+        }
+    }
+
+    /// Returns the counters of this data channel, see [`DataChannelStats`].
+    pub fn stats(&self) -> DataChannelStats {
+        self.state.counters.snapshot()
+    }
+
+    pub(crate) fn counters(&self) -> &Arc<ChannelCounters> {
+        &self.state.counters
+    }
+
+    /// Enables or disables batching, the pending messages are flushed first.
+    pub fn set_batching(&mut self, batching: Option<Batching>) -> Result<()> {
+        self.flush()?;
+        self.batcher = batching.map(|batching| Batcher::new(self.id, batching));
+        Ok(())
+    }
+
+    pub fn batching(&self) -> Option<Batching> {
+        self.batcher.as_ref().map(|batcher| batcher.batching)
+    }
+
+    /// Enables or disables the compression of the messages sent afterwards.
+    #[cfg(feature = "compression")]
+    pub fn set_compression(&mut self, compression: Option<Compression>) {
+        self.compression = compression;
+    }
+
+    #[cfg(feature = "compression")]
+    pub fn compression(&self) -> Option<Compression> {
+        self.compression
+    }
+
+    /// Enables or disables keepalive, both peers must enable it as pings are only told apart
+    /// from other messages when it is.
+    pub fn set_keepalive(&mut self, keepalive: Option<Keepalive>)
+    where
+        D: 'static,
+    {
+        let state = Arc::clone(&self.state);
+        let pulse = keepalive.map(|keepalive| {
+            Pulse::start(self.id, keepalive, move || {
+                let _span = state.span.enter();
+                state.dc_handler.lock().on_keepalive_timeout()
+            })
+        });
+        if let Some(previous) = std::mem::replace(&mut *self.state.pulse.lock(), pulse) {
+            previous.stop();
+        }
+    }
+
+    pub fn keepalive(&self) -> Option<Keepalive> {
+        self.state.pulse.lock().as_ref().map(|pulse| pulse.keepalive())
+    }
+
+    /// The round trip time measured by keepalive, once a pong was received.
+    pub fn round_trip_time(&self) -> Option<RoundTripTime> {
+        let pulse = self.state.pulse.lock();
+        pulse.as_ref().and_then(|pulse| pulse.round_trip_time())
+    }
+
+    /// Sends the pending batch right away, does nothing when batching is disabled.
+    pub fn flush(&mut self) -> Result<()> {
+        match &self.batcher {
+            Some(batcher) => batcher.flush(self.id),
+            None => Ok(()),
+        }
+    }
+
+    /// Sends the concatenation of `bufs` as a single message.
+    pub fn send_vectored(&mut self, bufs: &[IoSlice<'_>]) -> Result<()> {
+        match bufs {
+            [] => self.send(&[]),
+            [buf] => self.send(buf),
+            bufs => {
+                let mut msg = Vec::with_capacity(bufs.iter().map(|buf| buf.len()).sum());
+                bufs.iter().for_each(|buf| msg.extend_from_slice(buf));
+                self.send(&msg)
+            }
+        }
+    }
+
+    /// Sends each of `msgs` as a separate message, in order.
+    ///
+    /// Stops at the first error, the messages before it have been sent.
+    pub fn send_batch(&mut self, msgs: &[&[u8]]) -> Result<()> {
+        for msg in msgs {
+            self.send(msg)?;
+        }
+        Ok(())
+    }
+
+    pub fn label(&self) -> Result<String> {
         DataChannelInfo::label(self.id)
     }
 
-    pub fn protocol(&self) -> Option<String> {
+    pub fn protocol(&self) -> Result<Option<String>> {
         DataChannelInfo::protocol(self.id)
     }
 
-    pub fn reliability(&self) -> Reliability {
+    pub fn reliability(&self) -> Result<Reliability> {
         DataChannelInfo::reliability(self.id)
     }
 
-    pub fn stream(&self) -> usize {
+    pub fn stream(&self) -> Result<usize> {
         DataChannelInfo::stream(self.id)
     }
 
@@ -266,6 +808,19 @@ where
         Ok(())
     }
 
+    /// Makes [`send`](Self::send) fail with [`Error::WouldBlock`] once `limit` bytes are
+    /// buffered, otherwise libdatachannel buffers without bound. `None` by default.
+    ///
+    /// Pair it with [`set_buffered_amount_low_threshold`](Self::set_buffered_amount_low_threshold)
+    /// to know when to resume sending.
+    pub fn set_send_buffer_limit(&mut self, limit: Option<usize>) {
+        self.send_buffer_limit = limit;
+    }
+
+    pub fn send_buffer_limit(&self) -> Option<usize> {
+        self.send_buffer_limit
+    }
+
     /// Number of bytes currently queued to be consumed from the data channel.
     ///
     /// This method is the counterpart of [`buffered_amount`].
@@ -285,21 +840,308 @@ where
             }
         }
     }
+
+    /// Closes the data channel, [`on_closed`](DataChannelHandler::on_closed) is called once
+    /// the remote peer acknowledged it.
+    pub fn close(&mut self) -> Result<()> {
+        self.flush()?;
+        check(unsafe { sys::rtcClose(self.id.0) })?;
+        Ok(())
+    }
+
+    /// Shuts the data channel down gracefully, then deletes it.
+    ///
+    /// Taking the channel stops new sends. The pending batch is flushed and the buffered
+    /// amount awaited to drain. On channels using [`SHUTDOWN_PROTOCOL`], a close marker is
+    /// then exchanged with the remote peer, which answers it once the messages it sent before
+    /// are delivered here. The channel is finally closed and deleted, even when this fails.
+    ///
+    /// Fails with [`Error::Timeout`] when this doesn't complete within `timeout`, messages may
+    /// then be lost. The remote peer answers the marker as it receives it, when polling only
+    /// once it reads it, and sees its channel closed afterwards. The marker is never handed to
+    /// handlers nor returned by [`try_receive`](Self::try_receive).
+    pub fn shutdown(mut self: Box<Self>, timeout: Duration) -> Result<()> {
+        let deadline = Instant::now() + timeout;
+        let res = self.drain(deadline);
+        let closed = self.close_and_wait(deadline.saturating_duration_since(Instant::now()));
+        res.and(closed)
+    }
+
+    fn drain(&mut self, deadline: Instant) -> Result<()> {
+        if !unsafe { sys::rtcIsOpen(self.id.0) } {
+            return Err(Error::NotOpen);
+        }
+        self.flush()?;
+        while self.buffered_amount() > 0 {
+            if Instant::now() >= deadline {
+                return Err(Error::Timeout);
+            }
+            thread::sleep(DRAIN_POLL_INTERVAL);
+        }
+
+        if !self.state.graceful {
+            return Ok(());
+        }
+        if !std::mem::replace(&mut self.state.fin.lock().sent, true) {
+            send_message(self.id, SHUTDOWN_FIN)?;
+        }
+        let mut fin = self.state.fin.lock();
+        while !fin.received {
+            if self.state.fin_cvar.wait_until(&mut fin, deadline).timed_out() {
+                return Err(Error::Timeout);
+            }
+        }
+        Ok(())
+    }
+
+    /// How long dropping the data channel waits for it to be closed cleanly, 100ms by default.
+    ///
+    /// When dropped, an open data channel is closed first so that the remote peer observes a
+    /// regular close. With `None`, or when dropped from a handler callback, it is deleted
+    /// without waiting.
+    pub fn set_close_timeout(&mut self, timeout: Option<Duration>) {
+        self.close_timeout = timeout;
+    }
+
+    /// Switches between callback and polling reception.
+    ///
+    /// When polling, [`on_message`](DataChannelHandler::on_message) isn't called anymore and
+    /// messages stay queued until read with [`try_receive`](Self::try_receive). Messages still
+    /// queued when switching back are delivered to the handler.
+    pub fn set_polling(&mut self, polling: bool) -> Result<()> {
+        let cb = if polling {
+            None
+        } else {
+            Some(RtcDataChannel::<D>::message_cb as _)
+        };
+        check(unsafe { sys::rtcSetMessageCallback(self.id.0, cb) })?;
+        Ok(())
+    }
+
+    /// Pops the next queued message into `buf`, returns `false` when none is available.
+    ///
+    /// `buf` is cleared and grown as needed, reusing it avoids allocations. Only meaningful
+    /// in polling mode, see [`set_polling`](Self::set_polling). The shutdown and keepalive
+    /// markers are handled on the way and skipped.
+    pub fn try_receive(&mut self, buf: &mut Vec<u8>) -> Result<bool> {
+        loop {
+            buf.clear();
+            let mut size = buf.capacity() as i32;
+            match check(unsafe {
+                sys::rtcReceiveMessage(self.id.0, buf.as_mut_ptr() as *mut c_char, &mut size)
+            }) {
+                Ok(_) => {
+                    // A negative size denotes a string message, including its NUL terminator
+                    let len = if size < 0 { -size - 1 } else { size };
+                    unsafe { buf.set_len((len.max(0) as usize).min(buf.capacity())) };
+                    self.state.received(buf.len());
+                    if !self.state.intercept(self.id, buf) {
+                        return Ok(true);
+                    }
+                }
+                Err(Error::NotAvailable) => return Ok(false),
+                Err(Error::TooSmall) => {
+                    // The size reported along with this error isn't reliable, a null buffer
+                    // gets it without popping the message
+                    let mut size = 0;
+                    check(unsafe { sys::rtcReceiveMessage(self.id.0, ptr::null_mut(), &mut size) })?;
+                    buf.reserve(size.unsigned_abs() as usize);
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Spins on [`try_receive`](Self::try_receive), calling `f` with each message until it
+    /// breaks.
+    ///
+    /// Trades a busy core for lower and steadier latency than callbacks, which involve waking
+    /// up threads. Enables polling mode, it is meant to run on a dedicated thread, ideally
+    /// pinned to an isolated core. The thread yields while no message is queued, so that it
+    /// doesn't starve the network threads when sharing their core.
+    ///
+    /// Fails with [`Error::NotOpen`] once the channel is closed and its queued messages read.
+    pub fn busy_poll<F>(&mut self, mut f: F) -> Result<()>
+    where
+        F: FnMut(&[u8]) -> ControlFlow<()>,
+    {
+        self.set_polling(true)?;
+        let mut buf = Vec::new();
+        loop {
+            // Checked first, messages received before the channel closed are still read
+            let closed = *self.state.closed.lock();
+            if !self.try_receive(&mut buf)? {
+                if closed {
+                    return Err(Error::NotOpen);
+                }
+                thread::yield_now();
+                continue;
+            }
+            if f(&buf).is_break() {
+                return Ok(());
+            }
+        }
+    }
+}
+
+impl<D> RtcDataChannel<D> {
+    /// Deletes the data channel right away, returning the error dropping it would only log.
+    ///
+    /// An open data channel is closed first so that the remote peer observes a regular close,
+    /// without waiting for the closed event unlike when dropped.
+    #[allow(clippy::boxed_local)]
+    pub fn delete(mut self: Box<Self>) -> Result<()> {
+        self.deleted = true;
+        self.teardown(None)
+    }
+
+    /// Closes the data channel and waits up to `timeout` for the closed event before deleting
+    /// it, as when dropped but overriding [`set_close_timeout`](Self::set_close_timeout).
+    ///
+    /// Fails with [`Error::Timeout`] when the remote peer doesn't acknowledge the close in
+    /// time, the channel is deleted regardless.
+    #[allow(clippy::boxed_local)]
+    pub fn close_and_wait(mut self: Box<Self>, timeout: Duration) -> Result<()> {
+        self.deleted = true;
+        self.teardown(Some(timeout))
+    }
+
+    fn teardown(&mut self, timeout: Option<Duration>) -> Result<()> {
+        if let Some(pulse) = self.state.pulse.lock().take() {
+            pulse.stop();
+        }
+        if let Some(Err(err)) = self.batcher.as_ref().map(|batcher| batcher.flush(self.id)) {
+            logger::warn!(
+                "Couldn't flush pending batch of RtcDataChannel id={:?} {:p}: {}",
+                self.id,
+                self,
+                err
+            );
+        }
+
+        let mut res = Ok(());
+        let is_open = unsafe { sys::rtcIsOpen(self.id.0) };
+        if is_open && check(unsafe { sys::rtcClose(self.id.0) }).is_ok() {
+            if let Some(timeout) = timeout {
+                let deadline = Instant::now() + timeout;
+                let mut closed = self.state.closed.lock();
+                while !*closed {
+                    if self.state.closed_cvar.wait_until(&mut closed, deadline).timed_out() {
+                        res = Err(Error::Timeout);
+                        break;
+                    }
+                }
+            }
+        }
+
+        detach_callbacks(self.id.0);
+        let deleted = check(unsafe { sys::rtcDeleteDataChannel(self.id.0) });
+        crate::runtime::release();
+        crate::telemetry::data_channel_deleted();
+        res.and(deleted.map(|_| ()))
+    }
 }
 
 impl<D> Drop for RtcDataChannel<D> {
     fn drop(&mut self) {
-        if let Err(err) = check(unsafe { sys::rtcDeleteDataChannel(self.id.0) }) {
-            logger::error!(
+        if self.deleted {
+            return;
+        }
+        self.deleted = true;
+        // The closed event can't be delivered while a callback of libdatachannel is blocked
+        let timeout = self.close_timeout.filter(|_| !crate::panic::in_callback());
+        match self.teardown(timeout) {
+            Ok(()) => (),
+            Err(Error::Timeout) => logger::debug!(
+                "RtcDataChannel id={:?} {:p} dropped before being closed by the remote peer",
+                self.id,
+                self
+            ),
+            Err(err) => logger::error!(
                 "Error while dropping RtcDataChannel id={:?} {:p}: {}",
                 self.id,
                 self,
                 err
-            );
+            ),
         }
     }
 }
 
+/// A [`DataChannelHandler`] splitting the batches sent by a peer with [`Batching`] enabled.
+///
+/// Each message of a batch is handed to the inner handler, a malformed batch is reported
+/// through [`on_error`](DataChannelHandler::on_error) once its valid messages are delivered.
+pub struct Unbatch<H> {
+    handler: H,
+}
+
+impl<H> Unbatch<H> {
+    pub fn new(handler: H) -> Self {
+        Self { handler }
+    }
+
+    pub fn handler(&self) -> &H {
+        &self.handler
+    }
+
+    pub fn handler_mut(&mut self) -> &mut H {
+        &mut self.handler
+    }
+
+    pub fn into_inner(self) -> H {
+        self.handler
+    }
+}
+
+impl<H> DataChannelHandler for Unbatch<H>
+where
+    H: DataChannelHandler,
+{
+    fn on_open(&mut self) {
+        self.handler.on_open()
+    }
+
+    fn on_closed(&mut self) {
+        self.handler.on_closed()
+    }
+
+    fn on_error(&mut self, err: &ChannelError) {
+        self.handler.on_error(err)
+    }
+
+    fn on_message(&mut self, mut msg: &[u8]) {
+        while !msg.is_empty() {
+            let len = match msg.first_chunk::<4>() {
+                Some(len) => u32::from_be_bytes(*len) as usize,
+                None => break,
+            };
+            match msg.get(4..4 + len) {
+                Some(inner) => self.handler.on_message(inner),
+                None => break,
+            }
+            msg = &msg[4 + len..];
+        }
+        if !msg.is_empty() {
+            self.handler.on_error(&ChannelError::new(
+                ChannelErrorKind::Protocol,
+                "Malformed batch, truncated message",
+            ));
+        }
+    }
+
+    fn on_buffered_amount_low(&mut self) {
+        self.handler.on_buffered_amount_low()
+    }
+
+    fn on_available(&mut self) {
+        self.handler.on_available()
+    }
+
+    fn on_keepalive_timeout(&mut self) {
+        self.handler.on_keepalive_timeout()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct DataChannelInfo {
     pub id: DataChannelId,
@@ -310,63 +1152,28 @@ pub struct DataChannelInfo {
 }
 
 impl DataChannelInfo {
-    pub(crate) fn label(id: DataChannelId) -> String {
-        let buf_size =
-            check(unsafe { sys::rtcGetDataChannelLabel(id.0, ptr::null_mut() as *mut c_char, 0) })
-                .expect("Couldn't get buffer size") as usize;
-
-        let mut buf = vec![0; buf_size];
-        match check(unsafe {
-            sys::rtcGetDataChannelLabel(id.0, buf.as_mut_ptr() as *mut c_char, buf_size as i32)
-        }) {
-            Ok(_) => match crate::ffi_string(&buf) {
-                Ok(label) => label,
-                Err(err) => {
-                    logger::error!("Couldn't get label for RtcDataChannel id={:?}, {}", id, err);
-                    String::default()
-                }
-            },
-            Err(err) => {
-                logger::warn!("Couldn't get label for RtcDataChannel id={:?}, {}", id, err);
-                String::default()
-            }
-        }
+    pub(crate) fn fetch(id: DataChannelId) -> Result<Self> {
+        Ok(DataChannelInfo {
+            id,
+            label: Self::label(id)?,
+            protocol: Self::protocol(id)?,
+            reliability: Self::reliability(id)?,
+            stream: Self::stream(id)?,
+        })
     }
 
-    pub(crate) fn protocol(id: DataChannelId) -> Option<String> {
-        let buf_size = check(unsafe {
-            sys::rtcGetDataChannelProtocol(id.0, ptr::null_mut() as *mut c_char, 0)
-        })
-        .expect("Couldn't get buffer size") as usize;
-
-        let mut buf = vec![0; buf_size];
-        match check(unsafe {
-            sys::rtcGetDataChannelProtocol(id.0, buf.as_mut_ptr() as *mut c_char, buf_size as i32)
-        }) {
-            Ok(1) => None,
-            Ok(_) => match crate::ffi_string(&buf) {
-                Ok(protocol) => Some(protocol),
-                Err(err) => {
-                    logger::error!(
-                        "Couldn't get protocol for RtcDataChannel id={:?}, {}",
-                        id,
-                        err
-                    );
-                    None
-                }
-            },
-            Err(err) => {
-                logger::warn!(
-                    "Couldn't get protocol for RtcDataChannel id={:?}, {}",
-                    id,
-                    err
-                );
-                None
-            }
-        }
+    pub(crate) fn label(id: DataChannelId) -> Result<String> {
+        crate::read_ffi_string(|buf, size| unsafe { sys::rtcGetDataChannelLabel(id.0, buf, size) })
+    }
+
+    pub(crate) fn protocol(id: DataChannelId) -> Result<Option<String>> {
+        let protocol = crate::read_ffi_string(|buf, size| unsafe {
+            sys::rtcGetDataChannelProtocol(id.0, buf, size)
+        })?;
+        Ok(Some(protocol).filter(|protocol| !protocol.is_empty()))
     }
 
-    pub(crate) fn reliability(id: DataChannelId) -> Reliability {
+    pub(crate) fn reliability(id: DataChannelId) -> Result<Reliability> {
         let mut reliability = sys::rtcReliability {
             unordered: false,
             unreliable: false,
@@ -374,14 +1181,12 @@ impl DataChannelInfo {
             maxRetransmits: 0,
         };
 
-        check(unsafe { sys::rtcGetDataChannelReliability(id.0, &mut reliability) })
-            .expect("Couldn't get RtcDataChannel reliability");
+        check(unsafe { sys::rtcGetDataChannelReliability(id.0, &mut reliability) })?;
 
-        Reliability::from_raw(reliability)
+        Ok(Reliability::from_raw(reliability))
     }
 
-    pub(crate) fn stream(id: DataChannelId) -> usize {
-        check(unsafe { sys::rtcGetDataChannelStream(id.0) })
-            .expect("Couldn't get RtcDataChannel stream") as usize
+    pub(crate) fn stream(id: DataChannelId) -> Result<usize> {
+        check(unsafe { sys::rtcGetDataChannelStream(id.0) }).map(|stream| stream as usize)
     }
 }
@@ -0,0 +1,165 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+
+use parking_lot::{Mutex, MutexGuard};
+
+use crate::datachannel::DataChannelHandler;
+use crate::error::ChannelError;
+use crate::track::TrackHandler;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+struct Workers {
+    senders: Vec<mpsc::Sender<Job>>,
+    next: AtomicUsize,
+}
+
+/// A pool of threads running handler callbacks off libdatachannel's internal threads.
+///
+/// Each handler wrapped with [`dispatch`](Self::dispatch) is assigned to a single worker, so
+/// its callbacks keep their order while different channels and tracks run in parallel. The
+/// threads stop once the pool and all the handlers it wrapped are dropped. A panicking callback
+/// is reported like any handler panic, see [`set_panic_handler`](crate::set_panic_handler),
+/// and its worker keeps running the next ones.
+#[derive(Clone)]
+pub struct WorkerPool {
+    workers: Arc<Workers>,
+}
+
+impl WorkerPool {
+    /// Spawns a pool of `threads` workers.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `threads` is zero.
+    pub fn new(threads: usize) -> Self {
+        assert!(threads > 0, "WorkerPool needs at least one thread");
+        let senders = (0..threads)
+            .map(|_| {
+                let (tx, rx) = mpsc::channel::<Job>();
+                thread::spawn(move || {
+                    for job in rx {
+                        crate::panic::catch("WorkerPool::job", job);
+                    }
+                });
+                tx
+            })
+            .collect();
+
+        Self {
+            workers: Arc::new(Workers {
+                senders,
+                next: AtomicUsize::new(0),
+            }),
+        }
+    }
+
+    pub fn threads(&self) -> usize {
+        self.workers.senders.len()
+    }
+
+    /// Wraps a [`DataChannelHandler`] or [`TrackHandler`] so that its callbacks run on this pool.
+    pub fn dispatch<H>(&self, handler: H) -> Dispatched<H> {
+        let next = self.workers.next.fetch_add(1, Ordering::Relaxed);
+        let worker = self.workers.senders[next % self.workers.senders.len()].clone();
+        Dispatched {
+            handler: Arc::new(Mutex::new(handler)),
+            worker,
+        }
+    }
+}
+
+/// A handler whose callbacks are queued to a [`WorkerPool`] worker.
+///
+/// Callbacks return immediately, received messages are copied to be handed to the worker.
+pub struct Dispatched<H> {
+    handler: Arc<Mutex<H>>,
+    worker: mpsc::Sender<Job>,
+}
+
+impl<H> Dispatched<H>
+where
+    H: Send + 'static,
+{
+    /// Locks the inner handler.
+    pub fn handler(&self) -> MutexGuard<'_, H> {
+        self.handler.lock()
+    }
+
+    fn run<F>(&self, f: F)
+    where
+        F: FnOnce(&mut H) + Send + 'static,
+    {
+        let handler = Arc::clone(&self.handler);
+        self.worker
+            .send(Box::new(move || f(&mut handler.lock())))
+            .ok();
+    }
+}
+
+impl<H> DataChannelHandler for Dispatched<H>
+where
+    H: DataChannelHandler + Send + 'static,
+{
+    fn on_open(&mut self) {
+        self.run(|handler| handler.on_open())
+    }
+
+    fn on_closed(&mut self) {
+        self.run(|handler| handler.on_closed())
+    }
+
+    fn on_error(&mut self, err: &ChannelError) {
+        let err = err.clone();
+        self.run(move |handler| handler.on_error(&err))
+    }
+
+    fn on_message(&mut self, msg: &[u8]) {
+        let msg = msg.to_vec();
+        self.run(move |handler| handler.on_message(&msg))
+    }
+
+    fn on_buffered_amount_low(&mut self) {
+        self.run(|handler| handler.on_buffered_amount_low())
+    }
+
+    fn on_available(&mut self) {
+        self.run(|handler| handler.on_available())
+    }
+
+    fn on_keepalive_timeout(&mut self) {
+        self.run(|handler| handler.on_keepalive_timeout())
+    }
+}
+
+impl<H> TrackHandler for Dispatched<H>
+where
+    H: TrackHandler + Send + 'static,
+{
+    fn on_open(&mut self) {
+        self.run(|handler| handler.on_open())
+    }
+
+    fn on_closed(&mut self) {
+        self.run(|handler| handler.on_closed())
+    }
+
+    fn on_error(&mut self, err: &ChannelError) {
+        let err = err.clone();
+        self.run(move |handler| handler.on_error(&err))
+    }
+
+    fn on_message(&mut self, msg: &[u8]) {
+        let msg = msg.to_vec();
+        self.run(move |handler| handler.on_message(&msg))
+    }
+
+    fn on_available(&mut self) {
+        self.run(|handler| handler.on_available())
+    }
+
+    fn on_bitrate(&mut self, bitrate: u32) {
+        self.run(move |handler| handler.on_bitrate(bitrate))
+    }
+}
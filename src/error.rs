@@ -1,23 +1,94 @@
-use std::fmt::{self, Display};
-
 pub type Result<T> = std::result::Result<T, Error>;
 
-pub fn check(code: i32) -> Result<i32> {
-    if code < 0 {
-        Err(Error::from(code))
-    } else {
-        Ok(code)
+cfg_native! {
+    use std::cell::RefCell;
+
+    thread_local! {
+        /// Last error or warning logged by libdatachannel on this thread
+        static LAST_MESSAGE: RefCell<Option<String>> = const { RefCell::new(None) };
+    }
+
+    pub fn check(code: i32) -> Result<i32> {
+        if code < 0 {
+            Err(Error::from(code))
+        } else {
+            Ok(code)
+        }
+    }
+
+    /// Runs a libdatachannel call, adding its name, the id it applies to and the message logged
+    /// meanwhile to the error.
+    pub(crate) fn call<F>(call: &'static str, id: i32, f: F) -> Result<i32>
+    where
+        F: FnOnce() -> i32,
+    {
+        LAST_MESSAGE.with(|last| last.borrow_mut().take());
+        check(f()).map_err(|source| Error::Call {
+            call,
+            id,
+            source: Box::new(source),
+            message: LAST_MESSAGE.with(|last| last.borrow_mut().take()),
+        })
+    }
+
+    /// Keeps a message logged by libdatachannel, for the error of the call being run if any.
+    pub(crate) fn record_message(message: &str) {
+        LAST_MESSAGE.with(|last| *last.borrow_mut() = Some(message.to_string()));
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
 pub enum Error {
+    #[error("InvalidArg")]
     InvalidArg,
+    #[error("RuntimeError")]
     Runtime,
+    #[error("NotAvailable")]
     NotAvailable,
+    #[error("TooSmall")]
     TooSmall,
+    /// Never returned, see [`Error::Unknown`].
+    #[deprecated(note = "misspelled, use `Error::Unknown` which carries the error code")]
+    #[error("UnknownError")]
     Unkown,
+    /// An error code this crate doesn't know about, for instance from a newer libdatachannel.
+    #[error("UnknownError({0})")]
+    Unknown(i32),
+    #[error("BadString: {0}")]
     BadString(String),
+    /// The channel or track isn't open, either not yet or not anymore.
+    #[error("NotOpen")]
+    NotOpen,
+    /// The send buffer limit is reached, retry once the buffered amount got low.
+    #[error("WouldBlock")]
+    WouldBlock,
+    /// A graceful shutdown didn't complete in time.
+    #[error("Timeout")]
+    Timeout,
+    /// A libdatachannel call failed, `message` is the last error it logged meanwhile, only
+    /// available when its logging is enabled.
+    #[error(
+        "{call} failed for id={id}: {source}{}",
+        .message.as_ref().map(|msg| format!(" ({})", msg)).unwrap_or_default()
+    )]
+    Call {
+        call: &'static str,
+        id: i32,
+        #[source]
+        source: Box<Error>,
+        message: Option<String>,
+    },
+}
+
+impl Error {
+    /// Returns the error without the context of [`Error::Call`].
+    pub fn root(&self) -> &Error {
+        match self {
+            Self::Call { source, .. } => source.root(),
+            err => err,
+        }
+    }
 }
 
 impl From<i32> for Error {
@@ -27,26 +98,11 @@ impl From<i32> for Error {
             -2 => Self::Runtime,
             -3 => Self::NotAvailable,
             -4 => Self::TooSmall,
-            _ => Self::Unkown,
-        }
-    }
-}
-
-impl Display for Error {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            Self::InvalidArg => write!(f, "InvalidArg"),
-            Self::Runtime => write!(f, "RuntimeError"),
-            Self::NotAvailable => write!(f, "NotAvailable"),
-            Self::TooSmall => write!(f, "TooSmall"),
-            Self::Unkown => write!(f, "UnknownError"),
-            Self::BadString(msg) => write!(f, "BadString: {}", msg),
+            code => Self::Unknown(code),
         }
     }
 }
 
-impl std::error::Error for Error {}
-
 impl From<std::ffi::NulError> for Error {
     fn from(e: std::ffi::NulError) -> Self {
         Self::BadString(e.to_string())
@@ -64,3 +120,82 @@ impl From<std::string::FromUtf8Error> for Error {
         Self::BadString(e.to_string())
     }
 }
+
+/// Category of a [`ChannelError`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ChannelErrorKind {
+    /// The underlying SCTP, DTLS or ICE transport failed, the channel is unusable.
+    Transport,
+    /// The remote peer sent something unexpected or malformed.
+    Protocol,
+    /// Anything else, typically a failure within libdatachannel.
+    Internal,
+}
+
+/// An error reported to [`DataChannelHandler::on_error`] or [`TrackHandler::on_error`].
+///
+/// libdatachannel only provides a message, the kind is inferred from it on a best-effort
+/// basis and defaults to [`ChannelErrorKind::Internal`].
+///
+/// [`DataChannelHandler::on_error`]: crate::DataChannelHandler::on_error
+/// [`TrackHandler::on_error`]: crate::TrackHandler::on_error
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{message}")]
+pub struct ChannelError {
+    kind: ChannelErrorKind,
+    message: String,
+}
+
+impl ChannelError {
+    pub fn new(kind: ChannelErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+        }
+    }
+
+    /// Categorizes a message reported by libdatachannel, or by the browser on `wasm32`.
+    ///
+    /// The message is matched against whole words, case insensitively.
+    pub fn from_message(message: &str) -> Self {
+        let words = message
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(str::to_ascii_lowercase)
+            .collect::<Vec<_>>();
+        let matches = |phrases: &[&str]| {
+            phrases.iter().any(|phrase| {
+                let phrase = phrase.split(' ').collect::<Vec<_>>();
+                words.windows(phrase.len()).any(|window| window == phrase)
+            })
+        };
+        let kind = if matches(&[
+            "transport",
+            "dtls",
+            "sctp",
+            "ice",
+            "tls",
+            "tcp",
+            "timeout",
+            "timed out",
+            "connection failed",
+            "connection closed",
+        ]) {
+            ChannelErrorKind::Transport
+        } else if matches(&["invalid", "malformed", "unexpected", "unknown", "too large"]) {
+            ChannelErrorKind::Protocol
+        } else {
+            ChannelErrorKind::Internal
+        };
+        Self::new(kind, message)
+    }
+
+    pub fn kind(&self) -> ChannelErrorKind {
+        self.kind
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
@@ -0,0 +1,289 @@
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use parking_lot::{Condvar, Mutex};
+
+use crate::datachannel::{
+    DataChannelHandler, DataChannelId, DataChannelInfo, DataChannelInit, RtcDataChannel,
+};
+use crate::error::{ChannelError, Error};
+use crate::logger;
+use crate::peerconnection::{PeerConnectionHandler, RtcPeerConnection};
+
+/// Label of the data channels carrying the connections of a [`TcpForwarder`].
+pub const FORWARD_LABEL: &str = "tcp-forward";
+
+const CHUNK_SIZE: usize = 16 * 1024;
+/// Reading from TCP pauses while the buffered amount is above this
+const HIGH_WATER_MARK: usize = 1024 * 1024;
+const LOW_WATER_MARK: usize = HIGH_WATER_MARK / 4;
+const OPEN_TIMEOUT: Duration = Duration::from_secs(30);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Given to [`RtcDataChannel::shutdown`] once a connection ends
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, thiserror::Error)]
+pub enum ForwardError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Channel(#[from] Error),
+}
+
+/// Where the messages received are written, set once the TCP connection is established.
+#[derive(Default)]
+struct Writer {
+    tcp: Option<TcpStream>,
+    /// Received before the connection to the target is established
+    pending: Vec<u8>,
+}
+
+#[derive(Default)]
+struct Status {
+    open: bool,
+    closed: bool,
+}
+
+#[derive(Default)]
+struct Link {
+    writer: Mutex<Writer>,
+    status: Mutex<Status>,
+    cond: Condvar,
+}
+
+impl Link {
+    fn update(&self, f: impl FnOnce(&mut Status)) {
+        f(&mut self.status.lock());
+        self.cond.notify_all();
+    }
+
+    /// Waits for the channel to be open, returns `false` if it closed or never opened.
+    fn wait_open(&self) -> bool {
+        let deadline = Instant::now() + OPEN_TIMEOUT;
+        let mut status = self.status.lock();
+        while !status.open {
+            if status.closed || self.cond.wait_until(&mut status, deadline).timed_out() {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Waits for the buffered amount to get low, returns `false` if the channel closed.
+    fn wait_writable<D>(&self, dc: &RtcDataChannel<D>) -> bool
+    where
+        D: DataChannelHandler + Send,
+    {
+        let mut status = self.status.lock();
+        while dc.buffered_amount() >= HIGH_WATER_MARK {
+            if status.closed {
+                return false;
+            }
+            self.cond.wait_for(&mut status, Duration::from_millis(100));
+        }
+        !status.closed
+    }
+
+    /// Starts writing the messages received to `tcp`, those received so far first.
+    fn connect(&self, tcp: &TcpStream) -> io::Result<()> {
+        let mut writer = self.writer.lock();
+        let mut tcp = tcp.try_clone()?;
+        tcp.write_all(&std::mem::take(&mut writer.pending))?;
+        writer.tcp = Some(tcp);
+        Ok(())
+    }
+}
+
+/// Handler of a data channel carrying a TCP connection, see [`TcpForwarder`] and
+/// [`TcpEndpoint`].
+///
+/// Messages are written to the connection from the callback, a slow TCP peer thus holds
+/// the remote peer back.
+pub struct ForwardChannel {
+    /// `None` for a channel which doesn't carry a connection
+    link: Option<Arc<Link>>,
+}
+
+impl DataChannelHandler for ForwardChannel {
+    fn on_open(&mut self) {
+        if let Some(link) = &self.link {
+            link.update(|status| status.open = true);
+        }
+    }
+
+    fn on_closed(&mut self) {
+        let Some(link) = &self.link else {
+            return;
+        };
+        link.update(|status| status.closed = true);
+        if let Some(tcp) = &link.writer.lock().tcp {
+            tcp.shutdown(Shutdown::Both).ok();
+        }
+    }
+
+    fn on_error(&mut self, err: &ChannelError) {
+        if self.link.is_some() {
+            logger::warn!("Forwarded TCP connection failed: {}", err);
+        }
+    }
+
+    fn on_message(&mut self, msg: &[u8]) {
+        let Some(link) = &self.link else {
+            return;
+        };
+        let mut writer = link.writer.lock();
+        let Some(tcp) = &mut writer.tcp else {
+            writer.pending.extend_from_slice(msg);
+            return;
+        };
+        // The reading side then fails too, and closes the channel
+        if tcp.write_all(msg).is_err() {
+            tcp.shutdown(Shutdown::Both).ok();
+        }
+    }
+
+    fn on_buffered_amount_low(&mut self) {
+        if let Some(link) = &self.link {
+            link.update(|_| ());
+        }
+    }
+}
+
+/// Sends what is read from `tcp` over `dc` until either is closed, then closes both.
+fn pump(mut dc: Box<RtcDataChannel<ForwardChannel>>, mut tcp: TcpStream, link: Arc<Link>) {
+    if !link.wait_open() {
+        tcp.shutdown(Shutdown::Both).ok();
+        return;
+    }
+    if let Err(err) = dc.set_buffered_amount_low_threshold(LOW_WATER_MARK) {
+        logger::warn!("Couldn't set buffered amount low threshold: {}", err);
+    }
+
+    let mut buf = vec![0; CHUNK_SIZE];
+    loop {
+        let len = match tcp.read(&mut buf) {
+            Ok(0) | Err(_) => break,
+            Ok(len) => len,
+        };
+        if !link.wait_writable(&dc) || dc.send(&buf[..len]).is_err() {
+            break;
+        }
+    }
+
+    // Lets what the remote peer sent meanwhile reach the connection before closing it
+    if !link.status.lock().closed {
+        if let Err(err) = dc.shutdown(SHUTDOWN_TIMEOUT) {
+            logger::debug!("Forwarded TCP connection not closed cleanly: {}", err);
+        }
+    }
+    tcp.shutdown(Shutdown::Both).ok();
+}
+
+/// Forwards the TCP connections accepted on a local address to a [`TcpEndpoint`] of the
+/// remote peer, each one over its own reliable data channel labeled [`FORWARD_LABEL`] and
+/// shut down gracefully.
+///
+/// Typically run in a loop on a dedicated thread calling [`accept`](Self::accept). A
+/// connection is closed as a whole once either side closes it, half-closed connections
+/// aren't supported.
+pub struct TcpForwarder {
+    listener: TcpListener,
+}
+
+impl TcpForwarder {
+    pub fn bind(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        Ok(Self {
+            listener: TcpListener::bind(addr)?,
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Waits for a TCP connection and forwards it over a new data channel of `pc`, from a
+    /// thread of its own.
+    pub fn accept<P>(&self, pc: &mut RtcPeerConnection<P>) -> Result<SocketAddr, ForwardError>
+    where
+        P: PeerConnectionHandler + Send,
+        P::DCH: DataChannelHandler + Send,
+    {
+        let (tcp, addr) = self.listener.accept()?;
+        tcp.set_nodelay(true)?;
+        let link = Arc::new(Link::default());
+        link.connect(&tcp)?;
+        let handler = ForwardChannel {
+            link: Some(Arc::clone(&link)),
+        };
+        let init = DataChannelInit::default().graceful_shutdown();
+        let dc = pc.create_data_channel_ex(FORWARD_LABEL, handler, &init)?;
+        thread::spawn(move || pump(dc, tcp, link));
+        Ok(addr)
+    }
+}
+
+/// Connects the data channels opened by a remote [`TcpForwarder`] to a TCP endpoint, to be
+/// called from the [`PeerConnectionHandler`] of the connection.
+#[derive(Clone)]
+pub struct TcpEndpoint {
+    target: SocketAddr,
+    /// Links of the channels handed a handler, until accepted
+    links: Arc<Mutex<HashMap<DataChannelId, Arc<Link>>>>,
+}
+
+impl TcpEndpoint {
+    pub fn new(target: SocketAddr) -> Self {
+        Self {
+            target,
+            links: Arc::default(),
+        }
+    }
+
+    pub fn target(&self) -> SocketAddr {
+        self.target
+    }
+
+    /// The handler of a data channel opened by the remote peer, to be returned from
+    /// [`PeerConnectionHandler::data_channel_handler`].
+    ///
+    /// Channels not labeled [`FORWARD_LABEL`] get a handler ignoring them.
+    pub fn channel_handler(&self, info: &DataChannelInfo) -> ForwardChannel {
+        if info.label != FORWARD_LABEL {
+            return ForwardChannel { link: None };
+        }
+        let link = Arc::new(Link::default());
+        self.links.lock().insert(info.id, Arc::clone(&link));
+        ForwardChannel { link: Some(link) }
+    }
+
+    /// Connects a data channel opened by the remote peer to the target, from a thread of
+    /// its own, to be called from [`PeerConnectionHandler::on_data_channel`].
+    ///
+    /// Returns the channels not labeled [`FORWARD_LABEL`]. A channel whose connection fails
+    /// is closed.
+    pub fn accept(
+        &self,
+        dc: Box<RtcDataChannel<ForwardChannel>>,
+    ) -> Option<Box<RtcDataChannel<ForwardChannel>>> {
+        let Some(link) = self.links.lock().remove(&dc.id()) else {
+            return Some(dc);
+        };
+        let target = self.target;
+        thread::spawn(move || {
+            let tcp = TcpStream::connect_timeout(&target, CONNECT_TIMEOUT).and_then(|tcp| {
+                tcp.set_nodelay(true)?;
+                link.connect(&tcp)?;
+                Ok(tcp)
+            });
+            match tcp {
+                Ok(tcp) => pump(dc, tcp, link),
+                Err(err) => logger::warn!("Couldn't connect to {}: {}", target, err),
+            }
+        });
+        None
+    }
+}
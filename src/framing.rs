@@ -0,0 +1,252 @@
+use crate::datachannel::{DataChannelHandler, RtcDataChannel};
+use crate::error::{ChannelError, ChannelErrorKind, Result};
+
+/// Messages of [`FrameWriter::new`], fits in the default maximum message size.
+const DEFAULT_MESSAGE_SIZE: usize = 64 * 1024;
+
+/// Largest frame accepted by [`Deframe::new`], a length beyond is most likely garbage.
+const DEFAULT_MAX_FRAME_SIZE: usize = 16 << 20;
+
+/// How the length of each frame is prefixed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Framing {
+    /// 4 bytes big endian like [`Batching`](crate::Batching), frames up to 4 GiB
+    #[default]
+    LengthPrefix,
+    /// Unsigned LEB128, 1 byte for frames shorter than 128 bytes
+    Varint,
+}
+
+impl Framing {
+    fn encode_len(self, len: usize, buf: &mut [u8; 10]) -> &[u8] {
+        match self {
+            Self::LengthPrefix => {
+                buf[..4].copy_from_slice(&(len as u32).to_be_bytes());
+                &buf[..4]
+            }
+            Self::Varint => {
+                let mut len = len as u64;
+                let mut i = 0;
+                loop {
+                    let byte = (len & 0x7f) as u8;
+                    len >>= 7;
+                    if len == 0 {
+                        buf[i] = byte;
+                        return &buf[..=i];
+                    }
+                    buf[i] = byte | 0x80;
+                    i += 1;
+                }
+            }
+        }
+    }
+
+    /// Returns the length of the frame and the size of its prefix, `None` when `buf` doesn't
+    /// hold the whole prefix yet.
+    fn decode_len(self, buf: &[u8]) -> Option<std::result::Result<(usize, usize), ()>> {
+        match self {
+            Self::LengthPrefix => {
+                let len = buf.first_chunk::<4>()?;
+                Some(Ok((u32::from_be_bytes(*len) as usize, 4)))
+            }
+            Self::Varint => {
+                let mut len = 0u64;
+                for (i, &byte) in buf.iter().enumerate() {
+                    if i == 10 {
+                        return Some(Err(()));
+                    }
+                    len |= u64::from(byte & 0x7f) << (7 * i);
+                    if byte & 0x80 == 0 {
+                        return Some(usize::try_from(len).map(|len| (len, i + 1)).map_err(|_| ()));
+                    }
+                }
+                None
+            }
+        }
+    }
+}
+
+/// Sends frames of any size through a data channel, each one prefixed with its length and
+/// split over as many messages as needed.
+///
+/// The receiving side reassembles them with [`Deframe`]. Frames are only delimited by their
+/// prefix, so a failed send leaves the stream unusable and the channel should be closed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameWriter {
+    framing: Framing,
+    max_message_size: usize,
+}
+
+impl FrameWriter {
+    /// Writes messages of at most 64 KiB.
+    pub fn new(framing: Framing) -> Self {
+        Self {
+            framing,
+            max_message_size: DEFAULT_MESSAGE_SIZE,
+        }
+    }
+
+    /// Size of the messages sent, it must not exceed the maximum message size negotiated
+    /// with the remote peer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_message_size` can't hold a length prefix.
+    pub fn max_message_size(mut self, max_message_size: usize) -> Self {
+        assert!(max_message_size >= 10, "max_message_size must be at least 10");
+        self.max_message_size = max_message_size;
+        self
+    }
+
+    /// Splits `frame` into the messages to send, the first one starting with its length.
+    pub fn encode(&self, frame: &[u8]) -> Vec<Vec<u8>> {
+        let mut buf = [0; 10];
+        let prefix = self.framing.encode_len(frame.len(), &mut buf);
+        let (head, tail) = frame.split_at(frame.len().min(self.max_message_size - prefix.len()));
+
+        let mut first = Vec::with_capacity(prefix.len() + head.len());
+        first.extend_from_slice(prefix);
+        first.extend_from_slice(head);
+        let mut messages = vec![first];
+        messages.extend(tail.chunks(self.max_message_size).map(<[u8]>::to_vec));
+        messages
+    }
+
+    /// Sends `frame`, the messages already sent stay so if one of them fails.
+    pub fn send<D>(&self, dc: &mut RtcDataChannel<D>, frame: &[u8]) -> Result<()>
+    where
+        D: DataChannelHandler + Send,
+    {
+        self.encode(frame).iter().try_for_each(|msg| dc.send(msg))
+    }
+}
+
+/// A [`DataChannelHandler`] reassembling the frames sent with a [`FrameWriter`].
+///
+/// Each complete frame is handed to the inner handler. A malformed or oversized length is
+/// reported through [`on_error`](DataChannelHandler::on_error), the data received afterwards
+/// is then dropped as the frame boundaries are lost.
+pub struct Deframe<H> {
+    handler: H,
+    framing: Framing,
+    max_frame_size: usize,
+    buf: Vec<u8>,
+    broken: bool,
+}
+
+impl<H> Deframe<H> {
+    /// Accepts frames of at most 16 MiB.
+    pub fn new(handler: H, framing: Framing) -> Self {
+        Self {
+            handler,
+            framing,
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+            buf: Vec::new(),
+            broken: false,
+        }
+    }
+
+    pub fn max_frame_size(mut self, max_frame_size: usize) -> Self {
+        self.max_frame_size = max_frame_size;
+        self
+    }
+
+    pub fn handler(&self) -> &H {
+        &self.handler
+    }
+
+    pub fn handler_mut(&mut self) -> &mut H {
+        &mut self.handler
+    }
+
+    pub fn into_inner(self) -> H {
+        self.handler
+    }
+
+    /// Size of the incomplete frame buffered so far, prefix included.
+    pub fn pending(&self) -> usize {
+        self.buf.len()
+    }
+}
+
+impl<H> Deframe<H>
+where
+    H: DataChannelHandler,
+{
+    /// Delivers the complete frames of `data` and returns how many bytes were consumed.
+    fn deliver(&mut self, data: &[u8]) -> usize {
+        let mut consumed = 0;
+        while let Some(decoded) = self.framing.decode_len(&data[consumed..]) {
+            let (len, prefix) = match decoded {
+                Ok((len, _)) if len > self.max_frame_size => return self.fail("Frame too large"),
+                Ok(decoded) => decoded,
+                Err(()) => return self.fail("Malformed frame length"),
+            };
+            match data.get(consumed + prefix..consumed + prefix + len) {
+                Some(frame) => self.handler.on_message(frame),
+                None => break,
+            }
+            consumed += prefix + len;
+        }
+        consumed
+    }
+
+    fn fail(&mut self, reason: &str) -> usize {
+        self.broken = true;
+        self.buf = Vec::new();
+        self.handler
+            .on_error(&ChannelError::new(ChannelErrorKind::Protocol, reason));
+        0
+    }
+}
+
+impl<H> DataChannelHandler for Deframe<H>
+where
+    H: DataChannelHandler,
+{
+    fn on_open(&mut self) {
+        self.handler.on_open()
+    }
+
+    fn on_closed(&mut self) {
+        self.handler.on_closed()
+    }
+
+    fn on_error(&mut self, err: &ChannelError) {
+        self.handler.on_error(err)
+    }
+
+    fn on_message(&mut self, msg: &[u8]) {
+        if self.broken {
+            return;
+        }
+        // Frames held by a single message are delivered without being copied
+        if self.buf.is_empty() {
+            let consumed = self.deliver(msg);
+            if !self.broken {
+                self.buf.extend_from_slice(&msg[consumed..]);
+            }
+            return;
+        }
+
+        self.buf.extend_from_slice(msg);
+        let buf = std::mem::take(&mut self.buf);
+        let consumed = self.deliver(&buf);
+        if !self.broken {
+            self.buf = buf;
+            self.buf.drain(..consumed);
+        }
+    }
+
+    fn on_buffered_amount_low(&mut self) {
+        self.handler.on_buffered_amount_low()
+    }
+
+    fn on_available(&mut self) {
+        self.handler.on_available()
+    }
+
+    fn on_keepalive_timeout(&mut self) {
+        self.handler.on_keepalive_timeout()
+    }
+}
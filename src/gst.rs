@@ -0,0 +1,94 @@
+use std::sync::Arc;
+
+use gstreamer as gst;
+use gstreamer_app::{AppSink, AppSinkCallbacks, AppSrc};
+use parking_lot::Mutex;
+
+use crate::logger;
+use crate::track::{is_rtcp, RtcTrack, TrackHandler};
+
+/// Forwards the samples of an `appsink` to a track.
+///
+/// The track is expected to have a packetizer set (see [`RtcTrack::set_packetizer`]), each
+/// sample is sent as a media frame with an RTP timestamp derived from the buffer PTS.
+pub fn link_appsink<T>(appsink: &AppSink, track: Arc<Mutex<Box<RtcTrack<T>>>>)
+where
+    T: TrackHandler + Send + 'static,
+{
+    let mut base = None;
+    appsink.set_callbacks(
+        AppSinkCallbacks::builder()
+            .new_sample(move |appsink| {
+                let sample = appsink.pull_sample().map_err(|_| gst::FlowError::Eos)?;
+                let buffer = sample.buffer().ok_or(gst::FlowError::Error)?;
+                let map = buffer.map_readable().map_err(|_| gst::FlowError::Error)?;
+
+                let mut track = track.lock();
+                if let Some(pts) = buffer.pts() {
+                    let (base_pts, base_ts) = match base {
+                        Some(base) => base,
+                        None => {
+                            let base_ts = track.current_timestamp().map_err(|err| {
+                                logger::error!("Couldn't get track timestamp: {}", err);
+                                gst::FlowError::Error
+                            })?;
+                            *base.insert((pts, base_ts))
+                        }
+                    };
+                    let elapsed = pts.saturating_sub(base_pts).nseconds() as f64 / 1e9;
+                    let timestamp = track
+                        .seconds_to_timestamp(elapsed)
+                        .and_then(|offset| track.set_rtp_timestamp(base_ts.wrapping_add(offset)));
+                    if let Err(err) = timestamp {
+                        logger::error!("Couldn't set track timestamp: {}", err);
+                        return Err(gst::FlowError::Error);
+                    }
+                }
+
+                match track.send(map.as_slice()) {
+                    Ok(()) => Ok(gst::FlowSuccess::Ok),
+                    Err(err) => {
+                        logger::warn!("Couldn't send sample to track: {}", err);
+                        Err(gst::FlowError::Error)
+                    }
+                }
+            })
+            .build(),
+    );
+}
+
+/// A [`TrackHandler`] pushing the received RTP packets into an `appsrc`.
+///
+/// RTCP packets are skipped and the stream ends when the track is closed, the `appsrc` caps
+/// should be set to the matching `application/x-rtp` media.
+pub struct AppSrcHandler {
+    appsrc: AppSrc,
+}
+
+impl AppSrcHandler {
+    pub fn new(appsrc: AppSrc) -> Self {
+        Self { appsrc }
+    }
+
+    pub fn appsrc(&self) -> &AppSrc {
+        &self.appsrc
+    }
+}
+
+impl TrackHandler for AppSrcHandler {
+    fn on_closed(&mut self) {
+        self.appsrc.end_of_stream().ok();
+    }
+
+    fn on_message(&mut self, msg: &[u8]) {
+        if msg.len() < 2 || is_rtcp(msg) {
+            return;
+        }
+        if let Err(err) = self
+            .appsrc
+            .push_buffer(gst::Buffer::from_slice(msg.to_vec()))
+        {
+            logger::warn!("Couldn't push RTP packet to appsrc: {:?}", err);
+        }
+    }
+}
@@ -0,0 +1,75 @@
+//! Certificate fingerprints shared between the peer connections of the process, see
+//! [`RtcConfig::insecure_local_fingerprints`](crate::RtcConfig::insecure_local_fingerprints).
+
+use std::collections::BTreeMap;
+
+use parking_lot::Mutex;
+use webrtc_sdp::attribute_type::SdpAttribute;
+
+use crate::SessionDescription;
+
+/// Fingerprint attributes of the local descriptions, by ICE username fragment
+static FINGERPRINTS: Mutex<BTreeMap<String, Vec<SdpAttribute>>> = Mutex::new(BTreeMap::new());
+
+/// The ICE username fragments a peer connection registered its fingerprints under, until
+/// dropped.
+#[derive(Default)]
+pub(crate) struct LocalFingerprints {
+    ufrags: Mutex<Vec<String>>,
+}
+
+impl LocalFingerprints {
+    /// Registers the fingerprints of a local description.
+    pub(crate) fn register(&self, sess_desc: &SessionDescription) {
+        let Some(ufrag) = sess_desc.ice_ufrag() else {
+            return;
+        };
+        let fingerprints = fingerprints(sess_desc).cloned().collect::<Vec<_>>();
+        if fingerprints.is_empty() {
+            return;
+        }
+        FINGERPRINTS.lock().insert(ufrag.to_string(), fingerprints);
+        let mut ufrags = self.ufrags.lock();
+        if !ufrags.iter().any(|registered| registered == ufrag) {
+            ufrags.push(ufrag.to_string());
+        }
+    }
+
+    /// Returns a copy of a remote description without fingerprint, along with the ones
+    /// registered by the local peer connection it comes from. `None` when it has some already
+    /// or comes from another process.
+    pub(crate) fn fill(sess_desc: &SessionDescription) -> Option<SessionDescription> {
+        if fingerprints(sess_desc).next().is_some() {
+            return None;
+        }
+        let registered = FINGERPRINTS.lock().get(sess_desc.ice_ufrag()?)?.clone();
+        let mut sdp = sess_desc.sdp.clone();
+        for media in &mut sdp.media {
+            for fingerprint in &registered {
+                media.add_attribute(fingerprint.clone()).ok();
+            }
+        }
+        Some(SessionDescription {
+            sdp,
+            sdp_type: sess_desc.sdp_type.clone(),
+        })
+    }
+}
+
+impl Drop for LocalFingerprints {
+    fn drop(&mut self) {
+        let mut fingerprints = FINGERPRINTS.lock();
+        for ufrag in self.ufrags.get_mut().iter() {
+            fingerprints.remove(ufrag);
+        }
+    }
+}
+
+/// Fingerprint attributes of the session level and of every media section.
+fn fingerprints(sess_desc: &SessionDescription) -> impl Iterator<Item = &SdpAttribute> {
+    let sdp = &sess_desc.sdp;
+    sdp.attribute
+        .iter()
+        .chain(sdp.media.iter().flat_map(|media| media.get_attributes()))
+        .filter(|attr| matches!(attr, SdpAttribute::Fingerprint(_)))
+}
@@ -0,0 +1,74 @@
+use webrtc::ice_transport::ice_candidate::RTCIceCandidateInit;
+use webrtc::peer_connection::sdp::sdp_type::RTCSdpType;
+use webrtc::peer_connection::sdp::session_description::RTCSessionDescription;
+use webrtc_sdp::parse_sdp;
+
+use crate::error::{Error, Result};
+use crate::signaling::{IceCandidate, SdpType, SessionDescription};
+
+impl From<SdpType> for RTCSdpType {
+    fn from(sdp_type: SdpType) -> Self {
+        match sdp_type {
+            SdpType::Answer => Self::Answer,
+            SdpType::Offer => Self::Offer,
+            SdpType::Pranswer => Self::Pranswer,
+            SdpType::Rollback => Self::Rollback,
+        }
+    }
+}
+
+impl TryFrom<RTCSdpType> for SdpType {
+    type Error = Error;
+
+    fn try_from(sdp_type: RTCSdpType) -> Result<Self> {
+        match sdp_type {
+            RTCSdpType::Answer => Ok(Self::Answer),
+            RTCSdpType::Offer => Ok(Self::Offer),
+            RTCSdpType::Pranswer => Ok(Self::Pranswer),
+            RTCSdpType::Rollback => Ok(Self::Rollback),
+            RTCSdpType::Unspecified => Err(Error::InvalidArg),
+        }
+    }
+}
+
+impl From<SessionDescription> for RTCSessionDescription {
+    fn from(sess_desc: SessionDescription) -> Self {
+        let mut desc = RTCSessionDescription::default();
+        desc.sdp_type = sess_desc.sdp_type.into();
+        desc.sdp = sess_desc.sdp.to_string();
+        desc
+    }
+}
+
+impl TryFrom<RTCSessionDescription> for SessionDescription {
+    type Error = Error;
+
+    fn try_from(desc: RTCSessionDescription) -> Result<Self> {
+        Ok(SessionDescription {
+            sdp: parse_sdp(&desc.sdp, false).map_err(|err| Error::BadString(err.to_string()))?,
+            sdp_type: desc.sdp_type.try_into()?,
+        })
+    }
+}
+
+impl From<IceCandidate> for RTCIceCandidateInit {
+    fn from(cand: IceCandidate) -> Self {
+        RTCIceCandidateInit {
+            candidate: cand.candidate,
+            sdp_mid: Some(cand.mid),
+            ..Default::default()
+        }
+    }
+}
+
+/// Fails with [`Error::InvalidArg`] when the candidate has no `sdp_mid`.
+impl TryFrom<RTCIceCandidateInit> for IceCandidate {
+    type Error = Error;
+
+    fn try_from(cand: RTCIceCandidateInit) -> Result<Self> {
+        Ok(IceCandidate {
+            candidate: cand.candidate,
+            mid: cand.sdp_mid.ok_or(Error::InvalidArg)?,
+        })
+    }
+}
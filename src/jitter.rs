@@ -0,0 +1,162 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use parking_lot::{Condvar, Mutex, MutexGuard};
+
+use crate::error::ChannelError;
+use crate::track::{is_rtcp, TrackHandler};
+
+struct Pending {
+    packets: BTreeMap<u64, (Instant, Vec<u8>)>,
+    /// Extended sequence number of the highest packet received
+    highest: Option<u64>,
+    /// Extended sequence number of the last packet emitted
+    emitted: Option<u64>,
+    closed: bool,
+}
+
+impl Pending {
+    fn extend(&self, seq: u16) -> u64 {
+        match self.highest {
+            None => seq as u64 + (1 << 16),
+            Some(highest) => {
+                let delta = seq.wrapping_sub(highest as u16) as i16;
+                highest.wrapping_add_signed(delta as i64)
+            }
+        }
+    }
+}
+
+struct Shared<H> {
+    pending: Mutex<Pending>,
+    wakeup: Condvar,
+    handler: Mutex<H>,
+}
+
+/// A [`TrackHandler`] reordering received RTP packets before handing them to an inner handler.
+///
+/// Each packet is held for `target_delay` after its arrival, then packets are emitted in
+/// sequence number order from a dedicated thread. Packets arriving after a later packet has
+/// already been emitted are dropped, RTCP packets and other callbacks are forwarded right away.
+pub struct JitterBuffer<H> {
+    shared: Arc<Shared<H>>,
+    target_delay: Duration,
+}
+
+impl<H> JitterBuffer<H>
+where
+    H: TrackHandler + Send + 'static,
+{
+    pub fn new(handler: H, target_delay: Duration) -> Self {
+        let shared = Arc::new(Shared {
+            pending: Mutex::new(Pending {
+                packets: BTreeMap::new(),
+                highest: None,
+                emitted: None,
+                closed: false,
+            }),
+            wakeup: Condvar::new(),
+            handler: Mutex::new(handler),
+        });
+
+        let worker = Arc::clone(&shared);
+        thread::spawn(move || Self::run(worker, target_delay));
+
+        Self {
+            shared,
+            target_delay,
+        }
+    }
+
+    fn run(shared: Arc<Shared<H>>, target_delay: Duration) {
+        let mut pending = shared.pending.lock();
+        loop {
+            let next = pending
+                .packets
+                .first_key_value()
+                .map(|(_, (arrival, _))| *arrival + target_delay);
+            match next {
+                None if pending.closed => return,
+                None => {
+                    shared.wakeup.wait(&mut pending);
+                }
+                Some(deadline) if deadline > Instant::now() && !pending.closed => {
+                    shared.wakeup.wait_until(&mut pending, deadline);
+                }
+                Some(_) => {
+                    let (seq, (_, packet)) = pending.packets.pop_first().unwrap();
+                    pending.emitted = Some(seq);
+                    MutexGuard::unlocked(&mut pending, || {
+                        shared.handler.lock().on_message(&packet)
+                    });
+                }
+            }
+        }
+    }
+
+    /// Locks the inner handler.
+    pub fn handler(&self) -> MutexGuard<'_, H> {
+        self.shared.handler.lock()
+    }
+
+    pub fn target_delay(&self) -> Duration {
+        self.target_delay
+    }
+}
+
+impl<H> TrackHandler for JitterBuffer<H>
+where
+    H: TrackHandler + Send + 'static,
+{
+    fn on_open(&mut self) {
+        self.shared.handler.lock().on_open()
+    }
+
+    fn on_closed(&mut self) {
+        self.shared.pending.lock().closed = true;
+        self.shared.wakeup.notify_one();
+        self.shared.handler.lock().on_closed()
+    }
+
+    fn on_error(&mut self, err: &ChannelError) {
+        self.shared.handler.lock().on_error(err)
+    }
+
+    fn on_message(&mut self, msg: &[u8]) {
+        if msg.len() < 12 || is_rtcp(msg) {
+            self.shared.handler.lock().on_message(msg);
+            return;
+        }
+
+        let mut pending = self.shared.pending.lock();
+        let seq = pending.extend(u16::from_be_bytes([msg[2], msg[3]]));
+        if pending.emitted.is_some_and(|emitted| seq <= emitted) {
+            // Too late, a later packet was already emitted
+            return;
+        }
+        pending.highest = pending.highest.max(Some(seq));
+        pending
+            .packets
+            .entry(seq)
+            .or_insert_with(|| (Instant::now(), msg.to_vec()));
+        drop(pending);
+        self.shared.wakeup.notify_one();
+    }
+
+    fn on_available(&mut self) {
+        self.shared.handler.lock().on_available()
+    }
+
+    fn on_bitrate(&mut self, bitrate: u32) {
+        self.shared.handler.lock().on_bitrate(bitrate)
+    }
+}
+
+impl<H> Drop for JitterBuffer<H> {
+    fn drop(&mut self) {
+        self.shared.pending.lock().closed = true;
+        self.shared.wakeup.notify_one();
+    }
+}
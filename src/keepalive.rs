@@ -0,0 +1,199 @@
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use datachannel_sys as sys;
+use parking_lot::{Condvar, Mutex};
+
+use crate::datachannel::{send_message, DataChannelId, DataChannelInit, Reliability};
+
+/// Prefixes of the messages exchanged by keepalive, never handed to the handler. They are
+/// followed by the time the ping was sent, on 8 bytes big endian, which the pong echoes.
+const PING: &[u8] = b"\0datachannel-keepalive:ping";
+const PONG: &[u8] = b"\0datachannel-keepalive:pong";
+
+/// Heartbeat of a data channel, see [`RtcDataChannel::set_keepalive`].
+///
+/// A ping is sent every `interval` and answered right away by the remote peer, which must
+/// enable keepalive as well. The peer is declared dead through
+/// [`on_keepalive_timeout`](crate::DataChannelHandler::on_keepalive_timeout) when nothing,
+/// neither a pong nor any other message, was received for `timeout`.
+///
+/// The pongs also measure the round trip time of the channel, see
+/// [`RtcDataChannel::round_trip_time`]. As pings are queued behind the messages sent before
+/// them, this is the delay actually experienced by the application. A dedicated channel
+/// created with [`channel_init`](Self::channel_init) measures it regardless of the traffic.
+///
+/// [`RtcDataChannel::set_keepalive`]: crate::RtcDataChannel::set_keepalive
+/// [`RtcDataChannel::round_trip_time`]: crate::RtcDataChannel::round_trip_time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Keepalive {
+    pub interval: Duration,
+    pub timeout: Duration,
+}
+
+impl Keepalive {
+    /// # Panics
+    ///
+    /// Panics if `interval` is zero.
+    pub fn new(interval: Duration, timeout: Duration) -> Self {
+        assert!(!interval.is_zero(), "Keepalive interval must not be zero");
+        Self { interval, timeout }
+    }
+
+    /// The initialization of a channel negotiated on `stream` by both peers, dedicated to
+    /// keepalive. Its pings are unordered and never retransmitted, a lost one only misses a
+    /// sample.
+    pub fn channel_init(stream: u16) -> DataChannelInit {
+        DataChannelInit::default()
+            .negotiated()
+            .manual_stream()
+            .stream(stream)
+            .reliability(
+                Reliability::default()
+                    .unordered()
+                    .unreliable()
+                    .max_retransmits(0),
+            )
+    }
+}
+
+/// Round trip time of a data channel measured by keepalive, smoothed as TCP does (RFC 6298).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoundTripTime {
+    /// The last sample
+    pub latest: Duration,
+    pub smoothed: Duration,
+    /// Mean deviation of the samples
+    pub variation: Duration,
+    pub samples: u64,
+}
+
+impl RoundTripTime {
+    fn sample(previous: Option<Self>, rtt: Duration) -> Self {
+        match previous {
+            None => Self {
+                latest: rtt,
+                smoothed: rtt,
+                variation: rtt / 2,
+                samples: 1,
+            },
+            Some(previous) => {
+                let deviation = previous.smoothed.abs_diff(rtt);
+                Self {
+                    latest: rtt,
+                    smoothed: (previous.smoothed * 7 + rtt) / 8,
+                    variation: (previous.variation * 3 + deviation) / 4,
+                    samples: previous.samples + 1,
+                }
+            }
+        }
+    }
+}
+
+struct Liveness {
+    last_seen: Instant,
+    /// Set once the timeout is reported, until something is received again
+    dead: bool,
+    stopped: bool,
+    rtt: Option<RoundTripTime>,
+}
+
+/// State shared between the message callback and the thread sending pings.
+pub(crate) struct Pulse {
+    keepalive: Keepalive,
+    /// Origin of the times carried by pings
+    epoch: Instant,
+    liveness: Mutex<Liveness>,
+    stop: Condvar,
+}
+
+impl Pulse {
+    /// Starts pinging `id`, `on_timeout` is called from the pinging thread.
+    pub(crate) fn start<F>(id: DataChannelId, keepalive: Keepalive, on_timeout: F) -> Arc<Self>
+    where
+        F: Fn() + Send + 'static,
+    {
+        let pulse = Arc::new(Self {
+            keepalive,
+            epoch: Instant::now(),
+            liveness: Mutex::new(Liveness {
+                last_seen: Instant::now(),
+                dead: false,
+                stopped: false,
+                rtt: None,
+            }),
+            stop: Condvar::new(),
+        });
+        let running = Arc::clone(&pulse);
+        thread::spawn(move || running.run(id, on_timeout));
+        pulse
+    }
+
+    pub(crate) fn keepalive(&self) -> Keepalive {
+        self.keepalive
+    }
+
+    pub(crate) fn round_trip_time(&self) -> Option<RoundTripTime> {
+        self.liveness.lock().rtt
+    }
+
+    fn run(&self, id: DataChannelId, on_timeout: impl Fn()) {
+        let mut liveness = self.liveness.lock();
+        loop {
+            self.stop.wait_for(&mut liveness, self.keepalive.interval);
+            if liveness.stopped {
+                return;
+            }
+            // Not open yet, or closed and reported through on_closed
+            if !unsafe { sys::rtcIsOpen(id.0) } {
+                liveness.last_seen = Instant::now();
+                continue;
+            }
+            if !liveness.dead && liveness.last_seen.elapsed() >= self.keepalive.timeout {
+                liveness.dead = true;
+                drop(liveness);
+                on_timeout();
+                liveness = self.liveness.lock();
+                continue;
+            }
+            // Failures show up as a timeout
+            let sent = self.epoch.elapsed().as_nanos() as u64;
+            send_message(id, &[PING, &sent.to_be_bytes()].concat()).ok();
+        }
+    }
+
+    /// Notes that `msg` was received, returns whether it is a keepalive message which must
+    /// not be handed to the handler.
+    pub(crate) fn received(&self, id: DataChannelId, msg: &[u8]) -> bool {
+        let mut liveness = self.liveness.lock();
+        liveness.last_seen = Instant::now();
+        liveness.dead = false;
+
+        if let Some(sent) = Self::parse(PING, msg) {
+            drop(liveness);
+            send_message(id, &[PONG, &sent.to_be_bytes()].concat()).ok();
+            true
+        } else if let Some(sent) = Self::parse(PONG, msg) {
+            let sent = self.epoch + Duration::from_nanos(sent);
+            // Times yet to come were sent by a previous pulse of the same channel
+            if let Some(rtt) = Instant::now().checked_duration_since(sent) {
+                liveness.rtt = Some(RoundTripTime::sample(liveness.rtt, rtt));
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns the time carried by `msg` if it is a keepalive message of type `prefix`.
+    fn parse(prefix: &[u8], msg: &[u8]) -> Option<u64> {
+        let time = msg.strip_prefix(prefix)?;
+        time.try_into().ok().map(u64::from_be_bytes)
+    }
+
+    pub(crate) fn stop(&self) {
+        self.liveness.lock().stopped = true;
+        self.stop.notify_all();
+    }
+}
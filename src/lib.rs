@@ -4,100 +4,351 @@ compile_error!("one of the features ['log', 'tracing'] must be enabled");
 #[cfg(all(feature = "log", feature = "tracing"))]
 compile_error!("only one of ['log', 'tracing'] can be enabled");
 
-use std::sync::Once;
+/// Declares items backed by libdatachannel, which isn't available on wasm32 where
+/// the `wasm` module provides the API on top of the browser's WebRTC instead.
+macro_rules! cfg_native {
+    ($($item:item)*) => {
+        $(
+            #[cfg(not(target_arch = "wasm32"))]
+            $item
+        )*
+    };
+}
 
-mod config;
-mod datachannel;
 mod error;
+#[cfg_attr(target_arch = "wasm32", allow(unused_imports))]
 mod logger;
-mod peerconnection;
-mod track;
-
-static INIT_LOGGING: Once = Once::new();
+mod signaling;
+#[cfg(target_arch = "wasm32")]
+mod wasm;
 
-mod sys {
+cfg_native! {
     use std::ffi::CStr;
-    use std::os::raw::c_char;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Once;
+
+    mod capture;
+    #[cfg(feature = "compact")]
+    mod compact;
+    #[cfg(feature = "compression")]
+    mod compression;
+    mod config;
+    mod datachannel;
+    mod dispatch;
+    mod forward;
+    mod framing;
+    #[cfg(feature = "gstreamer")]
+    mod gst;
+    #[cfg(feature = "insecure")]
+    mod insecure;
+    #[cfg(feature = "webrtc")]
+    mod interop;
+    mod jitter;
+    mod keepalive;
+    mod log_filter;
+    #[cfg(feature = "mdns")]
+    mod mdns;
+    mod mesh;
+    mod mux;
+    mod negotiation;
+    mod panic;
+    mod peerconnection;
+    mod queue;
+    mod runtime;
+    mod stats;
+    mod streams;
+    mod telemetry;
+    #[cfg(feature = "test-util")]
+    #[doc(hidden)]
+    pub mod test_util;
+    mod timeline;
+    #[cfg(feature = "transfer")]
+    mod transfer;
+    mod track;
+    mod typed;
+
+    static INIT_LOGGING: Once = Once::new();
+    static UTF8_LOSSY: AtomicBool = AtomicBool::new(false);
+
+    mod sys {
+        use std::ffi::CStr;
+        use std::os::raw::c_char;
+
+        use datachannel_sys as sys;
 
-    use datachannel_sys as sys;
+        use crate::logger;
+        use crate::LogLevel;
 
-    use crate::logger;
+        pub(crate) unsafe extern "C" fn log_callback(
+            level: sys::rtcLogLevel,
+            message: *const c_char,
+        ) {
+            crate::panic::catch("log_callback", || {
+                let message = CStr::from_ptr(message).to_string_lossy();
+                let level = match level {
+                    sys::rtcLogLevel_RTC_LOG_NONE => return,
+                    sys::rtcLogLevel_RTC_LOG_ERROR | sys::rtcLogLevel_RTC_LOG_FATAL => {
+                        crate::error::record_message(&message);
+                        LogLevel::Error
+                    }
+                    sys::rtcLogLevel_RTC_LOG_WARNING => {
+                        crate::error::record_message(&message);
+                        LogLevel::Warn
+                    }
+                    sys::rtcLogLevel_RTC_LOG_INFO => LogLevel::Info,
+                    sys::rtcLogLevel_RTC_LOG_DEBUG => LogLevel::Debug,
+                    // RTC_LOG_VERBOSE, or a level added by a newer libdatachannel
+                    _ => LogLevel::Trace,
+                };
+                if !crate::log_filter::enabled(level, &message) {
+                    return;
+                }
+                match level {
+                    LogLevel::Off => (),
+                    LogLevel::Error => logger::error!("{}", message),
+                    LogLevel::Warn => logger::warn!("{}", message),
+                    LogLevel::Info => logger::info!("{}", message),
+                    LogLevel::Debug => logger::debug!("{}", message),
+                    LogLevel::Trace => logger::trace!("{}", message),
+                }
+            })
+        }
+    }
+
+    #[cfg(feature = "log")]
+    fn ensure_logging() {
+        INIT_LOGGING.call_once(|| {
+            let level = match log::max_level() {
+                log::LevelFilter::Off => datachannel_sys::rtcLogLevel_RTC_LOG_NONE,
+                log::LevelFilter::Error => datachannel_sys::rtcLogLevel_RTC_LOG_ERROR,
+                log::LevelFilter::Warn => datachannel_sys::rtcLogLevel_RTC_LOG_WARNING,
+                log::LevelFilter::Info => datachannel_sys::rtcLogLevel_RTC_LOG_INFO,
+                log::LevelFilter::Debug => datachannel_sys::rtcLogLevel_RTC_LOG_DEBUG,
+                log::LevelFilter::Trace => datachannel_sys::rtcLogLevel_RTC_LOG_VERBOSE,
+            };
+            unsafe { datachannel_sys::rtcInitLogger(level, Some(sys::log_callback)) };
+        });
+    }
+
+    /// How strings coming from libdatachannel which aren't valid UTF-8 are handled.
+    ///
+    /// It applies to descriptions, candidates, labels, protocols, mids and addresses. Error and
+    /// log messages are always converted lossily.
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+    pub enum Utf8Policy {
+        /// Fails with [`Error::BadString`](crate::Error::BadString), callbacks receiving such
+        /// strings are skipped with a warning.
+        #[default]
+        Strict,
+        /// Replaces invalid sequences with U+FFFD.
+        Lossy,
+    }
+
+    /// Sets the [`Utf8Policy`] of the whole process, [`Utf8Policy::Strict`] by default.
+    pub fn set_utf8_policy(policy: Utf8Policy) {
+        UTF8_LOSSY.store(policy == Utf8Policy::Lossy, Ordering::Relaxed);
+    }
+
+    pub fn utf8_policy() -> Utf8Policy {
+        match UTF8_LOSSY.load(Ordering::Relaxed) {
+            true => Utf8Policy::Lossy,
+            false => Utf8Policy::Strict,
+        }
+    }
+
+    /// Converts a string from libdatachannel according to the [`Utf8Policy`].
+    fn ffi_str(ffi: &CStr) -> crate::error::Result<String> {
+        match utf8_policy() {
+            Utf8Policy::Strict => Ok(String::from_utf8(ffi.to_bytes().to_vec())?),
+            Utf8Policy::Lossy => Ok(ffi.to_string_lossy().into_owned()),
+        }
+    }
+
+    /// Converts the string written by libdatachannel at the start of `buf`.
+    ///
+    /// `len` is the size it reported, the string must be NUL-terminated within it.
+    fn ffi_string(buf: &[u8], len: usize) -> crate::error::Result<String> {
+        let buf = &buf[..len.min(buf.len())];
+        match buf.iter().position(|&b| b == 0) {
+            Some(nul) => ffi_str(CStr::from_bytes_with_nul(&buf[..=nul])?),
+            None => Err(crate::error::Error::BadString(
+                "missing NUL terminator".to_string(),
+            )),
+        }
+    }
 
-    pub(crate) unsafe extern "C" fn log_callback(level: sys::rtcLogLevel, message: *const c_char) {
-        let message = CStr::from_ptr(message).to_string_lossy();
-        match level {
-            sys::rtcLogLevel_RTC_LOG_NONE => (),
-            sys::rtcLogLevel_RTC_LOG_ERROR | sys::rtcLogLevel_RTC_LOG_FATAL => {
-                logger::error!("{}", message)
+    /// Size of the stack buffer tried first by [`read_ffi_string`], large enough for labels,
+    /// mids, addresses and candidates.
+    const FFI_STRING_STACK_SIZE: usize = 256;
+
+    /// Largest size accepted from libdatachannel for a string, way above any description.
+    const FFI_STRING_MAX_SIZE: usize = 16 << 20;
+
+    /// Reads a string from a libdatachannel getter taking a buffer and its size.
+    ///
+    /// A stack buffer is tried first so that short strings only need a single call, the size is
+    /// queried and a heap buffer allocated only when the getter reports [`Error::TooSmall`]. The
+    /// string may grow in between, so this is retried with a growing buffer until it fits.
+    ///
+    /// [`Error::TooSmall`]: crate::Error::TooSmall
+    fn read_ffi_string<F>(mut str_fn: F) -> crate::error::Result<String>
+    where
+        F: FnMut(*mut std::os::raw::c_char, i32) -> i32,
+    {
+        use crate::error::{check, Error};
+
+        let mut buf = [0u8; FFI_STRING_STACK_SIZE];
+        match check(str_fn(buf.as_mut_ptr() as *mut _, buf.len() as i32)) {
+            Ok(len) => return ffi_string(&buf, len as usize),
+            Err(Error::TooSmall) => (),
+            Err(err) => return Err(err),
+        }
+
+        let mut buf_size = buf.len();
+        loop {
+            buf_size = next_ffi_size(buf_size, str_fn(std::ptr::null_mut(), 0))?;
+            let mut buf = vec![0; buf_size];
+            match check(str_fn(buf.as_mut_ptr() as *mut _, buf_size as i32)) {
+                Ok(len) => return ffi_string(&buf, len as usize),
+                Err(Error::TooSmall) => continue,
+                Err(err) => return Err(err),
             }
-            sys::rtcLogLevel_RTC_LOG_WARNING => logger::warn!("{}", message),
-            sys::rtcLogLevel_RTC_LOG_INFO => logger::info!("{}", message),
-            sys::rtcLogLevel_RTC_LOG_DEBUG => logger::debug!("{}", message),
-            sys::rtcLogLevel_RTC_LOG_VERBOSE => logger::trace!("{}", message),
-            _ => unreachable!(),
         }
     }
-}
 
-#[cfg(feature = "log")]
-fn ensure_logging() {
-    INIT_LOGGING.call_once(|| {
-        let level = match log::max_level() {
-            log::LevelFilter::Off => datachannel_sys::rtcLogLevel_RTC_LOG_NONE,
-            log::LevelFilter::Error => datachannel_sys::rtcLogLevel_RTC_LOG_ERROR,
-            log::LevelFilter::Warn => datachannel_sys::rtcLogLevel_RTC_LOG_WARNING,
-            log::LevelFilter::Info => datachannel_sys::rtcLogLevel_RTC_LOG_INFO,
-            log::LevelFilter::Debug => datachannel_sys::rtcLogLevel_RTC_LOG_DEBUG,
-            log::LevelFilter::Trace => datachannel_sys::rtcLogLevel_RTC_LOG_VERBOSE,
-        };
-        unsafe { datachannel_sys::rtcInitLogger(level, Some(sys::log_callback)) };
-    });
-}
+    /// Returns the buffer size to retry with after a buffer of `previous` bytes was too small,
+    /// `code` being the size returned by the getter called without buffer.
+    ///
+    /// The buffer at least doubles so that a string growing between calls is eventually read,
+    /// up to [`FFI_STRING_MAX_SIZE`].
+    fn next_ffi_size(previous: usize, code: i32) -> crate::error::Result<usize> {
+        use crate::error::{check, Error};
 
-fn ffi_string(ffi: &[u8]) -> crate::error::Result<String> {
-    use std::ffi::CStr;
-    let bytes = CStr::to_bytes(CStr::from_bytes_with_nul(ffi)?);
-    Ok(String::from_utf8(bytes.to_vec())?)
-}
+        let size = check(code)? as usize;
+        if size == 0 || size > FFI_STRING_MAX_SIZE {
+            return Err(Error::BadString(format!("unexpected string size {}", size)));
+        }
+        if previous >= FFI_STRING_MAX_SIZE {
+            return Err(Error::BadString(format!(
+                "string exceeds {} bytes",
+                FFI_STRING_MAX_SIZE
+            )));
+        }
+        Ok(size.max(previous * 2).min(FFI_STRING_MAX_SIZE))
+    }
 
-/// An optional function to enable libdatachannel logging via `tracing`, otherwise it will be disabled.
-#[cfg(feature = "tracing")]
-pub fn configure_logging(level: tracing::Level) {
-    INIT_LOGGING.call_once(|| {
-        let level = match level {
-            tracing::Level::ERROR => datachannel_sys::rtcLogLevel_RTC_LOG_ERROR,
-            tracing::Level::WARN => datachannel_sys::rtcLogLevel_RTC_LOG_WARNING,
-            tracing::Level::INFO => datachannel_sys::rtcLogLevel_RTC_LOG_INFO,
-            tracing::Level::DEBUG => datachannel_sys::rtcLogLevel_RTC_LOG_DEBUG,
-            tracing::Level::TRACE => datachannel_sys::rtcLogLevel_RTC_LOG_VERBOSE,
-        };
-
-        unsafe { datachannel_sys::rtcInitLogger(level, Some(sys::log_callback)) };
-    });
-}
+    /// An optional function to enable libdatachannel logging via `tracing`, otherwise it will be disabled.
+    ///
+    /// Handler callbacks run within `peer_connection`, `data_channel` and `track` spans carrying
+    /// `pc_id`, `dc_id` and `label`, or `track_id` and `mid`. libdatachannel logs emitted while
+    /// they run, or during calls made by a peer connection, are recorded within the same spans.
+    #[cfg(feature = "tracing")]
+    pub fn configure_logging(level: tracing::Level) {
+        INIT_LOGGING.call_once(|| {
+            let level = match level {
+                tracing::Level::ERROR => datachannel_sys::rtcLogLevel_RTC_LOG_ERROR,
+                tracing::Level::WARN => datachannel_sys::rtcLogLevel_RTC_LOG_WARNING,
+                tracing::Level::INFO => datachannel_sys::rtcLogLevel_RTC_LOG_INFO,
+                tracing::Level::DEBUG => datachannel_sys::rtcLogLevel_RTC_LOG_DEBUG,
+                tracing::Level::TRACE => datachannel_sys::rtcLogLevel_RTC_LOG_VERBOSE,
+            };
 
-/// An optional function to preload resources, otherwise they will be loaded lazily.
-pub fn preload() {
-    unsafe { datachannel_sys::rtcPreload() };
-}
+            unsafe { datachannel_sys::rtcInitLogger(level, Some(sys::log_callback)) };
+        });
+    }
+
+    /// An optional function to preload resources, otherwise they will be loaded lazily.
+    pub fn preload() {
+        unsafe { datachannel_sys::rtcPreload() };
+    }
 
-/// An optional resources cleanup function.
-pub fn cleanup() {
-    unsafe { datachannel_sys::rtcCleanup() };
+    /// An optional resources cleanup function.
+    ///
+    /// It must only be called once all peer connections, data channels and tracks are dropped,
+    /// see [`RtcRuntime`] which enforces it.
+    pub fn cleanup() {
+        unsafe { datachannel_sys::rtcCleanup() };
+    }
 }
 
-pub use crate::config::{CertificateType, RtcConfig, TransportPolicy};
-pub use crate::datachannel::{
-    DataChannelHandler, DataChannelId, DataChannelInfo, DataChannelInit, Reliability,
-    RtcDataChannel,
+pub use crate::error::{ChannelError, ChannelErrorKind, Error, Result};
+pub use crate::signaling::{
+    fmt_sdp, serde_sdp, ConnectionState, GatheringState, IceCandidate, IceState, SdpType,
+    SessionDescription, SignalingState,
 };
-pub use crate::error::{Error, Result};
-pub use crate::peerconnection::{
-    fmt_sdp, serde_sdp, CandidatePair, ConnectionState, GatheringState, IceCandidate, IceState,
-    PeerConnectionHandler, PeerConnectionId, RtcPeerConnection, SdpType, SessionDescription,
-    SignalingState,
+#[cfg(target_arch = "wasm32")]
+pub use crate::wasm::{
+    DataChannelHandler, DataChannelInfo, PeerConnectionHandler, RtcConfig, RtcDataChannel,
+    RtcPeerConnection,
 };
-pub use crate::track::{Codec, Direction, RtcTrack, TrackHandler, TrackInit};
+
+cfg_native! {
+    pub use crate::capture::CaptureFormat;
+    #[cfg(feature = "compression")]
+    pub use crate::compression::{Compression, CompressionAlgorithm, Decompress};
+    pub use crate::config::{
+        set_global_sctp_settings, AddressFamily, CandidateFilter, CertificateType, ConfigError,
+        CongestionControl, ErrorCallback, ErrorPolicy, ProxyServer, ProxyServerType, RtcConfig,
+        SctpSettings, TransportPolicy,
+    };
+    pub use crate::datachannel::{
+        Batching, DataChannelHandler, DataChannelId, DataChannelInfo, DataChannelInit, Reliability,
+        RtcDataChannel, Unbatch, SHUTDOWN_PROTOCOL,
+    };
+    pub use crate::dispatch::{Dispatched, WorkerPool};
+    pub use crate::forward::{
+        ForwardChannel, ForwardError, TcpEndpoint, TcpForwarder, FORWARD_LABEL,
+    };
+    pub use crate::framing::{Deframe, FrameWriter, Framing};
+    pub use crate::jitter::JitterBuffer;
+    pub use crate::keepalive::{Keepalive, RoundTripTime};
+    pub use crate::log_filter::{set_subsystem_log_level, LogLevel, LogSubsystem};
+    pub use crate::mesh::{
+        MeshChannel, MeshConnection, MeshHandler, MeshSignal, MeshTransport, PeerManager,
+        MESH_LABEL,
+    };
+    pub use crate::mux::{Mux, Subscriptions};
+    pub use crate::negotiation::{check_compatibility, Mismatch};
+    pub use crate::panic::set_panic_handler;
+    pub use crate::peerconnection::{
+        CandidatePair, LocalCandidate, PeerConnectionHandler, PeerConnectionId, RtcPeerConnection,
+    };
+    pub use crate::queue::{QueueOverflow, QueueReader, ReceiveQueue};
+    pub use crate::runtime::RtcRuntime;
+    pub use crate::stats::{
+        ConnectionQuality, DataChannelStats, PeerConnectionStats, QualityLevel, ReceptionReport,
+        StatsReport, TrackStats,
+    };
+    pub use crate::streams::{
+        StreamAcceptor, StreamChannel, StreamHandler, StreamId, StreamMux, StreamMuxConfig,
+        STREAM_MUX_LABEL,
+    };
+    pub use crate::timeline::{TimelineEntry, TimelineEvent};
+    pub use crate::track::{
+        Codec, Direction, Pacing, RtcTrack, SdpMediaBuilder, SimulcastLayer, TrackHandler,
+        TrackInit,
+    };
+    #[cfg(feature = "media")]
+    pub use crate::track::{NalUnitSeparator, PacketizerInit};
+    #[cfg(feature = "transfer")]
+    pub use crate::transfer::{
+        FileInfo, FileTransfer, Progress, ReceivedFile, TransferError, TransferHandler,
+        TransferInbox,
+    };
+
+    pub use crate::typed::{
+        CodecError, MessageCodec, Typed, TypedDataChannel, TypedError, TypedHandler,
+    };
+    #[cfg(feature = "bincode")]
+    pub use crate::typed::Bincode;
+    #[cfg(feature = "cbor")]
+    pub use crate::typed::Cbor;
+    #[cfg(feature = "json")]
+    pub use crate::typed::Json;
+
+    #[cfg(feature = "gstreamer")]
+    pub use crate::gst::{link_appsink, AppSrcHandler};
+}
 
 #[doc(inline)]
 pub use webrtc_sdp as sdp;
@@ -0,0 +1,73 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// The libdatachannel components whose forwarded logs can be filtered apart, see
+/// [`set_subsystem_log_level`].
+///
+/// libdatachannel doesn't tag its logs, the subsystem is inferred from the message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LogSubsystem {
+    /// ICE agent, including the logs of libjuice
+    Ice,
+    /// SCTP transport, including the logs of usrsctp
+    Sctp,
+    Dtls,
+    Srtp,
+    /// Anything else
+    Other,
+}
+
+/// Most verbose level let through for a [`LogSubsystem`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LogLevel {
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+static LEVELS: [AtomicU8; 5] = [
+    AtomicU8::new(LogLevel::Trace as u8),
+    AtomicU8::new(LogLevel::Trace as u8),
+    AtomicU8::new(LogLevel::Trace as u8),
+    AtomicU8::new(LogLevel::Trace as u8),
+    AtomicU8::new(LogLevel::Trace as u8),
+];
+
+/// Caps the level of the libdatachannel logs forwarded for `subsystem`, on top of the level
+/// libdatachannel's logger is initialized with. All subsystems default to
+/// [`LogLevel::Trace`].
+///
+/// For instance, lowering [`LogSubsystem::Ice`] and [`LogSubsystem::Sctp`] to
+/// [`LogLevel::Info`] keeps debug logging usable without the libjuice and usrsctp firehose.
+pub fn set_subsystem_log_level(subsystem: LogSubsystem, level: LogLevel) {
+    LEVELS[subsystem as usize].store(level as u8, Ordering::Relaxed);
+}
+
+impl LogSubsystem {
+    fn of(message: &str) -> Self {
+        let starts_with = |prefix: &str| {
+            message
+                .get(..prefix.len())
+                .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+        };
+        if starts_with("juice") || message.contains("ICE") {
+            Self::Ice
+        } else if starts_with("usrsctp") || message.contains("SCTP") {
+            Self::Sctp
+        } else if message.contains("SRTP") {
+            Self::Srtp
+        } else if message.contains("DTLS") {
+            Self::Dtls
+        } else {
+            Self::Other
+        }
+    }
+}
+
+/// Returns whether a forwarded log of `level` should be emitted.
+pub(crate) fn enabled(level: LogLevel, message: &str) -> bool {
+    let max = LEVELS[LogSubsystem::of(message) as usize].load(Ordering::Relaxed);
+    level as u8 <= max
+}
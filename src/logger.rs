@@ -19,3 +19,70 @@ pub use tracing::info;
 pub use tracing::trace;
 #[cfg(feature = "tracing")]
 pub use tracing::warn;
+
+cfg_native! {
+    /// A span carrying the ids of a peer connection, data channel or track, entered around
+    /// handler callbacks so that logs can be filtered per peer.
+    #[cfg(feature = "tracing")]
+    pub(crate) type Span = tracing::Span;
+
+    #[cfg(not(feature = "tracing"))]
+    #[derive(Debug, Clone)]
+    pub(crate) struct Span;
+
+    #[cfg(not(feature = "tracing"))]
+    impl Span {
+        pub(crate) fn enter(&self) -> Entered {
+            Entered
+        }
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    pub(crate) struct Entered;
+
+    pub(crate) fn peer_connection_span(pc_id: i32) -> Span {
+        #[cfg(feature = "tracing")]
+        {
+            tracing::info_span!("peer_connection", pc_id)
+        }
+        #[cfg(not(feature = "tracing"))]
+        {
+            let _ = pc_id;
+            Span
+        }
+    }
+
+    /// The label is only fetched when the `tracing` feature is enabled.
+    pub(crate) fn data_channel_span(pc_id: i32, dc_id: i32) -> Span {
+        #[cfg(feature = "tracing")]
+        {
+            let label = crate::read_ffi_string(|buf, size| unsafe {
+                datachannel_sys::rtcGetDataChannelLabel(dc_id, buf, size)
+            })
+            .unwrap_or_default();
+            tracing::info_span!("data_channel", pc_id, dc_id, label)
+        }
+        #[cfg(not(feature = "tracing"))]
+        {
+            let _ = (pc_id, dc_id);
+            Span
+        }
+    }
+
+    /// The mid is only fetched when the `tracing` feature is enabled.
+    pub(crate) fn track_span(pc_id: i32, track_id: i32) -> Span {
+        #[cfg(feature = "tracing")]
+        {
+            let mid = crate::read_ffi_string(|buf, size| unsafe {
+                datachannel_sys::rtcGetTrackMid(track_id, buf, size)
+            })
+            .unwrap_or_default();
+            tracing::info_span!("track", pc_id, track_id, mid)
+        }
+        #[cfg(not(feature = "tracing"))]
+        {
+            let _ = (pc_id, track_id);
+            Span
+        }
+    }
+}
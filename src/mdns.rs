@@ -0,0 +1,325 @@
+//! mDNS hostnames standing for the addresses of local host candidates, see
+//! [`RtcConfig::mdns_host_candidates`](crate::RtcConfig::mdns_host_candidates).
+//!
+//! Names are answered by a process-wide responder, running from a thread of its own while at
+//! least one name is registered. It answers the queries received over IPv4 multicast, and the
+//! legacy unicast ones sent straight to port 5353.
+
+use std::collections::hash_map::RandomState;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::BuildHasher;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, UdpSocket};
+use std::thread;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use socket2::{Domain, Protocol, Socket, Type};
+use webrtc_sdp::address::{Address, ExplicitlyTypedAddress};
+use webrtc_sdp::attribute_type::{
+    SdpAttribute, SdpAttributeCandidate, SdpAttributeCandidateType, SdpAttributeType,
+};
+use webrtc_sdp::{SdpConnection, SdpSession};
+
+use crate::logger;
+
+const MDNS_PORT: u16 = 5353;
+const MDNS_GROUP: Ipv4Addr = Ipv4Addr::new(224, 0, 0, 251);
+/// How often the responder checks whether names are still registered
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// TTL of the records sent to the multicast group, as recommended by RFC 6762
+const TTL: u32 = 120;
+/// TTL of the records answering legacy unicast queries, RFC 6762 caps it at 10 seconds
+const LEGACY_TTL: u32 = 10;
+
+const TYPE_A: u16 = 1;
+const TYPE_AAAA: u16 = 28;
+const TYPE_ANY: u16 = 255;
+const CLASS_IN: u16 = 1;
+/// Set on the class of questions asking for a unicast response
+const UNICAST_RESPONSE: u16 = 0x8000;
+/// Set on the class of records owned by this host alone
+const CACHE_FLUSH: u16 = 0x8000;
+
+struct Responder {
+    /// Registered names, lowercase and without the trailing dot
+    names: BTreeMap<String, IpAddr>,
+    running: bool,
+}
+
+static RESPONDER: Mutex<Responder> = Mutex::new(Responder {
+    names: BTreeMap::new(),
+    running: false,
+});
+
+/// A random `<uuid>.local` name answered with an address, until dropped.
+pub(crate) struct MdnsName {
+    name: String,
+}
+
+impl MdnsName {
+    pub(crate) fn register(addr: IpAddr) -> Self {
+        let name = format!("{}.local", random_uuid());
+        let mut responder = RESPONDER.lock();
+        responder.names.insert(name.clone(), addr);
+        if !responder.running {
+            match thread::Builder::new()
+                .name("mdns-responder".into())
+                .spawn(respond)
+            {
+                Ok(_) => responder.running = true,
+                Err(err) => logger::warn!("Couldn't start mDNS responder: {}", err),
+            }
+        }
+        Self { name }
+    }
+
+    pub(crate) fn as_str(&self) -> &str {
+        &self.name
+    }
+}
+
+impl Drop for MdnsName {
+    fn drop(&mut self) {
+        RESPONDER.lock().names.remove(&self.name);
+    }
+}
+
+/// The mDNS names standing for the host addresses of a peer connection.
+#[derive(Default)]
+pub(crate) struct HostNames {
+    names: Mutex<HashMap<IpAddr, MdnsName>>,
+}
+
+impl HostNames {
+    /// Replaces the address of a host candidate with its name, and hides the address related
+    /// to the other candidates. Returns whether `cand` changed.
+    pub(crate) fn obfuscate(&self, cand: &mut SdpAttributeCandidate) -> bool {
+        if cand.c_type != SdpAttributeCandidateType::Host {
+            let Some(Address::Ip(raddr)) = &cand.raddr else {
+                return false;
+            };
+            let unspecified = match raddr {
+                IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+                IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+            };
+            cand.raddr = Some(Address::Ip(unspecified));
+            cand.rport = Some(0);
+            return true;
+        }
+        let Address::Ip(addr) = cand.address else {
+            return false;
+        };
+        let mut names = self.names.lock();
+        let name = names
+            .entry(addr)
+            .or_insert_with(|| MdnsName::register(addr));
+        cand.address = Address::Fqdn(name.as_str().to_string());
+        true
+    }
+
+    /// Obfuscates the candidates of a local description, and its default candidate.
+    pub(crate) fn obfuscate_description(&self, sdp: &mut SdpSession) {
+        for media in &mut sdp.media {
+            let candidates = media
+                .get_attributes_of_type(SdpAttributeType::Candidate)
+                .into_iter()
+                .cloned()
+                .collect::<Vec<_>>();
+            media.remove_attribute(SdpAttributeType::Candidate);
+            for mut attr in candidates {
+                if let SdpAttribute::Candidate(cand) = &mut attr {
+                    self.obfuscate(cand);
+                }
+                media.add_attribute(attr).ok();
+            }
+            // The default candidate gives the address away too, browsers use these instead
+            media.set_port(9);
+            media.set_connection(SdpConnection {
+                address: ExplicitlyTypedAddress::Ip(Ipv4Addr::UNSPECIFIED.into()),
+                ttl: None,
+                amount: None,
+            });
+        }
+    }
+}
+
+/// Formats 128 random bits as a version 4 UUID, as browsers name their host candidates.
+fn random_uuid() -> String {
+    let state = RandomState::new();
+    let high = state.hash_one(0u8);
+    let low = state.hash_one(1u8);
+    format!(
+        "{:08x}-{:04x}-4{:03x}-{:04x}-{:012x}",
+        high >> 32,
+        (high >> 16) & 0xffff,
+        high & 0x0fff,
+        ((low >> 48) & 0x3fff) | 0x8000,
+        low & 0xffff_ffff_ffff,
+    )
+}
+
+fn bind() -> io::Result<UdpSocket> {
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+    // Shares the port with the system responder, if any
+    socket.set_reuse_address(true)?;
+    #[cfg(all(unix, not(any(target_os = "solaris", target_os = "illumos"))))]
+    socket.set_reuse_port(true)?;
+    socket.bind(&SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, MDNS_PORT).into())?;
+    let socket = UdpSocket::from(socket);
+    if let Err(err) = socket.join_multicast_v4(&MDNS_GROUP, &Ipv4Addr::UNSPECIFIED) {
+        logger::debug!("mDNS responder only answers unicast queries: {}", err);
+    }
+    socket.set_multicast_loop_v4(true)?;
+    socket.set_read_timeout(Some(POLL_INTERVAL))?;
+    Ok(socket)
+}
+
+/// Runs the responder until no name is registered.
+fn respond() {
+    let socket = bind();
+    if let Err(err) = &socket {
+        logger::warn!("Couldn't bind mDNS responder, names won't resolve: {}", err);
+    }
+    let mut buf = [0; 1500];
+    loop {
+        let query = match &socket {
+            Ok(socket) => socket.recv_from(&mut buf).ok(),
+            Err(_) => {
+                thread::sleep(POLL_INTERVAL);
+                None
+            }
+        };
+        let mut responder = RESPONDER.lock();
+        if responder.names.is_empty() {
+            // The next name registered starts another thread
+            responder.running = false;
+            return;
+        }
+        let (Ok(socket), Some((len, from))) = (&socket, query) else {
+            continue;
+        };
+        let Some((response, to)) = answer(&buf[..len], from, &responder.names) else {
+            continue;
+        };
+        drop(responder);
+        if let Err(err) = socket.send_to(&response, to) {
+            logger::debug!("Couldn't send mDNS response to {}: {}", to, err);
+        }
+    }
+}
+
+/// Builds the response to a query, along with where to send it, `None` when it asks for none
+/// of `names`.
+fn answer(
+    query: &[u8],
+    from: SocketAddr,
+    names: &BTreeMap<String, IpAddr>,
+) -> Option<(Vec<u8>, SocketAddr)> {
+    let flags = u16::from_be_bytes([*query.get(2)?, *query.get(3)?]);
+    // Responses, and queries other than standard ones, are ignored
+    if flags & 0xf800 != 0 {
+        return None;
+    }
+    let questions = u16::from_be_bytes([*query.get(4)?, *query.get(5)?]);
+
+    // Legacy unicast queries, sent from another port than 5353, expect a conventional DNS
+    // response echoing the id and questions
+    let legacy = from.port() != MDNS_PORT;
+    let mut unicast = legacy;
+    let mut records = Vec::new();
+    let mut offset = 12;
+    for _ in 0..questions {
+        let (name, end) = read_name(query, offset)?;
+        let qtype = u16::from_be_bytes([*query.get(end)?, *query.get(end + 1)?]);
+        let qclass = u16::from_be_bytes([*query.get(end + 2)?, *query.get(end + 3)?]);
+        offset = end + 4;
+
+        let Some(addr) = names.get(&name) else {
+            continue;
+        };
+        let rtype = match addr {
+            IpAddr::V4(_) => TYPE_A,
+            IpAddr::V6(_) => TYPE_AAAA,
+        };
+        if qclass & !UNICAST_RESPONSE != CLASS_IN || (qtype != rtype && qtype != TYPE_ANY) {
+            continue;
+        }
+        unicast |= qclass & UNICAST_RESPONSE != 0;
+        records.push((name, *addr));
+    }
+    if records.is_empty() {
+        return None;
+    }
+
+    let mut response = Vec::with_capacity(512);
+    let id = if legacy { [query[0], query[1]] } else { [0, 0] };
+    response.extend_from_slice(&id);
+    // Authoritative answer
+    response.extend_from_slice(&0x8400u16.to_be_bytes());
+    // The questions are echoed as is, the names they point to keep their offsets
+    let echoed = if legacy { &query[12..offset] } else { &[] };
+    let echoed_count = if legacy { questions } else { 0 };
+    response.extend_from_slice(&echoed_count.to_be_bytes());
+    response.extend_from_slice(&(records.len() as u16).to_be_bytes());
+    response.extend_from_slice(&[0, 0, 0, 0]);
+    response.extend_from_slice(echoed);
+    for (name, addr) in records {
+        for label in name.split('.') {
+            response.push(label.len() as u8);
+            response.extend_from_slice(label.as_bytes());
+        }
+        response.push(0);
+        let (rtype, data) = match addr {
+            IpAddr::V4(addr) => (TYPE_A, addr.octets().to_vec()),
+            IpAddr::V6(addr) => (TYPE_AAAA, addr.octets().to_vec()),
+        };
+        let (class, ttl) = if legacy {
+            (CLASS_IN, LEGACY_TTL)
+        } else {
+            (CLASS_IN | CACHE_FLUSH, TTL)
+        };
+        response.extend_from_slice(&rtype.to_be_bytes());
+        response.extend_from_slice(&class.to_be_bytes());
+        response.extend_from_slice(&ttl.to_be_bytes());
+        response.extend_from_slice(&(data.len() as u16).to_be_bytes());
+        response.extend_from_slice(&data);
+    }
+
+    let to = if unicast {
+        from
+    } else {
+        SocketAddr::V4(SocketAddrV4::new(MDNS_GROUP, MDNS_PORT))
+    };
+    Some((response, to))
+}
+
+/// Reads the name starting at `offset`, lowercase and without the trailing dot, along with
+/// the offset right after it.
+fn read_name(msg: &[u8], mut offset: usize) -> Option<(String, usize)> {
+    let mut labels = Vec::new();
+    // Set once a compression pointer is followed
+    let mut end = None;
+    // Bounds the pointers followed, a loop of them would never end otherwise
+    for _ in 0..128 {
+        let len = *msg.get(offset)? as usize;
+        match len {
+            0 => {
+                let name = labels.join(".").to_ascii_lowercase();
+                return Some((name, end.unwrap_or(offset + 1)));
+            }
+            len if len & 0xc0 == 0xc0 => {
+                let pointer = (len & 0x3f) << 8 | *msg.get(offset + 1)? as usize;
+                end.get_or_insert(offset + 2);
+                offset = pointer;
+            }
+            len if len & 0xc0 == 0 => {
+                let label = msg.get(offset + 1..offset + 1 + len)?;
+                labels.push(String::from_utf8_lossy(label).into_owned());
+                offset += 1 + len;
+            }
+            _ => return None,
+        }
+    }
+    None
+}
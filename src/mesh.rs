@@ -0,0 +1,433 @@
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::config::RtcConfig;
+use crate::datachannel::{DataChannelHandler, DataChannelInfo, RtcDataChannel};
+use crate::error::{Error, Result};
+use crate::logger;
+use crate::peerconnection::{PeerConnectionHandler, RtcPeerConnection};
+use crate::signaling::{ConnectionState, IceCandidate, SdpType, SessionDescription};
+
+/// Label of the data channel opened between each pair of peers of a [`PeerManager`].
+pub const MESH_LABEL: &str = "mesh";
+
+/// A signaling message of a [`PeerManager`], serializable to be carried by any transport.
+#[derive(Debug, Serialize, Deserialize)]
+pub enum MeshSignal {
+    /// Announces a peer joining the mesh, the peers already there connect to it
+    Join,
+    /// Announces a peer leaving the mesh
+    Leave,
+    Description(Box<SessionDescription>),
+    Candidate(IceCandidate),
+}
+
+/// Carries the [`MeshSignal`]s of a [`PeerManager`] to the other peers, which hand them to
+/// their own manager with [`PeerManager::handle_signal`].
+///
+/// The signals sent to a peer must be delivered in order.
+pub trait MeshTransport<K> {
+    fn send(&self, to: &K, signal: MeshSignal);
+    fn broadcast(&self, signal: MeshSignal);
+}
+
+/// Callbacks of a [`PeerManager`], run from libdatachannel threads.
+///
+/// They may send through the manager, but not join, leave or handle signals as peers would
+/// then be dropped from their own callbacks.
+#[allow(unused_variables)]
+pub trait MeshHandler<K> {
+    /// The data channel with `peer` is open, it can be sent messages.
+    fn on_peer_joined(&mut self, peer: &K) {}
+    /// The data channel with `peer` is closed, following its departure or a failure.
+    fn on_peer_left(&mut self, peer: &K) {}
+    fn on_message(&mut self, peer: &K, msg: &[u8]) {}
+}
+
+thread_local! {
+    /// Address of the [`SharedHandler`] this thread runs a callback of, if any.
+    static LOCKED_HANDLER: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Restores [`LOCKED_HANDLER`] once a callback returns.
+struct Unlocked(usize);
+
+impl Drop for Unlocked {
+    fn drop(&mut self) {
+        LOCKED_HANDLER.set(self.0);
+    }
+}
+
+/// The [`MeshHandler`] of a mesh, along with the peers that left and are yet to be notified.
+///
+/// The handler may send through the manager from its callbacks, which can drop peers. Their
+/// departure is notified once the handler is released rather than from within the callback.
+struct SharedHandler<K, H> {
+    handler: Mutex<H>,
+    departed: Mutex<Vec<K>>,
+}
+
+impl<K, H> SharedHandler<K, H>
+where
+    H: MeshHandler<K>,
+{
+    fn new(handler: H) -> Self {
+        Self {
+            handler: Mutex::new(handler),
+            departed: Mutex::new(Vec::new()),
+        }
+    }
+
+    fn addr(&self) -> usize {
+        self as *const Self as usize
+    }
+
+    fn lock(&self, f: impl FnOnce(&mut H)) {
+        let mut handler = self.handler.lock();
+        let _unlocked = Unlocked(LOCKED_HANDLER.replace(self.addr()));
+        f(&mut handler);
+    }
+
+    /// Runs a callback of the handler, then notifies the peers that left meanwhile.
+    fn call(&self, f: impl FnOnce(&mut H)) {
+        self.lock(f);
+        self.notify_departed();
+    }
+
+    /// Notifies the departure of `peer`, once the handler is released if called from one of
+    /// its callbacks.
+    fn depart(&self, peer: K) {
+        self.departed.lock().push(peer);
+        self.notify_departed();
+    }
+
+    fn notify_departed(&self) {
+        if LOCKED_HANDLER.get() == self.addr() {
+            return;
+        }
+        loop {
+            let departed = std::mem::take(&mut *self.departed.lock());
+            if departed.is_empty() {
+                break;
+            }
+            self.lock(|handler| {
+                for peer in &departed {
+                    handler.on_peer_left(peer);
+                }
+            });
+        }
+    }
+}
+
+/// Handler of the data channel with one peer.
+pub struct MeshChannel<K, H> {
+    peer: K,
+    open: Arc<AtomicBool>,
+    handler: Arc<SharedHandler<K, H>>,
+}
+
+impl<K, H> DataChannelHandler for MeshChannel<K, H>
+where
+    H: MeshHandler<K>,
+{
+    fn on_open(&mut self) {
+        if !self.open.swap(true, Ordering::SeqCst) {
+            self.handler.call(|handler| handler.on_peer_joined(&self.peer));
+        }
+    }
+
+    fn on_closed(&mut self) {
+        if self.open.swap(false, Ordering::SeqCst) {
+            self.handler.call(|handler| handler.on_peer_left(&self.peer));
+        }
+    }
+
+    fn on_message(&mut self, msg: &[u8]) {
+        self.handler.call(|handler| handler.on_message(&self.peer, msg));
+    }
+}
+
+type ChannelSlot<K, H> = Arc<Mutex<Option<Box<RtcDataChannel<MeshChannel<K, H>>>>>>;
+
+/// Peers whose connection failed or closed, along with the `open` flag telling their
+/// connection apart from a newer one.
+type Failed<K> = Arc<Mutex<Vec<(K, Arc<AtomicBool>)>>>;
+
+/// Handler of the peer connection with one peer, signaling it through the transport.
+pub struct MeshConnection<K, H, T> {
+    peer: K,
+    open: Arc<AtomicBool>,
+    handler: Arc<SharedHandler<K, H>>,
+    transport: Arc<T>,
+    channel: ChannelSlot<K, H>,
+    failed: Failed<K>,
+}
+
+impl<K, H, T> PeerConnectionHandler for MeshConnection<K, H, T>
+where
+    K: Clone + Send,
+    H: MeshHandler<K> + Send,
+    T: MeshTransport<K>,
+{
+    type DCH = MeshChannel<K, H>;
+
+    fn data_channel_handler(&mut self, _info: DataChannelInfo) -> Self::DCH {
+        MeshChannel {
+            peer: self.peer.clone(),
+            open: Arc::clone(&self.open),
+            handler: Arc::clone(&self.handler),
+        }
+    }
+
+    fn on_description(&mut self, sess_desc: SessionDescription) {
+        self.transport
+            .send(&self.peer, MeshSignal::Description(Box::new(sess_desc)));
+    }
+
+    fn on_candidate(&mut self, cand: IceCandidate) {
+        self.transport.send(&self.peer, MeshSignal::Candidate(cand));
+    }
+
+    fn on_connection_state_change(&mut self, state: ConnectionState) {
+        // Removed by the manager, it can't be dropped from its own callback
+        if let ConnectionState::Failed | ConnectionState::Closed = state {
+            let open = Arc::clone(&self.open);
+            self.failed.lock().push((self.peer.clone(), open));
+        }
+    }
+
+    fn on_data_channel(&mut self, dc: Box<RtcDataChannel<Self::DCH>>) {
+        match dc.label() {
+            Ok(label) if label == MESH_LABEL => *self.channel.lock() = Some(dc),
+            _ => logger::warn!("Ignoring unexpected data channel of a mesh peer"),
+        }
+    }
+}
+
+struct Peer<K, H, T> {
+    pc: Box<RtcPeerConnection<MeshConnection<K, H, T>>>,
+    channel: ChannelSlot<K, H>,
+    open: Arc<AtomicBool>,
+    offerer: bool,
+}
+
+/// Owns the peer connections of a full mesh, one per remote peer keyed by its id.
+///
+/// Peers announce themselves with [`join`](Self::join), those already in the mesh then
+/// offer them a connection along with a data channel labeled [`MESH_LABEL`]. Signals are
+/// carried by a [`MeshTransport`] and must be handed to [`handle_signal`](Self::handle_signal)
+/// on reception. When two peers join at the same time and offer each other a connection, the
+/// offer of the peer with the greatest id wins. Connections that fail or close are dropped on
+/// the next call to the manager.
+pub struct PeerManager<K, H, T> {
+    local: K,
+    config: RtcConfig,
+    handler: Arc<SharedHandler<K, H>>,
+    transport: Arc<T>,
+    peers: Mutex<HashMap<K, Peer<K, H, T>>>,
+    failed: Failed<K>,
+}
+
+impl<K, H, T> PeerManager<K, H, T>
+where
+    K: Clone + Ord + Hash + fmt::Debug + Send + 'static,
+    H: MeshHandler<K> + Send + 'static,
+    T: MeshTransport<K> + Send + Sync + 'static,
+{
+    /// Creates the manager of the peer `local`, each connection is configured with `config`.
+    pub fn new(local: K, config: RtcConfig, handler: H, transport: T) -> Self {
+        Self {
+            local,
+            config,
+            handler: Arc::new(SharedHandler::new(handler)),
+            transport: Arc::new(transport),
+            peers: Mutex::new(HashMap::new()),
+            failed: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    pub fn local_id(&self) -> &K {
+        &self.local
+    }
+
+    /// Announces this peer to the mesh.
+    pub fn join(&self) {
+        self.prune();
+        self.transport.broadcast(MeshSignal::Join);
+    }
+
+    /// Announces the departure of this peer and closes all its connections.
+    pub fn leave(&self) {
+        self.transport.broadcast(MeshSignal::Leave);
+        let peers = std::mem::take(&mut *self.peers.lock());
+        for (id, peer) in peers {
+            self.close(&id, peer);
+        }
+        self.failed.lock().clear();
+    }
+
+    /// Applies a signal received from the peer `from`.
+    pub fn handle_signal(&self, from: K, signal: MeshSignal) -> Result<()> {
+        self.prune();
+        if from == self.local {
+            return Ok(());
+        }
+        match signal {
+            MeshSignal::Join => {
+                self.remove(&from);
+                let mut peer = self.connect(from.clone(), true)?;
+                let dc = peer.pc.create_data_channel(
+                    MESH_LABEL,
+                    MeshChannel {
+                        peer: from.clone(),
+                        open: Arc::clone(&peer.open),
+                        handler: Arc::clone(&self.handler),
+                    },
+                )?;
+                *peer.channel.lock() = Some(dc);
+                self.insert(from, peer);
+            }
+            MeshSignal::Leave => self.remove(&from),
+            MeshSignal::Description(sess_desc) => {
+                if sess_desc.sdp_type == SdpType::Offer {
+                    if self.offering_to(&from) && self.local > from {
+                        return Ok(());
+                    }
+                    // Either a glare lost or the peer restarted meanwhile
+                    self.remove(&from);
+                    let peer = self.connect(from.clone(), false)?;
+                    self.insert(from.clone(), peer);
+                }
+                let mut peers = self.peers.lock();
+                let peer = peers.get_mut(&from).ok_or(Error::InvalidArg)?;
+                peer.pc.set_remote_description(&sess_desc)?;
+            }
+            MeshSignal::Candidate(cand) => {
+                let mut peers = self.peers.lock();
+                let peer = peers.get_mut(&from).ok_or(Error::InvalidArg)?;
+                peer.pc.add_remote_candidate(&cand)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Sends `msg` to `peer`, failing with [`Error::NotOpen`] if it isn't connected.
+    pub fn send(&self, peer: &K, msg: &[u8]) -> Result<()> {
+        self.prune();
+        let channel = match self.peers.lock().get(peer) {
+            Some(peer) => Arc::clone(&peer.channel),
+            None => return Err(Error::NotOpen),
+        };
+        let mut channel = channel.lock();
+        channel.as_mut().ok_or(Error::NotOpen)?.send(msg)
+    }
+
+    /// Sends `msg` to every connected peer, returning the result of each send.
+    pub fn broadcast(&self, msg: &[u8]) -> Vec<(K, Result<()>)> {
+        self.peers()
+            .into_iter()
+            .map(|peer| {
+                let res = self.send(&peer, msg);
+                (peer, res)
+            })
+            .collect()
+    }
+
+    /// The peers whose data channel is open.
+    pub fn peers(&self) -> Vec<K> {
+        self.prune();
+        let peers = self.peers.lock();
+        peers
+            .iter()
+            .filter(|(_, peer)| peer.open.load(Ordering::SeqCst))
+            .map(|(id, _)| id.clone())
+            .collect()
+    }
+
+    /// Runs `f` with the connection to `peer`, if any.
+    pub fn with_connection<F, R>(&self, peer: &K, f: F) -> Option<R>
+    where
+        F: FnOnce(&mut RtcPeerConnection<MeshConnection<K, H, T>>) -> R,
+    {
+        self.prune();
+        self.peers.lock().get_mut(peer).map(|peer| f(&mut peer.pc))
+    }
+
+    /// Whether this peer offered a connection to `id` which isn't open yet.
+    fn offering_to(&self, id: &K) -> bool {
+        let peers = self.peers.lock();
+        peers
+            .get(id)
+            .is_some_and(|peer| peer.offerer && !peer.open.load(Ordering::SeqCst))
+    }
+
+    fn connect(&self, id: K, offerer: bool) -> Result<Peer<K, H, T>> {
+        let open = Arc::new(AtomicBool::new(false));
+        let channel = Arc::new(Mutex::new(None));
+        let pc = RtcPeerConnection::new(
+            &self.config,
+            MeshConnection {
+                peer: id,
+                open: Arc::clone(&open),
+                handler: Arc::clone(&self.handler),
+                transport: Arc::clone(&self.transport),
+                channel: Arc::clone(&channel),
+                failed: Arc::clone(&self.failed),
+            },
+        )?;
+        Ok(Peer {
+            pc,
+            channel,
+            open,
+            offerer,
+        })
+    }
+
+    fn insert(&self, id: K, peer: Peer<K, H, T>) {
+        let replaced = self.peers.lock().insert(id.clone(), peer);
+        if let Some(replaced) = replaced {
+            self.close(&id, replaced);
+        }
+    }
+
+    fn remove(&self, id: &K) {
+        let removed = self.peers.lock().remove(id);
+        if let Some(peer) = removed {
+            self.close(id, peer);
+        }
+    }
+
+    /// Drops a peer outside of the lock, its callbacks being waited for.
+    fn close(&self, id: &K, peer: Peer<K, H, T>) {
+        let channel = peer.channel.lock().take();
+        drop(channel);
+        drop(peer.pc);
+        if peer.open.swap(false, Ordering::SeqCst) {
+            self.handler.depart(id.clone());
+        }
+    }
+
+    fn prune(&self) {
+        let failed = std::mem::take(&mut *self.failed.lock());
+        for (id, open) in failed {
+            let removed = {
+                let mut peers = self.peers.lock();
+                match peers.get(&id) {
+                    Some(peer) if Arc::ptr_eq(&peer.open, &open) => peers.remove(&id),
+                    _ => None,
+                }
+            };
+            if let Some(peer) = removed {
+                logger::info!("Removing mesh peer {:?}, its connection is closed", id);
+                self.close(&id, peer);
+            }
+        }
+    }
+}
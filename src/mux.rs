@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
+use crate::datachannel::{DataChannelHandler, RtcDataChannel};
+use crate::error::{ChannelError, ChannelErrorKind, Error, Result};
+use crate::logger;
+
+type TopicHandler = Arc<Mutex<Box<dyn DataChannelHandler + Send>>>;
+
+#[derive(Default)]
+struct Topics {
+    handlers: HashMap<String, TopicHandler>,
+    fallback: Option<TopicHandler>,
+}
+
+impl Topics {
+    /// Every handler, to broadcast the channel callbacks.
+    fn all(&self) -> Vec<TopicHandler> {
+        self.handlers
+            .values()
+            .chain(self.fallback.as_ref())
+            .cloned()
+            .collect()
+    }
+}
+
+/// A [`DataChannelHandler`] routing the messages of a single data channel to per-topic
+/// handlers, see [`Mux::new`].
+///
+/// Each message starts with the length of its topic on one byte followed by the topic
+/// itself, as written by [`Mux::publish`]. The channel callbacks, such as
+/// [`on_open`](DataChannelHandler::on_open), are broadcast to every handler.
+pub struct Mux {
+    topics: Arc<Mutex<Topics>>,
+}
+
+/// Manages the topic handlers of a [`Mux`], even once the data channel is created.
+///
+/// Handlers may subscribe and unsubscribe from within their own callbacks.
+#[derive(Clone)]
+pub struct Subscriptions {
+    topics: Arc<Mutex<Topics>>,
+}
+
+impl Mux {
+    pub fn new() -> (Self, Subscriptions) {
+        let topics = Arc::new(Mutex::new(Topics::default()));
+        let subscriptions = Subscriptions {
+            topics: Arc::clone(&topics),
+        };
+        (Self { topics }, subscriptions)
+    }
+
+    /// Prefixes `msg` with `topic`, failing with [`Error::InvalidArg`] if the topic is longer
+    /// than 255 bytes.
+    pub fn encode(topic: &str, msg: &[u8]) -> Result<Vec<u8>> {
+        let len = u8::try_from(topic.len()).map_err(|_| Error::InvalidArg)?;
+        let mut buf = Vec::with_capacity(1 + topic.len() + msg.len());
+        buf.push(len);
+        buf.extend_from_slice(topic.as_bytes());
+        buf.extend_from_slice(msg);
+        Ok(buf)
+    }
+
+    /// Sends `msg` on `topic` through `dc`, whose remote handler is a [`Mux`].
+    pub fn publish<D>(dc: &mut RtcDataChannel<D>, topic: &str, msg: &[u8]) -> Result<()>
+    where
+        D: DataChannelHandler + Send,
+    {
+        dc.send(&Self::encode(topic, msg)?)
+    }
+
+    fn broadcast(&self, f: impl Fn(&mut dyn DataChannelHandler)) {
+        let handlers = self.topics.lock().all();
+        for handler in handlers {
+            f(handler.lock().as_mut());
+        }
+    }
+}
+
+impl Subscriptions {
+    /// Routes the messages of `topic` to `handler`, replacing the previous one if any.
+    pub fn subscribe<H>(&self, topic: &str, handler: H)
+    where
+        H: DataChannelHandler + Send + 'static,
+    {
+        self.topics
+            .lock()
+            .handlers
+            .insert(topic.to_string(), Arc::new(Mutex::new(Box::new(handler))));
+    }
+
+    /// Returns whether `topic` was subscribed, its messages are then handed to the fallback
+    /// handler.
+    pub fn unsubscribe(&self, topic: &str) -> bool {
+        self.topics.lock().handlers.remove(topic).is_some()
+    }
+
+    pub fn is_subscribed(&self, topic: &str) -> bool {
+        self.topics.lock().handlers.contains_key(topic)
+    }
+
+    pub fn topics(&self) -> Vec<String> {
+        self.topics.lock().handlers.keys().cloned().collect()
+    }
+
+    /// Receives the messages of the topics not subscribed, with their topic prefix, which
+    /// are otherwise dropped.
+    pub fn set_fallback<H>(&self, handler: H)
+    where
+        H: DataChannelHandler + Send + 'static,
+    {
+        self.topics.lock().fallback = Some(Arc::new(Mutex::new(Box::new(handler))));
+    }
+
+    pub fn remove_fallback(&self) {
+        self.topics.lock().fallback = None;
+    }
+}
+
+impl DataChannelHandler for Mux {
+    fn on_open(&mut self) {
+        self.broadcast(|handler| handler.on_open())
+    }
+
+    fn on_closed(&mut self) {
+        self.broadcast(|handler| handler.on_closed())
+    }
+
+    fn on_error(&mut self, err: &ChannelError) {
+        self.broadcast(|handler| handler.on_error(err))
+    }
+
+    fn on_message(&mut self, msg: &[u8]) {
+        let topic = msg.split_first().and_then(|(&len, rest)| {
+            let topic = rest.get(..len as usize)?;
+            Some((std::str::from_utf8(topic).ok()?, &rest[len as usize..]))
+        });
+        let Some((topic, payload)) = topic else {
+            let err = ChannelError::new(ChannelErrorKind::Protocol, "Malformed topic header");
+            return self.broadcast(|handler| handler.on_error(&err));
+        };
+
+        let (handler, routed) = {
+            let topics = self.topics.lock();
+            match topics.handlers.get(topic) {
+                Some(handler) => (Some(Arc::clone(handler)), true),
+                None => (topics.fallback.clone(), false),
+            }
+        };
+        match handler {
+            Some(handler) if routed => handler.lock().on_message(payload),
+            Some(fallback) => fallback.lock().on_message(msg),
+            None => logger::debug!("Dropping message of unsubscribed topic {}", topic),
+        }
+    }
+
+    fn on_buffered_amount_low(&mut self) {
+        self.broadcast(|handler| handler.on_buffered_amount_low())
+    }
+
+    fn on_available(&mut self) {
+        self.broadcast(|handler| handler.on_available())
+    }
+
+    fn on_keepalive_timeout(&mut self) {
+        self.broadcast(|handler| handler.on_keepalive_timeout())
+    }
+}
@@ -0,0 +1,135 @@
+use webrtc_sdp::attribute_type::{SdpAttribute, SdpAttributeType};
+use webrtc_sdp::media_type::{SdpMedia, SdpMediaValue};
+
+use crate::signaling::{media_mid, SessionDescription};
+use crate::track::Direction;
+
+/// A problem found by [`check_compatibility`] between an offer and its answer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mismatch {
+    /// The offered media section is missing from the answer
+    MissingMid { mid: String },
+    /// The answer contains a media section which was not offered
+    UnexpectedMid { mid: String },
+    /// The answer rejected the media section (port 0)
+    Rejected { mid: String },
+    /// The media section kinds (audio, video or application) differ
+    MediaKind { mid: String },
+    /// The answer has no codec in common with the offer
+    NoCommonCodec { mid: String },
+    /// The answered direction is not allowed for the offered one
+    Direction {
+        mid: String,
+        offer: Direction,
+        answer: Direction,
+    },
+    /// No media flows in either direction
+    Inactive { mid: String },
+}
+
+/// Compares a local offer and a remote answer (mids, codecs and directions) to diagnose
+/// connections where media or data doesn't flow.
+pub fn check_compatibility(
+    offer: &SessionDescription,
+    answer: &SessionDescription,
+) -> Vec<Mismatch> {
+    let mut mismatches = vec![];
+
+    for offered in &offer.sdp.media {
+        let Some(mid) = media_mid(offered) else {
+            continue;
+        };
+        let mid = mid.to_string();
+        let Some(answered) = answer
+            .sdp
+            .media
+            .iter()
+            .find(|media| media_mid(media) == Some(&mid))
+        else {
+            mismatches.push(Mismatch::MissingMid { mid });
+            continue;
+        };
+
+        if answered.get_port() == 0 {
+            mismatches.push(Mismatch::Rejected { mid });
+            continue;
+        }
+        if offered.get_type() != answered.get_type() {
+            mismatches.push(Mismatch::MediaKind { mid });
+            continue;
+        }
+        if *offered.get_type() == SdpMediaValue::Application {
+            continue;
+        }
+
+        let offered_codecs = codecs(offered);
+        if !codecs(answered)
+            .iter()
+            .any(|codec| offered_codecs.contains(codec))
+        {
+            mismatches.push(Mismatch::NoCommonCodec { mid: mid.clone() });
+        }
+
+        let (offer_dir, answer_dir) = (direction(offered), direction(answered));
+        let allowed = match offer_dir {
+            Direction::SendOnly => matches!(answer_dir, Direction::RecvOnly | Direction::Inactive),
+            Direction::RecvOnly => matches!(answer_dir, Direction::SendOnly | Direction::Inactive),
+            Direction::Inactive => answer_dir == Direction::Inactive,
+            _ => true,
+        };
+        if !allowed {
+            mismatches.push(Mismatch::Direction {
+                mid,
+                offer: offer_dir,
+                answer: answer_dir,
+            });
+        } else if answer_dir == Direction::Inactive {
+            mismatches.push(Mismatch::Inactive { mid });
+        }
+    }
+
+    for answered in &answer.sdp.media {
+        if let Some(mid) = media_mid(answered) {
+            if !offer
+                .sdp
+                .media
+                .iter()
+                .any(|media| media_mid(media) == Some(mid))
+            {
+                mismatches.push(Mismatch::UnexpectedMid {
+                    mid: mid.to_string(),
+                });
+            }
+        }
+    }
+
+    mismatches
+}
+
+/// Codec names and clock rates, payload types may differ between offer and answer.
+fn codecs(media: &SdpMedia) -> Vec<(String, u32)> {
+    media
+        .get_attributes_of_type(SdpAttributeType::Rtpmap)
+        .into_iter()
+        .filter_map(|attr| match attr {
+            SdpAttribute::Rtpmap(rtpmap) => {
+                Some((rtpmap.codec_name.to_ascii_lowercase(), rtpmap.frequency))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn direction(media: &SdpMedia) -> Direction {
+    media
+        .get_attributes()
+        .iter()
+        .find_map(|attr| match attr {
+            SdpAttribute::Sendonly => Some(Direction::SendOnly),
+            SdpAttribute::Recvonly => Some(Direction::RecvOnly),
+            SdpAttribute::Sendrecv => Some(Direction::SendRecv),
+            SdpAttribute::Inactive => Some(Direction::Inactive),
+            _ => None,
+        })
+        .unwrap_or(Direction::SendRecv)
+}
@@ -0,0 +1,60 @@
+use std::any::Any;
+use std::cell::Cell;
+use std::panic::{self, AssertUnwindSafe};
+
+use parking_lot::RwLock;
+
+use crate::logger;
+
+type PanicHandler = Box<dyn Fn(&str, &str) + Send + Sync>;
+
+static PANIC_HANDLER: RwLock<Option<PanicHandler>> = RwLock::new(None);
+
+thread_local! {
+    /// Set while this thread runs a libdatachannel callback
+    static IN_CALLBACK: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Sets a function called when a handler panics, in addition to the panic being logged.
+///
+/// Panics in handlers are caught rather than unwinding into libdatachannel, which is undefined
+/// behavior. The function receives the name of the callback and the panic message, it can for
+/// instance forward them to the application's error reporting or abort the process.
+pub fn set_panic_handler<F>(handler: F)
+where
+    F: Fn(&str, &str) + Send + Sync + 'static,
+{
+    *PANIC_HANDLER.write() = Some(Box::new(handler));
+}
+
+/// Runs `f`, catching and reporting a panic instead of letting it unwind.
+pub(crate) fn catch<F>(callback: &str, f: F)
+where
+    F: FnOnce(),
+{
+    let outer = IN_CALLBACK.replace(true);
+    let res = panic::catch_unwind(AssertUnwindSafe(f));
+    IN_CALLBACK.set(outer);
+    if let Err(payload) = res {
+        let message = panic_message(&*payload);
+        logger::error!("Panic in {} callback: {}", callback, message);
+        if let Some(handler) = PANIC_HANDLER.read().as_ref() {
+            // The handler itself must not unwind either
+            panic::catch_unwind(AssertUnwindSafe(|| handler(callback, message))).ok();
+        }
+    }
+}
+
+/// Whether this thread runs a libdatachannel callback, where waiting for another event of
+/// libdatachannel could block it forever.
+pub(crate) fn in_callback() -> bool {
+    IN_CALLBACK.get()
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("Box<dyn Any>")
+}
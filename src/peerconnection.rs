@@ -1,32 +1,40 @@
+use std::cell::UnsafeCell;
 use std::ffi::{c_void, CStr, CString};
-use std::fmt;
 use std::os::raw::c_char;
 use std::ptr;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Weak};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
 
 use datachannel_sys as sys;
-use derivative::Derivative;
-use parking_lot::ReentrantMutex;
-use serde::{Deserialize, Serialize};
-use webrtc_sdp::{media_type::SdpMedia, parse_sdp, SdpSession};
-
-use crate::config::RtcConfig;
+use parking_lot::Mutex;
+use webrtc_sdp::attribute_type::{
+    SdpAttribute, SdpAttributeCandidate, SdpAttributeSetup, SdpAttributeType,
+};
+use webrtc_sdp::media_type::SdpMedia;
+use webrtc_sdp::{parse_sdp, parse_sdp_line, SdpSession};
+
+use crate::config::{AddressFamily, CandidateFilter, ErrorPolicy, RtcConfig};
 use crate::datachannel::{DataChannelHandler, DataChannelInit, RtcDataChannel};
-use crate::error::{check, Error, Result};
-use crate::track::{RtcTrack, TrackHandler, TrackInit};
+use crate::error::{call, check, Error, Result};
+#[cfg(feature = "insecure")]
+use crate::insecure::LocalFingerprints;
+#[cfg(feature = "mdns")]
+use crate::mdns::HostNames;
+use crate::signaling::{
+    ConnectionState, GatheringState, IceCandidate, IceState, SdpType, SessionDescription,
+    SignalingState,
+};
+use crate::stats::{
+    ChannelCounters, ConnectionQuality, PeerConnectionStats, QualityLevel, StatsReport, Traffic,
+};
+use crate::timeline::{Timeline, TimelineEntry, TimelineEvent};
+use crate::track::{RtcTrack, SimulcastLayer, TrackHandler, TrackInit};
 use crate::{logger, DataChannelId, DataChannelInfo};
 
-#[derive(Debug, PartialEq, Eq)]
-pub enum ConnectionState {
-    New,
-    Connecting,
-    Connected,
-    Disconnected,
-    Failed,
-    Closed,
-}
-
 impl ConnectionState {
-    fn from_raw(state: sys::rtcState) -> Self {
+    pub(crate) fn from_raw(state: sys::rtcState) -> Self {
         match state {
             sys::rtcState_RTC_NEW => Self::New,
             sys::rtcState_RTC_CONNECTING => Self::Connecting,
@@ -34,64 +42,37 @@ impl ConnectionState {
             sys::rtcState_RTC_DISCONNECTED => Self::Disconnected,
             sys::rtcState_RTC_FAILED => Self::Failed,
             sys::rtcState_RTC_CLOSED => Self::Closed,
-            _ => panic!("Unknown rtcState: {}", state),
+            state => Self::Unknown(state as i32),
         }
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
-pub enum GatheringState {
-    New,
-    InProgress,
-    Complete,
-}
-
 impl GatheringState {
-    fn from_raw(state: sys::rtcGatheringState) -> Self {
+    pub(crate) fn from_raw(state: sys::rtcGatheringState) -> Self {
         match state {
             sys::rtcGatheringState_RTC_GATHERING_NEW => Self::New,
             sys::rtcGatheringState_RTC_GATHERING_INPROGRESS => Self::InProgress,
             sys::rtcGatheringState_RTC_GATHERING_COMPLETE => Self::Complete,
-            _ => panic!("Unknown rtcGatheringState: {}", state),
+            state => Self::Unknown(state as i32),
         }
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
-pub enum SignalingState {
-    Stable,
-    HaveLocalOffer,
-    HaveRemoteOffer,
-    HaveLocalPranswer,
-    HaveRemotePranswer,
-}
-
 impl SignalingState {
-    fn from_raw(state: sys::rtcSignalingState) -> Self {
+    pub(crate) fn from_raw(state: sys::rtcSignalingState) -> Self {
         match state {
             sys::rtcSignalingState_RTC_SIGNALING_STABLE => Self::Stable,
             sys::rtcSignalingState_RTC_SIGNALING_HAVE_LOCAL_OFFER => Self::HaveLocalOffer,
             sys::rtcSignalingState_RTC_SIGNALING_HAVE_REMOTE_OFFER => Self::HaveRemoteOffer,
             sys::rtcSignalingState_RTC_SIGNALING_HAVE_LOCAL_PRANSWER => Self::HaveLocalPranswer,
             sys::rtcSignalingState_RTC_SIGNALING_HAVE_REMOTE_PRANSWER => Self::HaveRemotePranswer,
-            _ => panic!("Unknown rtcSignalingState: {}", state),
+            state => Self::Unknown(state as i32),
         }
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
-pub enum IceState {
-    New,
-    Checking,
-    Connected,
-    Completed,
-    Failed,
-    Disconnected,
-    Closed,
-}
-
 impl IceState {
-    fn from_raw(state: sys::rtcIceState) -> Self {
+    pub(crate) fn from_raw(state: sys::rtcIceState) -> Self {
         match state {
             sys::rtcIceState_RTC_ICE_NEW => Self::New,
             sys::rtcIceState_RTC_ICE_CHECKING => Self::Checking,
@@ -100,68 +81,13 @@ impl IceState {
             sys::rtcIceState_RTC_ICE_FAILED => Self::Failed,
             sys::rtcIceState_RTC_ICE_DISCONNECTED => Self::Disconnected,
             sys::rtcIceState_RTC_ICE_CLOSED => Self::Closed,
-            _ => panic!("Unknown rtcIceState: {}", state),
+            state => Self::Unknown(state as i32),
         }
     }
 }
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Hash)]
-pub struct CandidatePair {
-    pub local: String,
-    pub remote: String,
-}
-
-#[derive(Derivative, Serialize, Deserialize)]
-#[derivative(Debug)]
-pub struct SessionDescription {
-    #[derivative(Debug(format_with = "fmt_sdp"))]
-    #[serde(with = "serde_sdp")]
-    pub sdp: SdpSession,
-    #[serde(rename = "type")]
-    pub sdp_type: SdpType,
-}
-
-pub fn fmt_sdp(sdp: &SdpSession, f: &mut fmt::Formatter) -> std::result::Result<(), fmt::Error> {
-    let sdp = sdp
-        .to_string()
-        .trim_end()
-        .split("\r\n")
-        .collect::<Vec<_>>()
-        .join("; ");
-    f.write_str(format!("{{ {} }}", sdp).as_str())
-}
-
-pub mod serde_sdp {
-    use super::SdpSession;
-    use serde::{de, Deserialize, Deserializer, Serializer};
-
-    pub fn serialize<S>(sdp: &SdpSession, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        serializer.serialize_str(&sdp.to_string())
-    }
-
-    pub fn deserialize<'de, D>(deserializer: D) -> std::result::Result<SdpSession, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        let sdp = String::deserialize(deserializer)?;
-        webrtc_sdp::parse_sdp(&sdp, false).map_err(de::Error::custom)
-    }
-}
-
-#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
-pub enum SdpType {
-    Answer,
-    Offer,
-    Pranswer,
-    Rollback,
-}
-
 impl SdpType {
-    fn from(val: &str) -> Result<Self> {
+    pub(crate) fn from(val: &str) -> Result<Self> {
         match val {
             "answer" => Ok(Self::Answer),
             "offer" => Ok(Self::Offer),
@@ -171,7 +97,7 @@ impl SdpType {
         }
     }
 
-    fn val(&self) -> &'static str {
+    pub(crate) fn val(&self) -> &'static str {
         match self {
             Self::Answer => "answer",
             Self::Offer => "offer",
@@ -181,13 +107,31 @@ impl SdpType {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub struct IceCandidate {
-    pub candidate: String,
-    #[serde(rename = "sdpMid")]
-    pub mid: String,
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Hash)]
+pub struct CandidatePair {
+    pub local: String,
+    pub remote: String,
+}
+
+/// A local candidate gathered so far, see [`RtcPeerConnection::local_candidates`].
+#[derive(Debug, Clone)]
+pub struct LocalCandidate {
+    pub candidate: IceCandidate,
+    /// Type, transport, address and priority of the candidate, `None` when it couldn't be
+    /// parsed
+    pub attribute: Option<SdpAttributeCandidate>,
 }
 
+/// Callbacks of a [`RtcPeerConnection`].
+///
+/// Events are delivered in order from a thread dedicated to the peer connection, a slow
+/// callback delays the following events but never blocks libdatachannel's own threads.
+///
+/// As callbacks run outside of libdatachannel's call stack, they may call back into the
+/// connection they belong to, for instance through an `Arc<Mutex<_>>` shared with the
+/// signaling code. The handlers of data channels and tracks are called from libdatachannel's
+/// threads, wrap them with [`WorkerPool::dispatch`](crate::WorkerPool::dispatch) to defer
+/// their callbacks as well.
 #[allow(unused_variables)]
 #[allow(clippy::boxed_local)]
 pub trait PeerConnectionHandler {
@@ -195,22 +139,282 @@ pub trait PeerConnectionHandler {
 
     fn data_channel_handler(&mut self, info: DataChannelInfo) -> Self::DCH;
 
+    /// Called before [`on_description`](Self::on_description) to adjust the local description
+    /// about to be signaled. Changes only affect what the remote peer receives.
+    fn transform_local_description(&mut self, sess_desc: &mut SessionDescription) {}
     fn on_description(&mut self, sess_desc: SessionDescription) {}
     fn on_candidate(&mut self, cand: IceCandidate) {}
     fn on_connection_state_change(&mut self, state: ConnectionState) {}
     fn on_gathering_state_change(&mut self, state: GatheringState) {}
+    /// Called once gathering is complete, after the last [`on_candidate`](Self::on_candidate).
+    fn on_end_of_candidates(&mut self) {}
     fn on_signaling_state_change(&mut self, state: SignalingState) {}
     fn on_ice_state_change(&mut self, state: IceState) {}
     fn on_data_channel(&mut self, data_channel: Box<RtcDataChannel<Self::DCH>>) {}
+    /// Called every [`RtcConfig::stats_interval`], never called otherwise.
+    fn on_stats(&mut self, report: StatsReport) {}
+    /// Called when the [`QualityLevel`] changes, it is only evaluated along with
+    /// [`on_stats`](Self::on_stats).
+    fn on_quality_change(&mut self, quality: ConnectionQuality) {}
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Hash)]
-pub struct PeerConnectionId(i32);
+pub struct PeerConnectionId(pub(crate) i32);
+
+/// Events queued by the libdatachannel callbacks for the dispatcher thread.
+enum PeerConnectionEvent {
+    Description(Box<SessionDescription>),
+    Candidate(IceCandidate),
+    ConnectionState(ConnectionState),
+    GatheringState(GatheringState),
+    SignalingState(SignalingState),
+    IceState(IceState),
+    DataChannel(DataChannelInfo),
+    /// Sent by the stats poller
+    Stats,
+}
+
+/// State reached from the callbacks, registered as user pointer.
+///
+/// It lives in its own allocation so that moving a [`RtcPeerConnection`] out of its box
+/// doesn't invalidate the pointer held by libdatachannel nor the dispatcher thread.
+struct PeerConnectionState<P> {
+    id: PeerConnectionId,
+    /// Taken on drop to stop the dispatcher
+    events: Mutex<Option<mpsc::Sender<PeerConnectionEvent>>>,
+    /// Only ever accessed from the dispatcher thread
+    pc_handler: UnsafeCell<P>,
+    address_family: AddressFamily,
+    candidate_filter: Option<CandidateFilter>,
+    /// Set when host candidates are signaled with mDNS names
+    #[cfg(feature = "mdns")]
+    host_names: Option<HostNames>,
+    /// Set when fingerprints are shared with the peer connections of the process
+    #[cfg(feature = "insecure")]
+    fingerprints: Option<LocalFingerprints>,
+    /// Set before the connection is deleted, the dispatcher must not close it afterwards
+    deleted: Mutex<bool>,
+    error_policy: ErrorPolicy,
+    /// Shared with the data channels and tracks
+    traffic: Arc<Traffic>,
+    status: Mutex<Status>,
+    /// Entered by the dispatcher, the callbacks and around libdatachannel calls, so that the
+    /// logs forwarded meanwhile carry the connection id
+    span: logger::Span,
+    /// Data channels to include in the [`StatsReport`]
+    channels: Mutex<Vec<(DataChannelId, Weak<ChannelCounters>)>>,
+    /// Signaled local candidates, cleared when gathering starts over
+    local_candidates: Mutex<Vec<LocalCandidate>>,
+    timeline: Option<Mutex<Timeline>>,
+    /// Last level passed to the handler
+    quality_level: Mutex<Option<QualityLevel>>,
+}
+
+/// Latest states reported by the callbacks, for [`RtcPeerConnection::stats`].
+struct Status {
+    connection_state: ConnectionState,
+    ice_state: IceState,
+    connected_at: Option<SystemTime>,
+}
+
+// The callbacks share the state across libdatachannel's threads, but the handler is only
+// reached from the dispatcher thread.
+unsafe impl<P: Send> Sync for PeerConnectionState<P> {}
+
+impl<P> PeerConnectionState<P> {
+    fn keep_candidate(&self, cand: &SdpAttributeCandidate) -> bool {
+        self.address_family.allows(&cand.address)
+            && self
+                .candidate_filter
+                .as_ref()
+                .is_none_or(|filter| filter(cand))
+    }
+
+    /// Removes the candidates which shouldn't be signaled from a local description.
+    fn filter_candidates(&self, sdp: &mut SdpSession) {
+        for media in &mut sdp.media {
+            let candidates = media.get_attributes_of_type(SdpAttributeType::Candidate);
+            let kept = candidates
+                .iter()
+                .filter(|attr| match attr {
+                    SdpAttribute::Candidate(cand) => self.keep_candidate(cand),
+                    _ => true,
+                })
+                .map(|&attr| attr.clone())
+                .collect::<Vec<_>>();
+            if kept.len() == candidates.len() {
+                continue;
+            }
+            media.remove_attribute(SdpAttributeType::Candidate);
+            for attr in kept {
+                media.add_attribute(attr).ok();
+            }
+        }
+    }
 
+    /// Replaces the local addresses of a candidate with mDNS names, when enabled.
+    #[cfg(feature = "mdns")]
+    fn obfuscate_candidate(
+        &self,
+        mut cand: IceCandidate,
+        mut attribute: Option<SdpAttributeCandidate>,
+    ) -> (IceCandidate, Option<SdpAttributeCandidate>) {
+        if let (Some(names), Some(attr)) = (&self.host_names, &mut attribute) {
+            if names.obfuscate(attr) {
+                let prefix = if cand.candidate.starts_with("a=") { "a=" } else { "" };
+                cand.candidate = format!("{}candidate:{}", prefix, attr);
+            }
+        }
+        (cand, attribute)
+    }
+
+    fn send_event(&self, event: PeerConnectionEvent) {
+        if let Some(events) = &*self.events.lock() {
+            events.send(event).ok();
+        }
+    }
+
+    fn selected_candidate_pair(&self) -> Result<Option<CandidatePair>> {
+        let mut local_buf = [0u8; crate::FFI_STRING_STACK_SIZE];
+        let mut remote_buf = [0u8; crate::FFI_STRING_STACK_SIZE];
+        let mut pair = self.read_candidate_pair(&mut local_buf, &mut remote_buf);
+        let mut buf_size = local_buf.len();
+        while let Err(Error::TooSmall) = pair {
+            pair = crate::next_ffi_size(buf_size, unsafe {
+                sys::rtcGetSelectedCandidatePair(
+                    self.id.0,
+                    ptr::null_mut() as *mut c_char,
+                    0,
+                    ptr::null_mut() as *mut c_char,
+                    0,
+                )
+            })
+            .and_then(|size| {
+                buf_size = size;
+                let mut local_buf = vec![0; buf_size];
+                let mut remote_buf = vec![0; buf_size];
+                self.read_candidate_pair(&mut local_buf, &mut remote_buf)
+            });
+        }
+
+        match pair {
+            Ok(pair) => Ok(Some(pair)),
+            Err(Error::NotAvailable) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn stats(&self) -> PeerConnectionStats {
+        let (connection_state, ice_state, connected_at) = {
+            let status = self.status.lock();
+            (
+                status.connection_state,
+                status.ice_state,
+                status.connected_at,
+            )
+        };
+        PeerConnectionStats::new(
+            &self.traffic,
+            connection_state,
+            ice_state,
+            connected_at,
+            self.selected_candidate_pair().unwrap_or_else(|err| {
+                self.report("selected_candidate_pair", &err);
+                None
+            }),
+        )
+    }
+
+    fn stats_report(&self) -> StatsReport {
+        let mut channels = self.channels.lock();
+        channels.retain(|(_, counters)| counters.strong_count() > 0);
+        StatsReport {
+            connection: self.stats(),
+            data_channels: channels
+                .iter()
+                .filter_map(|(id, counters)| Some((*id, counters.upgrade()?.snapshot())))
+                .collect(),
+        }
+    }
+
+    /// Keeps track of a data channel for the [`StatsReport`].
+    fn watch_channel<D>(&self, dc: &RtcDataChannel<D>)
+    where
+        D: DataChannelHandler + Send,
+    {
+        self.channels
+            .lock()
+            .push((dc.id(), Arc::downgrade(dc.counters())));
+    }
+
+    fn read_candidate_pair(
+        &self,
+        local_buf: &mut [u8],
+        remote_buf: &mut [u8],
+    ) -> Result<CandidatePair> {
+        let len = check(unsafe {
+            sys::rtcGetSelectedCandidatePair(
+                self.id.0,
+                local_buf.as_mut_ptr() as *mut c_char,
+                local_buf.len() as i32,
+                remote_buf.as_mut_ptr() as *mut c_char,
+                remote_buf.len() as i32,
+            )
+        })? as usize;
+
+        // The returned size is the largest of both, so each string ends at its own NUL
+        Ok(CandidatePair {
+            local: crate::ffi_string(local_buf, len)?,
+            remote: crate::ffi_string(remote_buf, len)?,
+        })
+    }
+
+    fn record(&self, event: TimelineEvent) {
+        if let Some(timeline) = &self.timeline {
+            timeline.lock().record(event);
+        }
+    }
+
+    /// Records the outcome of a call made on the connection.
+    fn record_call<T>(
+        &self,
+        res: Result<T>,
+        call: &'static str,
+        event: TimelineEvent,
+    ) -> Result<T> {
+        match &res {
+            Ok(_) => self.record(event),
+            Err(err) => self.record(TimelineEvent::Failed {
+                call,
+                error: err.to_string(),
+            }),
+        }
+        res
+    }
+
+    /// Handles a failure of `op` according to the [`ErrorPolicy`].
+    fn report(&self, op: &str, err: &Error) {
+        match &self.error_policy {
+            ErrorPolicy::Log => {
+                logger::warn!("RtcPeerConnection id={:?} {}: {}", self.id, op, err)
+            }
+            ErrorPolicy::Callback(callback) => callback(op, err),
+        }
+    }
+}
+
+/// A peer connection, returned boxed by [`RtcPeerConnection::new`].
+///
+/// The callback state has its own allocation, it is fine to move the connection out of
+/// the box.
 pub struct RtcPeerConnection<P> {
-    lock: ReentrantMutex<()>,
     id: PeerConnectionId,
-    pc_handler: P,
+    state: Arc<PeerConnectionState<P>>,
+    dispatcher: Option<thread::JoinHandle<()>>,
+    /// Stopped by dropping the sender
+    stats_poller: Option<(mpsc::Sender<()>, thread::JoinHandle<()>)>,
+    /// Set once deleted, the id may then be reused by libdatachannel
+    deleted: bool,
 }
 
 impl<P> RtcPeerConnection<P>
@@ -223,15 +427,101 @@ where
         crate::ensure_logging();
 
         unsafe {
-            let id = check(sys::rtcCreatePeerConnection(&config.as_raw()))?;
+            let urls = config.raw_urls();
+            let id = call("rtcCreatePeerConnection", -1, || {
+                sys::rtcCreatePeerConnection(&config.as_raw(&urls))
+            })?;
+            crate::runtime::register();
+            crate::telemetry::peer_connection_created();
+            let (events, rx) = mpsc::channel();
             let mut rtc_pc = Box::new(RtcPeerConnection {
-                lock: ReentrantMutex::new(()),
                 id: PeerConnectionId(id),
-                pc_handler,
+                state: Arc::new(PeerConnectionState {
+                    id: PeerConnectionId(id),
+                    events: Mutex::new(Some(events)),
+                    pc_handler: UnsafeCell::new(pc_handler),
+                    address_family: config.address_family,
+                    candidate_filter: config.candidate_filter.clone(),
+                    #[cfg(feature = "mdns")]
+                    host_names: config.mdns_host_candidates.then(HostNames::default),
+                    #[cfg(feature = "insecure")]
+                    fingerprints: config
+                        .insecure_local_fingerprints
+                        .then(LocalFingerprints::default),
+                    deleted: Mutex::new(false),
+                    error_policy: config.error_policy.clone(),
+                    traffic: Arc::default(),
+                    status: Mutex::new(Status {
+                        connection_state: ConnectionState::New,
+                        ice_state: IceState::New,
+                        connected_at: None,
+                    }),
+                    span: logger::peer_connection_span(id),
+                    channels: Mutex::new(vec![]),
+                    local_candidates: Mutex::new(vec![]),
+                    timeline: config
+                        .timeline_capacity
+                        .map(|capacity| Mutex::new(Timeline::new(capacity))),
+                    quality_level: Mutex::new(None),
+                }),
+                dispatcher: None,
+                stats_poller: None,
+                deleted: false,
             });
-            let ptr = &mut *rtc_pc;
+            // The handler is only ever called from this thread, see `dispatch`. It holds a
+            // reference to the state, which outlives the connection when dropped from one of
+            // its own callbacks.
+            let addr = Arc::into_raw(Arc::clone(&rtc_pc.state)) as usize;
+            let dispatch: unsafe fn(usize, PeerConnectionEvent) = Self::dispatch;
+            let time_out: unsafe fn(usize) = Self::time_out;
+            let release: unsafe fn(usize) = Self::release;
+            let span = rtc_pc.state.span.clone();
+            let connection_timeout = config.connection_timeout;
+            rtc_pc.dispatcher = Some(thread::spawn(move || {
+                let _span = span.enter();
+                // Set while connecting with a connection timeout
+                let mut deadline: Option<Instant> = None;
+                loop {
+                    let event = match deadline {
+                        Some(deadline) => {
+                            rx.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                        }
+                        None => rx.recv().map_err(RecvTimeoutError::from),
+                    };
+                    let event = match event {
+                        Ok(event) => event,
+                        Err(RecvTimeoutError::Timeout) => {
+                            deadline = None;
+                            time_out(addr);
+                            continue;
+                        }
+                        Err(RecvTimeoutError::Disconnected) => break,
+                    };
+                    if let PeerConnectionEvent::ConnectionState(state) = &event {
+                        deadline = connection_timeout
+                            .filter(|_| *state == ConnectionState::Connecting)
+                            .map(|timeout| Instant::now() + timeout);
+                    }
+                    crate::panic::catch("RtcPeerConnection::dispatch", || dispatch(addr, event));
+                }
+                release(addr);
+            }));
+            if let (Some(interval), Some(events)) =
+                (config.stats_interval, rtc_pc.state.events.lock().clone())
+            {
+                let (stop, stopped) = mpsc::channel::<()>();
+                let poller = thread::spawn(move || {
+                    while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                        if events.send(PeerConnectionEvent::Stats).is_err() {
+                            break;
+                        }
+                    }
+                });
+                rtc_pc.stats_poller = Some((stop, poller));
+            }
+            let ptr = Arc::as_ptr(&rtc_pc.state);
 
-            sys::rtcSetUserPointer(id, ptr as *mut _ as *mut c_void);
+            sys::rtcSetUserPointer(id, ptr as *mut c_void);
 
             check(sys::rtcSetLocalDescriptionCallback(
                 id,
@@ -278,32 +568,53 @@ where
         sdp_type: *const c_char,
         ptr: *mut c_void,
     ) {
-        let rtc_pc = &mut *(ptr as *mut RtcPeerConnection<P>);
-
-        let sdp = CStr::from_ptr(sdp).to_string_lossy();
-        let sdp = match parse_sdp(&sdp, false) {
-            Ok(sdp) => sdp,
-            Err(err) => {
-                logger::warn!("Ignoring invalid SDP: {}", err);
-                logger::debug!("{}", sdp);
+        crate::panic::catch("RtcPeerConnection::local_description_cb", || {
+            let Some(pc) = (ptr as *const PeerConnectionState<P>).as_ref() else {
                 return;
-            }
-        };
+            };
+            let _span = pc.span.enter();
 
-        let sdp_type = CStr::from_ptr(sdp_type).to_string_lossy();
-        let sdp_type = match SdpType::from(&sdp_type) {
-            Ok(sdp_type) => sdp_type,
-            Err(_) => {
-                logger::warn!("Ignoring session with invalid SdpType: {}", sdp_type);
-                logger::debug!("{}", sdp);
-                return;
+            let sdp = match crate::ffi_str(CStr::from_ptr(sdp)) {
+                Ok(sdp) => sdp,
+                Err(err) => {
+                    pc.report("local_description_cb", &err);
+                    return;
+                }
+            };
+            let mut sdp = match parse_sdp(&sdp, false) {
+                Ok(sdp) => sdp,
+                Err(err) => {
+                    logger::debug!("{}", sdp);
+                    pc.report("local_description_cb", &Error::BadString(err.to_string()));
+                    return;
+                }
+            };
+            pc.filter_candidates(&mut sdp);
+            #[cfg(feature = "mdns")]
+            if let Some(names) = &pc.host_names {
+                names.obfuscate_description(&mut sdp);
             }
-        };
 
-        let sess_desc = SessionDescription { sdp, sdp_type };
+            let sdp_type = CStr::from_ptr(sdp_type).to_string_lossy();
+            let sdp_type = match SdpType::from(&sdp_type) {
+                Ok(sdp_type) => sdp_type,
+                Err(_) => {
+                    let err = Error::BadString(format!("invalid SdpType {}", sdp_type));
+                    pc.report("local_description_cb", &err);
+                    return;
+                }
+            };
 
-        let _guard = rtc_pc.lock.lock();
-        rtc_pc.pc_handler.on_description(sess_desc);
+            pc.record(TimelineEvent::LocalDescription {
+                sdp_type: sdp_type.clone(),
+            });
+            let sess_desc = SessionDescription { sdp, sdp_type };
+            #[cfg(feature = "insecure")]
+            if let Some(fingerprints) = &pc.fingerprints {
+                fingerprints.register(&sess_desc);
+            }
+            pc.send_event(PeerConnectionEvent::Description(Box::new(sess_desc)));
+        })
     }
 
     unsafe extern "C" fn local_candidate_cb(
@@ -312,82 +623,220 @@ where
         mid: *const c_char,
         ptr: *mut c_void,
     ) {
-        let rtc_pc = &mut *(ptr as *mut RtcPeerConnection<P>);
-
-        let candidate = CStr::from_ptr(cand).to_string_lossy().to_string();
-        let mid = CStr::from_ptr(mid).to_string_lossy().to_string();
-        let cand = IceCandidate { candidate, mid };
+        crate::panic::catch("RtcPeerConnection::local_candidate_cb", || {
+            let Some(pc) = (ptr as *const PeerConnectionState<P>).as_ref() else {
+                return;
+            };
+            let _span = pc.span.enter();
+
+            let (candidate, mid) = match (
+                crate::ffi_str(CStr::from_ptr(cand)),
+                crate::ffi_str(CStr::from_ptr(mid)),
+            ) {
+                (Ok(candidate), Ok(mid)) => (candidate, mid),
+                (Err(err), _) | (_, Err(err)) => {
+                    pc.report("local_candidate_cb", &err);
+                    return;
+                }
+            };
+            let cand = IceCandidate { candidate, mid };
+
+            let attribute = parse_candidate(&cand.candidate);
+            if let Some(attr) = &attribute {
+                if !pc.keep_candidate(attr) {
+                    logger::debug!("Filtered out local candidate: {}", cand.candidate);
+                    return;
+                }
+            }
+            #[cfg(feature = "mdns")]
+            let (cand, attribute) = pc.obfuscate_candidate(cand, attribute);
 
-        let _guard = rtc_pc.lock.lock();
-        rtc_pc.pc_handler.on_candidate(cand);
+            pc.record(TimelineEvent::LocalCandidate {
+                candidate: cand.candidate.clone(),
+            });
+            pc.local_candidates.lock().push(LocalCandidate {
+                candidate: cand.clone(),
+                attribute,
+            });
+            pc.send_event(PeerConnectionEvent::Candidate(cand));
+        })
     }
 
     unsafe extern "C" fn state_change_cb(_: i32, state: sys::rtcState, ptr: *mut c_void) {
-        let rtc_pc = &mut *(ptr as *mut RtcPeerConnection<P>);
-
-        let state = ConnectionState::from_raw(state);
+        crate::panic::catch("RtcPeerConnection::state_change_cb", || {
+            let Some(pc) = (ptr as *const PeerConnectionState<P>).as_ref() else {
+                return;
+            };
+            let _span = pc.span.enter();
+
+            let state = ConnectionState::from_raw(state);
+            crate::telemetry::connection_state_changed(state);
+            pc.record(TimelineEvent::ConnectionState { state });
+            {
+                let mut status = pc.status.lock();
+                status.connection_state = state;
+                if state == ConnectionState::Connected {
+                    status.connected_at = Some(SystemTime::now());
+                }
+            }
 
-        let _guard = rtc_pc.lock.lock();
-        rtc_pc.pc_handler.on_connection_state_change(state);
+            pc.send_event(PeerConnectionEvent::ConnectionState(state));
+        })
     }
 
     unsafe extern "C" fn gathering_state_cb(_: i32, state: sys::rtcState, ptr: *mut c_void) {
-        let rtc_pc = &mut *(ptr as *mut RtcPeerConnection<P>);
-
-        let state = GatheringState::from_raw(state);
+        crate::panic::catch("RtcPeerConnection::gathering_state_cb", || {
+            let Some(pc) = (ptr as *const PeerConnectionState<P>).as_ref() else {
+                return;
+            };
+            let _span = pc.span.enter();
 
-        let _guard = rtc_pc.lock.lock();
-        rtc_pc.pc_handler.on_gathering_state_change(state);
+            let state = GatheringState::from_raw(state);
+            if state == GatheringState::InProgress {
+                pc.local_candidates.lock().clear();
+            }
+            pc.record(TimelineEvent::GatheringState { state });
+            pc.send_event(PeerConnectionEvent::GatheringState(state));
+        })
     }
 
     unsafe extern "C" fn signaling_state_cb(_: i32, state: sys::rtcState, ptr: *mut c_void) {
-        let rtc_pc = &mut *(ptr as *mut RtcPeerConnection<P>);
+        crate::panic::catch("RtcPeerConnection::signaling_state_cb", || {
+            let Some(pc) = (ptr as *const PeerConnectionState<P>).as_ref() else {
+                return;
+            };
+            let _span = pc.span.enter();
 
-        let state = SignalingState::from_raw(state);
+            let state = SignalingState::from_raw(state);
+            pc.record(TimelineEvent::SignalingState { state });
 
-        let _guard = rtc_pc.lock.lock();
-        rtc_pc.pc_handler.on_signaling_state_change(state);
+            pc.send_event(PeerConnectionEvent::SignalingState(state));
+        })
     }
 
     unsafe extern "C" fn ice_state_cb(_: i32, state: sys::rtcIceState, ptr: *mut c_void) {
-        let rtc_pc = &mut *(ptr as *mut RtcPeerConnection<P>);
+        crate::panic::catch("RtcPeerConnection::ice_state_cb", || {
+            let Some(pc) = (ptr as *const PeerConnectionState<P>).as_ref() else {
+                return;
+            };
+            let _span = pc.span.enter();
 
-        let state = IceState::from_raw(state);
+            let state = IceState::from_raw(state);
+            pc.status.lock().ice_state = state;
+            pc.record(TimelineEvent::IceState { state });
 
-        let _guard = rtc_pc.lock.lock();
-        rtc_pc.pc_handler.on_ice_state_change(state);
+            pc.send_event(PeerConnectionEvent::IceState(state));
+        })
     }
 
     unsafe extern "C" fn data_channel_cb(_: i32, id: i32, ptr: *mut c_void) {
-        let rtc_pc = &mut *(ptr as *mut RtcPeerConnection<P>);
+        crate::panic::catch("RtcPeerConnection::data_channel_cb", || {
+            let Some(pc) = (ptr as *const PeerConnectionState<P>).as_ref() else {
+                // The peer connection is being dropped, nobody would release the channel
+                sys::rtcDeleteDataChannel(id);
+                return;
+            };
+            let _span = pc.span.enter();
 
-        let id = DataChannelId(id);
-        let info = DataChannelInfo {
-            id,
-            label: DataChannelInfo::label(id),
-            protocol: DataChannelInfo::protocol(id),
-            reliability: DataChannelInfo::reliability(id),
-            stream: DataChannelInfo::stream(id),
-        };
+            let id = DataChannelId(id);
+            let info = match DataChannelInfo::fetch(id) {
+                Ok(info) => info,
+                Err(err) => {
+                    // Most likely closed already, nobody would ever release it otherwise
+                    logger::debug!("Dropping incoming RtcDataChannel id={:?}", id);
+                    pc.report("data_channel_cb", &err);
+                    sys::rtcDeleteDataChannel(id.0);
+                    return;
+                }
+            };
 
-        let guard = rtc_pc.lock.lock();
-        let dc = rtc_pc.pc_handler.data_channel_handler(info);
-        drop(guard);
+            // Not waiting for the dispatcher here, libdatachannel replays the open event and
+            // buffers the messages until the data channel callbacks are set
+            pc.send_event(PeerConnectionEvent::DataChannel(info));
+        })
+    }
 
-        match RtcDataChannel::new(id, dc) {
-            Ok(dc) => {
-                let _guard = rtc_pc.lock.lock();
-                rtc_pc.pc_handler.on_data_channel(dc);
+    /// Delivers an event to the handler of the peer connection at `addr`.
+    ///
+    /// # Safety
+    ///
+    /// Must only be called from the dispatcher thread, before [`release`](Self::release).
+    unsafe fn dispatch(addr: usize, event: PeerConnectionEvent) {
+        let state = &*(addr as *const PeerConnectionState<P>);
+        let pc_handler = &mut *state.pc_handler.get();
+
+        match event {
+            PeerConnectionEvent::Description(mut sess_desc) => {
+                pc_handler.transform_local_description(&mut sess_desc);
+                pc_handler.on_description(*sess_desc);
             }
-            Err(err) => logger::error!(
-                "Couldn't create RtcDataChannel with id={:?} from RtcPeerConnection {:p}: {}",
-                id,
-                ptr,
-                err
-            ),
+            PeerConnectionEvent::Candidate(cand) => pc_handler.on_candidate(cand),
+            PeerConnectionEvent::ConnectionState(state) => {
+                pc_handler.on_connection_state_change(state)
+            }
+            PeerConnectionEvent::GatheringState(state) => {
+                let complete = state == GatheringState::Complete;
+                pc_handler.on_gathering_state_change(state);
+                if complete {
+                    pc_handler.on_end_of_candidates();
+                }
+            }
+            PeerConnectionEvent::SignalingState(state) => {
+                pc_handler.on_signaling_state_change(state)
+            }
+            PeerConnectionEvent::IceState(state) => pc_handler.on_ice_state_change(state),
+            PeerConnectionEvent::Stats => {
+                let report = state.stats_report();
+                let quality = report.connection.quality();
+                let previous = state.quality_level.lock().replace(quality.level());
+                pc_handler.on_stats(report);
+                if previous != Some(quality.level()) {
+                    pc_handler.on_quality_change(quality);
+                }
+            }
+            PeerConnectionEvent::DataChannel(info) => {
+                let id = info.id;
+                let dc = pc_handler.data_channel_handler(info);
+                let span = logger::data_channel_span(state.id.0, id.0);
+                let dc = RtcDataChannel::new(id, dc, Arc::clone(&state.traffic), span);
+
+                match dc {
+                    Ok(dc) => {
+                        state.watch_channel(&dc);
+                        pc_handler.on_data_channel(dc)
+                    }
+                    Err(err) => {
+                        logger::debug!("Couldn't create RtcDataChannel with id={:?}", id);
+                        state.report("data_channel", &err)
+                    }
+                }
+            }
+        }
+    }
+
+    /// Closes the peer connection at `addr`, which stayed connecting for too long.
+    ///
+    /// # Safety
+    ///
+    /// Must only be called from the dispatcher thread, before [`release`](Self::release).
+    unsafe fn time_out(addr: usize) {
+        let state = &*(addr as *const PeerConnectionState<P>);
+        let deleted = state.deleted.lock();
+        if !*deleted {
+            logger::warn!("PeerConnection {:?} timed out while connecting", state.id);
+            sys::rtcClosePeerConnection(state.id.0);
         }
     }
 
+    /// Drops the reference to the state held by the dispatcher thread.
+    ///
+    /// # Safety
+    ///
+    /// Must be called once by the dispatcher thread, after its last [`dispatch`](Self::dispatch).
+    unsafe fn release(addr: usize) {
+        drop(Arc::from_raw(addr as *const PeerConnectionState<P>));
+    }
+
     pub fn id(&self) -> PeerConnectionId {
         self.id
     }
@@ -402,10 +851,17 @@ where
         C: DataChannelHandler + Send,
     {
         let label = CString::new(label)?;
-        let id = DataChannelId(check(unsafe {
+        let _span = self.state.span.enter();
+        let id = DataChannelId(call("rtcCreateDataChannel", self.id.0, || unsafe {
             sys::rtcCreateDataChannel(self.id.0, label.as_ptr())
         })?);
-        RtcDataChannel::new(id, dc_handler)
+        RtcDataChannel::new(
+            id,
+            dc_handler,
+            Arc::clone(&self.state.traffic),
+            logger::data_channel_span(self.id.0, id.0),
+        )
+        .inspect(|dc| self.state.watch_channel(dc))
     }
 
     pub fn create_data_channel_ex<C>(
@@ -417,11 +873,28 @@ where
     where
         C: DataChannelHandler + Send,
     {
+        dc_init.validate(self.is_dtls_client())?;
+        #[cfg(feature = "compression")]
+        let compression = dc_init.compression_enabled();
         let label = CString::new(label)?;
-        let id = DataChannelId(check(unsafe {
-            sys::rtcCreateDataChannelEx(self.id.0, label.as_ptr(), &dc_init.as_raw()?)
+        let dc_init = dc_init.as_raw()?;
+        let _span = self.state.span.enter();
+        let id = DataChannelId(call("rtcCreateDataChannelEx", self.id.0, || unsafe {
+            sys::rtcCreateDataChannelEx(self.id.0, label.as_ptr(), &dc_init)
         })?);
-        RtcDataChannel::new(id, dc_handler)
+        let dc = RtcDataChannel::new(
+            id,
+            dc_handler,
+            Arc::clone(&self.state.traffic),
+            logger::data_channel_span(self.id.0, id.0),
+        )
+        .inspect(|dc| self.state.watch_channel(dc));
+        #[cfg(feature = "compression")]
+        let dc = dc.map(|mut dc| {
+            dc.set_compression(compression);
+            dc
+        });
+        dc
     }
 
     /// Creates a boxed [`RtcTrack`].
@@ -429,189 +902,378 @@ where
     where
         C: TrackHandler + Send,
     {
-        let desc = sdp_media.to_string();
-        let desc = CString::new(desc.strip_prefix("m=").unwrap_or(&desc))?;
-        let id = check(unsafe { sys::rtcAddTrack(self.id.0, desc.as_ptr()) })?;
-        RtcTrack::new(id, t_handler)
+        self.add_track_raw(&sdp_media.to_string(), t_handler)
+    }
+
+    /// Creates a boxed [`RtcTrack`] from a media description given as is to libdatachannel.
+    pub fn add_track_raw<C>(&mut self, media_desc: &str, t_handler: C) -> Result<Box<RtcTrack<C>>>
+    where
+        C: TrackHandler + Send,
+    {
+        let desc = CString::new(media_desc.strip_prefix("m=").unwrap_or(media_desc))?;
+        let _span = self.state.span.enter();
+        let id = call("rtcAddTrack", self.id.0, || unsafe {
+            sys::rtcAddTrack(self.id.0, desc.as_ptr())
+        })?;
+        RtcTrack::new(
+            id,
+            t_handler,
+            Arc::clone(&self.state.traffic),
+            logger::track_span(self.id.0, id),
+        )
+    }
+
+    /// Creates a boxed [`RtcTrack`] sending one simulcast encoding per layer.
+    ///
+    /// The `a=rid` and `a=simulcast` attributes are added to `sdp_media`, use
+    /// [`RtcTrack::send_layer`] to send RTP packets on a given layer.
+    pub fn add_simulcast_track<C>(
+        &mut self,
+        sdp_media: &SdpMedia,
+        layers: Vec<SimulcastLayer>,
+        t_handler: C,
+    ) -> Result<Box<RtcTrack<C>>>
+    where
+        C: TrackHandler + Send,
+    {
+        let mut sdp_media = sdp_media.clone();
+        SimulcastLayer::add_to(&layers, &mut sdp_media)?;
+        let mut track = self.add_track(&sdp_media, t_handler)?;
+        track.set_layers(layers);
+        Ok(track)
     }
 
     pub fn add_track_ex<C>(&mut self, t_init: &TrackInit, t_handler: C) -> Result<Box<RtcTrack<C>>>
     where
         C: TrackHandler + Send,
     {
-        let id = check(unsafe { sys::rtcAddTrackEx(self.id.0, &t_init.as_raw()) })?;
-        RtcTrack::new(id, t_handler)
+        match t_init.as_raw() {
+            Some(raw) => {
+                let _span = self.state.span.enter();
+                let id = call("rtcAddTrackEx", self.id.0, || unsafe {
+                    sys::rtcAddTrackEx(self.id.0, &raw)
+                })?;
+                RtcTrack::new(
+                    id,
+                    t_handler,
+                    Arc::clone(&self.state.traffic),
+                    logger::track_span(self.id.0, id),
+                )
+            }
+            None => self.add_track(&t_init.sdp_media()?, t_handler),
+        }
     }
 
     pub fn set_local_description(&mut self, sdp_type: SdpType) -> Result<()> {
         let sdp_type = CString::new(sdp_type.val())?;
-        check(unsafe { sys::rtcSetLocalDescription(self.id.0, sdp_type.as_ptr()) })?;
+        let _span = self.state.span.enter();
+        call("rtcSetLocalDescription", self.id.0, || unsafe {
+            sys::rtcSetLocalDescription(self.id.0, sdp_type.as_ptr())
+        })?;
         Ok(())
     }
 
+    /// The remote description must carry the DTLS certificate fingerprint of the peer, unless
+    /// [`RtcConfig::insecure_local_fingerprints`] is enabled (**insecure** feature).
     pub fn set_remote_description(&mut self, sess_desc: &SessionDescription) -> Result<()> {
+        #[cfg(feature = "insecure")]
+        let filled = self
+            .state
+            .fingerprints
+            .as_ref()
+            .and_then(|_| LocalFingerprints::fill(sess_desc));
+        #[cfg(feature = "insecure")]
+        let sess_desc = filled.as_ref().unwrap_or(sess_desc);
         let sdp = CString::new(sess_desc.sdp.to_string())?;
         let sdp_type = CString::new(sess_desc.sdp_type.val())?;
-        check(unsafe { sys::rtcSetRemoteDescription(self.id.0, sdp.as_ptr(), sdp_type.as_ptr()) })?;
+        let _span = self.state.span.enter();
+        let res = call("rtcSetRemoteDescription", self.id.0, || unsafe {
+            sys::rtcSetRemoteDescription(self.id.0, sdp.as_ptr(), sdp_type.as_ptr())
+        });
+        let event = TimelineEvent::RemoteDescriptionSet {
+            sdp_type: sess_desc.sdp_type.clone(),
+        };
+        self.state
+            .record_call(res, "rtcSetRemoteDescription", event)?;
         Ok(())
     }
 
+    /// Adds a remote candidate, the end-of-candidates marker (see
+    /// [`IceCandidate::is_end_of_candidates`]) is accepted and ignored.
+    ///
+    /// Fails when libdatachannel rejects the candidate, for instance when it is malformed or
+    /// when no remote description is set yet.
     pub fn add_remote_candidate(&mut self, cand: &IceCandidate) -> Result<()> {
-        let mid = CString::new(cand.mid.clone())?;
-        let cand = CString::new(cand.candidate.clone())?;
-        unsafe { sys::rtcAddRemoteCandidate(self.id.0, cand.as_ptr(), mid.as_ptr()) };
+        if cand.is_end_of_candidates() {
+            return Ok(());
+        }
+        let mid = CString::new(cand.mid.as_str())?;
+        let candidate = CString::new(cand.candidate.as_str())?;
+        let _span = self.state.span.enter();
+        let res = call("rtcAddRemoteCandidate", self.id.0, || unsafe {
+            sys::rtcAddRemoteCandidate(self.id.0, candidate.as_ptr(), mid.as_ptr())
+        });
+        let event = TimelineEvent::RemoteCandidateAdded {
+            candidate: cand.candidate.clone(),
+        };
+        self.state
+            .record_call(res, "rtcAddRemoteCandidate", event)?;
         Ok(())
     }
 
+    /// Adds a batch of remote candidates, for instance when signaling delivers them all once
+    /// gathering is complete.
+    ///
+    /// A result is returned for each candidate, in order, and a failing candidate doesn't
+    /// prevent the following ones from being added.
+    pub fn add_remote_candidates(&mut self, cands: &[IceCandidate]) -> Vec<Result<()>> {
+        cands
+            .iter()
+            .map(|cand| self.add_remote_candidate(cand))
+            .collect()
+    }
+
+    /// Returns whether the local peer is the DTLS client, once the descriptions tell.
+    fn is_dtls_client(&self) -> Option<bool> {
+        match setup_role(&self.local_description()?.sdp)? {
+            SdpAttributeSetup::Active => Some(true),
+            SdpAttributeSetup::Passive => Some(false),
+            SdpAttributeSetup::Actpass => match setup_role(&self.remote_description()?.sdp)? {
+                SdpAttributeSetup::Active => Some(false),
+                SdpAttributeSetup::Passive => Some(true),
+                _ => None,
+            },
+            SdpAttributeSetup::Holdconn => None,
+        }
+    }
+
+    /// Returns `None` when there is no local description yet, other failures are handled
+    /// according to the [`ErrorPolicy`].
     pub fn local_description(&self) -> Option<SessionDescription> {
-        let sdp = self
-            .read_string_ffi(sys::rtcGetLocalDescription, "local_description")
-            .map(|sdp| webrtc_sdp::parse_sdp(&sdp, false).map_err(|e| e.to_string()));
-
-        let sdp_type = self
-            .read_string_ffi(sys::rtcGetLocalDescriptionType, "local_description_type")
-            .map(|sdp_type| SdpType::from(&sdp_type).map_err(|e| e.to_string()));
-
-        match (sdp, sdp_type) {
-            (Some(Ok(sdp)), Some(Ok(sdp_type))) => Some(SessionDescription { sdp, sdp_type }),
-            (Some(Err(e)), _) | (None, Some(Err(e))) => {
-                logger::error!("Got an invalid Sessiondescription: {}", e);
-                None
+        self.or_report("local_description", self.try_local_description())
+    }
+
+    /// Like [`local_description`](Self::local_description), but returns the errors.
+    pub fn try_local_description(&self) -> Result<Option<SessionDescription>> {
+        let sess_desc =
+            self.read_description(sys::rtcGetLocalDescription, sys::rtcGetLocalDescriptionType)?;
+        // Signaled as is by some, it must not give away the addresses hidden by the callbacks
+        #[cfg(feature = "mdns")]
+        let sess_desc = sess_desc.map(|mut sess_desc| {
+            if let Some(names) = &self.state.host_names {
+                names.obfuscate_description(&mut sess_desc.sdp);
             }
-            _ => None,
-        }
+            sess_desc
+        });
+        Ok(sess_desc)
     }
 
+    /// Returns `None` when there is no remote description yet, other failures are handled
+    /// according to the [`ErrorPolicy`].
     pub fn remote_description(&self) -> Option<SessionDescription> {
-        let sdp = self
-            .read_string_ffi(sys::rtcGetRemoteDescription, "remote_description")
-            .map(|sdp| webrtc_sdp::parse_sdp(&sdp, false).map_err(|e| e.to_string()));
-
-        let sdp_type = self
-            .read_string_ffi(sys::rtcGetRemoteDescriptionType, "remote_description_type")
-            .map(|sdp_type| SdpType::from(&sdp_type).map_err(|e| e.to_string()));
-
-        match (sdp, sdp_type) {
-            (Some(Ok(sdp)), Some(Ok(sdp_type))) => Some(SessionDescription { sdp, sdp_type }),
-            (Some(Err(e)), _) | (None, Some(Err(e))) => {
-                logger::error!("Got an invalid Sessiondescription: {}", e);
-                None
-            }
-            _ => None,
-        }
+        self.or_report("remote_description", self.try_remote_description())
+    }
+
+    /// Like [`remote_description`](Self::remote_description), but returns the errors.
+    pub fn try_remote_description(&self) -> Result<Option<SessionDescription>> {
+        self.read_description(
+            sys::rtcGetRemoteDescription,
+            sys::rtcGetRemoteDescriptionType,
+        )
     }
 
     pub fn local_address(&self) -> Option<String> {
-        self.read_string_ffi(sys::rtcGetLocalAddress, "local_address")
+        self.or_report("local_address", self.try_local_address())
+    }
+
+    /// Like [`local_address`](Self::local_address), but returns the errors.
+    pub fn try_local_address(&self) -> Result<Option<String>> {
+        self.read_string_ffi(sys::rtcGetLocalAddress)
     }
 
     pub fn remote_address(&self) -> Option<String> {
-        self.read_string_ffi(sys::rtcGetRemoteAddress, "remote_address")
+        self.or_report("remote_address", self.try_remote_address())
     }
 
+    /// Like [`remote_address`](Self::remote_address), but returns the errors.
+    pub fn try_remote_address(&self) -> Result<Option<String>> {
+        self.read_string_ffi(sys::rtcGetRemoteAddress)
+    }
+
+    /// Returns `None` until a candidate pair is selected, other failures are handled
+    /// according to the [`ErrorPolicy`].
     pub fn selected_candidate_pair(&self) -> Option<CandidatePair> {
-        let buf_size = check(unsafe {
-            sys::rtcGetSelectedCandidatePair(
-                self.id.0,
-                ptr::null_mut() as *mut c_char,
-                0,
-                ptr::null_mut() as *mut c_char,
-                0,
-            )
-        });
+        self.or_report(
+            "selected_candidate_pair",
+            self.try_selected_candidate_pair(),
+        )
+    }
 
-        let buf_size = match buf_size {
-            Ok(buf_size) => buf_size as usize,
-            Err(err) => {
-                logger::error!("Couldn't get buffer size: {}", err);
-                return None;
-            }
+    /// Like [`selected_candidate_pair`](Self::selected_candidate_pair), but returns the errors.
+    pub fn try_selected_candidate_pair(&self) -> Result<Option<CandidatePair>> {
+        self.state.selected_candidate_pair()
+    }
+
+    /// Returns the recorded negotiation events, oldest first, see
+    /// [`RtcConfig::record_timeline`].
+    pub fn timeline(&self) -> Vec<TimelineEntry> {
+        self.state
+            .timeline
+            .as_ref()
+            .map(|timeline| timeline.lock().entries())
+            .unwrap_or_default()
+    }
+
+    /// Returns the local candidates signaled so far, the filtered out ones aren't included.
+    pub fn local_candidates(&self) -> Vec<LocalCandidate> {
+        self.state.local_candidates.lock().clone()
+    }
+
+    /// Takes a snapshot of the connection, the equivalent of the browser's `getStats()`.
+    pub fn stats(&self) -> PeerConnectionStats {
+        self.state.stats()
+    }
+
+    /// Shorthand for the [`quality`](PeerConnectionStats::quality) of a [`stats`](Self::stats)
+    /// snapshot.
+    pub fn quality(&self) -> ConnectionQuality {
+        self.stats().quality()
+    }
+
+    fn read_description(
+        &self,
+        sdp_fn: unsafe extern "C" fn(i32, *mut c_char, i32) -> i32,
+        sdp_type_fn: unsafe extern "C" fn(i32, *mut c_char, i32) -> i32,
+    ) -> Result<Option<SessionDescription>> {
+        let Some(sdp) = self.read_string_ffi(sdp_fn)? else {
+            return Ok(None);
         };
+        let sdp = parse_sdp(&sdp, false).map_err(|err| Error::BadString(err.to_string()))?;
 
-        let mut local_buf = vec![0; buf_size];
-        let mut remote_buf = vec![0; buf_size];
-        match check(unsafe {
-            sys::rtcGetSelectedCandidatePair(
-                self.id.0,
-                local_buf.as_mut_ptr() as *mut c_char,
-                buf_size as i32,
-                remote_buf.as_mut_ptr() as *mut c_char,
-                buf_size as i32,
-            )
-        }) {
-            Ok(_) => {
-                let local = crate::ffi_string(&local_buf);
-                let remote = crate::ffi_string(&remote_buf);
-                match (local, remote) {
-                    (Ok(local), Ok(remote)) => Some(CandidatePair { local, remote }),
-                    (Ok(_), Err(err)) | (Err(err), Ok(_)) | (Err(err), Err(_)) => {
-                        logger::error!(
-                            "Couldn't get RtcPeerConnection {:p} candidate_pair: {}",
-                            self,
-                            err
-                        );
-                        None
-                    }
-                }
-            }
-            Err(Error::NotAvailable) => None,
-            Err(err) => {
-                logger::warn!(
-                    "Couldn't get RtcPeerConnection {:p} candidate_pair: {}",
-                    self,
-                    err
-                );
-                None
-            }
-        }
+        let Some(sdp_type) = self.read_string_ffi(sdp_type_fn)? else {
+            return Ok(None);
+        };
+        let sdp_type = SdpType::from(&sdp_type)
+            .map_err(|_| Error::BadString(format!("invalid SdpType {}", sdp_type)))?;
+
+        Ok(Some(SessionDescription { sdp, sdp_type }))
     }
 
     fn read_string_ffi(
         &self,
         str_fn: unsafe extern "C" fn(i32, *mut c_char, i32) -> i32,
-        prop: &str,
-    ) -> Option<String> {
-        let buf_size = match check(unsafe { str_fn(self.id.0, ptr::null_mut() as *mut c_char, 0) })
-        {
-            Ok(buf_size) => buf_size as usize,
-            Err(err) => {
-                logger::error!("Couldn't get buffer size: {}", err);
-                return None;
-            }
-        };
+    ) -> Result<Option<String>> {
+        match crate::read_ffi_string(|buf, size| unsafe { str_fn(self.id.0, buf, size) }) {
+            Ok(value) => Ok(Some(value)),
+            Err(Error::NotAvailable) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
 
-        let mut buf = vec![0; buf_size];
-        match check(unsafe { str_fn(self.id.0, buf.as_mut_ptr() as *mut c_char, buf_size as i32) })
-        {
-            Ok(_) => match String::from_utf8(buf) {
-                Ok(local) => Some(local.trim_matches(char::from(0)).to_string()),
-                Err(err) => {
-                    logger::error!(
-                        "Couldn't get RtcPeerConnection {:p} {}: {}",
-                        self,
-                        prop,
-                        err
-                    );
-                    None
-                }
-            },
-            Err(Error::NotAvailable) => None,
-            Err(err) => {
-                logger::warn!(
-                    "Couldn't get RtcPeerConnection {:p} {}: {}",
-                    self,
-                    prop,
-                    err
-                );
-                None
+    /// Flattens the result of a `try_*` accessor, reporting its error.
+    fn or_report<T>(&self, op: &str, res: Result<Option<T>>) -> Option<T> {
+        res.unwrap_or_else(|err| {
+            self.state.report(op, &err);
+            None
+        })
+    }
+}
+
+/// Returns the `a=setup` attribute of the first media having one, or of the session.
+fn setup_role(sdp: &SdpSession) -> Option<SdpAttributeSetup> {
+    sdp.media
+        .iter()
+        .map(|media| media.get_attribute(SdpAttributeType::Setup))
+        .chain(std::iter::once(sdp.get_attribute(SdpAttributeType::Setup)))
+        .find_map(|attr| match attr {
+            Some(SdpAttribute::Setup(setup)) => Some(setup.clone()),
+            _ => None,
+        })
+}
+
+fn parse_candidate(candidate: &str) -> Option<SdpAttributeCandidate> {
+    let line = format!("a={}", candidate.trim_start_matches("a="));
+    match parse_sdp_line(&line, 0).ok()?.sdp_type {
+        webrtc_sdp::SdpType::Attribute(SdpAttribute::Candidate(cand)) => Some(cand),
+        _ => None,
+    }
+}
+
+impl<P> RtcPeerConnection<P> {
+    /// Shuts `channels` down gracefully within `timeout` overall, then deletes the peer
+    /// connection, see [`RtcDataChannel::shutdown`].
+    ///
+    /// Every channel and the connection are deleted even when one fails, the first error is
+    /// returned.
+    pub fn shutdown<D, I>(self: Box<Self>, channels: I, timeout: Duration) -> Result<()>
+    where
+        D: DataChannelHandler + Send,
+        I: IntoIterator<Item = Box<RtcDataChannel<D>>>,
+    {
+        let deadline = Instant::now() + timeout;
+        let mut res = Ok(());
+        for dc in channels {
+            let shutdown = dc.shutdown(deadline.saturating_duration_since(Instant::now()));
+            res = res.and(shutdown);
+        }
+        let deleted = self.delete();
+        res.and(deleted)
+    }
+
+    /// Deletes the peer connection right away, returning the error dropping it would only log.
+    ///
+    /// Its data channels and tracks are closed but must still be dropped.
+    #[allow(clippy::boxed_local)]
+    pub fn delete(mut self: Box<Self>) -> Result<()> {
+        self.deleted = true;
+        self.teardown()
+    }
+
+    fn teardown(&mut self) -> Result<()> {
+        // Callbacks starting from now get a null pointer and return right away, those being
+        // run are waited for, none can reach the state afterwards
+        unsafe {
+            let id = self.id.0;
+            sys::rtcSetUserPointer(id, ptr::null_mut());
+            sys::rtcSetLocalDescriptionCallback(id, None);
+            sys::rtcSetLocalCandidateCallback(id, None);
+            sys::rtcSetStateChangeCallback(id, None);
+            sys::rtcSetGatheringStateChangeCallback(id, None);
+            sys::rtcSetSignalingStateChangeCallback(id, None);
+            sys::rtcSetIceStateChangeCallback(id, None);
+            sys::rtcSetDataChannelCallback(id, None);
+        }
+
+        *self.state.deleted.lock() = true;
+        let res = check(unsafe { sys::rtcDeletePeerConnection(self.id.0) });
+
+        // The poller holds a sender too, it must be stopped for the dispatcher to stop
+        if let Some((stop, poller)) = self.stats_poller.take() {
+            drop(stop);
+            poller.join().ok();
+        }
+
+        // Let the dispatcher deliver the pending events and stop. When dropped from one of its
+        // callbacks it can't be joined, it then releases the state once done.
+        drop(self.state.events.lock().take());
+        if let Some(dispatcher) = self.dispatcher.take() {
+            if dispatcher.thread().id() != thread::current().id() {
+                dispatcher.join().ok();
             }
         }
+        crate::runtime::release();
+        crate::telemetry::peer_connection_deleted();
+        res.map(|_| ())
     }
 }
 
 impl<P> Drop for RtcPeerConnection<P> {
     fn drop(&mut self) {
-        if let Err(err) = check(unsafe { sys::rtcDeletePeerConnection(self.id.0) }) {
+        if self.deleted {
+            return;
+        }
+        self.deleted = true;
+        if let Err(err) = self.teardown() {
             logger::error!(
                 "Error while dropping RtcPeerConnection id={:?} {:p}: {}",
                 self.id,
@@ -0,0 +1,177 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+
+use crossbeam_queue::ArrayQueue;
+
+use crate::datachannel::DataChannelHandler;
+use crate::error::ChannelError;
+use crate::track::TrackHandler;
+
+/// What to do with a received message when the queue is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum QueueOverflow {
+    /// Drops the message being received.
+    #[default]
+    DropNewest,
+    /// Evicts the oldest queued message to make room.
+    DropOldest,
+}
+
+struct Shared {
+    messages: ArrayQueue<Vec<u8>>,
+    overflow: QueueOverflow,
+    dropped: AtomicU64,
+    closed: AtomicBool,
+}
+
+/// A handler pushing received messages into a bounded lock-free queue.
+///
+/// Messages are never handed to the inner handler, they are read from the [`QueueReader`]
+/// at the application's own pace, so a slow consumer can't stall libdatachannel's network
+/// thread. Other callbacks are forwarded to the inner handler right away.
+pub struct ReceiveQueue<H> {
+    handler: H,
+    shared: Arc<Shared>,
+}
+
+/// The reading end of a [`ReceiveQueue`], it can be cloned and sent to other threads.
+#[derive(Clone)]
+pub struct QueueReader {
+    shared: Arc<Shared>,
+}
+
+impl<H> ReceiveQueue<H> {
+    /// Creates a queue holding at most `capacity` messages, along with its reader.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn new(handler: H, capacity: usize, overflow: QueueOverflow) -> (Self, QueueReader) {
+        let shared = Arc::new(Shared {
+            messages: ArrayQueue::new(capacity),
+            overflow,
+            dropped: AtomicU64::new(0),
+            closed: AtomicBool::new(false),
+        });
+        let reader = QueueReader {
+            shared: Arc::clone(&shared),
+        };
+        (Self { handler, shared }, reader)
+    }
+
+    pub fn handler(&self) -> &H {
+        &self.handler
+    }
+
+    pub fn handler_mut(&mut self) -> &mut H {
+        &mut self.handler
+    }
+
+    fn push(&self, msg: &[u8]) {
+        let full = match self.shared.overflow {
+            QueueOverflow::DropNewest => self.shared.messages.push(msg.to_vec()).is_err(),
+            QueueOverflow::DropOldest => self.shared.messages.force_push(msg.to_vec()).is_some(),
+        };
+        if full {
+            self.shared.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+impl QueueReader {
+    /// Pops the oldest queued message.
+    pub fn pop(&self) -> Option<Vec<u8>> {
+        self.shared.messages.pop()
+    }
+
+    /// Pops the messages queued so far, messages received meanwhile are left for later.
+    pub fn drain(&self) -> impl Iterator<Item = Vec<u8>> + '_ {
+        (0..self.len()).map_while(|_| self.pop())
+    }
+
+    pub fn len(&self) -> usize {
+        self.shared.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shared.messages.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.shared.messages.capacity()
+    }
+
+    /// Number of messages dropped because the queue was full.
+    pub fn dropped(&self) -> u64 {
+        self.shared.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Whether the channel or track was closed, queued messages can still be read.
+    pub fn is_closed(&self) -> bool {
+        self.shared.closed.load(Ordering::Acquire)
+    }
+}
+
+impl<H> DataChannelHandler for ReceiveQueue<H>
+where
+    H: DataChannelHandler,
+{
+    fn on_open(&mut self) {
+        self.handler.on_open()
+    }
+
+    fn on_closed(&mut self) {
+        self.shared.closed.store(true, Ordering::Release);
+        self.handler.on_closed()
+    }
+
+    fn on_error(&mut self, err: &ChannelError) {
+        self.handler.on_error(err)
+    }
+
+    fn on_message(&mut self, msg: &[u8]) {
+        self.push(msg)
+    }
+
+    fn on_buffered_amount_low(&mut self) {
+        self.handler.on_buffered_amount_low()
+    }
+
+    fn on_available(&mut self) {
+        self.handler.on_available()
+    }
+
+    fn on_keepalive_timeout(&mut self) {
+        self.handler.on_keepalive_timeout()
+    }
+}
+
+impl<H> TrackHandler for ReceiveQueue<H>
+where
+    H: TrackHandler,
+{
+    fn on_open(&mut self) {
+        self.handler.on_open()
+    }
+
+    fn on_closed(&mut self) {
+        self.shared.closed.store(true, Ordering::Release);
+        self.handler.on_closed()
+    }
+
+    fn on_error(&mut self, err: &ChannelError) {
+        self.handler.on_error(err)
+    }
+
+    fn on_message(&mut self, msg: &[u8]) {
+        self.push(msg)
+    }
+
+    fn on_available(&mut self) {
+        self.handler.on_available()
+    }
+
+    fn on_bitrate(&mut self, bitrate: u32) {
+        self.handler.on_bitrate(bitrate)
+    }
+}
@@ -0,0 +1,106 @@
+use std::thread;
+
+use datachannel_sys as sys;
+use parking_lot::Mutex;
+
+use crate::error::{Error, Result};
+use crate::logger;
+
+struct Live {
+    /// Peer connections, data channels and tracks not dropped yet
+    objects: usize,
+    /// Set when a dropped runtime couldn't clean up right away
+    cleanup_pending: bool,
+}
+
+static LIVE: Mutex<Live> = Mutex::new(Live {
+    objects: 0,
+    cleanup_pending: false,
+});
+
+/// Called when a peer connection, data channel or track is created.
+pub(crate) fn register() {
+    LIVE.lock().objects += 1;
+}
+
+/// Called once a peer connection, data channel or track is deleted, runs the cleanup
+/// requested by a dropped [`RtcRuntime`] if it was the last one alive.
+///
+/// The last one may be dropped from a libdatachannel callback, whose thread `rtcCleanup`
+/// joins, so the cleanup runs from a thread of its own once the lock is released.
+pub(crate) fn release() {
+    let cleanup = {
+        let mut live = LIVE.lock();
+        live.objects -= 1;
+        live.objects == 0 && std::mem::take(&mut live.cleanup_pending)
+    };
+    if !cleanup {
+        return;
+    }
+    logger::debug!("Last object released, cleaning up libdatachannel");
+    let spawned = thread::Builder::new()
+        .name("rtc-cleanup".into())
+        .spawn(|| {
+            // Objects created in the meantime need the resources
+            if LIVE.lock().objects == 0 {
+                unsafe { sys::rtcCleanup() };
+            }
+        });
+    if let Err(err) = spawned {
+        logger::warn!("Couldn't start libdatachannel cleanup: {}", err);
+    }
+}
+
+/// A guard over libdatachannel's global resources.
+///
+/// Resources are preloaded when the runtime is created and released when it is dropped. As
+/// cleaning up while peer connections, data channels or tracks are still alive leads to
+/// crashes, the cleanup is deferred until the last of them is dropped.
+pub struct RtcRuntime {
+    cleaned_up: bool,
+}
+
+impl RtcRuntime {
+    pub fn new() -> Self {
+        LIVE.lock().cleanup_pending = false;
+        unsafe { sys::rtcPreload() };
+        Self { cleaned_up: false }
+    }
+
+    /// Returns the number of peer connections, data channels and tracks alive.
+    pub fn live_objects(&self) -> usize {
+        LIVE.lock().objects
+    }
+
+    /// Cleans up right away, fails with [`Error::Runtime`] when objects are still alive.
+    pub fn try_cleanup(&mut self) -> Result<()> {
+        let live = LIVE.lock();
+        if live.objects > 0 {
+            logger::warn!("Can't clean up, {} objects are still alive", live.objects);
+            return Err(Error::Runtime);
+        }
+        unsafe { sys::rtcCleanup() };
+        self.cleaned_up = true;
+        Ok(())
+    }
+}
+
+impl Default for RtcRuntime {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for RtcRuntime {
+    fn drop(&mut self) {
+        if self.cleaned_up {
+            return;
+        }
+        let mut live = LIVE.lock();
+        if live.objects == 0 {
+            unsafe { sys::rtcCleanup() };
+        } else {
+            live.cleanup_pending = true;
+        }
+    }
+}
@@ -0,0 +1,274 @@
+//! Types exchanged with the remote peer and states of the negotiation, shared by the
+//! libdatachannel and wasm backends.
+
+use std::fmt;
+
+use derivative::Derivative;
+use serde::{Deserialize, Serialize};
+use webrtc_sdp::attribute_type::{SdpAttribute, SdpAttributeType};
+use webrtc_sdp::media_type::{parse_media_vector, SdpMedia, SdpMediaValue};
+use webrtc_sdp::{parse_sdp_line, SdpBandwidth, SdpLine, SdpSession};
+
+use crate::error::{Error, Result};
+use crate::logger;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionState {
+    New,
+    Connecting,
+    Connected,
+    Disconnected,
+    Failed,
+    Closed,
+    /// A state unknown to this version of the crate, reported by a newer libdatachannel
+    Unknown(i32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GatheringState {
+    New,
+    InProgress,
+    Complete,
+    /// A state unknown to this version of the crate, reported by a newer libdatachannel
+    Unknown(i32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignalingState {
+    Stable,
+    HaveLocalOffer,
+    HaveRemoteOffer,
+    HaveLocalPranswer,
+    HaveRemotePranswer,
+    /// A state unknown to this version of the crate, reported by a newer libdatachannel
+    Unknown(i32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IceState {
+    New,
+    Checking,
+    Connected,
+    Completed,
+    Failed,
+    Disconnected,
+    Closed,
+    /// A state unknown to this version of the crate, reported by a newer libdatachannel
+    Unknown(i32),
+}
+
+#[derive(Derivative, Serialize, Deserialize)]
+#[derivative(Debug)]
+pub struct SessionDescription {
+    #[derivative(Debug(format_with = "fmt_sdp"))]
+    #[serde(with = "serde_sdp")]
+    pub sdp: SdpSession,
+    #[serde(rename = "type")]
+    pub sdp_type: SdpType,
+}
+
+impl SessionDescription {
+    /// ICE username fragment, from the session level or else the first media section.
+    pub fn ice_ufrag(&self) -> Option<&str> {
+        match self.ice_attribute(SdpAttributeType::IceUfrag)? {
+            SdpAttribute::IceUfrag(ufrag) => Some(ufrag),
+            _ => None,
+        }
+    }
+
+    /// ICE password, from the session level or else the first media section.
+    pub fn ice_pwd(&self) -> Option<&str> {
+        match self.ice_attribute(SdpAttributeType::IcePwd)? {
+            SdpAttribute::IcePwd(pwd) => Some(pwd),
+            _ => None,
+        }
+    }
+
+    /// Bandwidth lines (`b=AS`, `b=TIAS`, ...) of the media section identified by `mid`.
+    pub fn bandwidth(&self, mid: &str) -> Option<&[SdpBandwidth]> {
+        let media = self.media(mid)?;
+        Some(media.get_bandwidth())
+    }
+
+    /// Replaces the bandwidth lines of the media section identified by `mid`, typically to
+    /// cap the bitrate of the remote sender before signaling the description.
+    pub fn set_bandwidth(&mut self, mid: &str, bandwidth: &[SdpBandwidth]) -> Result<()> {
+        let index = self
+            .sdp
+            .media
+            .iter()
+            .position(|media| media_mid(media) == Some(mid))
+            .ok_or(Error::InvalidArg)?;
+
+        let media = self.sdp.media[index].to_string();
+        let mut lines = media.lines().filter(|line| !line.starts_with("b="));
+        let mut rebuilt = lines
+            .next()
+            .into_iter()
+            .map(String::from)
+            .collect::<Vec<_>>();
+        rebuilt.extend(bandwidth.iter().map(|bw| format!("b={}", bw)));
+        rebuilt.extend(lines.map(String::from));
+
+        let mut sdp_lines = rebuilt
+            .iter()
+            .enumerate()
+            .map(|(line_number, line)| parse_sdp_line(line, line_number))
+            .collect::<std::result::Result<Vec<SdpLine>, _>>()
+            .map_err(|err| {
+                logger::error!("Couldn't parse SdpLine: {}", err);
+                Error::InvalidArg
+            })?;
+        self.sdp.media[index] = parse_media_vector(&mut sdp_lines)
+            .map_err(|err| {
+                logger::error!("Couldn't parse SdpMedia: {}", err);
+                Error::InvalidArg
+            })?
+            .pop()
+            .ok_or(Error::InvalidArg)?;
+        Ok(())
+    }
+
+    /// Strips everything a data channel only session doesn't need: audio and video media
+    /// sections and all the attributes but the ICE, DTLS and SCTP ones. This reduces the
+    /// signaling payload for constrained transports, e.g. when called from
+    /// [`PeerConnectionHandler::transform_local_description`].
+    ///
+    /// [`PeerConnectionHandler::transform_local_description`]: crate::PeerConnectionHandler::transform_local_description
+    pub fn minimize_for_data_channels(&mut self) {
+        self.sdp
+            .media
+            .retain(|media| *media.get_type() == SdpMediaValue::Application);
+        let mids = self
+            .sdp
+            .media
+            .iter()
+            .filter_map(|media| media_mid(media).map(String::from))
+            .collect::<Vec<_>>();
+
+        self.sdp.attribute.retain_mut(|attr| match attr {
+            SdpAttribute::Group(group) => {
+                group.tags.retain(|tag| mids.contains(tag));
+                !group.tags.is_empty()
+            }
+            SdpAttribute::MsidSemantic(_) | SdpAttribute::IceOptions(_) => false,
+            _ => true,
+        });
+
+        for media in &mut self.sdp.media {
+            let stripped = media
+                .get_attributes()
+                .iter()
+                .map(SdpAttributeType::from)
+                .filter(|t| {
+                    !matches!(
+                        t,
+                        SdpAttributeType::Mid
+                            | SdpAttributeType::SctpPort
+                            | SdpAttributeType::MaxMessageSize
+                            | SdpAttributeType::Setup
+                            | SdpAttributeType::IceUfrag
+                            | SdpAttributeType::IcePwd
+                            | SdpAttributeType::Fingerprint
+                            | SdpAttributeType::Candidate
+                            | SdpAttributeType::EndOfCandidates
+                    )
+                })
+                .collect::<Vec<_>>();
+            for t in stripped {
+                media.remove_attribute(t);
+            }
+        }
+    }
+
+    fn media(&self, mid: &str) -> Option<&SdpMedia> {
+        self.sdp
+            .media
+            .iter()
+            .find(|media| media_mid(media) == Some(mid))
+    }
+
+    fn ice_attribute(&self, t: SdpAttributeType) -> Option<&SdpAttribute> {
+        self.sdp.get_attribute(t.clone()).or_else(|| {
+            self.sdp
+                .media
+                .iter()
+                .find_map(|media| media.get_attribute(t.clone()))
+        })
+    }
+}
+
+pub(crate) fn media_mid(media: &SdpMedia) -> Option<&str> {
+    match media.get_attribute(SdpAttributeType::Mid)? {
+        SdpAttribute::Mid(mid) => Some(mid),
+        _ => None,
+    }
+}
+
+pub fn fmt_sdp(sdp: &SdpSession, f: &mut fmt::Formatter) -> std::result::Result<(), fmt::Error> {
+    let sdp = sdp
+        .to_string()
+        .trim_end()
+        .split("\r\n")
+        .collect::<Vec<_>>()
+        .join("; ");
+    f.write_str(format!("{{ {} }}", sdp).as_str())
+}
+
+pub mod serde_sdp {
+    use super::SdpSession;
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(sdp: &SdpSession, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&sdp.to_string())
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> std::result::Result<SdpSession, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let sdp = String::deserialize(deserializer)?;
+        webrtc_sdp::parse_sdp(&sdp, false).map_err(de::Error::custom)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SdpType {
+    Answer,
+    Offer,
+    Pranswer,
+    Rollback,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IceCandidate {
+    pub candidate: String,
+    #[serde(rename = "sdpMid")]
+    pub mid: String,
+}
+
+impl IceCandidate {
+    /// The trickle ICE end-of-candidates marker for `mid`, an empty candidate as browsers
+    /// signal it.
+    pub fn end_of_candidates<S: AsRef<str>>(mid: S) -> Self {
+        Self {
+            candidate: String::new(),
+            mid: mid.as_ref().to_string(),
+        }
+    }
+
+    pub fn is_end_of_candidates(&self) -> bool {
+        let candidate = self.candidate.trim();
+        candidate.is_empty()
+            || candidate == "end-of-candidates"
+            || candidate == "a=end-of-candidates"
+    }
+}
@@ -0,0 +1,355 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use parking_lot::Mutex;
+
+use crate::datachannel::DataChannelId;
+use crate::peerconnection::CandidatePair;
+use crate::signaling::{ConnectionState, IceState};
+use crate::track::is_rtcp;
+
+/// Counters shared by a peer connection with its data channels and tracks.
+#[derive(Debug, Default)]
+pub(crate) struct Traffic {
+    bytes_sent: AtomicU64,
+    bytes_received: AtomicU64,
+    messages_sent: AtomicU64,
+    messages_received: AtomicU64,
+    rolling: Mutex<Rolling>,
+}
+
+/// Weight of the latest sample in the rolling averages
+const SMOOTHING: f64 = 0.2;
+
+/// Exponential moving averages of the reception reports received on the tracks.
+#[derive(Debug, Default, Clone, Copy)]
+struct Rolling {
+    loss: Option<f64>,
+    round_trip_time: Option<Duration>,
+}
+
+impl Traffic {
+    pub(crate) fn sent(&self, len: usize) {
+        self.bytes_sent.fetch_add(len as u64, Ordering::Relaxed);
+        self.messages_sent.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn received(&self, len: usize) {
+        self.bytes_received.fetch_add(len as u64, Ordering::Relaxed);
+        self.messages_received.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn reported(&self, report: &ReceptionReport, round_trip_time: Option<Duration>) {
+        let mut rolling = self.rolling.lock();
+        let loss = report.fraction_lost as f64 / 256.0;
+        rolling.loss = Some(match rolling.loss {
+            Some(average) => average + SMOOTHING * (loss - average),
+            None => loss,
+        });
+        if let Some(rtt) = round_trip_time {
+            rolling.round_trip_time = Some(match rolling.round_trip_time {
+                Some(average) => average.mul_f64(1.0 - SMOOTHING) + rtt.mul_f64(SMOOTHING),
+                None => rtt,
+            });
+        }
+    }
+}
+
+/// A snapshot of a peer connection, returned by
+/// [`RtcPeerConnection::stats`](crate::RtcPeerConnection::stats).
+///
+/// Bytes and messages are counted at the payload level, as handed to and received from the
+/// data channels and tracks of the connection, protocol overhead isn't included.
+///
+/// libdatachannel doesn't expose the round trip time through its C API, it is estimated from
+/// the RTCP reports received on the tracks, hence unknown for connections only carrying data
+/// channels. Neither does it expose the DTLS transport state, which is reflected by
+/// [`ConnectionState`] as it only reaches [`ConnectionState::Connected`] once the DTLS
+/// handshake is over.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeerConnectionStats {
+    /// When the snapshot was taken
+    pub timestamp: SystemTime,
+    /// When the connection last reached [`ConnectionState::Connected`]
+    pub connected_at: Option<SystemTime>,
+    pub connection_state: ConnectionState,
+    pub ice_state: IceState,
+    pub selected_candidate_pair: Option<CandidatePair>,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub messages_sent: u64,
+    pub messages_received: u64,
+    /// Rolling average of the round trip times reported by RTCP
+    pub round_trip_time: Option<Duration>,
+    /// Rolling average of the fraction of packets lost reported by RTCP, between 0 and 1
+    pub packet_loss: Option<f64>,
+}
+
+impl PeerConnectionStats {
+    pub(crate) fn new(
+        traffic: &Traffic,
+        connection_state: ConnectionState,
+        ice_state: IceState,
+        connected_at: Option<SystemTime>,
+        selected_candidate_pair: Option<CandidatePair>,
+    ) -> Self {
+        let rolling = *traffic.rolling.lock();
+        Self {
+            timestamp: SystemTime::now(),
+            connected_at,
+            connection_state,
+            ice_state,
+            selected_candidate_pair,
+            bytes_sent: traffic.bytes_sent.load(Ordering::Relaxed),
+            bytes_received: traffic.bytes_received.load(Ordering::Relaxed),
+            messages_sent: traffic.messages_sent.load(Ordering::Relaxed),
+            messages_received: traffic.messages_received.load(Ordering::Relaxed),
+            round_trip_time: rolling.round_trip_time,
+            packet_loss: rolling.loss,
+        }
+    }
+
+    /// Derives a [`ConnectionQuality`] from the packet loss, the round trip time and whether
+    /// the traffic is relayed through a TURN server.
+    pub fn quality(&self) -> ConnectionQuality {
+        if self.connection_state != ConnectionState::Connected {
+            return ConnectionQuality { score: 0 };
+        }
+        let mut score = 100.0;
+        if let Some(loss) = self.packet_loss {
+            // 5% loss costs 20 points, 25% loss the whole score
+            score -= loss * 400.0;
+        }
+        if let Some(rtt) = self.round_trip_time {
+            // 20 points per 100ms above 150ms
+            score -= (rtt.as_secs_f64() * 1000.0 - 150.0).max(0.0) / 5.0;
+        }
+        let relayed = self.selected_candidate_pair.as_ref().is_some_and(|pair| {
+            pair.local.contains(" typ relay") || pair.remote.contains(" typ relay")
+        });
+        if relayed {
+            score -= 10.0;
+        }
+        ConnectionQuality {
+            score: score.clamp(0.0, 100.0) as u8,
+        }
+    }
+}
+
+/// A rough indicator of the quality of a connection, see [`PeerConnectionStats::quality`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ConnectionQuality {
+    /// From 0, when not connected, to 100
+    pub score: u8,
+}
+
+impl ConnectionQuality {
+    pub fn level(&self) -> QualityLevel {
+        match self.score {
+            80.. => QualityLevel::Good,
+            50.. => QualityLevel::Fair,
+            _ => QualityLevel::Poor,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum QualityLevel {
+    Poor,
+    Fair,
+    Good,
+}
+
+/// Counters of a single data channel, on top of the connection wide [`Traffic`].
+#[derive(Debug, Default)]
+pub(crate) struct ChannelCounters {
+    pub(crate) traffic: Traffic,
+    send_failures: AtomicU64,
+    max_buffered_amount: AtomicU64,
+}
+
+impl ChannelCounters {
+    pub(crate) fn send_failed(&self) {
+        self.send_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn buffered(&self, amount: usize) {
+        self.max_buffered_amount
+            .fetch_max(amount as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> DataChannelStats {
+        DataChannelStats {
+            bytes_sent: self.traffic.bytes_sent.load(Ordering::Relaxed),
+            bytes_received: self.traffic.bytes_received.load(Ordering::Relaxed),
+            messages_sent: self.traffic.messages_sent.load(Ordering::Relaxed),
+            messages_received: self.traffic.messages_received.load(Ordering::Relaxed),
+            send_failures: self.send_failures.load(Ordering::Relaxed),
+            max_buffered_amount: self.max_buffered_amount.load(Ordering::Relaxed) as usize,
+        }
+    }
+}
+
+/// Counters of a data channel, returned by
+/// [`RtcDataChannel::stats`](crate::RtcDataChannel::stats).
+///
+/// Messages are counted as handed to [`send`](crate::RtcDataChannel::send), batches aren't
+/// counted on their own.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DataChannelStats {
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub messages_sent: u64,
+    pub messages_received: u64,
+    /// Sends which returned an error, including [`Error::WouldBlock`](crate::Error::WouldBlock)
+    pub send_failures: u64,
+    /// Highest buffered amount observed right after a send
+    pub max_buffered_amount: usize,
+}
+
+/// Statistics of a track, returned by [`RtcTrack::stats`](crate::RtcTrack::stats).
+///
+/// Messages are counted as they go through the track, so when a packetizer is set the sent
+/// media frames are counted instead of RTP packets, see
+/// [`current_timestamp`](crate::RtcTrack::current_timestamp) for the sent RTP timestamp.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TrackStats {
+    pub packets_sent: u64,
+    pub bytes_sent: u64,
+    /// RTP packets received, RTCP packets aren't counted
+    pub packets_received: u64,
+    pub bytes_received: u64,
+    pub last_sent_timestamp: Option<u32>,
+    pub last_received_timestamp: Option<u32>,
+    /// Latest report block received in an RTCP sender or receiver report
+    pub reception_report: Option<ReceptionReport>,
+    /// Computed from the latest report block, requires RTCP sender reports to be sent, see
+    /// [`chain_rtcp_sr_reporter`](crate::RtcTrack::chain_rtcp_sr_reporter)
+    pub round_trip_time: Option<Duration>,
+}
+
+/// A report block of an RTCP sender or receiver report (RFC 3550 section 6.4.1).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReceptionReport {
+    /// SSRC of the reported source
+    pub ssrc: u32,
+    /// Fraction of packets lost since the previous report, in 1/256
+    pub fraction_lost: u8,
+    /// Cumulative number of packets lost, negative when duplicates were received
+    pub packets_lost: i32,
+    pub highest_sequence: u32,
+    /// Interarrival jitter, in RTP timestamp units
+    pub jitter: u32,
+    /// Middle 32 bits of the NTP timestamp of the last sender report received by the remote
+    /// peer, 0 when none was
+    pub last_sender_report: u32,
+    /// Delay between the last sender report and this report, in 1/65536 seconds
+    pub delay_since_last_sender_report: u32,
+}
+
+impl ReceptionReport {
+    /// Computes the round trip time as of the report being received at `received_at`
+    /// (RFC 3550 section 6.4.1), assuming sender reports carry wall clock NTP timestamps.
+    ///
+    /// Returns `None` when the remote peer didn't receive any sender report yet.
+    pub fn round_trip_time(&self, received_at: SystemTime) -> Option<Duration> {
+        if self.last_sender_report == 0 {
+            return None;
+        }
+        // Seconds between 1900 and 1970
+        const NTP_UNIX_OFFSET: u64 = 2_208_988_800;
+        let now = received_at.duration_since(UNIX_EPOCH).ok()?;
+        let seconds = now.as_secs() + NTP_UNIX_OFFSET;
+        let fraction = ((now.subsec_nanos() as u64) << 32) / 1_000_000_000;
+        let middle = ((seconds as u32) << 16) | (fraction >> 16) as u32;
+
+        let rtt = middle
+            .wrapping_sub(self.last_sender_report)
+            .wrapping_sub(self.delay_since_last_sender_report);
+        // A negative round trip time, the clocks don't match
+        if rtt > u32::MAX / 2 {
+            return None;
+        }
+        Some(Duration::from_secs_f64(rtt as f64 / 65536.0))
+    }
+
+    /// Returns the first report block of a compound RTCP packet, as received by
+    /// [`TrackHandler::on_message`](crate::TrackHandler::on_message).
+    pub fn parse(mut msg: &[u8]) -> Option<Self> {
+        while msg.len() >= 4 {
+            let count = msg[0] & 0x1f;
+            let len = (u16::from_be_bytes([msg[2], msg[3]]) as usize + 1) * 4;
+            let packet = msg.get(..len)?;
+            let blocks = match packet[1] {
+                200 => 28, // SR, after the sender info
+                201 => 8,  // RR
+                _ => len,
+            };
+            if count > 0 {
+                if let Some(block) = packet.get(blocks..blocks + 24) {
+                    return Some(Self {
+                        ssrc: be_u32(&block[0..4]),
+                        fraction_lost: block[4],
+                        // Sign extends the 24 bits cumulative number
+                        packets_lost: (be_u32(&block[4..8]) << 8) as i32 >> 8,
+                        highest_sequence: be_u32(&block[8..12]),
+                        jitter: be_u32(&block[12..16]),
+                        last_sender_report: be_u32(&block[16..20]),
+                        delay_since_last_sender_report: be_u32(&block[20..24]),
+                    });
+                }
+            }
+            msg = &msg[len..];
+        }
+        None
+    }
+}
+
+impl TrackStats {
+    pub(crate) fn sent(&mut self, msg: &[u8]) {
+        self.packets_sent += 1;
+        self.bytes_sent += msg.len() as u64;
+        if let Some(timestamp) = rtp_timestamp(msg) {
+            self.last_sent_timestamp = Some(timestamp);
+        }
+    }
+
+    /// Also returns the report block and round trip time of received RTCP reports.
+    pub(crate) fn received(&mut self, msg: &[u8]) -> Option<(ReceptionReport, Option<Duration>)> {
+        if is_rtcp(msg) {
+            let report = ReceptionReport::parse(msg)?;
+            let round_trip_time = report.round_trip_time(SystemTime::now());
+            self.reception_report = Some(report);
+            if round_trip_time.is_some() {
+                self.round_trip_time = round_trip_time;
+            }
+            return Some((report, round_trip_time));
+        }
+        self.packets_received += 1;
+        self.bytes_received += msg.len() as u64;
+        if let Some(timestamp) = rtp_timestamp(msg) {
+            self.last_received_timestamp = Some(timestamp);
+        }
+        None
+    }
+}
+
+fn rtp_timestamp(msg: &[u8]) -> Option<u32> {
+    if msg.len() < 12 || msg[0] >> 6 != 2 {
+        return None;
+    }
+    Some(be_u32(&msg[4..8]))
+}
+
+fn be_u32(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+/// Statistics sampled periodically, see
+/// [`RtcConfig::stats_interval`](crate::RtcConfig::stats_interval).
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatsReport {
+    pub connection: PeerConnectionStats,
+    /// Data channels of the connection still alive
+    pub data_channels: Vec<(DataChannelId, DataChannelStats)>,
+}
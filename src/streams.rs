@@ -0,0 +1,650 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Weak};
+use std::thread;
+
+use parking_lot::Mutex;
+
+use crate::datachannel::{
+    send_message, DataChannelHandler, DataChannelId, DataChannelInfo, RtcDataChannel,
+};
+use crate::error::{ChannelError, ChannelErrorKind, Error, Result};
+use crate::peerconnection::{PeerConnectionHandler, RtcPeerConnection};
+
+/// Label of the data channels carrying the streams of a [`StreamMux`].
+pub const STREAM_MUX_LABEL: &str = "stream-mux";
+
+/// Type of the messages exchanged on a channel, on their first byte
+const OPEN: u8 = 0;
+const DATA: u8 = 1;
+/// Followed by the number of bytes granted, on 4 bytes big endian
+const WINDOW: u8 = 2;
+const FIN: u8 = 3;
+
+const DEFAULT_WINDOW: usize = 256 * 1024;
+const DEFAULT_MAX_IDLE_CHANNELS: usize = 8;
+
+/// Identifies a stream of a [`StreamMux`], only meaningful to the peer it belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StreamId(u64);
+
+/// Callbacks of a [`StreamMux`], run from libdatachannel threads.
+///
+/// They may open, send to and close streams through the mux.
+#[allow(unused_variables)]
+pub trait StreamHandler {
+    /// The remote peer opened `stream`.
+    fn on_stream(&mut self, stream: StreamId) {}
+    /// `stream` can be sent messages: opened on a new data channel which is now open, or
+    /// out of window until the remote peer consumed enough.
+    fn on_writable(&mut self, stream: StreamId) {}
+    fn on_message(&mut self, stream: StreamId, msg: &[u8]) {}
+    /// The remote peer closed its side of `stream`, nothing more will be received.
+    fn on_finished(&mut self, stream: StreamId) {}
+    /// `stream` is lost along with its data channel, which closed or failed.
+    fn on_reset(&mut self, stream: StreamId) {}
+    /// A data channel failed, or the remote peer sent something malformed.
+    fn on_error(&mut self, err: &ChannelError) {}
+}
+
+/// Configuration of a [`StreamMux`], both peers must use the same window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamMuxConfig {
+    window: usize,
+    max_idle_channels: usize,
+}
+
+impl Default for StreamMuxConfig {
+    fn default() -> Self {
+        Self {
+            window: DEFAULT_WINDOW,
+            max_idle_channels: DEFAULT_MAX_IDLE_CHANNELS,
+        }
+    }
+}
+
+impl StreamMuxConfig {
+    /// Bytes a stream may send before the remote peer consumed them, 256 KiB by default. It
+    /// also bounds the size of a message.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `window` is zero or doesn't fit on 32 bits.
+    pub fn window(mut self, window: usize) -> Self {
+        assert!(
+            window > 0 && u32::try_from(window).is_ok(),
+            "window must fit on 32 bits and not be zero"
+        );
+        self.window = window;
+        self
+    }
+
+    /// Data channels kept open once their stream is closed, to host the next ones, 8 by
+    /// default.
+    pub fn max_idle_channels(mut self, max_idle_channels: usize) -> Self {
+        self.max_idle_channels = max_idle_channels;
+        self
+    }
+}
+
+struct Stream {
+    id: StreamId,
+    /// Cleared until the data channel of a stream opened on a new one is open
+    writable: bool,
+    /// Bytes which may still be sent
+    credit: usize,
+    /// Set when a send ran out of window
+    blocked: bool,
+    /// Bytes received since the last window granted
+    consumed: usize,
+    fin_sent: bool,
+    fin_received: bool,
+}
+
+impl Stream {
+    fn new(id: StreamId, window: usize, writable: bool) -> Self {
+        Self {
+            id,
+            writable,
+            credit: window,
+            blocked: false,
+            consumed: 0,
+            fin_sent: false,
+            fin_received: false,
+        }
+    }
+}
+
+/// A data channel of the mux, hosting one stream at a time.
+struct Lane<H> {
+    id: Option<DataChannelId>,
+    /// Set once created, or accepted for those of the remote peer
+    dc: Option<Box<RtcDataChannel<StreamChannel<H>>>>,
+    /// Created by this side, which alone opens streams on it
+    own: bool,
+    open: bool,
+    /// Dropped on the next call to the mux
+    closed: bool,
+    /// The first stream of a channel is opened along with it, the next ones explicitly
+    used: bool,
+    stream: Option<Stream>,
+}
+
+impl<H> Lane<H>
+where
+    H: StreamHandler + Send,
+{
+    fn new(id: Option<DataChannelId>, own: bool) -> Self {
+        Self {
+            id,
+            dc: None,
+            own,
+            open: false,
+            closed: false,
+            used: false,
+            stream: None,
+        }
+    }
+
+    fn send(&mut self, msg: &[u8]) -> Result<()> {
+        match (&mut self.dc, self.id) {
+            (Some(dc), _) => dc.send(msg),
+            (None, Some(id)) => send_message(id, msg),
+            (None, None) => Err(Error::NotOpen),
+        }
+    }
+}
+
+struct Inner<H> {
+    next_stream: u64,
+    next_lane: u64,
+    lanes: HashMap<u64, Lane<H>>,
+    streams: HashMap<StreamId, u64>,
+    /// Own open channels without a stream
+    idle: Vec<u64>,
+}
+
+impl<H> Inner<H> {
+    fn stream_id(&mut self) -> StreamId {
+        self.next_stream += 1;
+        StreamId(self.next_stream)
+    }
+
+    fn lane_key(&mut self) -> u64 {
+        self.next_lane += 1;
+        self.next_lane
+    }
+
+    /// Opens the implicit first stream of a remote channel, if not done yet.
+    fn first_stream(&mut self, key: u64, window: usize) -> Option<StreamId> {
+        let lane = self.lanes.get(&key)?;
+        if lane.own || lane.used || lane.closed {
+            return None;
+        }
+        let id = self.stream_id();
+        let lane = self.lanes.get_mut(&key)?;
+        lane.used = true;
+        lane.stream = Some(Stream::new(id, window, true));
+        self.streams.insert(id, key);
+        Some(id)
+    }
+
+    /// Frees the channel of a stream closed both ways.
+    fn release(&mut self, key: u64, max_idle_channels: usize) {
+        let Some(lane) = self.lanes.get_mut(&key) else {
+            return;
+        };
+        if let Some(stream) = lane.stream.take() {
+            self.streams.remove(&stream.id);
+        }
+        if lane.own && lane.open && !lane.closed {
+            if self.idle.len() < max_idle_channels {
+                self.idle.push(key);
+            } else {
+                lane.closed = true;
+            }
+        }
+    }
+}
+
+struct Shared<H> {
+    config: StreamMuxConfig,
+    handler: Mutex<H>,
+    inner: Mutex<Inner<H>>,
+}
+
+enum Event {
+    Stream(StreamId),
+    Writable(StreamId),
+    Finished(StreamId),
+    Reset(StreamId),
+    Error(ChannelError),
+}
+
+impl<H> Shared<H>
+where
+    H: StreamHandler,
+{
+    fn notify(&self, events: Vec<Event>) {
+        if events.is_empty() {
+            return;
+        }
+        let mut handler = self.handler.lock();
+        for event in events {
+            match event {
+                Event::Stream(id) => handler.on_stream(id),
+                Event::Writable(id) => handler.on_writable(id),
+                Event::Finished(id) => handler.on_finished(id),
+                Event::Reset(id) => handler.on_reset(id),
+                Event::Error(err) => handler.on_error(&err),
+            }
+        }
+    }
+}
+
+/// Handler of the data channels of a [`StreamMux`].
+pub struct StreamChannel<H> {
+    /// `None` for a channel which isn't part of the mux
+    lane: Option<u64>,
+    shared: Weak<Shared<H>>,
+}
+
+impl<H> StreamChannel<H>
+where
+    H: StreamHandler + Send,
+{
+    fn shared(&self) -> Option<(u64, Arc<Shared<H>>)> {
+        Some((self.lane?, self.shared.upgrade()?))
+    }
+
+    /// Handles a message of the stream protocol, returns the data to deliver along with the
+    /// events to report.
+    fn receive<'a>(
+        shared: &Shared<H>,
+        key: u64,
+        msg: &'a [u8],
+        events: &mut Vec<Event>,
+    ) -> Option<(StreamId, &'a [u8])> {
+        let config = shared.config;
+        let mut inner = shared.inner.lock();
+        events.extend(inner.first_stream(key, config.window).map(Event::Stream));
+
+        let malformed = |reason: &str| {
+            let err = ChannelError::new(ChannelErrorKind::Protocol, reason);
+            Event::Error(err)
+        };
+        let Some((&kind, payload)) = msg.split_first() else {
+            events.push(malformed("Empty stream message"));
+            return None;
+        };
+        let lane = inner.lanes.get_mut(&key)?;
+        match (kind, lane.stream.as_mut()) {
+            (OPEN, None) if !lane.own => {
+                let id = inner.stream_id();
+                let lane = inner.lanes.get_mut(&key)?;
+                lane.used = true;
+                lane.stream = Some(Stream::new(id, config.window, true));
+                inner.streams.insert(id, key);
+                events.push(Event::Stream(id));
+            }
+            (DATA, Some(stream)) if !stream.fin_received => return Some((stream.id, payload)),
+            (WINDOW, Some(stream)) => match payload.try_into().map(u32::from_be_bytes) {
+                Ok(granted) => {
+                    stream.credit = stream.credit.saturating_add(granted as usize);
+                    if std::mem::take(&mut stream.blocked) {
+                        events.push(Event::Writable(stream.id));
+                    }
+                }
+                Err(_) => events.push(malformed("Malformed stream window")),
+            },
+            (FIN, Some(stream)) if !stream.fin_received => {
+                stream.fin_received = true;
+                events.push(Event::Finished(stream.id));
+                if stream.fin_sent {
+                    inner.release(key, config.max_idle_channels);
+                }
+            }
+            _ => events.push(malformed("Unexpected stream message")),
+        }
+        None
+    }
+
+    /// Grants the window consumed by `len` delivered bytes once it reaches half of it.
+    fn consumed(shared: &Shared<H>, key: u64, id: StreamId, len: usize) {
+        let window = shared.config.window;
+        let mut inner = shared.inner.lock();
+        let Some(lane) = inner.lanes.get_mut(&key) else {
+            return;
+        };
+        let Some(stream) = lane.stream.as_mut().filter(|stream| stream.id == id) else {
+            return;
+        };
+        stream.consumed += len;
+        if stream.consumed >= window / 2 {
+            let granted = std::mem::take(&mut stream.consumed) as u32;
+            let mut msg = vec![WINDOW];
+            msg.extend_from_slice(&granted.to_be_bytes());
+            // A failure shows up as the channel closing
+            lane.send(&msg).ok();
+        }
+    }
+}
+
+impl<H> DataChannelHandler for StreamChannel<H>
+where
+    H: StreamHandler + Send,
+{
+    fn on_open(&mut self) {
+        let Some((key, shared)) = self.shared() else {
+            return;
+        };
+        let events = {
+            let mut inner = shared.inner.lock();
+            let Some(lane) = inner.lanes.get_mut(&key) else {
+                return;
+            };
+            lane.open = true;
+            match lane.stream.as_mut() {
+                Some(stream) if lane.own && !stream.writable => {
+                    stream.writable = true;
+                    vec![Event::Writable(stream.id)]
+                }
+                _ => Vec::from_iter(
+                    inner
+                        .first_stream(key, shared.config.window)
+                        .map(Event::Stream),
+                ),
+            }
+        };
+        shared.notify(events);
+    }
+
+    fn on_closed(&mut self) {
+        let Some((key, shared)) = self.shared() else {
+            return;
+        };
+        let events = {
+            let mut inner = shared.inner.lock();
+            inner.idle.retain(|idle| *idle != key);
+            let Some(lane) = inner.lanes.get_mut(&key) else {
+                return;
+            };
+            lane.open = false;
+            lane.closed = true;
+            let reset = lane.stream.take().map(|stream| stream.id);
+            if let Some(id) = reset {
+                inner.streams.remove(&id);
+            }
+            Vec::from_iter(reset.map(Event::Reset))
+        };
+        shared.notify(events);
+    }
+
+    fn on_error(&mut self, err: &ChannelError) {
+        if let Some((_, shared)) = self.shared() {
+            shared.handler.lock().on_error(err);
+        }
+    }
+
+    fn on_message(&mut self, msg: &[u8]) {
+        let Some((key, shared)) = self.shared() else {
+            return;
+        };
+        let mut events = Vec::new();
+        let data = Self::receive(&shared, key, msg, &mut events);
+        shared.notify(events);
+        if let Some((id, data)) = data {
+            shared.handler.lock().on_message(id, data);
+            Self::consumed(&shared, key, id, data.len());
+        }
+    }
+}
+
+/// Opens lightweight bidirectional streams with a remote peer, each one carried by a data
+/// channel.
+///
+/// Once both sides closed a stream, its channel is kept to carry the next stream opened
+/// from the same side, up to [`StreamMuxConfig::max_idle_channels`]. Many short-lived
+/// streams thus don't cost a channel negotiation each. Every stream gets its own window of
+/// bytes in flight, so that a stream whose messages aren't consumed doesn't starve the
+/// others.
+///
+/// The channels opened by the remote peer reach the mux through its [`StreamAcceptor`].
+pub struct StreamMux<H> {
+    shared: Arc<Shared<H>>,
+}
+
+/// Adopts the data channels opened by the remote [`StreamMux`], to be called from the
+/// [`PeerConnectionHandler`] of the connection.
+pub struct StreamAcceptor<H> {
+    shared: Weak<Shared<H>>,
+}
+
+impl<H> Clone for StreamAcceptor<H> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: Weak::clone(&self.shared),
+        }
+    }
+}
+
+impl<H> StreamAcceptor<H>
+where
+    H: StreamHandler + Send,
+{
+    /// The handler of a data channel opened by the remote peer, to be returned from
+    /// [`PeerConnectionHandler::data_channel_handler`].
+    ///
+    /// Channels not labeled [`STREAM_MUX_LABEL`] get a handler ignoring them.
+    pub fn channel_handler(&self, info: &DataChannelInfo) -> StreamChannel<H> {
+        let lane = match self.shared.upgrade() {
+            Some(shared) if info.label == STREAM_MUX_LABEL => {
+                let mut inner = shared.inner.lock();
+                let key = inner.lane_key();
+                inner.lanes.insert(key, Lane::new(Some(info.id), false));
+                Some(key)
+            }
+            _ => None,
+        };
+        StreamChannel {
+            lane,
+            shared: Weak::clone(&self.shared),
+        }
+    }
+
+    /// Keeps a data channel opened by the remote peer, to be called from
+    /// [`PeerConnectionHandler::on_data_channel`].
+    ///
+    /// Returns the channels which aren't part of the mux.
+    pub fn accept(
+        &self,
+        dc: Box<RtcDataChannel<StreamChannel<H>>>,
+    ) -> Option<Box<RtcDataChannel<StreamChannel<H>>>> {
+        let Some(shared) = self.shared.upgrade() else {
+            return Some(dc);
+        };
+        let mut inner = shared.inner.lock();
+        let lane = inner
+            .lanes
+            .values_mut()
+            .find(|lane| !lane.own && lane.dc.is_none() && lane.id == Some(dc.id()));
+        match lane {
+            Some(lane) => {
+                lane.dc = Some(dc);
+                None
+            }
+            None => Some(dc),
+        }
+    }
+}
+
+impl<H> StreamMux<H>
+where
+    H: StreamHandler + Send + 'static,
+{
+    pub fn new(config: StreamMuxConfig, handler: H) -> (Self, StreamAcceptor<H>) {
+        let shared = Arc::new(Shared {
+            config,
+            handler: Mutex::new(handler),
+            inner: Mutex::new(Inner {
+                next_stream: 0,
+                next_lane: 0,
+                lanes: HashMap::new(),
+                streams: HashMap::new(),
+                idle: Vec::new(),
+            }),
+        });
+        let acceptor = StreamAcceptor {
+            shared: Arc::downgrade(&shared),
+        };
+        (Self { shared }, acceptor)
+    }
+
+    /// Opens a stream on an idle data channel, or on a new one created through `pc`.
+    ///
+    /// A stream opened on a new channel can only be sent messages once
+    /// [`on_writable`](StreamHandler::on_writable) is called.
+    pub fn open<P>(&self, pc: &mut RtcPeerConnection<P>) -> Result<StreamId>
+    where
+        P: PeerConnectionHandler + Send,
+        P::DCH: DataChannelHandler + Send,
+    {
+        self.prune();
+        let window = self.shared.config.window;
+        let mut inner = self.shared.inner.lock();
+        let id = inner.stream_id();
+        while let Some(key) = inner.idle.pop() {
+            let Some(lane) = inner.lanes.get_mut(&key) else {
+                continue;
+            };
+            // A failure shows up as the channel closing
+            if lane.send(&[OPEN]).is_ok() {
+                lane.stream = Some(Stream::new(id, window, true));
+                inner.streams.insert(id, key);
+                return Ok(id);
+            }
+        }
+
+        let key = inner.lane_key();
+        let mut lane = Lane::new(None, true);
+        lane.used = true;
+        lane.stream = Some(Stream::new(id, window, false));
+        inner.lanes.insert(key, lane);
+        inner.streams.insert(id, key);
+        drop(inner);
+
+        let handler = StreamChannel {
+            lane: Some(key),
+            shared: Arc::downgrade(&self.shared),
+        };
+        let res = pc.create_data_channel(STREAM_MUX_LABEL, handler);
+        let mut inner = self.shared.inner.lock();
+        match (res, inner.lanes.get_mut(&key)) {
+            (Ok(dc), Some(lane)) => {
+                lane.id = Some(dc.id());
+                lane.dc = Some(dc);
+                Ok(id)
+            }
+            (res, _) => {
+                inner.lanes.remove(&key);
+                inner.streams.remove(&id);
+                drop(inner);
+                res.and(Err(Error::NotOpen))
+            }
+        }
+    }
+
+    /// Sends `msg` on `stream`.
+    ///
+    /// Fails with [`Error::NotOpen`] if the stream isn't writable or was closed, with
+    /// [`Error::WouldBlock`] when it is out of window, and with [`Error::InvalidArg`] if
+    /// `msg` is larger than the window. [`on_writable`](StreamHandler::on_writable) is called
+    /// once a blocked stream can send again.
+    pub fn send(&self, stream: StreamId, msg: &[u8]) -> Result<()> {
+        if msg.len() > self.shared.config.window {
+            return Err(Error::InvalidArg);
+        }
+        let mut inner = self.shared.inner.lock();
+        let key = *inner.streams.get(&stream).ok_or(Error::NotOpen)?;
+        let lane = inner.lanes.get_mut(&key).ok_or(Error::NotOpen)?;
+        let state = lane.stream.as_mut().ok_or(Error::NotOpen)?;
+        if !state.writable || state.fin_sent {
+            return Err(Error::NotOpen);
+        }
+        if msg.len() > state.credit {
+            state.blocked = true;
+            return Err(Error::WouldBlock);
+        }
+        state.credit -= msg.len();
+
+        let mut buf = Vec::with_capacity(1 + msg.len());
+        buf.push(DATA);
+        buf.extend_from_slice(msg);
+        lane.send(&buf)
+    }
+
+    /// Closes this side of `stream`, the remote peer may still send until it closes its own.
+    ///
+    /// Fails with [`Error::NotOpen`] if the stream isn't writable or was closed already.
+    pub fn close(&self, stream: StreamId) -> Result<()> {
+        let max_idle_channels = self.shared.config.max_idle_channels;
+        let mut inner = self.shared.inner.lock();
+        let key = *inner.streams.get(&stream).ok_or(Error::NotOpen)?;
+        let lane = inner.lanes.get_mut(&key).ok_or(Error::NotOpen)?;
+        match lane.stream.as_ref() {
+            Some(state) if state.writable && !state.fin_sent => (),
+            _ => return Err(Error::NotOpen),
+        }
+        lane.send(&[FIN])?;
+        let state = lane.stream.as_mut().ok_or(Error::NotOpen)?;
+        state.fin_sent = true;
+        if state.fin_received {
+            inner.release(key, max_idle_channels);
+        }
+        Ok(())
+    }
+
+    /// The streams open in at least one direction.
+    pub fn streams(&self) -> Vec<StreamId> {
+        self.shared.inner.lock().streams.keys().copied().collect()
+    }
+
+    /// Number of data channels held, idle ones included.
+    pub fn channels(&self) -> usize {
+        let inner = self.shared.inner.lock();
+        inner.lanes.values().filter(|lane| !lane.closed).count()
+    }
+
+    /// Number of own data channels waiting for a stream.
+    pub fn idle_channels(&self) -> usize {
+        self.shared.inner.lock().idle.len()
+    }
+
+    /// Drops the closed channels, from another thread as this may be called from the
+    /// callback of one of them.
+    fn prune(&self) {
+        let closed = {
+            let mut inner = self.shared.inner.lock();
+            let keys = inner
+                .lanes
+                .iter()
+                .filter(|(_, lane)| lane.closed)
+                .map(|(key, _)| *key)
+                .collect::<Vec<_>>();
+            inner.idle.retain(|key| !keys.contains(key));
+            keys.iter()
+                .filter_map(|key| inner.lanes.remove(key)?.dc)
+                .collect::<Vec<_>>()
+        };
+        if !closed.is_empty() {
+            thread::spawn(move || drop(closed));
+        }
+    }
+}
+
+impl<H> Drop for StreamMux<H> {
+    fn drop(&mut self) {
+        // Dropped here rather than from a callback holding the last reference
+        let lanes = std::mem::take(&mut self.shared.inner.lock().lanes);
+        drop(lanes);
+    }
+}
@@ -0,0 +1,87 @@
+//! Metrics emitted through the [`metrics`](https://docs.rs/metrics) facade when the `metrics`
+//! feature is enabled, these are no-ops otherwise.
+
+use std::time::Duration;
+
+use crate::signaling::ConnectionState;
+
+pub(crate) fn peer_connection_created() {
+    #[cfg(feature = "metrics")]
+    ::metrics::gauge!("datachannel_peer_connections").increment(1.0);
+}
+
+pub(crate) fn peer_connection_deleted() {
+    #[cfg(feature = "metrics")]
+    ::metrics::gauge!("datachannel_peer_connections").decrement(1.0);
+}
+
+pub(crate) fn data_channel_created() {
+    #[cfg(feature = "metrics")]
+    ::metrics::gauge!("datachannel_data_channels").increment(1.0);
+}
+
+pub(crate) fn data_channel_deleted() {
+    #[cfg(feature = "metrics")]
+    ::metrics::gauge!("datachannel_data_channels").decrement(1.0);
+}
+
+pub(crate) fn track_created() {
+    #[cfg(feature = "metrics")]
+    ::metrics::gauge!("datachannel_tracks").increment(1.0);
+}
+
+pub(crate) fn track_deleted() {
+    #[cfg(feature = "metrics")]
+    ::metrics::gauge!("datachannel_tracks").decrement(1.0);
+}
+
+pub(crate) fn connection_state_changed(state: ConnectionState) {
+    #[cfg(feature = "metrics")]
+    {
+        let state = match state {
+            ConnectionState::New => "new",
+            ConnectionState::Connecting => "connecting",
+            ConnectionState::Connected => "connected",
+            ConnectionState::Disconnected => "disconnected",
+            ConnectionState::Failed => "failed",
+            ConnectionState::Closed => "closed",
+            ConnectionState::Unknown(_) => "unknown",
+        };
+        ::metrics::counter!("datachannel_connection_state_changes_total", "state" => state)
+            .increment(1);
+    }
+    #[cfg(not(feature = "metrics"))]
+    let _ = state;
+}
+
+pub(crate) fn sent(kind: &'static str, len: usize) {
+    #[cfg(feature = "metrics")]
+    {
+        ::metrics::counter!("datachannel_messages_sent_total", "kind" => kind).increment(1);
+        ::metrics::counter!("datachannel_bytes_sent_total", "kind" => kind).increment(len as u64);
+    }
+    #[cfg(not(feature = "metrics"))]
+    let _ = (kind, len);
+}
+
+pub(crate) fn received(kind: &'static str, len: usize) {
+    #[cfg(feature = "metrics")]
+    {
+        ::metrics::counter!("datachannel_messages_received_total", "kind" => kind).increment(1);
+        ::metrics::counter!("datachannel_bytes_received_total", "kind" => kind)
+            .increment(len as u64);
+    }
+    #[cfg(not(feature = "metrics"))]
+    let _ = (kind, len);
+}
+
+/// Records a data channel send, along with the buffered amount right after it.
+pub(crate) fn data_channel_send(latency: Duration, buffered_amount: usize) {
+    #[cfg(feature = "metrics")]
+    {
+        ::metrics::histogram!("datachannel_send_duration_seconds").record(latency);
+        ::metrics::histogram!("datachannel_buffered_amount_bytes").record(buffered_amount as f64);
+    }
+    #[cfg(not(feature = "metrics"))]
+    let _ = (latency, buffered_amount);
+}
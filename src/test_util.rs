@@ -0,0 +1,1392 @@
+//! Helpers to test handlers against real connections within a single process, either
+//! signaling to each other directly with [`local_pair`] or through [`TestSignaling`], and
+//! recording handlers to assert on the callbacks received. [`Soak`] puts many of them
+//! under load.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::collections::{HashMap, VecDeque};
+use std::ffi::CString;
+use std::fmt;
+use std::net::{IpAddr, SocketAddr, UdpSocket};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use datachannel_sys as sys;
+use parking_lot::{Condvar, Mutex};
+
+use crate::config::RtcConfig;
+use crate::datachannel::{
+    DataChannelHandler, DataChannelInfo, DataChannelInit, Reliability, RtcDataChannel,
+};
+use crate::error::{call, ChannelError, Result};
+use crate::logger;
+use crate::peerconnection::{PeerConnectionHandler, RtcPeerConnection};
+use crate::signaling::{
+    ConnectionState, GatheringState, IceCandidate, IceState, SdpType, SessionDescription,
+    SignalingState,
+};
+use crate::stats::{ConnectionQuality, StatsReport};
+use crate::track::TrackHandler;
+
+/// How long [`local_pair`] waits for the connection when [`RtcConfig::connection_timeout`]
+/// isn't set.
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Label of the data channel [`local_pair`] opens to get the connection negotiated.
+pub const BOOTSTRAP_LABEL: &str = "local_pair";
+
+/// Handler of the data channel opened by [`local_pair`].
+pub struct Bootstrap {
+    /// Signaled once open, until [`local_pair`] returns
+    opened: Option<mpsc::Sender<()>>,
+}
+
+impl DataChannelHandler for Bootstrap {
+    fn on_open(&mut self) {
+        if let Some(opened) = self.opened.take() {
+            opened.send(()).ok();
+        }
+    }
+}
+
+/// Wiring between the two peers of a [`local_pair`].
+struct Link {
+    /// Id of the other peer connection, set once both are created
+    remote: Mutex<Option<i32>>,
+    /// Connection states reported while [`local_pair`] waits, along with the peer index
+    states: Mutex<Option<mpsc::Sender<(usize, ConnectionState)>>>,
+    /// Kept open for as long as the offering peer lives
+    bootstrap: Mutex<Option<Box<RtcDataChannel<Bootstrap>>>>,
+    /// Set by [`impaired_pair`], candidates are then signaled through it
+    proxy: Option<Arc<Proxy>>,
+}
+
+/// A [`PeerConnectionHandler`] forwarding every callback to the wrapped handler, and the
+/// descriptions and candidates to the other peer of a [`local_pair`].
+pub struct Loopback<P> {
+    index: usize,
+    inner: P,
+    link: Arc<Link>,
+}
+
+impl<P> Loopback<P> {
+    fn remote(&self) -> Option<i32> {
+        *self.link.remote.lock()
+    }
+}
+
+impl<P> PeerConnectionHandler for Loopback<P>
+where
+    P: PeerConnectionHandler,
+{
+    type DCH = P::DCH;
+
+    fn data_channel_handler(&mut self, info: DataChannelInfo) -> Self::DCH {
+        self.inner.data_channel_handler(info)
+    }
+
+    fn transform_local_description(&mut self, sess_desc: &mut SessionDescription) {
+        self.inner.transform_local_description(sess_desc)
+    }
+
+    fn on_description(&mut self, sess_desc: SessionDescription) {
+        if let Some(remote) = self.remote() {
+            if let Err(err) = set_remote_description(remote, &sess_desc) {
+                logger::warn!("Loopback {}: {}", self.index, err);
+            }
+        }
+        self.inner.on_description(sess_desc)
+    }
+
+    fn on_candidate(&mut self, cand: IceCandidate) {
+        let signaled = match &self.link.proxy {
+            Some(proxy) => proxy.rewrite(self.index, &cand),
+            None => Some(cand.clone()),
+        };
+        if let (Some(remote), Some(signaled)) = (self.remote(), signaled) {
+            if let Err(err) = add_remote_candidate(remote, &signaled) {
+                logger::warn!("Loopback {}: {}", self.index, err);
+            }
+        }
+        self.inner.on_candidate(cand)
+    }
+
+    fn on_connection_state_change(&mut self, state: ConnectionState) {
+        if let Some(states) = &*self.link.states.lock() {
+            states.send((self.index, state)).ok();
+        }
+        self.inner.on_connection_state_change(state)
+    }
+
+    fn on_gathering_state_change(&mut self, state: GatheringState) {
+        self.inner.on_gathering_state_change(state)
+    }
+
+    fn on_end_of_candidates(&mut self) {
+        self.inner.on_end_of_candidates()
+    }
+
+    fn on_signaling_state_change(&mut self, state: SignalingState) {
+        self.inner.on_signaling_state_change(state)
+    }
+
+    fn on_ice_state_change(&mut self, state: IceState) {
+        self.inner.on_ice_state_change(state)
+    }
+
+    fn on_data_channel(&mut self, data_channel: Box<RtcDataChannel<Self::DCH>>) {
+        self.inner.on_data_channel(data_channel)
+    }
+
+    fn on_stats(&mut self, report: StatsReport) {
+        self.inner.on_stats(report)
+    }
+
+    fn on_quality_change(&mut self, quality: ConnectionQuality) {
+        self.inner.on_quality_change(quality)
+    }
+}
+
+fn set_remote_description(id: i32, sess_desc: &SessionDescription) -> Result<()> {
+    let sdp = CString::new(sess_desc.sdp.to_string())?;
+    let sdp_type = CString::new(sess_desc.sdp_type.val())?;
+    call("rtcSetRemoteDescription", id, || unsafe {
+        sys::rtcSetRemoteDescription(id, sdp.as_ptr(), sdp_type.as_ptr())
+    })?;
+    Ok(())
+}
+
+fn add_remote_candidate(id: i32, cand: &IceCandidate) -> Result<()> {
+    if cand.is_end_of_candidates() {
+        return Ok(());
+    }
+    let mid = CString::new(cand.mid.as_str())?;
+    let candidate = CString::new(cand.candidate.as_str())?;
+    call("rtcAddRemoteCandidate", id, || unsafe {
+        sys::rtcAddRemoteCandidate(id, candidate.as_ptr(), mid.as_ptr())
+    })?;
+    Ok(())
+}
+
+/// A peer connection of a [`local_pair`].
+pub type LocalPeer<P> = Box<RtcPeerConnection<Loopback<P>>>;
+
+/// Creates two peer connections signaling to each other directly, and returns them once both
+/// are connected and the bootstrap channel is open.
+///
+/// The first one offers a data channel labeled [`BOOTSTRAP_LABEL`] to get the connection
+/// negotiated, the second one receives it through its handler. Later negotiations, for
+/// instance when creating more data channels, are signaled the same way.
+///
+/// # Panics
+///
+/// Panics if the peers aren't connected nor the channel open within
+/// [`RtcConfig::connection_timeout`], 10 seconds when not set.
+pub fn local_pair<P>(
+    config: &RtcConfig,
+    offerer: P,
+    answerer: P,
+) -> Result<(LocalPeer<P>, LocalPeer<P>)>
+where
+    P: PeerConnectionHandler + Send,
+    P::DCH: DataChannelHandler + Send,
+{
+    connect_pair(config, offerer, answerer, None)
+}
+
+/// Like [`local_pair`], but the peers reach each other through a UDP proxy applying
+/// `impairment` to every packet, ICE and DTLS included.
+///
+/// Only the first IPv4 host candidate of each peer is signaled, rewritten to the address of
+/// the proxy. The proxy stops once the returned [`ImpairedLink`] is dropped.
+///
+/// # Panics
+///
+/// Panics like [`local_pair`], which is more likely to happen with a high loss rate.
+pub fn impaired_pair<P>(
+    config: &RtcConfig,
+    impairment: Impairment,
+    offerer: P,
+    answerer: P,
+) -> Result<(LocalPeer<P>, LocalPeer<P>, ImpairedLink)>
+where
+    P: PeerConnectionHandler + Send,
+    P::DCH: DataChannelHandler + Send,
+{
+    let proxy = Arc::new(Proxy::new(impairment));
+    let (offerer, answerer) = connect_pair(config, offerer, answerer, Some(Arc::clone(&proxy)))?;
+    Ok((offerer, answerer, ImpairedLink { proxy }))
+}
+
+fn connect_pair<P>(
+    config: &RtcConfig,
+    offerer: P,
+    answerer: P,
+    proxy: Option<Arc<Proxy>>,
+) -> Result<(LocalPeer<P>, LocalPeer<P>)>
+where
+    P: PeerConnectionHandler + Send,
+    P::DCH: DataChannelHandler + Send,
+{
+    let (states, rx) = mpsc::channel();
+    let links = [0, 1].map(|_| {
+        Arc::new(Link {
+            remote: Mutex::new(None),
+            states: Mutex::new(Some(states.clone())),
+            bootstrap: Mutex::new(None),
+            proxy: proxy.clone(),
+        })
+    });
+    drop(states);
+
+    let mut peers = Vec::with_capacity(2);
+    for (index, inner) in [offerer, answerer].into_iter().enumerate() {
+        let link = Arc::clone(&links[index]);
+        peers.push(RtcPeerConnection::new(
+            config,
+            Loopback { index, inner, link },
+        )?);
+    }
+    let answerer = peers.pop().unwrap();
+    let mut offerer = peers.pop().unwrap();
+    *links[0].remote.lock() = Some(answerer.id().0);
+    *links[1].remote.lock() = Some(offerer.id().0);
+
+    let (opened, rx_opened) = mpsc::channel();
+    let bootstrap = Bootstrap {
+        opened: Some(opened),
+    };
+    let bootstrap = offerer.create_data_channel(BOOTSTRAP_LABEL, bootstrap)?;
+    links[0].bootstrap.lock().replace(bootstrap);
+
+    let timeout = config.connection_timeout.unwrap_or(DEFAULT_CONNECT_TIMEOUT);
+    let deadline = Instant::now() + timeout;
+    let mut connected = [false; 2];
+    while connected != [true; 2] {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        match rx.recv_timeout(remaining) {
+            Ok((_, ConnectionState::Failed)) => panic!("local_pair connection failed"),
+            Ok((index, state)) => connected[index] = state == ConnectionState::Connected,
+            Err(_) => panic!("local_pair not connected after {:?}", timeout),
+        }
+    }
+    // libdatachannel opens the channels created before the connection from a task queued once
+    // connected, a channel created before it runs would be opened twice and then closed
+    let remaining = deadline.saturating_duration_since(Instant::now());
+    if rx_opened.recv_timeout(remaining).is_err() {
+        panic!("local_pair bootstrap channel not open after {:?}", timeout);
+    }
+    for link in &links {
+        link.states.lock().take();
+    }
+    Ok((offerer, answerer))
+}
+
+/// A message exchanged through [`TestSignaling`].
+#[derive(Debug)]
+pub enum SignalingMessage {
+    Description(Box<SessionDescription>),
+    Candidate(IceCandidate),
+}
+
+/// A [`SignalingMessage`] along with its sender and recipient.
+#[derive(Debug)]
+pub struct Envelope<K> {
+    pub from: K,
+    pub to: K,
+    pub message: SignalingMessage,
+}
+
+struct Mailbox<K> {
+    queue: Mutex<VecDeque<Envelope<K>>>,
+    posted: Condvar,
+}
+
+/// An in-memory signaling server routing descriptions and candidates between any number of
+/// peer connections of the same process.
+///
+/// Each peer connection is identified by a key of type `K`, chosen by the test. Handlers
+/// post messages through a [`Signaler`], which the test then applies to their recipient with
+/// [`deliver`](Self::deliver), in the order they were posted. Nothing is delivered behind the
+/// test's back, so the interleaving of signaling with the rest of a test is deterministic.
+pub struct TestSignaling<K> {
+    mailbox: Arc<Mailbox<K>>,
+}
+
+impl<K> Clone for TestSignaling<K> {
+    fn clone(&self) -> Self {
+        Self {
+            mailbox: Arc::clone(&self.mailbox),
+        }
+    }
+}
+
+impl<K> Default for TestSignaling<K> {
+    fn default() -> Self {
+        Self {
+            mailbox: Arc::new(Mailbox {
+                queue: Mutex::new(VecDeque::new()),
+                posted: Condvar::new(),
+            }),
+        }
+    }
+}
+
+impl<K> fmt::Debug for TestSignaling<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TestSignaling")
+            .field("pending", &self.mailbox.queue.lock().len())
+            .finish()
+    }
+}
+
+impl<K> TestSignaling<K>
+where
+    K: Clone + PartialEq,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a [`Signaler`] posting the messages of the peer connection `from` to `to`.
+    pub fn signaler(&self, from: K, to: K) -> Signaler<K> {
+        Signaler {
+            from,
+            to,
+            mailbox: Arc::clone(&self.mailbox),
+        }
+    }
+
+    /// Number of messages posted but not delivered yet, to any recipient.
+    pub fn pending(&self) -> usize {
+        self.mailbox.queue.lock().len()
+    }
+
+    /// Removes and returns the messages addressed to `to`, in the order they were posted.
+    pub fn take(&self, to: &K) -> Vec<Envelope<K>> {
+        let mut queue = self.mailbox.queue.lock();
+        let (taken, kept) = queue.drain(..).partition(|envelope| envelope.to == *to);
+        *queue = kept;
+        taken.into()
+    }
+
+    /// Applies the messages addressed to `to` to its peer connection, returning how many
+    /// were delivered.
+    ///
+    /// Delivery stops at the first message the peer connection rejects, the following ones
+    /// are discarded.
+    pub fn deliver<P>(&self, to: &K, pc: &mut RtcPeerConnection<P>) -> Result<usize>
+    where
+        P: PeerConnectionHandler + Send,
+        P::DCH: DataChannelHandler + Send,
+    {
+        let envelopes = self.take(to);
+        let count = envelopes.len();
+        for envelope in envelopes {
+            match &envelope.message {
+                SignalingMessage::Description(sess_desc) => pc.set_remote_description(sess_desc)?,
+                SignalingMessage::Candidate(cand) => pc.add_remote_candidate(cand)?,
+            }
+        }
+        Ok(count)
+    }
+
+    /// Blocks until a message addressed to `to` is pending, returning false if none was
+    /// posted within `timeout`.
+    pub fn wait(&self, to: &K, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        let mut queue = self.mailbox.queue.lock();
+        while !queue.iter().any(|envelope| envelope.to == *to) {
+            if self
+                .mailbox
+                .posted
+                .wait_until(&mut queue, deadline)
+                .timed_out()
+            {
+                return queue.iter().any(|envelope| envelope.to == *to);
+            }
+        }
+        true
+    }
+}
+
+/// Posts the messages of one peer connection to another through [`TestSignaling`], meant to
+/// be held by its [`PeerConnectionHandler`].
+pub struct Signaler<K> {
+    from: K,
+    to: K,
+    mailbox: Arc<Mailbox<K>>,
+}
+
+impl<K> Signaler<K>
+where
+    K: Clone,
+{
+    pub fn send(&self, message: SignalingMessage) {
+        self.mailbox.queue.lock().push_back(Envelope {
+            from: self.from.clone(),
+            to: self.to.clone(),
+            message,
+        });
+        self.mailbox.posted.notify_all();
+    }
+
+    pub fn send_description(&self, sess_desc: SessionDescription) {
+        self.send(SignalingMessage::Description(Box::new(sess_desc)))
+    }
+
+    pub fn send_candidate(&self, cand: IceCandidate) {
+        self.send(SignalingMessage::Candidate(cand))
+    }
+}
+
+/// A callback recorded by a mock handler, along with when it was received.
+#[derive(Debug, Clone)]
+pub struct Recorded<E> {
+    pub timestamp: Instant,
+    pub event: E,
+}
+
+struct Log<E> {
+    entries: Mutex<Vec<Recorded<E>>>,
+    recorded: Condvar,
+}
+
+/// The callbacks received by a mock handler, shared between the handler and the test.
+pub struct Recording<E> {
+    log: Arc<Log<E>>,
+}
+
+impl<E> Clone for Recording<E> {
+    fn clone(&self) -> Self {
+        Self {
+            log: Arc::clone(&self.log),
+        }
+    }
+}
+
+impl<E> Default for Recording<E> {
+    fn default() -> Self {
+        Self {
+            log: Arc::new(Log {
+                entries: Mutex::new(vec![]),
+                recorded: Condvar::new(),
+            }),
+        }
+    }
+}
+
+impl<E: fmt::Debug> fmt::Debug for Recording<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.log.entries.lock().iter())
+            .finish()
+    }
+}
+
+impl<E> Recording<E>
+where
+    E: Clone + fmt::Debug,
+{
+    fn record(&self, event: E) {
+        self.log.entries.lock().push(Recorded {
+            timestamp: Instant::now(),
+            event,
+        });
+        self.log.recorded.notify_all();
+    }
+
+    /// The recorded callbacks, oldest first.
+    pub fn entries(&self) -> Vec<Recorded<E>> {
+        self.log.entries.lock().clone()
+    }
+
+    /// The recorded callbacks without their timestamps, oldest first.
+    pub fn events(&self) -> Vec<E> {
+        let entries = self.log.entries.lock();
+        entries.iter().map(|entry| entry.event.clone()).collect()
+    }
+
+    /// Number of recorded callbacks matching `predicate`.
+    pub fn count(&self, predicate: impl Fn(&E) -> bool) -> usize {
+        let entries = self.log.entries.lock();
+        entries
+            .iter()
+            .filter(|entry| predicate(&entry.event))
+            .count()
+    }
+
+    pub fn clear(&self) {
+        self.log.entries.lock().clear();
+    }
+
+    /// Blocks until a callback matching `predicate` is recorded, or returns `None` after
+    /// `timeout`. Callbacks recorded beforehand are matched too.
+    pub fn wait_for(&self, predicate: impl Fn(&E) -> bool, timeout: Duration) -> Option<E> {
+        let deadline = Instant::now() + timeout;
+        let mut entries = self.log.entries.lock();
+        loop {
+            if let Some(entry) = entries.iter().find(|entry| predicate(&entry.event)) {
+                return Some(entry.event.clone());
+            }
+            if self
+                .log
+                .recorded
+                .wait_until(&mut entries, deadline)
+                .timed_out()
+            {
+                return None;
+            }
+        }
+    }
+
+    /// Like [`wait_for`](Self::wait_for), but panics with the recorded callbacks when none
+    /// matches.
+    #[track_caller]
+    pub fn assert_recorded(&self, predicate: impl Fn(&E) -> bool, timeout: Duration) -> E {
+        match self.wait_for(predicate, timeout) {
+            Some(event) => event,
+            None => panic!(
+                "no matching callback recorded within {:?}, got {:?}",
+                timeout,
+                self.events()
+            ),
+        }
+    }
+
+    /// Panics if a callback matching `predicate` was recorded.
+    #[track_caller]
+    pub fn assert_not_recorded(&self, predicate: impl Fn(&E) -> bool) {
+        let events = self.events();
+        if let Some(event) = events.iter().find(|event| predicate(event)) {
+            panic!("unexpected callback {:?} recorded, got {:?}", event, events);
+        }
+    }
+}
+
+/// A callback of [`DataChannelHandler`] or [`TrackHandler`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChannelCall {
+    Open,
+    Closed,
+    Error(ChannelError),
+    Message(Vec<u8>),
+    BufferedAmountLow,
+    Available,
+    /// Only received by data channels with keepalive enabled
+    KeepaliveTimeout,
+    /// Only received by tracks
+    Bitrate(u32),
+}
+
+/// A [`DataChannelHandler`] and [`TrackHandler`] recording every callback it receives.
+#[derive(Debug, Clone, Default)]
+pub struct MockChannelHandler {
+    recording: Recording<ChannelCall>,
+}
+
+impl MockChannelHandler {
+    /// Returns the handler along with the recording of its callbacks.
+    pub fn new() -> (Self, Recording<ChannelCall>) {
+        let handler = Self::default();
+        let recording = handler.recording.clone();
+        (handler, recording)
+    }
+
+    pub fn recording(&self) -> &Recording<ChannelCall> {
+        &self.recording
+    }
+}
+
+impl DataChannelHandler for MockChannelHandler {
+    fn on_open(&mut self) {
+        self.recording.record(ChannelCall::Open)
+    }
+
+    fn on_closed(&mut self) {
+        self.recording.record(ChannelCall::Closed)
+    }
+
+    fn on_error(&mut self, err: &ChannelError) {
+        self.recording.record(ChannelCall::Error(err.clone()))
+    }
+
+    fn on_message(&mut self, msg: &[u8]) {
+        self.recording.record(ChannelCall::Message(msg.to_vec()))
+    }
+
+    fn on_buffered_amount_low(&mut self) {
+        self.recording.record(ChannelCall::BufferedAmountLow)
+    }
+
+    fn on_available(&mut self) {
+        self.recording.record(ChannelCall::Available)
+    }
+
+    fn on_keepalive_timeout(&mut self) {
+        self.recording.record(ChannelCall::KeepaliveTimeout)
+    }
+}
+
+impl TrackHandler for MockChannelHandler {
+    fn on_open(&mut self) {
+        self.recording.record(ChannelCall::Open)
+    }
+
+    fn on_closed(&mut self) {
+        self.recording.record(ChannelCall::Closed)
+    }
+
+    fn on_error(&mut self, err: &ChannelError) {
+        self.recording.record(ChannelCall::Error(err.clone()))
+    }
+
+    fn on_message(&mut self, msg: &[u8]) {
+        self.recording.record(ChannelCall::Message(msg.to_vec()))
+    }
+
+    fn on_available(&mut self) {
+        self.recording.record(ChannelCall::Available)
+    }
+
+    fn on_bitrate(&mut self, bitrate: u32) {
+        self.recording.record(ChannelCall::Bitrate(bitrate))
+    }
+}
+
+/// A callback of [`PeerConnectionHandler`].
+#[derive(Debug, Clone)]
+pub enum PeerConnectionCall {
+    Description {
+        sdp_type: SdpType,
+        sdp: String,
+    },
+    Candidate(IceCandidate),
+    ConnectionState(ConnectionState),
+    GatheringState(GatheringState),
+    EndOfCandidates,
+    SignalingState(SignalingState),
+    IceState(IceState),
+    /// A data channel opened by the remote peer, kept open by the handler unless forwarded
+    DataChannel {
+        info: DataChannelInfo,
+        recording: Recording<ChannelCall>,
+    },
+    Stats(StatsReport),
+    QualityChange(ConnectionQuality),
+}
+
+/// A data channel opened by the remote peer, along with the recording of its handler.
+pub type ForwardedChannel = (Box<RtcDataChannel<MockChannelHandler>>, Recording<ChannelCall>);
+
+/// A [`PeerConnectionHandler`] recording every callback it receives.
+///
+/// Data channels opened by the remote peer get a [`MockChannelHandler`] each, whose
+/// recording is carried by [`PeerConnectionCall::DataChannel`].
+#[derive(Default)]
+pub struct MockPeerConnectionHandler {
+    recording: Recording<PeerConnectionCall>,
+    channels: Vec<RtcDataChannel<MockChannelHandler>>,
+    /// Handed out by `data_channel_handler` until the channel is received
+    pending: Vec<(DataChannelInfo, Recording<ChannelCall>)>,
+    /// Label of the channels handed out, and where to
+    forward: Option<(String, mpsc::Sender<ForwardedChannel>)>,
+}
+
+impl MockPeerConnectionHandler {
+    /// Returns the handler along with the recording of its callbacks.
+    pub fn new() -> (Self, Recording<PeerConnectionCall>) {
+        let handler = Self::default();
+        let recording = handler.recording.clone();
+        (handler, recording)
+    }
+
+    /// Hands out the data channels labeled `label` opened by the remote peer through
+    /// `channels`, instead of keeping them.
+    pub fn forward_channels(
+        mut self,
+        label: impl Into<String>,
+        channels: mpsc::Sender<ForwardedChannel>,
+    ) -> Self {
+        self.forward = Some((label.into(), channels));
+        self
+    }
+}
+
+impl PeerConnectionHandler for MockPeerConnectionHandler {
+    type DCH = MockChannelHandler;
+
+    fn data_channel_handler(&mut self, info: DataChannelInfo) -> MockChannelHandler {
+        let (handler, recording) = MockChannelHandler::new();
+        self.pending.push((info, recording));
+        handler
+    }
+
+    fn on_description(&mut self, sess_desc: SessionDescription) {
+        self.recording.record(PeerConnectionCall::Description {
+            sdp_type: sess_desc.sdp_type,
+            sdp: sess_desc.sdp.to_string(),
+        })
+    }
+
+    fn on_candidate(&mut self, cand: IceCandidate) {
+        self.recording.record(PeerConnectionCall::Candidate(cand))
+    }
+
+    fn on_connection_state_change(&mut self, state: ConnectionState) {
+        self.recording
+            .record(PeerConnectionCall::ConnectionState(state))
+    }
+
+    fn on_gathering_state_change(&mut self, state: GatheringState) {
+        self.recording
+            .record(PeerConnectionCall::GatheringState(state))
+    }
+
+    fn on_end_of_candidates(&mut self) {
+        self.recording.record(PeerConnectionCall::EndOfCandidates)
+    }
+
+    fn on_signaling_state_change(&mut self, state: SignalingState) {
+        self.recording
+            .record(PeerConnectionCall::SignalingState(state))
+    }
+
+    fn on_ice_state_change(&mut self, state: IceState) {
+        self.recording.record(PeerConnectionCall::IceState(state))
+    }
+
+    fn on_data_channel(&mut self, data_channel: Box<RtcDataChannel<MockChannelHandler>>) {
+        let id = data_channel.id();
+        let Some(pos) = self.pending.iter().position(|(info, _)| info.id == id) else {
+            self.channels.push(*data_channel);
+            return;
+        };
+        let (info, recording) = self.pending.remove(pos);
+        let forward = match &self.forward {
+            Some((label, channels)) if info.label == *label => Some(channels.clone()),
+            _ => None,
+        };
+        self.recording.record(PeerConnectionCall::DataChannel {
+            info,
+            recording: recording.clone(),
+        });
+        match forward {
+            Some(channels) => {
+                channels.send((data_channel, recording)).ok();
+            }
+            None => self.channels.push(*data_channel),
+        }
+    }
+
+    fn on_stats(&mut self, report: StatsReport) {
+        self.recording.record(PeerConnectionCall::Stats(report))
+    }
+
+    fn on_quality_change(&mut self, quality: ConnectionQuality) {
+        self.recording
+            .record(PeerConnectionCall::QualityChange(quality))
+    }
+}
+
+/// A soak test opening many connections and channels at once and checking the delivery of
+/// the messages sent through them.
+///
+/// Each round creates [`connections`](Self::connections) pairs with [`local_pair`], opens
+/// [`channels`](Self::channels) data channels on each of them and sends
+/// [`messages`](Self::messages) numbered messages through every channel, from one thread per
+/// connection. Everything is dropped before the next round, so that ids get reused.
+#[derive(Debug, Clone)]
+pub struct Soak {
+    rounds: usize,
+    connections: usize,
+    channels: usize,
+    messages: usize,
+    message_size: usize,
+    reliability: Reliability,
+    impairment: Option<Impairment>,
+    timeout: Duration,
+}
+
+impl Default for Soak {
+    fn default() -> Self {
+        Self {
+            rounds: 1,
+            connections: 4,
+            channels: 4,
+            messages: 100,
+            message_size: 64,
+            reliability: Reliability::default(),
+            impairment: None,
+            timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Delivery of the messages sent during a [`Soak`] run, summed over all channels.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SoakReport {
+    pub sent: usize,
+    pub received: usize,
+    /// Messages never received
+    pub lost: usize,
+    /// Messages received more than once
+    pub duplicated: usize,
+    /// Messages received after one sent later
+    pub out_of_order: usize,
+    pub elapsed: Duration,
+}
+
+impl SoakReport {
+    /// Whether every message was received once and in order, as reliable ordered channels
+    /// guarantee.
+    pub fn is_lossless(&self) -> bool {
+        self.lost == 0 && self.duplicated == 0 && self.out_of_order == 0
+    }
+
+    fn merge(&mut self, other: &SoakReport) {
+        self.sent += other.sent;
+        self.received += other.received;
+        self.lost += other.lost;
+        self.duplicated += other.duplicated;
+        self.out_of_order += other.out_of_order;
+    }
+}
+
+impl Soak {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn rounds(mut self, rounds: usize) -> Self {
+        self.rounds = rounds;
+        self
+    }
+
+    /// Number of peer connection pairs alive at once.
+    pub fn connections(mut self, connections: usize) -> Self {
+        self.connections = connections;
+        self
+    }
+
+    /// Number of data channels opened on each connection.
+    pub fn channels(mut self, channels: usize) -> Self {
+        self.channels = channels;
+        self
+    }
+
+    /// Number of messages sent through each channel.
+    pub fn messages(mut self, messages: usize) -> Self {
+        self.messages = messages;
+        self
+    }
+
+    /// Size of each message, at least the 8 bytes of its sequence number.
+    pub fn message_size(mut self, message_size: usize) -> Self {
+        self.message_size = message_size.max(8);
+        self
+    }
+
+    pub fn reliability(mut self, reliability: Reliability) -> Self {
+        self.reliability = reliability;
+        self
+    }
+
+    /// Connects the peers through [`impaired_pair`] instead of [`local_pair`], to check how the
+    /// [`reliability`](Self::reliability) copes with loss and delays.
+    pub fn impairment(mut self, impairment: Impairment) -> Self {
+        self.impairment = Some(impairment);
+        self
+    }
+
+    /// How long each round waits for the channels to open and for the messages to arrive.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Runs all rounds, failing on the first connection, channel creation or send error.
+    ///
+    /// Messages still missing after the [`timeout`](Self::timeout) are reported as lost.
+    pub fn run(&self, config: &RtcConfig) -> Result<SoakReport> {
+        let start = Instant::now();
+        let mut report = SoakReport::default();
+        for _ in 0..self.rounds {
+            report.merge(&self.run_round(config)?);
+        }
+        report.elapsed = start.elapsed();
+        Ok(report)
+    }
+
+    fn run_round(&self, config: &RtcConfig) -> Result<SoakReport> {
+        let tally = Arc::new(Tally::default());
+        let mut pairs = Vec::with_capacity(self.connections);
+        let mut links = Vec::new();
+        for _ in 0..self.connections {
+            let peer = || SoakPeer {
+                tally: Arc::clone(&tally),
+                messages: self.messages,
+                channels: vec![],
+            };
+            match self.impairment {
+                Some(impairment) => {
+                    let (offerer, answerer, link) =
+                        impaired_pair(config, impairment, peer(), peer())?;
+                    pairs.push((offerer, answerer));
+                    links.push(link);
+                }
+                None => pairs.push(local_pair(config, peer(), peer())?),
+            }
+        }
+
+        let (opened, rx) = mpsc::channel();
+        let dc_init = DataChannelInit::default().reliability(self.reliability.clone());
+        let mut channels = Vec::with_capacity(self.connections);
+        for (conn, (offerer, _)) in pairs.iter_mut().enumerate() {
+            let mut conn_channels = Vec::with_capacity(self.channels);
+            for chan in 0..self.channels {
+                let label = format!("soak-{}-{}", conn, chan);
+                let handler = SoakSender(opened.clone());
+                conn_channels.push(offerer.create_data_channel_ex(&label, handler, &dc_init)?);
+            }
+            channels.push(conn_channels);
+        }
+        drop(opened);
+
+        let deadline = Instant::now() + self.timeout;
+        for _ in 0..self.connections * self.channels {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if rx.recv_timeout(remaining).is_err() {
+                panic!("Soak channels not open after {:?}", self.timeout);
+            }
+        }
+
+        let payload = vec![0u8; self.message_size];
+        thread::scope(|scope| {
+            let senders = channels
+                .iter_mut()
+                .map(|conn_channels| {
+                    let mut payload = payload.clone();
+                    scope.spawn(move || -> Result<()> {
+                        for seq in 0..self.messages as u64 {
+                            payload[..8].copy_from_slice(&seq.to_be_bytes());
+                            for dc in conn_channels.iter_mut() {
+                                dc.send(&payload)?;
+                            }
+                        }
+                        Ok(())
+                    })
+                })
+                .collect::<Vec<_>>();
+            senders
+                .into_iter()
+                .try_for_each(|sender| sender.join().expect("Soak sender panicked"))
+        })?;
+
+        let expected = self.connections * self.channels * self.messages;
+        tally.wait(expected, deadline);
+        let mut report = tally.report(self.messages);
+        report.sent = expected;
+        report.lost +=
+            (self.connections * self.channels).saturating_sub(tally.channels()) * self.messages;
+        Ok(report)
+    }
+}
+
+/// Handler of the sending side of a [`Soak`] channel.
+struct SoakSender(mpsc::Sender<()>);
+
+impl DataChannelHandler for SoakSender {
+    fn on_open(&mut self) {
+        self.0.send(()).ok();
+    }
+}
+
+/// Sequence numbers received on a [`Soak`] channel.
+#[derive(Default)]
+struct ChannelTally {
+    seen: Vec<bool>,
+    highest: Option<u64>,
+    received: usize,
+    duplicated: usize,
+    out_of_order: usize,
+}
+
+#[derive(Default)]
+struct Tally {
+    channels: Mutex<HashMap<String, ChannelTally>>,
+    received: Condvar,
+}
+
+impl Tally {
+    fn record(&self, label: &str, messages: usize, msg: &[u8]) {
+        let Some(seq) = msg
+            .get(..8)
+            .map(|seq| u64::from_be_bytes(seq.try_into().unwrap()))
+        else {
+            return;
+        };
+        let mut channels = self.channels.lock();
+        let tally = channels.entry(label.to_string()).or_default();
+        tally.seen.resize(messages, false);
+        tally.received += 1;
+        match tally.seen.get_mut(seq as usize) {
+            Some(true) => tally.duplicated += 1,
+            Some(seen) => *seen = true,
+            None => (),
+        }
+        if tally.highest.is_some_and(|highest| seq < highest) {
+            tally.out_of_order += 1;
+        }
+        tally.highest = tally.highest.max(Some(seq));
+        drop(channels);
+        self.received.notify_all();
+    }
+
+    fn wait(&self, expected: usize, deadline: Instant) {
+        let mut channels = self.channels.lock();
+        while channels.values().map(|tally| tally.received).sum::<usize>() < expected {
+            if self
+                .received
+                .wait_until(&mut channels, deadline)
+                .timed_out()
+            {
+                return;
+            }
+        }
+    }
+
+    fn channels(&self) -> usize {
+        self.channels.lock().len()
+    }
+
+    fn report(&self, messages: usize) -> SoakReport {
+        let channels = self.channels.lock();
+        let mut report = SoakReport::default();
+        for tally in channels.values() {
+            let seen = tally.seen.iter().filter(|seen| **seen).count();
+            report.received += tally.received;
+            report.lost += messages - seen;
+            report.duplicated += tally.duplicated;
+            report.out_of_order += tally.out_of_order;
+        }
+        report
+    }
+}
+
+/// Handler of both peers of a [`Soak`] connection, tallying what the channels receive.
+struct SoakPeer {
+    tally: Arc<Tally>,
+    messages: usize,
+    channels: Vec<RtcDataChannel<SoakReceiver>>,
+}
+
+struct SoakReceiver {
+    tally: Arc<Tally>,
+    messages: usize,
+    /// `None` for the channel opened by [`local_pair`]
+    label: Option<String>,
+}
+
+impl DataChannelHandler for SoakReceiver {
+    fn on_message(&mut self, msg: &[u8]) {
+        if let Some(label) = &self.label {
+            self.tally.record(label, self.messages, msg);
+        }
+    }
+}
+
+impl PeerConnectionHandler for SoakPeer {
+    type DCH = SoakReceiver;
+
+    fn data_channel_handler(&mut self, info: DataChannelInfo) -> SoakReceiver {
+        SoakReceiver {
+            tally: Arc::clone(&self.tally),
+            messages: self.messages,
+            label: (info.label != BOOTSTRAP_LABEL).then_some(info.label),
+        }
+    }
+
+    fn on_data_channel(&mut self, data_channel: Box<RtcDataChannel<SoakReceiver>>) {
+        self.channels.push(*data_channel);
+    }
+}
+
+/// Network impairment applied by the proxy of [`impaired_pair`], in both directions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Impairment {
+    loss: f64,
+    delay: Duration,
+    jitter: Duration,
+    seed: u64,
+}
+
+impl Default for Impairment {
+    fn default() -> Self {
+        Self {
+            loss: 0.0,
+            delay: Duration::ZERO,
+            jitter: Duration::ZERO,
+            seed: 0x5eed,
+        }
+    }
+}
+
+impl Impairment {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Probability for each packet to be dropped, between 0 and 1.
+    pub fn loss(mut self, loss: f64) -> Self {
+        self.loss = loss.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Delay added to each packet.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Random delay of up to `jitter` added on top of [`delay`](Self::delay), packets may
+    /// then be reordered.
+    pub fn jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Seed of the pseudo-random drops and jitter, so that runs are reproducible.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+}
+
+/// Packets handled by the proxy of an [`ImpairedLink`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImpairmentStats {
+    pub forwarded: u64,
+    pub dropped: u64,
+}
+
+/// The proxy between the peers of an [`impaired_pair`], stopped when dropped.
+pub struct ImpairedLink {
+    proxy: Arc<Proxy>,
+}
+
+impl ImpairedLink {
+    pub fn stats(&self) -> ImpairmentStats {
+        ImpairmentStats {
+            forwarded: self.proxy.forwarded.load(Ordering::Relaxed),
+            dropped: self.proxy.dropped.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Drop for ImpairedLink {
+    fn drop(&mut self) {
+        self.proxy.stopped.store(true, Ordering::Relaxed);
+    }
+}
+
+/// How often the proxy threads check whether they should stop.
+const PROXY_POLL: Duration = Duration::from_millis(50);
+
+/// The proxy socket standing for a peer, and the actual address of that peer.
+///
+/// The packets a peer sends to the socket standing for the other one are forwarded from its
+/// own socket, so that each peer only ever sees the address signaled for the other one.
+struct Endpoint {
+    socket: UdpSocket,
+    peer: SocketAddr,
+}
+
+/// A packet held back until `due`.
+struct Delayed {
+    due: Instant,
+    order: u64,
+    /// Index of the peer the packet is sent to
+    to: usize,
+    packet: Vec<u8>,
+}
+
+impl PartialEq for Delayed {
+    fn eq(&self, other: &Self) -> bool {
+        (self.due, self.order) == (other.due, other.order)
+    }
+}
+
+impl Eq for Delayed {}
+
+impl PartialOrd for Delayed {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Delayed {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.due, self.order).cmp(&(other.due, other.order))
+    }
+}
+
+struct Proxy {
+    impairment: Impairment,
+    endpoints: Mutex<[Option<Arc<Endpoint>>; 2]>,
+    /// xorshift state
+    rng: Mutex<u64>,
+    delayed: Mutex<Option<mpsc::Sender<Delayed>>>,
+    order: AtomicU64,
+    stopped: AtomicBool,
+    forwarded: AtomicU64,
+    dropped: AtomicU64,
+}
+
+impl Proxy {
+    fn new(impairment: Impairment) -> Self {
+        Self {
+            impairment,
+            endpoints: Mutex::new([None, None]),
+            rng: Mutex::new(impairment.seed.max(1)),
+            delayed: Mutex::new(None),
+            order: AtomicU64::new(0),
+            stopped: AtomicBool::new(false),
+            forwarded: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns a uniformly distributed number in `[0, 1)`.
+    fn random(&self) -> f64 {
+        let mut state = self.rng.lock();
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        (*state >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Rewrites the first IPv4 host candidate of peer `index` to the address of its proxy
+    /// socket, other candidates aren't signaled.
+    fn rewrite(self: &Arc<Self>, index: usize, cand: &IceCandidate) -> Option<IceCandidate> {
+        let mut fields = cand.candidate.split_whitespace().collect::<Vec<_>>();
+        let is_udp_host = fields.len() >= 8
+            && fields[2].eq_ignore_ascii_case("udp")
+            && fields[6] == "typ"
+            && fields[7] == "host";
+        let peer = SocketAddr::new(fields.get(4)?.parse().ok()?, fields.get(5)?.parse().ok()?);
+        if !is_udp_host || !matches!(peer.ip(), IpAddr::V4(_)) {
+            return None;
+        }
+
+        let mut endpoints = self.endpoints.lock();
+        if endpoints[index].is_some() {
+            return None;
+        }
+        let socket = match UdpSocket::bind(SocketAddr::new(peer.ip(), 0)) {
+            Ok(socket) => socket,
+            Err(err) => {
+                logger::warn!("Proxy {}: couldn't bind: {}", index, err);
+                return None;
+            }
+        };
+        socket.set_read_timeout(Some(PROXY_POLL)).ok()?;
+        let local = socket.local_addr().ok()?;
+        let endpoint = Arc::new(Endpoint { socket, peer });
+        endpoints[index] = Some(Arc::clone(&endpoint));
+        drop(endpoints);
+
+        self.spawn_forwarder(index, endpoint);
+
+        let (ip, port) = (local.ip().to_string(), local.port().to_string());
+        fields[4] = &ip;
+        fields[5] = &port;
+        Some(IceCandidate {
+            candidate: fields.join(" "),
+            mid: cand.mid.clone(),
+        })
+    }
+
+    /// Forwards the packets received on the socket standing for peer `index`, sent by the
+    /// other peer, to peer `index`.
+    fn spawn_forwarder(self: &Arc<Self>, index: usize, endpoint: Arc<Endpoint>) {
+        let delaying = !self.impairment.delay.is_zero() || !self.impairment.jitter.is_zero();
+        if delaying {
+            self.spawn_delayer();
+        }
+        let proxy = Arc::clone(self);
+        thread::spawn(move || {
+            let mut buf = vec![0u8; 65536];
+            while !proxy.stopped.load(Ordering::Relaxed) {
+                let Ok(len) = endpoint.socket.recv(&mut buf) else {
+                    continue;
+                };
+                if proxy.random() < proxy.impairment.loss {
+                    proxy.dropped.fetch_add(1, Ordering::Relaxed);
+                    continue;
+                }
+                let to = index;
+                if delaying {
+                    let jitter = proxy.impairment.jitter.mul_f64(proxy.random());
+                    let delayed = Delayed {
+                        due: Instant::now() + proxy.impairment.delay + jitter,
+                        order: proxy.order.fetch_add(1, Ordering::Relaxed),
+                        to,
+                        packet: buf[..len].to_vec(),
+                    };
+                    if let Some(delayed_tx) = &*proxy.delayed.lock() {
+                        delayed_tx.send(delayed).ok();
+                    }
+                } else {
+                    proxy.send(to, &buf[..len]);
+                }
+            }
+        });
+    }
+
+    /// Starts the thread sending the delayed packets once due, unless already running.
+    fn spawn_delayer(self: &Arc<Self>) {
+        let mut delayed_tx = self.delayed.lock();
+        if delayed_tx.is_some() {
+            return;
+        }
+        let (tx, rx) = mpsc::channel::<Delayed>();
+        *delayed_tx = Some(tx);
+        let proxy = Arc::clone(self);
+        thread::spawn(move || {
+            let mut queue = BinaryHeap::new();
+            while !proxy.stopped.load(Ordering::Relaxed) {
+                let now = Instant::now();
+                while queue
+                    .peek()
+                    .is_some_and(|Reverse(delayed): &Reverse<Delayed>| delayed.due <= now)
+                {
+                    let Reverse(delayed) = queue.pop().unwrap();
+                    proxy.send(delayed.to, &delayed.packet);
+                }
+                let wait = queue
+                    .peek()
+                    .map(|Reverse(delayed)| delayed.due.saturating_duration_since(now))
+                    .unwrap_or(PROXY_POLL)
+                    .min(PROXY_POLL);
+                if let Ok(delayed) = rx.recv_timeout(wait) {
+                    queue.push(Reverse(delayed));
+                }
+            }
+        });
+    }
+
+    /// Sends `packet` to peer `to`, from the socket standing for the other peer.
+    fn send(&self, to: usize, packet: &[u8]) {
+        let endpoints = self.endpoints.lock();
+        if let (Some(from), Some(to)) = (&endpoints[1 - to], &endpoints[to]) {
+            if from.socket.send_to(packet, to.peer).is_ok() {
+                self.forwarded.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use std::collections::VecDeque;
+use std::time::SystemTime;
+
+use serde::Serialize;
+
+use crate::signaling::{ConnectionState, GatheringState, IceState, SdpType, SignalingState};
+
+/// A negotiation event of a peer connection, see
+/// [`RtcConfig::record_timeline`](crate::RtcConfig::record_timeline).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum TimelineEvent {
+    /// A local description was generated
+    LocalDescription {
+        sdp_type: SdpType,
+    },
+    RemoteDescriptionSet {
+        sdp_type: SdpType,
+    },
+    /// A local candidate was signaled
+    LocalCandidate {
+        candidate: String,
+    },
+    RemoteCandidateAdded {
+        candidate: String,
+    },
+    ConnectionState {
+        state: ConnectionState,
+    },
+    GatheringState {
+        state: GatheringState,
+    },
+    SignalingState {
+        state: SignalingState,
+    },
+    IceState {
+        state: IceState,
+    },
+    /// A call made on the connection failed
+    Failed {
+        call: &'static str,
+        error: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TimelineEntry {
+    pub timestamp: SystemTime,
+    #[serde(flatten)]
+    pub event: TimelineEvent,
+}
+
+/// A ring buffer of the latest negotiation events.
+pub(crate) struct Timeline {
+    capacity: usize,
+    entries: VecDeque<TimelineEntry>,
+}
+
+impl Timeline {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    pub(crate) fn record(&mut self, event: TimelineEvent) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(TimelineEntry {
+            timestamp: SystemTime::now(),
+            event,
+        });
+    }
+
+    pub(crate) fn entries(&self) -> Vec<TimelineEntry> {
+        self.entries.iter().cloned().collect()
+    }
+}
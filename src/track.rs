@@ -1,15 +1,27 @@
 use std::ffi::{c_void, CStr, CString};
 use std::os::raw::c_char;
-use std::{ptr, slice};
+use std::path::Path;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use std::{slice, thread};
 
 use datachannel_sys as sys;
+use parking_lot::Mutex;
+use webrtc_sdp::attribute_type::{
+    SdpAttribute, SdpAttributeRid, SdpAttributeRidParameters, SdpAttributeRtpmap,
+    SdpAttributeSimulcast, SdpAttributeSimulcastId, SdpAttributeSimulcastVersion,
+    SdpSingleDirection,
+};
 use webrtc_sdp::media_type::{parse_media_vector, SdpMedia};
 use webrtc_sdp::{parse_sdp_line, SdpLine};
 
-use crate::error::{check, Result};
+use crate::capture::{CaptureDirection, CaptureFormat, RtpCapture};
+use crate::error::{check, ChannelError, Error, Result};
 use crate::logger;
+use crate::stats::{TrackStats, Traffic};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(any(not(target_os = "windows"), target_env = "gnu"), repr(u32))]
 #[cfg_attr(all(target_os = "windows", not(target_env = "gnu")), repr(i32))]
 pub enum Direction {
@@ -52,14 +64,74 @@ impl TryFrom<i32> for Direction {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-#[cfg_attr(any(not(target_os = "windows"), target_env = "gnu"), repr(u32))]
-#[cfg_attr(all(target_os = "windows", not(target_env = "gnu")), repr(i32))]
+impl Direction {
+    fn sdp_attribute(&self) -> Option<&'static str> {
+        match self {
+            Self::Unknown => None,
+            Self::SendOnly => Some("sendonly"),
+            Self::RecvOnly => Some("recvonly"),
+            Self::SendRecv => Some("sendrecv"),
+            Self::Inactive => Some("inactive"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Codec {
-    H264 = sys::rtcCodec_RTC_CODEC_H264,
-    VP8 = sys::rtcCodec_RTC_CODEC_VP8,
-    VP9 = sys::rtcCodec_RTC_CODEC_VP9,
-    Opus = sys::rtcCodec_RTC_CODEC_OPUS,
+    H264,
+    VP8,
+    VP9,
+    H265,
+    AV1,
+    Opus,
+    PCMU,
+    PCMA,
+    G722,
+    AAC,
+    /// A codec unknown to libdatachannel, described by its `rtpmap` encoding (for instance
+    /// `"G729/8000"`).
+    ///
+    /// Tracks with a clock rate of 90000 are video tracks, other ones are audio tracks. The
+    /// [`TrackInit::profile`] is used as the `fmtp` parameters of the codec.
+    Custom(String),
+}
+
+impl Codec {
+    fn as_raw(&self) -> Option<sys::rtcCodec> {
+        match self {
+            Self::H264 => Some(sys::rtcCodec_RTC_CODEC_H264),
+            Self::VP8 => Some(sys::rtcCodec_RTC_CODEC_VP8),
+            Self::VP9 => Some(sys::rtcCodec_RTC_CODEC_VP9),
+            Self::H265 => Some(sys::rtcCodec_RTC_CODEC_H265),
+            Self::AV1 => Some(sys::rtcCodec_RTC_CODEC_AV1),
+            Self::Opus => Some(sys::rtcCodec_RTC_CODEC_OPUS),
+            Self::PCMU => Some(sys::rtcCodec_RTC_CODEC_PCMU),
+            Self::PCMA => Some(sys::rtcCodec_RTC_CODEC_PCMA),
+            Self::AAC => Some(sys::rtcCodec_RTC_CODEC_AAC),
+            Self::G722 | Self::Custom(_) => None,
+        }
+    }
+
+    /// The `rtpmap` encoding of the codec, such as `"opus/48000/2"`.
+    pub fn encoding(&self) -> &str {
+        match self {
+            Self::H264 => "H264/90000",
+            Self::VP8 => "VP8/90000",
+            Self::VP9 => "VP9/90000",
+            Self::H265 => "H265/90000",
+            Self::AV1 => "AV1/90000",
+            Self::Opus => "opus/48000/2",
+            Self::PCMU => "PCMU/8000/1",
+            Self::PCMA => "PCMA/8000/1",
+            Self::G722 => "G722/8000/1",
+            Self::AAC => "MP4A-LATM/48000/2",
+            Self::Custom(encoding) => encoding,
+        }
+    }
+
+    pub fn is_video(&self) -> bool {
+        self.encoding().split('/').nth(1) == Some("90000")
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -76,10 +148,11 @@ pub struct TrackInit {
 }
 
 impl TrackInit {
-    pub(crate) fn as_raw(&self) -> sys::rtcTrackInit {
-        sys::rtcTrackInit {
+    /// Returns `None` if the codec has to be described with [`TrackInit::media_description`].
+    pub(crate) fn as_raw(&self) -> Option<sys::rtcTrackInit> {
+        Some(sys::rtcTrackInit {
             direction: self.direction as _,
-            codec: self.codec as _,
+            codec: self.codec.as_raw()?,
             payloadType: self.payload_type,
             ssrc: self.ssrc,
             mid: self.mid.as_ptr(),
@@ -103,7 +176,243 @@ impl TrackInit {
                 .as_ref()
                 .map(|s| s.as_ptr())
                 .unwrap_or(std::ptr::null()),
+        })
+    }
+
+    /// Builds the media description libdatachannel would generate for this track, which is
+    /// needed for codecs it doesn't know about.
+    pub(crate) fn sdp_media(&self) -> Result<SdpMedia> {
+        let payload_type = u8::try_from(self.payload_type).map_err(|_| Error::InvalidArg)?;
+        let mid = self.mid.to_str().map_err(|_| Error::InvalidArg)?;
+
+        let mut builder = if self.codec.is_video() {
+            SdpMediaBuilder::video()
+        } else {
+            SdpMediaBuilder::audio()
+        }
+        .mid(mid)
+        .direction(self.direction)
+        .ssrc(self.ssrc);
+        builder = match &self.profile {
+            Some(profile) => builder.codec_with_fmtp(
+                payload_type,
+                self.codec.clone(),
+                &profile.to_string_lossy(),
+            ),
+            None => builder.codec(payload_type, self.codec.clone()),
+        };
+        if let Some(name) = &self.name {
+            builder = builder.cname(&name.to_string_lossy());
+        }
+        if let Some(msid) = &self.msid {
+            let msid = msid.to_string_lossy();
+            let track_id = self
+                .track_id
+                .as_ref()
+                .map(|id| id.to_string_lossy())
+                .unwrap_or_else(|| msid.clone());
+            builder = builder.msid(&msid, &track_id);
+        }
+        builder.build()
+    }
+}
+
+/// A fluent builder of audio or video media sections, as expected by
+/// [`add_track`](crate::RtcPeerConnection::add_track).
+///
+/// ```
+/// use datachannel::{Codec, Direction, SdpMediaBuilder};
+///
+/// let media = SdpMediaBuilder::video()
+///     .mid("video")
+///     .direction(Direction::SendOnly)
+///     .codec(96, Codec::H264)
+///     .ssrc(42)
+///     .cname("video-send")
+///     .msid("stream1", "video1")
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct SdpMediaBuilder {
+    video: bool,
+    mid: String,
+    direction: Direction,
+    codecs: Vec<(u8, Codec, Option<String>)>,
+    ssrc: Option<u32>,
+    cname: Option<String>,
+    msid: Option<(String, String)>,
+}
+
+impl SdpMediaBuilder {
+    pub fn audio() -> Self {
+        Self::new(false)
+    }
+
+    pub fn video() -> Self {
+        Self::new(true)
+    }
+
+    fn new(video: bool) -> Self {
+        Self {
+            video,
+            mid: if video { "video" } else { "audio" }.to_string(),
+            direction: Direction::Unknown,
+            codecs: vec![],
+            ssrc: None,
+            cname: None,
+            msid: None,
+        }
+    }
+
+    pub fn mid(mut self, mid: &str) -> Self {
+        self.mid = mid.to_string();
+        self
+    }
+
+    pub fn direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Adds a codec, codecs are listed in order of preference.
+    pub fn codec(mut self, payload_type: u8, codec: Codec) -> Self {
+        self.codecs.push((payload_type, codec, None));
+        self
+    }
+
+    /// Adds a codec along with its `fmtp` parameters (for instance
+    /// `"profile-level-id=42e01f;packetization-mode=1"`).
+    pub fn codec_with_fmtp(mut self, payload_type: u8, codec: Codec, fmtp: &str) -> Self {
+        self.codecs
+            .push((payload_type, codec, Some(fmtp.to_string())));
+        self
+    }
+
+    pub fn ssrc(mut self, ssrc: u32) -> Self {
+        self.ssrc = Some(ssrc);
+        self
+    }
+
+    /// Canonical name associated to the SSRC, ignored if no SSRC is set.
+    pub fn cname(mut self, cname: &str) -> Self {
+        self.cname = Some(cname.to_string());
+        self
+    }
+
+    /// Media stream and track ids associated to the SSRC, ignored if no SSRC is set.
+    pub fn msid(mut self, msid: &str, track_id: &str) -> Self {
+        self.msid = Some((msid.to_string(), track_id.to_string()));
+        self
+    }
+
+    pub fn build(&self) -> Result<SdpMedia> {
+        if self.codecs.is_empty() {
+            return Err(Error::InvalidArg);
+        }
+
+        let payload_types = self
+            .codecs
+            .iter()
+            .map(|(pt, _, _)| pt.to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let kind = if self.video { "video" } else { "audio" };
+
+        let mut lines = vec![
+            format!("m={} 9 UDP/TLS/RTP/SAVPF {}", kind, payload_types),
+            format!("a=mid:{}", self.mid),
+        ];
+        if let Some(direction) = self.direction.sdp_attribute() {
+            lines.push(format!("a={}", direction));
+        }
+        for (pt, codec, fmtp) in &self.codecs {
+            lines.push(format!("a=rtpmap:{} {}", pt, codec.encoding()));
+            if let Some(fmtp) = fmtp {
+                lines.push(format!("a=fmtp:{} {}", pt, fmtp));
+            }
+        }
+        if let Some(ssrc) = self.ssrc {
+            if let Some(cname) = &self.cname {
+                lines.push(format!("a=ssrc:{} cname:{}", ssrc, cname));
+            }
+            if let Some((msid, track_id)) = &self.msid {
+                lines.push(format!("a=ssrc:{} msid:{} {}", ssrc, msid, track_id));
+            }
+            if self.cname.is_none() && self.msid.is_none() {
+                lines.push(format!("a=ssrc:{}", ssrc));
+            }
         }
+
+        let mut sdp_lines = lines
+            .iter()
+            .enumerate()
+            .map(|(line_number, line)| parse_sdp_line(line, line_number))
+            .collect::<std::result::Result<Vec<SdpLine>, _>>()
+            .map_err(|err| {
+                logger::error!("Couldn't parse SdpLine: {}", err);
+                Error::InvalidArg
+            })?;
+        parse_media_vector(&mut sdp_lines)
+            .map_err(|err| {
+                logger::error!("Couldn't parse SdpMedia: {}", err);
+                Error::InvalidArg
+            })?
+            .pop()
+            .ok_or(Error::InvalidArg)
+    }
+}
+
+/// A simulcast encoding of a track, identified by its RTP stream id (`rid`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimulcastLayer {
+    pub rid: String,
+    pub ssrc: u32,
+}
+
+impl SimulcastLayer {
+    pub fn new(rid: &str, ssrc: u32) -> Self {
+        Self {
+            rid: rid.to_string(),
+            ssrc,
+        }
+    }
+
+    /// Adds the `a=rid` and `a=simulcast` attributes describing `layers` to `sdp_media`.
+    pub(crate) fn add_to(layers: &[SimulcastLayer], sdp_media: &mut SdpMedia) -> Result<()> {
+        for layer in layers {
+            sdp_media
+                .add_attribute(SdpAttribute::Rid(SdpAttributeRid {
+                    id: layer.rid.clone(),
+                    direction: SdpSingleDirection::Send,
+                    formats: vec![],
+                    params: SdpAttributeRidParameters {
+                        max_width: 0,
+                        max_height: 0,
+                        max_fps: 0,
+                        max_fs: 0,
+                        max_br: 0,
+                        max_pps: 0,
+                        unknown: vec![],
+                    },
+                    depends: vec![],
+                }))
+                .map_err(|_| Error::InvalidArg)?;
+        }
+        sdp_media
+            .add_attribute(SdpAttribute::Simulcast(SdpAttributeSimulcast {
+                send: layers
+                    .iter()
+                    .map(|layer| SdpAttributeSimulcastVersion {
+                        ids: vec![SdpAttributeSimulcastId {
+                            id: layer.rid.clone(),
+                            paused: false,
+                        }],
+                    })
+                    .collect(),
+                receive: vec![],
+            }))
+            .map_err(|_| Error::InvalidArg)
     }
 }
 
@@ -111,24 +420,208 @@ impl TrackInit {
 pub trait TrackHandler {
     fn on_open(&mut self) {}
     fn on_closed(&mut self) {}
-    fn on_error(&mut self, err: &str) {}
+    fn on_error(&mut self, err: &ChannelError) {}
     fn on_message(&mut self, msg: &[u8]) {}
     fn on_available(&mut self) {}
+    fn on_bitrate(&mut self, bitrate: u32) {}
+}
+
+/// Whether a packet multiplexed with RTP is RTCP, whose packet types 192 to 223 show up as
+/// marked payload types 64 to 95 (RFC 5761).
+pub(crate) fn is_rtcp(packet: &[u8]) -> bool {
+    packet.len() >= 2 && (192..=223).contains(&packet[1])
+}
+
+/// Tracks received RTP sequence numbers to issue rate limited keyframe requests.
+struct KeyframeRecovery {
+    min_interval: Duration,
+    last_request: Option<Instant>,
+    last_packet: Option<(u32, u16)>,
+}
+
+impl KeyframeRecovery {
+    fn new(min_interval: Duration) -> Self {
+        Self {
+            min_interval,
+            last_request: None,
+            last_packet: None,
+        }
+    }
+
+    /// Returns true when a received packet reveals lost packets or a new stream.
+    fn detect_gap(&mut self, packet: &[u8]) -> bool {
+        if packet.len() < 12 || is_rtcp(packet) {
+            return false;
+        }
+        let seq = u16::from_be_bytes([packet[2], packet[3]]);
+        let ssrc = u32::from_be_bytes([packet[8], packet[9], packet[10], packet[11]]);
+        match self.last_packet {
+            Some((last_ssrc, last_seq)) if last_ssrc == ssrc => {
+                let delta = seq.wrapping_sub(last_seq);
+                if delta == 0 || delta >= 0x8000 {
+                    // Duplicated or reordered packet
+                    return false;
+                }
+                self.last_packet = Some((ssrc, seq));
+                delta > 1
+            }
+            Some(_) => {
+                self.last_packet = Some((ssrc, seq));
+                true
+            }
+            None => {
+                self.last_packet = Some((ssrc, seq));
+                false
+            }
+        }
+    }
+
+    fn should_request(&mut self) -> bool {
+        let now = Instant::now();
+        match self.last_request {
+            Some(last) if now.duration_since(last) < self.min_interval => false,
+            _ => {
+                self.last_request = Some(now);
+                true
+            }
+        }
+    }
+}
+
+/// Token bucket pacing of [`RtcTrack::send`], see [`RtcTrack::set_pacing`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pacing {
+    /// Bytes allowed to be sent per `interval`
+    pub bytes_per_interval: usize,
+    pub interval: Duration,
+    /// Bytes that can be sent at once after an idle period
+    pub burst: usize,
+}
+
+impl Pacing {
+    pub fn new(bytes_per_interval: usize, interval: Duration, burst: usize) -> Self {
+        Self {
+            bytes_per_interval,
+            interval,
+            burst,
+        }
+    }
+
+    /// Paces at `bitrate` bits per second with 5ms intervals and a burst of one interval.
+    pub fn bitrate(bitrate: usize) -> Self {
+        let bytes_per_interval = bitrate / 8 / 200;
+        Self::new(
+            bytes_per_interval,
+            Duration::from_millis(5),
+            bytes_per_interval,
+        )
+    }
+}
+
+struct Pacer {
+    pacing: Pacing,
+    tokens: f64,
+    last: Instant,
+}
+
+impl Pacer {
+    fn new(pacing: Pacing) -> Self {
+        Self {
+            pacing,
+            tokens: pacing.burst as f64,
+            last: Instant::now(),
+        }
+    }
+
+    /// Blocks until `len` bytes can be sent.
+    fn wait(&mut self, len: usize) {
+        let rate = self.pacing.bytes_per_interval as f64 / self.pacing.interval.as_secs_f64();
+        let now = Instant::now();
+        let refill = now.duration_since(self.last).as_secs_f64() * rate;
+        self.tokens = (self.tokens + refill).min(self.pacing.burst as f64);
+        self.last = now;
+
+        // Packets larger than the remaining tokens are let through once the bucket is no
+        // longer in debt, so the debt they create delays the next ones.
+        if self.tokens < 0.0 && rate > 0.0 {
+            let delay = Duration::from_secs_f64(-self.tokens / rate);
+            thread::sleep(delay);
+            self.tokens = 0.0;
+            self.last = Instant::now();
+        }
+        self.tokens -= len as f64;
+    }
+}
+
+/// State reached from the callbacks, registered as user pointer.
+///
+/// It lives in its own allocation so that moving a [`RtcTrack`] out of its box doesn't
+/// invalidate the pointer held by libdatachannel.
+///
+/// libdatachannel may run different callbacks of a track concurrently, hence the locks.
+struct TrackState<T> {
+    id: i32,
+    t_handler: Mutex<T>,
+    keyframe_recovery: Mutex<Option<KeyframeRecovery>>,
+    remote_bitrate: AtomicU32,
+    capture: Mutex<Option<RtpCapture>>,
+    /// Shared with the peer connection
+    traffic: Arc<Traffic>,
+    stats: Mutex<TrackStats>,
+    span: logger::Span,
+}
+
+impl<T> TrackState<T> {
+    fn capture(&self, direction: CaptureDirection, packet: &[u8]) {
+        let mut capture = self.capture.lock();
+        if let Some(writer) = capture.as_mut() {
+            if let Err(err) = writer.record(direction, packet) {
+                logger::warn!("Stopping capture on track {}: {}", self.id, err);
+                capture.take();
+            }
+        }
+    }
 }
 
 pub struct RtcTrack<T> {
     id: i32,
-    t_handler: T,
+    state: Box<TrackState<T>>,
+    layers: Vec<SimulcastLayer>,
+    pacer: Option<Pacer>,
+    /// Set once deleted, the id may then be reused by libdatachannel
+    deleted: bool,
 }
 
 impl<T> RtcTrack<T>
 where
     T: TrackHandler + Send,
 {
-    pub(crate) fn new(id: i32, t_handler: T) -> Result<Box<Self>> {
+    pub(crate) fn new(
+        id: i32,
+        t_handler: T,
+        traffic: Arc<Traffic>,
+        span: logger::Span,
+    ) -> Result<Box<Self>> {
+        crate::runtime::register();
+        crate::telemetry::track_created();
         unsafe {
-            let mut rtc_t = Box::new(RtcTrack { id, t_handler });
-            let ptr = &mut *rtc_t;
+            let mut rtc_t = Box::new(RtcTrack {
+                id,
+                state: Box::new(TrackState {
+                    id,
+                    t_handler: Mutex::new(t_handler),
+                    keyframe_recovery: Mutex::new(None),
+                    remote_bitrate: AtomicU32::new(0),
+                    capture: Mutex::new(None),
+                    traffic,
+                    stats: Mutex::new(TrackStats::default()),
+                    span,
+                }),
+                layers: vec![],
+                pacer: None,
+                deleted: false,
+            });
+            let ptr = &mut *rtc_t.state;
 
             sys::rtcSetUserPointer(id, ptr as *mut _ as *mut c_void);
 
@@ -156,121 +649,301 @@ where
     }
 
     unsafe extern "C" fn open_cb(_: i32, ptr: *mut c_void) {
-        let rtc_t = &mut *(ptr as *mut RtcTrack<T>);
-        rtc_t.t_handler.on_open()
+        crate::panic::catch("RtcTrack::open_cb", || {
+            let Some(state) = (ptr as *const TrackState<T>).as_ref() else {
+                return;
+            };
+            let _span = state.span.enter();
+            state.t_handler.lock().on_open()
+        })
     }
 
     unsafe extern "C" fn closed_cb(_: i32, ptr: *mut c_void) {
-        let rtc_t = &mut *(ptr as *mut RtcTrack<T>);
-        rtc_t.t_handler.on_closed()
+        crate::panic::catch("RtcTrack::closed_cb", || {
+            let Some(state) = (ptr as *const TrackState<T>).as_ref() else {
+                return;
+            };
+            let _span = state.span.enter();
+            state.t_handler.lock().on_closed()
+        })
     }
 
     unsafe extern "C" fn error_cb(_: i32, err: *const c_char, ptr: *mut c_void) {
-        let rtc_t = &mut *(ptr as *mut RtcTrack<T>);
-        let err = CStr::from_ptr(err).to_string_lossy();
-        rtc_t.t_handler.on_error(&err)
+        crate::panic::catch("RtcTrack::error_cb", || {
+            let Some(state) = (ptr as *const TrackState<T>).as_ref() else {
+                return;
+            };
+            let _span = state.span.enter();
+            let err = ChannelError::from_message(&CStr::from_ptr(err).to_string_lossy());
+            state.t_handler.lock().on_error(&err)
+        })
     }
 
     unsafe extern "C" fn message_cb(_: i32, msg: *const c_char, size: i32, ptr: *mut c_void) {
-        let rtc_t = &mut *(ptr as *mut RtcTrack<T>);
-        let msg = if size < 0 {
-            CStr::from_ptr(msg).to_bytes()
-        } else {
-            slice::from_raw_parts(msg as *const u8, size as usize)
-        };
-        rtc_t.t_handler.on_message(msg)
+        crate::panic::catch("RtcTrack::message_cb", || {
+            let Some(state) = (ptr as *const TrackState<T>).as_ref() else {
+                return;
+            };
+            let _span = state.span.enter();
+            let msg = if size < 0 {
+                CStr::from_ptr(msg).to_bytes()
+            } else {
+                slice::from_raw_parts(msg as *const u8, size as usize)
+            };
+            state.capture(CaptureDirection::Received, msg);
+            state.traffic.received(msg.len());
+            crate::telemetry::received("track", msg.len());
+            if let Some((report, round_trip_time)) = state.stats.lock().received(msg) {
+                state.traffic.reported(&report, round_trip_time);
+            }
+            if let Some(recovery) = state.keyframe_recovery.lock().as_mut() {
+                if recovery.detect_gap(msg) && recovery.should_request() {
+                    if let Err(err) = check(sys::rtcRequestKeyframe(state.id)) {
+                        logger::warn!("Couldn't request keyframe on track {}: {}", state.id, err);
+                    }
+                }
+            }
+            state.t_handler.lock().on_message(msg)
+        })
     }
 
     unsafe extern "C" fn available_cb(_: i32, ptr: *mut c_void) {
-        let rtc_t = &mut *(ptr as *mut RtcTrack<T>);
-        rtc_t.t_handler.on_available()
+        crate::panic::catch("RtcTrack::available_cb", || {
+            let Some(state) = (ptr as *const TrackState<T>).as_ref() else {
+                return;
+            };
+            let _span = state.span.enter();
+            state.t_handler.lock().on_available()
+        })
+    }
+
+    #[cfg(feature = "media")]
+    unsafe extern "C" fn remb_cb(_: i32, bitrate: u32, ptr: *mut c_void) {
+        crate::panic::catch("RtcTrack::remb_cb", || {
+            // The REMB handler can't be unchained, it outlives the track once dropped
+            let Some(state) = (ptr as *const TrackState<T>).as_ref() else {
+                return;
+            };
+            let _span = state.span.enter();
+            state.remote_bitrate.store(bitrate, Ordering::Relaxed);
+            state.t_handler.lock().on_bitrate(bitrate)
+        })
     }
 
     pub fn send(&mut self, msg: &[u8]) -> Result<()> {
-        check(unsafe {
+        if let Some(pacer) = &mut self.pacer {
+            pacer.wait(msg.len());
+        }
+        crate::datachannel::check_send(self.id, unsafe {
             sys::rtcSendMessage(self.id, msg.as_ptr() as *const c_char, msg.len() as i32)
-        })
-        .map(|_| ())
+        })?;
+        self.state.capture(CaptureDirection::Sent, msg);
+        self.state.traffic.sent(msg.len());
+        crate::telemetry::sent("track", msg.len());
+        self.state.stats.lock().sent(msg);
+        Ok(())
+    }
+
+    /// Paces [`send`](Self::send), which then blocks as needed to smooth bursts such as large
+    /// keyframes over time. `None` disables pacing.
+    ///
+    /// When a packetizer is set, whole frames are paced rather than individual RTP packets.
+    pub fn set_pacing(&mut self, pacing: Option<Pacing>) {
+        self.pacer = pacing.map(Pacer::new);
+    }
+
+    pub fn pacing(&self) -> Option<Pacing> {
+        self.pacer.as_ref().map(|pacer| pacer.pacing)
+    }
+
+    /// Returns the statistics of this track, see [`TrackStats`].
+    pub fn stats(&self) -> TrackStats {
+        *self.state.stats.lock()
+    }
+
+    /// Starts recording the packets sent and received on this track to `path`, replacing
+    /// any ongoing capture.
+    ///
+    /// Messages are recorded as they go through the track, so when a packetizer is set the
+    /// sent media frames are recorded instead of RTP packets.
+    pub fn start_capture<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        format: CaptureFormat,
+    ) -> std::io::Result<()> {
+        let capture = RtpCapture::create(path.as_ref(), format)?;
+        if let Some(mut previous) = self.state.capture.lock().replace(capture) {
+            previous.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Stops the ongoing capture, if any, and flushes it.
+    pub fn stop_capture(&mut self) -> std::io::Result<()> {
+        match self.state.capture.lock().take() {
+            Some(mut capture) => capture.flush(),
+            None => Ok(()),
+        }
+    }
+
+    pub fn is_capturing(&self) -> bool {
+        self.state.capture.lock().is_some()
+    }
+
+    /// Sends an RTP packet on the simulcast layer identified by `rid`.
+    ///
+    /// The SSRC of the packet header is rewritten to the one of the layer.
+    pub fn send_layer(&mut self, rid: &str, packet: &[u8]) -> Result<()> {
+        let ssrc = self
+            .layers
+            .iter()
+            .find(|layer| layer.rid == rid)
+            .map(|layer| layer.ssrc)
+            .ok_or(Error::InvalidArg)?;
+        if packet.len() < 12 {
+            return Err(Error::InvalidArg);
+        }
+        let mut packet = packet.to_vec();
+        packet[8..12].copy_from_slice(&ssrc.to_be_bytes());
+        self.send(&packet)
+    }
+
+    /// Simulcast layers this track was created with, see
+    /// [`add_simulcast_track`](crate::RtcPeerConnection::add_simulcast_track).
+    pub fn layers(&self) -> &[SimulcastLayer] {
+        &self.layers
+    }
+
+    pub(crate) fn set_layers(&mut self, layers: Vec<SimulcastLayer>) {
+        self.layers = layers;
+    }
+
+    /// Requests a keyframe from the remote sender (PLI).
+    pub fn request_keyframe(&mut self) -> Result<()> {
+        check(unsafe { sys::rtcRequestKeyframe(self.id) })?;
+        Ok(())
+    }
+
+    /// Reports the bitrate in bits per second available to the remote sender (REMB).
+    pub fn request_bitrate(&mut self, bitrate: u32) -> Result<()> {
+        check(unsafe { sys::rtcRequestBitrate(self.id, bitrate) })?;
+        Ok(())
+    }
+
+    /// Latest bitrate estimate in bits per second reported by the remote receiver, requires a
+    /// REMB handler to be chained (`media` feature).
+    ///
+    /// Only REMB is supported, libdatachannel doesn't implement transport-cc feedback.
+    pub fn remote_bitrate(&self) -> Option<u32> {
+        match self.state.remote_bitrate.load(Ordering::Relaxed) {
+            0 => None,
+            bitrate => Some(bitrate),
+        }
+    }
+
+    /// Enables automatic keyframe requests on received video whenever a gap in RTP sequence
+    /// numbers or a new SSRC is detected, at most once per `min_interval`. `None` disables them.
+    pub fn set_keyframe_recovery(&mut self, min_interval: Option<Duration>) {
+        *self.state.keyframe_recovery.lock() = min_interval.map(KeyframeRecovery::new);
+    }
+
+    /// Signals that the decoder was reset, a keyframe is requested unless one was already
+    /// requested less than `min_interval` ago. Returns whether a request was issued.
+    ///
+    /// Keyframe recovery must be enabled with [`set_keyframe_recovery`](Self::set_keyframe_recovery).
+    pub fn on_decoder_reset(&mut self) -> Result<bool> {
+        let should_request = self
+            .state
+            .keyframe_recovery
+            .lock()
+            .as_mut()
+            .is_some_and(KeyframeRecovery::should_request);
+        if should_request {
+            self.request_keyframe()?;
+        }
+        Ok(should_request)
     }
 
     pub fn description(&self) -> Option<Vec<SdpMedia>> {
-        let buf_size = check(unsafe {
-            sys::rtcGetTrackDescription(self.id, ptr::null_mut() as *mut c_char, 0)
-        })
-        .expect("Couldn't get buffer size") as usize;
+        self.try_description()
+            .map_err(|err| {
+                logger::warn!(
+                    "Couldn't get description for RtcTrack id={} {:p}, {}",
+                    self.id,
+                    self,
+                    err
+                )
+            })
+            .ok()
+            .flatten()
+    }
 
-        let mut buf = vec![0; buf_size];
-        check(unsafe {
-            sys::rtcGetTrackDescription(self.id, buf.as_mut_ptr() as *mut c_char, buf_size as i32)
-        })
-        .map_err(|err| {
-            logger::warn!(
-                "Couldn't get description for RtcTrack id={} {:p}, {}",
-                self.id,
-                self,
-                err
-            );
-        })
-        .ok()
-        .and_then(|_| {
-            crate::ffi_string(&buf)
-                .map_err(|err| {
-                    logger::error!(
-                        "Couldn't get description for RtcTrack id={} {:p}, {}",
-                        self.id,
-                        self,
-                        err
-                    );
-                })
-                .ok()
-        })
-        .and_then(|description| {
-            description
-                .split('\n')
-                .enumerate()
-                .map(|(line_number, line)| parse_sdp_line(line, line_number))
-                .collect::<std::result::Result<Vec<SdpLine>, _>>()
-                .map_err(|err| logger::error!("Couldn't parse SdpLine: {}", err))
-                .ok()
-        })
-        .and_then(|mut sdp_lines| {
-            parse_media_vector(&mut sdp_lines)
-                .map_err(|err| logger::error!("Couldn't parse SdpMedia: {}", err))
-                .ok()
-        })
+    /// Like [`description`](Self::description), but returns the errors.
+    pub fn try_description(&self) -> Result<Option<Vec<SdpMedia>>> {
+        let description = match crate::read_ffi_string(|buf, size| unsafe {
+            sys::rtcGetTrackDescription(self.id, buf, size)
+        }) {
+            Ok(description) => description,
+            Err(Error::NotAvailable) => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        let mut sdp_lines = description
+            .split('\n')
+            .enumerate()
+            .map(|(line_number, line)| parse_sdp_line(line, line_number))
+            .collect::<std::result::Result<Vec<SdpLine>, _>>()
+            .map_err(|err| Error::BadString(err.to_string()))?;
+        parse_media_vector(&mut sdp_lines)
+            .map(Some)
+            .map_err(|err| Error::BadString(err.to_string()))
     }
 
-    pub fn mid(&self) -> String {
-        let buf_size =
-            check(unsafe { sys::rtcGetTrackMid(self.id, ptr::null_mut() as *mut c_char, 0) })
-                .expect("Couldn't get buffer size") as usize;
+    /// SSRCs announced in the track description.
+    pub fn ssrcs(&self) -> Vec<u32> {
+        let mut ssrcs = vec![];
+        for media in self.description().unwrap_or_default() {
+            for attr in media.get_attributes() {
+                if let SdpAttribute::Ssrc(ssrc) = attr {
+                    if !ssrcs.contains(&ssrc.id) {
+                        ssrcs.push(ssrc.id);
+                    }
+                }
+            }
+        }
+        ssrcs
+    }
 
-        let mut buf = vec![0; buf_size];
-        check(unsafe {
-            sys::rtcGetTrackMid(self.id, buf.as_mut_ptr() as *mut c_char, buf_size as i32)
-        })
-        .map_err(|err| {
-            logger::warn!(
-                "Couldn't get mid for RtcTrack id={} {:p}, {}",
-                self.id,
-                self,
-                err
-            );
-        })
-        .ok()
-        .and_then(|_| {
-            crate::ffi_string(&buf)
-                .map_err(|err| {
-                    logger::error!(
-                        "Couldn't get mid for RtcTrack id={} {:p}, {}",
-                        self.id,
-                        self,
-                        err
-                    );
-                })
-                .ok()
-        })
-        .unwrap_or_default()
+    /// Payload types announced in the track description, in order of preference.
+    pub fn payload_types(&self) -> Vec<u8> {
+        self.rtpmaps()
+            .into_iter()
+            .map(|rtpmap| rtpmap.payload_type)
+            .collect()
+    }
+
+    /// Payload types announced for the given codec name (for instance `"H264"` or `"opus"`).
+    pub fn payload_types_for(&self, codec_name: &str) -> Vec<u8> {
+        self.rtpmaps()
+            .into_iter()
+            .filter(|rtpmap| rtpmap.codec_name.eq_ignore_ascii_case(codec_name))
+            .map(|rtpmap| rtpmap.payload_type)
+            .collect()
+    }
+
+    fn rtpmaps(&self) -> Vec<SdpAttributeRtpmap> {
+        self.description()
+            .unwrap_or_default()
+            .iter()
+            .flat_map(|media| media.get_attributes())
+            .filter_map(|attr| match attr {
+                SdpAttribute::Rtpmap(rtpmap) => Some(rtpmap.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn mid(&self) -> Result<String> {
+        crate::read_ffi_string(|buf, size| unsafe { sys::rtcGetTrackMid(self.id, buf, size) })
     }
 
     pub fn direction(&self) -> Direction {
@@ -281,9 +954,166 @@ where
     }
 }
 
+#[cfg(feature = "media")]
+impl<T> RtcTrack<T>
+where
+    T: TrackHandler + Send,
+{
+    /// Sets a packetizer on the track, messages sent afterwards are media frames which get
+    /// split into RTP packets.
+    pub fn set_packetizer(&mut self, codec: &Codec, init: &PacketizerInit) -> Result<()> {
+        let raw = init.as_raw();
+        check(unsafe {
+            match codec {
+                Codec::H264 => sys::rtcSetH264Packetizer(self.id, &raw),
+                Codec::H265 => sys::rtcSetH265Packetizer(self.id, &raw),
+                Codec::AV1 => sys::rtcSetAV1Packetizer(self.id, &raw),
+                Codec::Opus => sys::rtcSetOpusPacketizer(self.id, &raw),
+                Codec::AAC => sys::rtcSetAACPacketizer(self.id, &raw),
+                _ => return Err(Error::InvalidArg),
+            }
+        })?;
+        Ok(())
+    }
+
+    /// Chains an RTCP sender reports generator, requires a packetizer.
+    pub fn chain_rtcp_sr_reporter(&mut self) -> Result<()> {
+        check(unsafe { sys::rtcChainRtcpSrReporter(self.id) })?;
+        Ok(())
+    }
+
+    /// Chains an RTCP NACK responder keeping up to `max_stored_packets` packets for
+    /// retransmission, requires a packetizer.
+    pub fn chain_rtcp_nack_responder(&mut self, max_stored_packets: u32) -> Result<()> {
+        check(unsafe { sys::rtcChainRtcpNackResponder(self.id, max_stored_packets) })?;
+        Ok(())
+    }
+
+    /// Chains a REMB handler, received estimates are reported to
+    /// [`TrackHandler::on_bitrate`] and through [`remote_bitrate`](Self::remote_bitrate).
+    pub fn chain_remb_handler(&mut self) -> Result<()> {
+        check(unsafe { sys::rtcChainRembHandler(self.id, Some(RtcTrack::<T>::remb_cb)) })?;
+        Ok(())
+    }
+
+    /// Current RTP timestamp of the packetizer.
+    pub fn current_timestamp(&self) -> Result<u32> {
+        let mut timestamp = 0;
+        check(unsafe { sys::rtcGetCurrentTrackTimestamp(self.id, &mut timestamp) })?;
+        Ok(timestamp)
+    }
+
+    /// Sets the RTP timestamp used by the packetizer for the next frames.
+    pub fn set_rtp_timestamp(&mut self, timestamp: u32) -> Result<()> {
+        check(unsafe { sys::rtcSetTrackRtpTimestamp(self.id, timestamp) })?;
+        Ok(())
+    }
+
+    /// Converts a duration in seconds to RTP timestamp units using the track clock rate.
+    pub fn seconds_to_timestamp(&self, seconds: f64) -> Result<u32> {
+        let mut timestamp = 0;
+        check(unsafe { sys::rtcTransformSecondsToTimestamp(self.id, seconds, &mut timestamp) })?;
+        Ok(timestamp)
+    }
+}
+
+#[cfg(feature = "media")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(any(not(target_os = "windows"), target_env = "gnu"), repr(u32))]
+#[cfg_attr(all(target_os = "windows", not(target_env = "gnu")), repr(i32))]
+pub enum NalUnitSeparator {
+    /// The first 4 bytes are the NAL unit length
+    Length = sys::rtcNalUnitSeparator_RTC_NAL_SEPARATOR_LENGTH,
+    /// 0x00, 0x00, 0x00, 0x01
+    LongStartSequence = sys::rtcNalUnitSeparator_RTC_NAL_SEPARATOR_LONG_START_SEQUENCE,
+    /// 0x00, 0x00, 0x01
+    ShortStartSequence = sys::rtcNalUnitSeparator_RTC_NAL_SEPARATOR_SHORT_START_SEQUENCE,
+    /// Long or short start sequence
+    StartSequence = sys::rtcNalUnitSeparator_RTC_NAL_SEPARATOR_START_SEQUENCE,
+}
+
+#[cfg(feature = "media")]
+#[derive(Debug, Clone)]
+pub struct PacketizerInit {
+    pub ssrc: u32,
+    pub cname: CString,
+    pub payload_type: u8,
+    pub clock_rate: u32,
+    pub sequence_number: u16,
+    pub timestamp: u32,
+    /// Maximum fragment size for H264, H265 and AV1, 0 means default
+    pub max_fragment_size: u16,
+    /// NAL unit separator for H264 and H265
+    pub nal_separator: NalUnitSeparator,
+}
+
+#[cfg(feature = "media")]
+impl PacketizerInit {
+    pub fn new(ssrc: u32, cname: &str, payload_type: u8, clock_rate: u32) -> Self {
+        Self {
+            ssrc,
+            cname: CString::new(cname).unwrap(),
+            payload_type,
+            clock_rate,
+            sequence_number: 0,
+            timestamp: 0,
+            max_fragment_size: 0,
+            nal_separator: NalUnitSeparator::Length,
+        }
+    }
+
+    pub fn nal_separator(mut self, nal_separator: NalUnitSeparator) -> Self {
+        self.nal_separator = nal_separator;
+        self
+    }
+
+    pub fn max_fragment_size(mut self, max_fragment_size: u16) -> Self {
+        self.max_fragment_size = max_fragment_size;
+        self
+    }
+
+    pub(crate) fn as_raw(&self) -> sys::rtcPacketizerInit {
+        sys::rtcPacketizerInit {
+            ssrc: self.ssrc,
+            cname: self.cname.as_ptr(),
+            payloadType: self.payload_type,
+            clockRate: self.clock_rate,
+            sequenceNumber: self.sequence_number,
+            timestamp: self.timestamp,
+            maxFragmentSize: self.max_fragment_size,
+            nalSeparator: self.nal_separator as _,
+            obuPacketization: sys::rtcObuPacketization_RTC_OBU_PACKETIZED_OBU,
+            playoutDelayId: 0,
+            playoutDelayMin: 0,
+            playoutDelayMax: 0,
+        }
+    }
+}
+
+impl<T> RtcTrack<T> {
+    /// Deletes the track right away, returning the error dropping it would only log.
+    #[allow(clippy::boxed_local)]
+    pub fn delete(mut self: Box<Self>) -> Result<()> {
+        self.deleted = true;
+        self.teardown()
+    }
+
+    fn teardown(&mut self) -> Result<()> {
+        crate::datachannel::detach_callbacks(self.id);
+        let res = check(unsafe { sys::rtcDeleteTrack(self.id) });
+        crate::runtime::release();
+        crate::telemetry::track_deleted();
+        res.map(|_| ())
+    }
+}
+
 impl<T> Drop for RtcTrack<T> {
     fn drop(&mut self) {
-        if let Err(err) = check(unsafe { sys::rtcDeleteTrack(self.id) }) {
+        if self.deleted {
+            return;
+        }
+        self.deleted = true;
+        if let Err(err) = self.teardown() {
             logger::error!(
                 "Error while dropping RtcTrack id={} {:p}: {}",
                 self.id,
@@ -0,0 +1,474 @@
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::{Condvar, Mutex};
+use sha2::{Digest, Sha256};
+
+use crate::datachannel::{DataChannelHandler, RtcDataChannel};
+use crate::error::{ChannelError, Error};
+
+const DEFAULT_CHUNK_SIZE: usize = 16 * 1024;
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Chunks are only sent while the buffered amount is below this, so that a large file isn't
+/// buffered in memory as a whole.
+const HIGH_WATER_MARK: usize = 1024 * 1024;
+const LOW_WATER_MARK: usize = HIGH_WATER_MARK / 4;
+
+// Message tags, all integers are big-endian
+const OFFER: u8 = 0; // size: u64, sha256: [u8; 32], name
+const ACCEPT: u8 = 1; // offset: u64
+const CHUNK: u8 = 2; // offset: u64, data
+const DONE: u8 = 3;
+const VERIFIED: u8 = 4; // ok: u8
+
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum TransferError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Channel(#[from] Error),
+    /// Files can only be transferred over reliable and ordered channels.
+    #[error("the data channel isn't reliable and ordered")]
+    Unreliable,
+    #[error("the data channel was closed")]
+    Closed,
+    #[error("no message from the remote peer within the timeout")]
+    Timeout,
+    #[error("unexpected message from the remote peer: {0}")]
+    Protocol(&'static str),
+    /// The received file doesn't match the hash announced by the sender, the partial file
+    /// is removed so that the next attempt starts over.
+    #[error("the received file doesn't match its hash")]
+    HashMismatch,
+}
+
+/// The file being transferred, as announced by the sender.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileInfo {
+    /// File name without any directory
+    pub name: String,
+    pub size: u64,
+    pub sha256: [u8; 32],
+}
+
+impl FileInfo {
+    /// Reads the file at `path` to compute its hash.
+    pub fn from_path(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid file name"))?;
+        let (size, sha256) = hash(&mut File::open(path)?)?;
+        Ok(Self {
+            name: name.to_string(),
+            size,
+            sha256,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    /// Bytes sent or received so far, including the ones of a resumed transfer
+    pub transferred: u64,
+    pub total: u64,
+    /// Where the transfer started from, non-zero when it was resumed
+    pub resumed_from: u64,
+}
+
+impl Progress {
+    pub fn fraction(&self) -> f64 {
+        match self.total {
+            0 => 1.0,
+            total => self.transferred as f64 / total as f64,
+        }
+    }
+}
+
+/// A file received by [`FileTransfer::receive`].
+#[derive(Debug, Clone)]
+pub struct ReceivedFile {
+    pub info: FileInfo,
+    pub path: PathBuf,
+}
+
+#[derive(Default)]
+struct Inbox {
+    messages: VecDeque<Vec<u8>>,
+    open: bool,
+    closed: bool,
+}
+
+struct Shared {
+    inbox: Mutex<Inbox>,
+    cond: Condvar,
+}
+
+/// The handler of a data channel used by [`FileTransfer`], see [`TransferHandler::new`].
+pub struct TransferHandler {
+    shared: Arc<Shared>,
+}
+
+/// The receiving end of a [`TransferHandler`], passed to [`FileTransfer`] along with the
+/// data channel.
+pub struct TransferInbox {
+    shared: Arc<Shared>,
+}
+
+impl TransferHandler {
+    pub fn new() -> (Self, TransferInbox) {
+        let shared = Arc::new(Shared {
+            inbox: Mutex::new(Inbox::default()),
+            cond: Condvar::new(),
+        });
+        let inbox = TransferInbox {
+            shared: Arc::clone(&shared),
+        };
+        (Self { shared }, inbox)
+    }
+
+    fn update(&self, f: impl FnOnce(&mut Inbox)) {
+        f(&mut self.shared.inbox.lock());
+        self.shared.cond.notify_all();
+    }
+}
+
+impl DataChannelHandler for TransferHandler {
+    fn on_open(&mut self) {
+        self.update(|inbox| inbox.open = true);
+    }
+
+    fn on_closed(&mut self) {
+        self.update(|inbox| inbox.closed = true);
+    }
+
+    fn on_error(&mut self, _err: &ChannelError) {
+        self.update(|inbox| inbox.closed = true);
+    }
+
+    fn on_message(&mut self, msg: &[u8]) {
+        self.update(|inbox| inbox.messages.push_back(msg.to_vec()));
+    }
+
+    fn on_buffered_amount_low(&mut self) {
+        self.update(|_| ());
+    }
+}
+
+impl TransferInbox {
+    /// Waits for the channel to be open, a channel received from the remote peer may
+    /// already be.
+    fn wait_open(&self, timeout: Duration) -> Result<(), TransferError> {
+        let deadline = Instant::now() + timeout;
+        let mut inbox = self.shared.inbox.lock();
+        while !inbox.open {
+            if inbox.closed {
+                return Err(TransferError::Closed);
+            }
+            if self.shared.cond.wait_until(&mut inbox, deadline).timed_out() {
+                return Err(TransferError::Timeout);
+            }
+        }
+        Ok(())
+    }
+
+    fn recv(&self, timeout: Duration) -> Result<Vec<u8>, TransferError> {
+        let deadline = Instant::now() + timeout;
+        let mut inbox = self.shared.inbox.lock();
+        loop {
+            if let Some(msg) = inbox.messages.pop_front() {
+                return Ok(msg);
+            }
+            if inbox.closed {
+                return Err(TransferError::Closed);
+            }
+            if self.shared.cond.wait_until(&mut inbox, deadline).timed_out() {
+                return Err(TransferError::Timeout);
+            }
+        }
+    }
+
+    /// Waits for any event, typically the buffered amount getting low.
+    fn wait(&self, timeout: Duration) -> Result<(), TransferError> {
+        let mut inbox = self.shared.inbox.lock();
+        if inbox.closed {
+            return Err(TransferError::Closed);
+        }
+        self.shared.cond.wait_for(&mut inbox, timeout);
+        Ok(())
+    }
+}
+
+/// Sends and receives files over a reliable data channel, see [`FileTransfer::send`] and
+/// [`FileTransfer::receive`].
+///
+/// Files are sent in chunks and checked against their SHA-256 once received. A transfer
+/// interrupted by a disconnection resumes where it stopped when the same file is sent again,
+/// over a new channel, to the same directory.
+pub struct FileTransfer {
+    chunk_size: usize,
+    timeout: Duration,
+    on_progress: Option<Box<dyn FnMut(Progress) + Send>>,
+}
+
+impl Default for FileTransfer {
+    fn default() -> Self {
+        Self {
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            timeout: DEFAULT_TIMEOUT,
+            on_progress: None,
+        }
+    }
+}
+
+impl FileTransfer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Size of the chunks sent, 16 KiB by default which any implementation accepts.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is zero.
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        assert!(chunk_size > 0, "chunk_size must be positive");
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    /// How long to wait for the remote peer, 30 seconds by default.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Called after each chunk sent or received.
+    pub fn on_progress<F>(mut self, on_progress: F) -> Self
+    where
+        F: FnMut(Progress) + Send + 'static,
+    {
+        self.on_progress = Some(Box::new(on_progress));
+        self
+    }
+
+    /// Sends the file at `path`, blocking until the receiver verified it.
+    pub fn send<D>(
+        &mut self,
+        dc: &mut RtcDataChannel<D>,
+        inbox: &TransferInbox,
+        path: impl AsRef<Path>,
+    ) -> Result<FileInfo, TransferError>
+    where
+        D: DataChannelHandler + Send,
+    {
+        check_reliable(dc)?;
+        let info = FileInfo::from_path(&path)?;
+        let (size, sha256) = (info.size, info.sha256);
+        let mut file = File::open(path)?;
+
+        inbox.wait_open(self.timeout)?;
+        dc.set_buffered_amount_low_threshold(LOW_WATER_MARK)?;
+        let mut offer = vec![OFFER];
+        offer.extend_from_slice(&size.to_be_bytes());
+        offer.extend_from_slice(&sha256);
+        offer.extend_from_slice(info.name.as_bytes());
+        dc.send(&offer)?;
+
+        let msg = inbox.recv(self.timeout)?;
+        let accept = parse(&msg, ACCEPT)?;
+        if accept.len() != 8 {
+            return Err(TransferError::Protocol("malformed accept"));
+        }
+        let offset = read_u64(accept);
+        if offset > size {
+            return Err(TransferError::Protocol("accept beyond the end of the file"));
+        }
+
+        file.seek(SeekFrom::Start(offset))?;
+        let mut chunk = vec![0; 9 + self.chunk_size];
+        let mut sent = offset;
+        while sent < size {
+            let len = file.read(&mut chunk[9..])?;
+            if len == 0 {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+            }
+            chunk[0] = CHUNK;
+            chunk[1..9].copy_from_slice(&sent.to_be_bytes());
+            self.send_chunk(dc, inbox, &chunk[..9 + len])?;
+            sent += len as u64;
+            self.progress(sent, size, offset);
+        }
+        dc.send(&[DONE])?;
+
+        let msg = inbox.recv(self.timeout)?;
+        match parse(&msg, VERIFIED)? {
+            [1] => Ok(info),
+            [0] => Err(TransferError::HashMismatch),
+            _ => Err(TransferError::Protocol("malformed verification")),
+        }
+    }
+
+    fn send_chunk<D>(
+        &self,
+        dc: &mut RtcDataChannel<D>,
+        inbox: &TransferInbox,
+        chunk: &[u8],
+    ) -> Result<(), TransferError>
+    where
+        D: DataChannelHandler + Send,
+    {
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            if dc.buffered_amount() < HIGH_WATER_MARK {
+                match dc.send(chunk) {
+                    Err(Error::WouldBlock) => (),
+                    res => return res.map_err(TransferError::from),
+                }
+            }
+            if Instant::now() >= deadline {
+                return Err(TransferError::Timeout);
+            }
+            inbox.wait(Duration::from_millis(100))?;
+        }
+    }
+
+    /// Receives a file into `dir`, blocking until it is complete and verified.
+    ///
+    /// Data is written to a `.part` file named after the file and its hash, which is kept
+    /// when the transfer is interrupted so that it can be resumed, and renamed once verified.
+    pub fn receive<D>(
+        &mut self,
+        dc: &mut RtcDataChannel<D>,
+        inbox: &TransferInbox,
+        dir: impl AsRef<Path>,
+    ) -> Result<ReceivedFile, TransferError>
+    where
+        D: DataChannelHandler + Send,
+    {
+        check_reliable(dc)?;
+        let msg = inbox.recv(self.timeout)?;
+        let offer = parse(&msg, OFFER)?;
+        if offer.len() < 40 {
+            return Err(TransferError::Protocol("malformed offer"));
+        }
+        let name = String::from_utf8_lossy(&offer[40..]);
+        // Never write outside of `dir`
+        let name = Path::new(name.as_ref())
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or(TransferError::Protocol("invalid file name"))?;
+        let info = FileInfo {
+            name: name.to_string(),
+            size: read_u64(&offer[..8]),
+            sha256: offer[8..40].try_into().unwrap(),
+        };
+
+        let dir = dir.as_ref();
+        let part = Self::partial_path(dir, &info);
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .read(true)
+            .open(&part)?;
+        let mut offset = file.metadata()?.len();
+        if offset > info.size {
+            file.set_len(0)?;
+            offset = 0;
+        }
+        let mut accept = vec![ACCEPT];
+        accept.extend_from_slice(&offset.to_be_bytes());
+        dc.send(&accept)?;
+
+        let mut received = offset;
+        loop {
+            let msg = inbox.recv(self.timeout)?;
+            match msg.first() {
+                Some(&CHUNK) if msg.len() >= 9 => {
+                    if read_u64(&msg[1..9]) != received {
+                        return Err(TransferError::Protocol("chunk out of sequence"));
+                    }
+                    file.write_all(&msg[9..])?;
+                    received += (msg.len() - 9) as u64;
+                    if received > info.size {
+                        return Err(TransferError::Protocol("more data than announced"));
+                    }
+                    self.progress(received, info.size, offset);
+                }
+                Some(&DONE) if received == info.size => break,
+                _ => return Err(TransferError::Protocol("expected a chunk")),
+            }
+        }
+
+        file.flush()?;
+        file.seek(SeekFrom::Start(0))?;
+        let (_, sha256) = hash(&mut file)?;
+        let ok = sha256 == info.sha256;
+        dc.send(&[VERIFIED, ok as u8])?;
+        drop(file);
+        if !ok {
+            fs::remove_file(&part)?;
+            return Err(TransferError::HashMismatch);
+        }
+
+        let path = dir.join(&info.name);
+        fs::rename(&part, &path)?;
+        Ok(ReceivedFile { info, path })
+    }
+
+    /// Where [`receive`](Self::receive) writes `info` into `dir` until it is verified, named
+    /// after the file and its hash so that only the same file resumes from it.
+    pub fn partial_path(dir: impl AsRef<Path>, info: &FileInfo) -> PathBuf {
+        let hash = info.sha256[..8]
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>();
+        dir.as_ref().join(format!("{}.{}.part", info.name, hash))
+    }
+
+    fn progress(&mut self, transferred: u64, total: u64, resumed_from: u64) {
+        if let Some(on_progress) = &mut self.on_progress {
+            on_progress(Progress {
+                transferred,
+                total,
+                resumed_from,
+            });
+        }
+    }
+}
+
+fn check_reliable<D>(dc: &RtcDataChannel<D>) -> Result<(), TransferError>
+where
+    D: DataChannelHandler + Send,
+{
+    let reliability = dc.reliability()?;
+    if reliability.unordered || reliability.unreliable {
+        return Err(TransferError::Unreliable);
+    }
+    Ok(())
+}
+
+/// Checks the tag of `msg` and returns what follows it.
+fn parse(msg: &[u8], tag: u8) -> Result<&[u8], TransferError> {
+    match msg.split_first() {
+        Some((&first, rest)) if first == tag => Ok(rest),
+        _ => Err(TransferError::Protocol("unexpected message")),
+    }
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    u64::from_be_bytes(bytes[..8].try_into().unwrap())
+}
+
+fn hash(file: &mut File) -> io::Result<(u64, [u8; 32])> {
+    let mut hasher = Sha256::new();
+    let size = io::copy(file, &mut hasher)?;
+    Ok((size, hasher.finalize().into()))
+}
@@ -0,0 +1,216 @@
+use std::marker::PhantomData;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::datachannel::{DataChannelHandler, RtcDataChannel};
+use crate::error::{ChannelError, ChannelErrorKind, Error};
+
+/// Failure to encode or decode a message, as reported by the underlying serde format.
+#[derive(Debug, thiserror::Error)]
+#[error("{0}")]
+pub struct CodecError(Box<dyn std::error::Error + Send + Sync>);
+
+impl CodecError {
+    pub fn new(err: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Self {
+        Self(err.into())
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum TypedError {
+    #[error("Failed to encode message: {0}")]
+    Encode(#[from] CodecError),
+    #[error(transparent)]
+    Channel(#[from] Error),
+}
+
+/// The serde format of the messages exchanged over a [`TypedDataChannel`].
+///
+/// Each value is encoded as a single message, both peers must use the same codec.
+pub trait MessageCodec {
+    fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>, CodecError>;
+    fn decode<T: DeserializeOwned>(msg: &[u8]) -> Result<T, CodecError>;
+}
+
+/// JSON through `serde_json`, readable and handy to debug.
+#[cfg(feature = "json")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Json;
+
+#[cfg(feature = "json")]
+impl MessageCodec for Json {
+    fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>, CodecError> {
+        serde_json::to_vec(value).map_err(CodecError::new)
+    }
+
+    fn decode<T: DeserializeOwned>(msg: &[u8]) -> Result<T, CodecError> {
+        serde_json::from_slice(msg).map_err(CodecError::new)
+    }
+}
+
+/// Bincode 1 with its default options, the most compact of the three but not self-describing.
+#[cfg(feature = "bincode")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Bincode;
+
+#[cfg(feature = "bincode")]
+impl MessageCodec for Bincode {
+    fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>, CodecError> {
+        bincode::serialize(value).map_err(CodecError::new)
+    }
+
+    fn decode<T: DeserializeOwned>(msg: &[u8]) -> Result<T, CodecError> {
+        bincode::deserialize(msg).map_err(CodecError::new)
+    }
+}
+
+/// CBOR through `ciborium`, binary yet self-describing.
+#[cfg(feature = "cbor")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Cbor;
+
+#[cfg(feature = "cbor")]
+impl MessageCodec for Cbor {
+    fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>, CodecError> {
+        let mut buf = Vec::new();
+        ciborium::into_writer(value, &mut buf).map_err(CodecError::new)?;
+        Ok(buf)
+    }
+
+    fn decode<T: DeserializeOwned>(msg: &[u8]) -> Result<T, CodecError> {
+        ciborium::from_reader(msg).map_err(CodecError::new)
+    }
+}
+
+/// Callbacks of a [`TypedDataChannel`], [`DataChannelHandler`] with decoded messages.
+#[allow(unused_variables)]
+pub trait TypedHandler<T> {
+    fn on_open(&mut self) {}
+    fn on_closed(&mut self) {}
+    fn on_error(&mut self, err: &ChannelError) {}
+    fn on_message(&mut self, msg: T) {}
+    fn on_buffered_amount_low(&mut self) {}
+    fn on_available(&mut self) {}
+    fn on_keepalive_timeout(&mut self) {}
+}
+
+/// A [`DataChannelHandler`] decoding each message with `C` before handing it to a
+/// [`TypedHandler`].
+///
+/// A message that fails to decode is reported through
+/// [`on_error`](TypedHandler::on_error) as a [`ChannelErrorKind::Protocol`] error.
+pub struct Typed<H, T, C> {
+    handler: H,
+    _marker: PhantomData<fn() -> (T, C)>,
+}
+
+impl<H, T, C> Typed<H, T, C> {
+    pub fn new(handler: H) -> Self {
+        Self {
+            handler,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn handler(&self) -> &H {
+        &self.handler
+    }
+
+    pub fn handler_mut(&mut self) -> &mut H {
+        &mut self.handler
+    }
+
+    pub fn into_inner(self) -> H {
+        self.handler
+    }
+}
+
+impl<H, T, C> DataChannelHandler for Typed<H, T, C>
+where
+    H: TypedHandler<T>,
+    T: DeserializeOwned,
+    C: MessageCodec,
+{
+    fn on_open(&mut self) {
+        self.handler.on_open()
+    }
+
+    fn on_closed(&mut self) {
+        self.handler.on_closed()
+    }
+
+    fn on_error(&mut self, err: &ChannelError) {
+        self.handler.on_error(err)
+    }
+
+    fn on_message(&mut self, msg: &[u8]) {
+        match C::decode(msg) {
+            Ok(msg) => self.handler.on_message(msg),
+            Err(err) => self.handler.on_error(&ChannelError::new(
+                ChannelErrorKind::Protocol,
+                format!("Failed to decode message: {}", err),
+            )),
+        }
+    }
+
+    fn on_buffered_amount_low(&mut self) {
+        self.handler.on_buffered_amount_low()
+    }
+
+    fn on_available(&mut self) {
+        self.handler.on_available()
+    }
+
+    fn on_keepalive_timeout(&mut self) {
+        self.handler.on_keepalive_timeout()
+    }
+}
+
+/// A data channel exchanging values of type `T` encoded with `C`.
+///
+/// It wraps the channel created with, or received by a peer connection handler using, a
+/// [`Typed`] handler, e.g. `TypedDataChannel::new(pc.create_data_channel("rpc", typed)?)`.
+pub struct TypedDataChannel<H, T, C> {
+    dc: Box<RtcDataChannel<Typed<H, T, C>>>,
+}
+
+impl<H, T, C> TypedDataChannel<H, T, C>
+where
+    H: TypedHandler<T> + Send,
+    T: Serialize + DeserializeOwned,
+    C: MessageCodec,
+{
+    pub fn new(dc: Box<RtcDataChannel<Typed<H, T, C>>>) -> Self {
+        Self { dc }
+    }
+
+    pub fn send(&mut self, msg: &T) -> Result<(), TypedError> {
+        let msg = C::encode(msg)?;
+        Ok(self.dc.send(&msg)?)
+    }
+
+    /// The underlying channel, for everything but sending.
+    pub fn channel(&self) -> &RtcDataChannel<Typed<H, T, C>> {
+        &self.dc
+    }
+
+    pub fn channel_mut(&mut self) -> &mut RtcDataChannel<Typed<H, T, C>> {
+        &mut self.dc
+    }
+
+    pub fn into_inner(self) -> Box<RtcDataChannel<Typed<H, T, C>>> {
+        self.dc
+    }
+}
+
+impl<H, T, C> From<Box<RtcDataChannel<Typed<H, T, C>>>> for TypedDataChannel<H, T, C>
+where
+    H: TypedHandler<T> + Send,
+    T: Serialize + DeserializeOwned,
+    C: MessageCodec,
+{
+    fn from(dc: Box<RtcDataChannel<Typed<H, T, C>>>) -> Self {
+        Self::new(dc)
+    }
+}
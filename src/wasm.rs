@@ -0,0 +1,551 @@
+//! Peer connections and data channels on top of the browser's WebRTC, used instead of
+//! libdatachannel when targeting wasm32.
+//!
+//! The handler traits and the methods kept are the same as the native ones so that
+//! application code builds for both. Everything runs on the browser's event loop: handlers
+//! are called from JavaScript events, and as the browser negotiates asynchronously, failures
+//! happening after a method returned are logged.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use js_sys::{Array, Reflect, Uint8Array};
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+use web_sys::{
+    MessageEvent, RtcConfiguration, RtcDataChannelEvent, RtcDataChannelState, RtcDataChannelType,
+    RtcIceCandidateInit, RtcIceConnectionState, RtcIceGatheringState, RtcIceServer,
+    RtcPeerConnectionIceEvent, RtcPeerConnectionState, RtcSdpType, RtcSessionDescription,
+    RtcSessionDescriptionInit, RtcSignalingState,
+};
+use webrtc_sdp::parse_sdp;
+
+use crate::error::{ChannelError, Error, Result};
+use crate::logger;
+use crate::signaling::{
+    ConnectionState, GatheringState, IceCandidate, IceState, SdpType, SessionDescription,
+    SignalingState,
+};
+
+type Callback = Closure<dyn FnMut(JsValue)>;
+
+/// Logs a rejected JavaScript call and turns it into an [`Error::Runtime`].
+fn js_error(call: &str, err: JsValue) -> Error {
+    logger::error!("{} failed: {}", call, js_message(&err));
+    Error::Runtime
+}
+
+fn js_message(value: &JsValue) -> String {
+    let error = Reflect::get(value, &"error".into())
+        .ok()
+        .filter(|error| !error.is_undefined())
+        .unwrap_or_else(|| value.clone());
+    Reflect::get(&error, &"message".into())
+        .ok()
+        .and_then(|message| message.as_string())
+        .or_else(|| error.as_string())
+        .unwrap_or_else(|| format!("{:?}", error))
+}
+
+impl ConnectionState {
+    fn from_js(state: RtcPeerConnectionState) -> Self {
+        match state {
+            RtcPeerConnectionState::New => Self::New,
+            RtcPeerConnectionState::Connecting => Self::Connecting,
+            RtcPeerConnectionState::Connected => Self::Connected,
+            RtcPeerConnectionState::Disconnected => Self::Disconnected,
+            RtcPeerConnectionState::Failed => Self::Failed,
+            RtcPeerConnectionState::Closed => Self::Closed,
+            _ => Self::Unknown(-1),
+        }
+    }
+}
+
+impl GatheringState {
+    fn from_js(state: RtcIceGatheringState) -> Self {
+        match state {
+            RtcIceGatheringState::New => Self::New,
+            RtcIceGatheringState::Gathering => Self::InProgress,
+            RtcIceGatheringState::Complete => Self::Complete,
+            _ => Self::Unknown(-1),
+        }
+    }
+}
+
+impl SignalingState {
+    fn from_js(state: RtcSignalingState) -> Self {
+        match state {
+            RtcSignalingState::Stable => Self::Stable,
+            RtcSignalingState::HaveLocalOffer => Self::HaveLocalOffer,
+            RtcSignalingState::HaveRemoteOffer => Self::HaveRemoteOffer,
+            RtcSignalingState::HaveLocalPranswer => Self::HaveLocalPranswer,
+            RtcSignalingState::HaveRemotePranswer => Self::HaveRemotePranswer,
+            // `closed` has no native counterpart, the connection state tells it anyway
+            _ => Self::Unknown(-1),
+        }
+    }
+}
+
+impl IceState {
+    fn from_js(state: RtcIceConnectionState) -> Self {
+        match state {
+            RtcIceConnectionState::New => Self::New,
+            RtcIceConnectionState::Checking => Self::Checking,
+            RtcIceConnectionState::Connected => Self::Connected,
+            RtcIceConnectionState::Completed => Self::Completed,
+            RtcIceConnectionState::Failed => Self::Failed,
+            RtcIceConnectionState::Disconnected => Self::Disconnected,
+            RtcIceConnectionState::Closed => Self::Closed,
+            _ => Self::Unknown(-1),
+        }
+    }
+}
+
+impl SdpType {
+    fn as_js(&self) -> RtcSdpType {
+        match self {
+            Self::Answer => RtcSdpType::Answer,
+            Self::Offer => RtcSdpType::Offer,
+            Self::Pranswer => RtcSdpType::Pranswer,
+            Self::Rollback => RtcSdpType::Rollback,
+        }
+    }
+}
+
+impl SessionDescription {
+    fn from_js(sess_desc: &RtcSessionDescription) -> Result<Self> {
+        let sdp = parse_sdp(&sess_desc.sdp(), false).map_err(|err| {
+            logger::error!("Couldn't parse SdpSession: {}", err);
+            Error::BadString(err.to_string())
+        })?;
+        let sdp_type = match sess_desc.type_() {
+            RtcSdpType::Answer => SdpType::Answer,
+            RtcSdpType::Offer => SdpType::Offer,
+            RtcSdpType::Pranswer => SdpType::Pranswer,
+            RtcSdpType::Rollback => SdpType::Rollback,
+            _ => return Err(Error::InvalidArg),
+        };
+        Ok(Self { sdp, sdp_type })
+    }
+
+    fn as_js(&self) -> RtcSessionDescriptionInit {
+        let init = RtcSessionDescriptionInit::new(self.sdp_type.as_js());
+        init.set_sdp(&self.sdp.to_string());
+        init
+    }
+}
+
+/// The ICE servers of a [`RtcPeerConnection`].
+///
+/// URLs follow libdatachannel's format, `turn:user:password@host:port` for instance, and are
+/// split into the browser's `urls`, `username` and `credential`.
+#[derive(Debug, Clone, Default)]
+pub struct RtcConfig {
+    pub ice_servers: Vec<String>,
+}
+
+impl RtcConfig {
+    pub fn new<S: AsRef<str>>(ice_servers: &[S]) -> Self {
+        RtcConfig {
+            ice_servers: ice_servers
+                .iter()
+                .map(|url| url.as_ref().to_string())
+                .collect(),
+        }
+    }
+
+    fn as_js(&self) -> RtcConfiguration {
+        let ice_servers = self
+            .ice_servers
+            .iter()
+            .map(|url| {
+                let server = RtcIceServer::new();
+                let (scheme, rest) = url.split_once(':').unwrap_or((url, ""));
+                match rest.rsplit_once('@') {
+                    Some((credentials, host)) => {
+                        let (username, credential) =
+                            credentials.split_once(':').unwrap_or((credentials, ""));
+                        server.set_urls_str(&format!("{}:{}", scheme, host));
+                        server.set_username(username);
+                        server.set_credential(credential);
+                    }
+                    None => server.set_urls_str(url),
+                }
+                server
+            })
+            .collect::<Array>();
+
+        let config = RtcConfiguration::new();
+        config.set_ice_servers(&ice_servers);
+        config
+    }
+}
+
+#[allow(unused_variables)]
+pub trait DataChannelHandler {
+    fn on_open(&mut self) {}
+    fn on_closed(&mut self) {}
+    fn on_error(&mut self, err: &ChannelError) {}
+    fn on_message(&mut self, msg: &[u8]) {}
+    fn on_buffered_amount_low(&mut self) {}
+    /// Never called, browsers only deliver messages through
+    /// [`on_message`](Self::on_message).
+    fn on_available(&mut self) {}
+    /// Never called, keepalive isn't available.
+    fn on_keepalive_timeout(&mut self) {}
+}
+
+#[derive(Debug, Clone)]
+pub struct DataChannelInfo {
+    pub label: String,
+    pub stream: usize,
+}
+
+impl DataChannelInfo {
+    fn from_js(channel: &web_sys::RtcDataChannel) -> Self {
+        DataChannelInfo {
+            label: channel.label(),
+            stream: channel.id().unwrap_or_default() as usize,
+        }
+    }
+}
+
+pub struct RtcDataChannel<D> {
+    channel: web_sys::RtcDataChannel,
+    _dc_handler: Rc<RefCell<D>>,
+    _callbacks: Vec<Callback>,
+}
+
+impl<D> RtcDataChannel<D>
+where
+    D: DataChannelHandler + 'static,
+{
+    fn new(channel: web_sys::RtcDataChannel, dc_handler: D) -> Box<Self> {
+        channel.set_binary_type(RtcDataChannelType::Arraybuffer);
+        let dc_handler = Rc::new(RefCell::new(dc_handler));
+        let callback = |f: fn(&mut D, JsValue)| {
+            let dc_handler = Rc::clone(&dc_handler);
+            Callback::new(move |event| f(&mut dc_handler.borrow_mut(), event))
+        };
+
+        let on_open = callback(|h, _| h.on_open());
+        let on_closed = callback(|h, _| h.on_closed());
+        let on_error =
+            callback(|h, event| h.on_error(&ChannelError::from_message(&js_message(&event))));
+        let on_message = callback(|h, event| {
+            let data = event.unchecked_into::<MessageEvent>().data();
+            match data.as_string() {
+                Some(text) => h.on_message(text.as_bytes()),
+                None => h.on_message(&Uint8Array::new(&data).to_vec()),
+            }
+        });
+        let on_buffered_amount_low = callback(|h, _| h.on_buffered_amount_low());
+
+        channel.set_onopen(Some(on_open.as_ref().unchecked_ref()));
+        channel.set_onclose(Some(on_closed.as_ref().unchecked_ref()));
+        channel.set_onerror(Some(on_error.as_ref().unchecked_ref()));
+        channel.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+        channel.set_onbufferedamountlow(Some(on_buffered_amount_low.as_ref().unchecked_ref()));
+
+        Box::new(RtcDataChannel {
+            channel,
+            _dc_handler: dc_handler,
+            _callbacks: vec![
+                on_open,
+                on_closed,
+                on_error,
+                on_message,
+                on_buffered_amount_low,
+            ],
+        })
+    }
+
+    pub fn send(&mut self, msg: &[u8]) -> Result<()> {
+        if self.channel.ready_state() != RtcDataChannelState::Open {
+            return Err(Error::NotOpen);
+        }
+        self.channel
+            .send_with_u8_array(msg)
+            .map_err(|err| js_error("RTCDataChannel.send", err))
+    }
+
+    pub fn label(&self) -> Result<String> {
+        Ok(self.channel.label())
+    }
+
+    pub fn stream(&self) -> Result<usize> {
+        self.channel
+            .id()
+            .map(|id| id as usize)
+            .ok_or(Error::NotAvailable)
+    }
+
+    pub fn buffered_amount(&self) -> usize {
+        self.channel.buffered_amount() as usize
+    }
+
+    pub fn set_buffered_amount_low_threshold(&mut self, amount: usize) -> Result<()> {
+        let amount = u32::try_from(amount).map_err(|_| Error::InvalidArg)?;
+        self.channel.set_buffered_amount_low_threshold(amount);
+        Ok(())
+    }
+
+    pub fn close(&mut self) -> Result<()> {
+        self.channel.close();
+        Ok(())
+    }
+}
+
+impl<D> Drop for RtcDataChannel<D> {
+    fn drop(&mut self) {
+        self.channel.set_onopen(None);
+        self.channel.set_onclose(None);
+        self.channel.set_onerror(None);
+        self.channel.set_onmessage(None);
+        self.channel.set_onbufferedamountlow(None);
+        self.channel.close();
+    }
+}
+
+/// Callbacks of a [`RtcPeerConnection`], called from the browser's event loop.
+///
+/// The statistics callbacks of the native handler aren't available.
+#[allow(unused_variables)]
+#[allow(clippy::boxed_local)]
+pub trait PeerConnectionHandler {
+    type DCH;
+
+    fn data_channel_handler(&mut self, info: DataChannelInfo) -> Self::DCH;
+
+    /// Called before [`on_description`](Self::on_description) to adjust the local description
+    /// about to be signaled. Changes only affect what the remote peer receives.
+    fn transform_local_description(&mut self, sess_desc: &mut SessionDescription) {}
+    fn on_description(&mut self, sess_desc: SessionDescription) {}
+    fn on_candidate(&mut self, cand: IceCandidate) {}
+    fn on_connection_state_change(&mut self, state: ConnectionState) {}
+    fn on_gathering_state_change(&mut self, state: GatheringState) {}
+    /// Called once gathering is complete, after the last [`on_candidate`](Self::on_candidate).
+    fn on_end_of_candidates(&mut self) {}
+    fn on_signaling_state_change(&mut self, state: SignalingState) {}
+    fn on_ice_state_change(&mut self, state: IceState) {}
+    fn on_data_channel(&mut self, data_channel: Box<RtcDataChannel<Self::DCH>>) {}
+}
+
+/// What the asynchronous negotiation steps share with the [`RtcPeerConnection`].
+struct Shared<P> {
+    pc: web_sys::RtcPeerConnection,
+    pc_handler: RefCell<P>,
+}
+
+impl<P> Shared<P>
+where
+    P: PeerConnectionHandler,
+{
+    /// Creates an offer or an answer, sets it as the local description and signals it.
+    async fn negotiate(&self, sdp_type: SdpType) -> Result<()> {
+        let created = match sdp_type {
+            SdpType::Offer => self.pc.create_offer(),
+            SdpType::Answer => self.pc.create_answer(),
+            SdpType::Pranswer | SdpType::Rollback => {
+                let init = RtcSessionDescriptionInit::new(sdp_type.as_js());
+                return self.set_local_description(&init).await;
+            }
+        };
+        let created = JsFuture::from(created)
+            .await
+            .map_err(|err| js_error("RTCPeerConnection.createOffer/createAnswer", err))?;
+        self.set_local_description(created.unchecked_ref()).await
+    }
+
+    async fn set_local_description(&self, init: &RtcSessionDescriptionInit) -> Result<()> {
+        JsFuture::from(self.pc.set_local_description(init))
+            .await
+            .map_err(|err| js_error("RTCPeerConnection.setLocalDescription", err))?;
+
+        let Some(local) = self.pc.local_description() else {
+            return Ok(());
+        };
+        if local.type_() == RtcSdpType::Rollback {
+            return Ok(());
+        }
+        let mut sess_desc = SessionDescription::from_js(&local)?;
+        let mut pc_handler = self.pc_handler.borrow_mut();
+        pc_handler.transform_local_description(&mut sess_desc);
+        pc_handler.on_description(sess_desc);
+        Ok(())
+    }
+}
+
+pub struct RtcPeerConnection<P> {
+    shared: Rc<Shared<P>>,
+    _callbacks: Vec<Callback>,
+}
+
+impl<P> RtcPeerConnection<P>
+where
+    P: PeerConnectionHandler + 'static,
+    P::DCH: DataChannelHandler + 'static,
+{
+    pub fn new(config: &RtcConfig, pc_handler: P) -> Result<Box<Self>> {
+        let pc = web_sys::RtcPeerConnection::new_with_configuration(&config.as_js())
+            .map_err(|err| js_error("new RTCPeerConnection", err))?;
+        let shared = Rc::new(Shared {
+            pc,
+            pc_handler: RefCell::new(pc_handler),
+        });
+        let callback = |f: fn(&Rc<Shared<P>>, JsValue)| {
+            let shared = Rc::clone(&shared);
+            Callback::new(move |event| f(&shared, event))
+        };
+
+        let on_negotiation_needed = callback(|shared, _| {
+            let shared = Rc::clone(shared);
+            spawn_local(async move {
+                shared.negotiate(SdpType::Offer).await.ok();
+            });
+        });
+        let on_candidate = callback(|shared, event| {
+            let event = event.unchecked_into::<RtcPeerConnectionIceEvent>();
+            let mut pc_handler = shared.pc_handler.borrow_mut();
+            match event.candidate() {
+                Some(cand) => pc_handler.on_candidate(IceCandidate {
+                    candidate: cand.candidate(),
+                    mid: cand.sdp_mid().unwrap_or_default(),
+                }),
+                None => pc_handler.on_end_of_candidates(),
+            }
+        });
+        let on_connection_state_change = callback(|shared, _| {
+            let state = ConnectionState::from_js(shared.pc.connection_state());
+            shared
+                .pc_handler
+                .borrow_mut()
+                .on_connection_state_change(state);
+        });
+        let on_gathering_state_change = callback(|shared, _| {
+            let state = GatheringState::from_js(shared.pc.ice_gathering_state());
+            shared
+                .pc_handler
+                .borrow_mut()
+                .on_gathering_state_change(state);
+        });
+        let on_signaling_state_change = callback(|shared, _| {
+            let state = SignalingState::from_js(shared.pc.signaling_state());
+            shared
+                .pc_handler
+                .borrow_mut()
+                .on_signaling_state_change(state);
+        });
+        let on_ice_state_change = callback(|shared, _| {
+            let state = IceState::from_js(shared.pc.ice_connection_state());
+            shared.pc_handler.borrow_mut().on_ice_state_change(state);
+        });
+        let on_data_channel = callback(|shared, event| {
+            let channel = event.unchecked_into::<RtcDataChannelEvent>().channel();
+            let mut pc_handler = shared.pc_handler.borrow_mut();
+            let dc_handler = pc_handler.data_channel_handler(DataChannelInfo::from_js(&channel));
+            pc_handler.on_data_channel(RtcDataChannel::new(channel, dc_handler));
+        });
+
+        let pc = &shared.pc;
+        pc.set_onnegotiationneeded(Some(on_negotiation_needed.as_ref().unchecked_ref()));
+        pc.set_onicecandidate(Some(on_candidate.as_ref().unchecked_ref()));
+        pc.set_onconnectionstatechange(Some(on_connection_state_change.as_ref().unchecked_ref()));
+        pc.set_onicegatheringstatechange(Some(on_gathering_state_change.as_ref().unchecked_ref()));
+        pc.set_onsignalingstatechange(Some(on_signaling_state_change.as_ref().unchecked_ref()));
+        pc.set_oniceconnectionstatechange(Some(on_ice_state_change.as_ref().unchecked_ref()));
+        pc.set_ondatachannel(Some(on_data_channel.as_ref().unchecked_ref()));
+
+        Ok(Box::new(RtcPeerConnection {
+            shared,
+            _callbacks: vec![
+                on_negotiation_needed,
+                on_candidate,
+                on_connection_state_change,
+                on_gathering_state_change,
+                on_signaling_state_change,
+                on_ice_state_change,
+                on_data_channel,
+            ],
+        }))
+    }
+
+    /// Creates a boxed [`RtcDataChannel`], the browser then asks for an offer which is
+    /// signaled through [`PeerConnectionHandler::on_description`].
+    pub fn create_data_channel<C>(
+        &mut self,
+        label: &str,
+        dc_handler: C,
+    ) -> Result<Box<RtcDataChannel<C>>>
+    where
+        C: DataChannelHandler + 'static,
+    {
+        let channel = self.shared.pc.create_data_channel(label);
+        Ok(RtcDataChannel::new(channel, dc_handler))
+    }
+
+    pub fn set_local_description(&mut self, sdp_type: SdpType) -> Result<()> {
+        let shared = Rc::clone(&self.shared);
+        spawn_local(async move {
+            shared.negotiate(sdp_type).await.ok();
+        });
+        Ok(())
+    }
+
+    /// Sets the remote description, an offer is answered with a local description signaled
+    /// through [`PeerConnectionHandler::on_description`].
+    ///
+    /// Candidates may be added right after, the browser applies them once the description
+    /// is set.
+    pub fn set_remote_description(&mut self, sess_desc: &SessionDescription) -> Result<()> {
+        let set = self.shared.pc.set_remote_description(&sess_desc.as_js());
+        let shared = Rc::clone(&self.shared);
+        let is_offer = sess_desc.sdp_type == SdpType::Offer;
+        spawn_local(async move {
+            if let Err(err) = JsFuture::from(set).await {
+                js_error("RTCPeerConnection.setRemoteDescription", err);
+            } else if is_offer {
+                shared.negotiate(SdpType::Answer).await.ok();
+            }
+        });
+        Ok(())
+    }
+
+    pub fn add_remote_candidate(&mut self, cand: &IceCandidate) -> Result<()> {
+        let init = RtcIceCandidateInit::new(&cand.candidate);
+        init.set_sdp_mid(Some(&cand.mid));
+        let added = self
+            .shared
+            .pc
+            .add_ice_candidate_with_opt_rtc_ice_candidate_init(Some(&init));
+        spawn_local(async move {
+            if let Err(err) = JsFuture::from(added).await {
+                js_error("RTCPeerConnection.addIceCandidate", err);
+            }
+        });
+        Ok(())
+    }
+
+    pub fn local_description(&self) -> Option<SessionDescription> {
+        SessionDescription::from_js(&self.shared.pc.local_description()?).ok()
+    }
+
+    pub fn remote_description(&self) -> Option<SessionDescription> {
+        SessionDescription::from_js(&self.shared.pc.remote_description()?).ok()
+    }
+}
+
+impl<P> Drop for RtcPeerConnection<P> {
+    fn drop(&mut self) {
+        let pc = &self.shared.pc;
+        pc.set_onnegotiationneeded(None);
+        pc.set_onicecandidate(None);
+        pc.set_onconnectionstatechange(None);
+        pc.set_onicegatheringstatechange(None);
+        pc.set_onsignalingstatechange(None);
+        pc.set_oniceconnectionstatechange(None);
+        pc.set_ondatachannel(None);
+        pc.close();
+    }
+}
@@ -0,0 +1,51 @@
+use datachannel::{ChannelError, ChannelErrorKind, DataChannelHandler, Unbatch};
+
+#[derive(Default)]
+struct Collect {
+    messages: Vec<Vec<u8>>,
+    errors: usize,
+}
+
+impl DataChannelHandler for Collect {
+    fn on_error(&mut self, err: &ChannelError) {
+        assert_eq!(err.kind(), ChannelErrorKind::Protocol);
+        self.errors += 1;
+    }
+
+    fn on_message(&mut self, msg: &[u8]) {
+        self.messages.push(msg.to_vec());
+    }
+}
+
+fn batch(msgs: &[&[u8]]) -> Vec<u8> {
+    msgs.iter()
+        .flat_map(|msg| {
+            (msg.len() as u32)
+                .to_be_bytes()
+                .into_iter()
+                .chain(msg.to_vec())
+        })
+        .collect()
+}
+
+#[test]
+fn test_unbatch() {
+    let mut unbatch = Unbatch::new(Collect::default());
+    unbatch.on_message(&batch(&[b"hello", b"", b"world"]));
+    unbatch.on_message(&batch(&[b"!"]));
+
+    let collect = unbatch.into_inner();
+    assert_eq!(collect.messages, vec![&b"hello"[..], b"", b"world", b"!"]);
+    assert_eq!(collect.errors, 0);
+}
+
+#[test]
+fn test_unbatch_truncated() {
+    let mut unbatch = Unbatch::new(Collect::default());
+    let mut msg = batch(&[b"complete", b"truncated"]);
+    msg.truncate(msg.len() - 2);
+    unbatch.on_message(&msg);
+
+    assert_eq!(unbatch.handler().messages, vec![b"complete".to_vec()]);
+    assert_eq!(unbatch.handler().errors, 1);
+}
@@ -0,0 +1,98 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use datachannel::sdp::attribute_type::SdpAttributeCandidateType;
+use datachannel::{
+    DataChannelHandler, DataChannelInfo, IceCandidate, PeerConnectionHandler, RtcConfig,
+    RtcPeerConnection, TimelineEvent,
+};
+
+struct Handler;
+
+impl DataChannelHandler for Handler {}
+
+impl PeerConnectionHandler for Handler {
+    type DCH = Handler;
+
+    fn data_channel_handler(&mut self, _info: DataChannelInfo) -> Handler {
+        Handler
+    }
+}
+
+#[test]
+fn test_rejected_candidates() {
+    let conf = RtcConfig::new::<&str>(&[]);
+    let mut pc = RtcPeerConnection::new(&conf, Handler).unwrap();
+
+    let cand = IceCandidate {
+        candidate: "candidate:1 1 UDP 2122317823 192.168.1.2 50000 typ host".to_string(),
+        mid: "0".to_string(),
+    };
+    // No remote description yet
+    assert!(pc.add_remote_candidate(&cand).is_err());
+
+    let results = pc.add_remote_candidates(&[cand, IceCandidate::end_of_candidates("0")]);
+    assert!(results[0].is_err());
+    assert!(results[1].is_ok());
+}
+
+#[test]
+fn test_not_available_yet() {
+    let conf = RtcConfig::new::<&str>(&[]);
+    let pc = RtcPeerConnection::new(&conf, Handler).unwrap();
+
+    // Nothing negotiated, which isn't an error
+    assert!(matches!(pc.try_remote_description(), Ok(None)));
+    assert!(matches!(pc.try_remote_address(), Ok(None)));
+    assert!(matches!(pc.try_selected_candidate_pair(), Ok(None)));
+}
+
+#[test]
+fn test_local_candidates() {
+    let conf = RtcConfig::new::<&str>(&[]);
+    let mut pc = RtcPeerConnection::new(&conf, Handler).unwrap();
+    assert!(pc.local_candidates().is_empty());
+
+    // Starts gathering
+    let _dc = pc.create_data_channel("gather", Handler).unwrap();
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while pc.local_candidates().is_empty() && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    let candidates = pc.local_candidates();
+    assert!(!candidates.is_empty());
+    let attr = candidates[0].attribute.as_ref().unwrap();
+    assert_eq!(attr.c_type, SdpAttributeCandidateType::Host);
+    assert!(attr.priority > 0);
+}
+
+#[test]
+fn test_timeline() {
+    let conf = RtcConfig::new::<&str>(&[]).record_timeline(2);
+    let mut pc = RtcPeerConnection::new(&conf, Handler).unwrap();
+
+    let cand = IceCandidate {
+        candidate: "candidate:1 1 UDP 2122317823 192.168.1.2 50000 typ host".to_string(),
+        mid: "0".to_string(),
+    };
+    for _ in 0..3 {
+        pc.add_remote_candidate(&cand).ok();
+    }
+
+    // Only the latest entries are kept
+    let timeline = pc.timeline();
+    assert_eq!(timeline.len(), 2);
+    assert!(matches!(
+        timeline[1].event,
+        TimelineEvent::Failed {
+            call: "rtcAddRemoteCandidate",
+            ..
+        }
+    ));
+
+    let json = serde_json::to_value(&timeline[1]).unwrap();
+    assert_eq!(json["event"], "failed");
+    assert_eq!(json["call"], "rtcAddRemoteCandidate");
+}
@@ -0,0 +1,111 @@
+#![cfg(feature = "compression")]
+
+use std::time::Duration;
+
+use crossbeam_channel as chan;
+
+use datachannel::test_util::{local_pair, ChannelCall, MockChannelHandler, Recording};
+use datachannel::{
+    ChannelErrorKind, Compression, CompressionAlgorithm, DataChannelHandler, DataChannelInfo,
+    DataChannelInit, Decompress, PeerConnectionHandler, RtcConfig, RtcDataChannel,
+};
+
+type Channel = (
+    Box<RtcDataChannel<Decompress<MockChannelHandler>>>,
+    Recording<ChannelCall>,
+);
+
+struct Conn {
+    pending: Option<Recording<ChannelCall>>,
+    channels: chan::Sender<Channel>,
+}
+
+impl PeerConnectionHandler for Conn {
+    type DCH = Decompress<MockChannelHandler>;
+
+    fn data_channel_handler(&mut self, info: DataChannelInfo) -> Self::DCH {
+        assert!(Compression::from_protocol(info.protocol.as_deref()).is_some());
+        let (handler, recording) = MockChannelHandler::new();
+        self.pending = Some(recording);
+        Decompress::new(handler)
+    }
+
+    fn on_data_channel(&mut self, mut dc: Box<RtcDataChannel<Self::DCH>>) {
+        let protocol = dc.protocol().unwrap();
+        dc.set_compression(Compression::from_protocol(protocol.as_deref()));
+        self.channels.send((dc, self.pending.take().unwrap())).ok();
+    }
+}
+
+#[test]
+fn test_decompress() {
+    let (handler, recording) = MockChannelHandler::new();
+    let mut decompress = Decompress::new(handler);
+    decompress.on_message(b"\0raw");
+    decompress.on_message(b"\x01not deflate");
+    decompress.on_message(b"\x7funknown");
+    decompress.on_message(b"");
+
+    let events = recording.events();
+    assert_eq!(events[0], ChannelCall::Message(b"raw".to_vec()));
+    assert_eq!(events.len(), 4);
+    assert!(events[1..].iter().all(|call| matches!(
+        call,
+        ChannelCall::Error(err) if err.kind() == ChannelErrorKind::Protocol
+    )));
+}
+
+fn check_algorithm(algorithm: CompressionAlgorithm) {
+    let (tx, rx) = chan::unbounded();
+    let conn = || Conn {
+        pending: None,
+        channels: tx.clone(),
+    };
+    let config = RtcConfig::new::<&str>(&[]);
+    let (mut offerer, _answerer) = local_pair(&config, conn(), conn()).unwrap();
+
+    let compression = Compression::new(algorithm).threshold(64);
+    let init = DataChannelInit::default().compression(compression);
+    let (handler, local) = MockChannelHandler::new();
+    let mut dc = offerer
+        .create_data_channel_ex("state", Decompress::new(handler), &init)
+        .unwrap();
+    assert_eq!(dc.compression(), Some(compression));
+    let (mut remote_dc, remote) = rx.recv_timeout(Duration::from_secs(10)).unwrap();
+    local.assert_recorded(|call| *call == ChannelCall::Open, Duration::from_secs(10));
+
+    let small = b"small".to_vec();
+    let large = "{\"key\":\"value\"},".repeat(1000).into_bytes();
+    dc.send(&small).unwrap();
+    dc.send(&large).unwrap();
+    remote_dc.send(&large).unwrap();
+
+    let is_large = |call: &ChannelCall| *call == ChannelCall::Message(large.clone());
+    remote.assert_recorded(is_large, Duration::from_secs(10));
+    local.assert_recorded(is_large, Duration::from_secs(10));
+    let messages = remote
+        .events()
+        .into_iter()
+        .filter(|call| matches!(call, ChannelCall::Message(_)))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        messages,
+        [
+            ChannelCall::Message(small),
+            ChannelCall::Message(large.clone())
+        ]
+    );
+    // Counted as received from the transport, compressed
+    assert!(remote_dc.stats().bytes_received < large.len() as u64 / 4);
+}
+
+#[test]
+fn test_compression_deflate() {
+    check_algorithm(CompressionAlgorithm::Deflate);
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn test_compression_zstd() {
+    check_algorithm(CompressionAlgorithm::Zstd);
+}
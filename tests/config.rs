@@ -0,0 +1,125 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use datachannel::{
+    set_global_sctp_settings, ConfigError, CongestionControl, DataChannelHandler, DataChannelInfo,
+    Error, ErrorPolicy, PeerConnectionHandler, ProxyServer, RtcConfig, RtcPeerConnection,
+    SctpSettings, TransportPolicy,
+};
+
+struct Handler;
+
+impl DataChannelHandler for Handler {}
+
+impl PeerConnectionHandler for Handler {
+    type DCH = Handler;
+
+    fn data_channel_handler(&mut self, _info: DataChannelInfo) -> Handler {
+        Handler
+    }
+}
+
+#[test]
+fn test_proxy_server_url() {
+    let proxy = ProxyServer::http("proxy.example.com", 3128);
+    assert_eq!(proxy.to_string(), "http://proxy.example.com:3128");
+
+    let proxy = ProxyServer::socks5("::1", 1080).credentials("user", "secret");
+    assert_eq!(proxy.to_string(), "socks5://user:secret@[::1]:1080");
+}
+
+#[test]
+fn test_validate() {
+    let conf = RtcConfig::new(&[
+        "stun:stun.l.google.com:19302",
+        "turn:user:pass@turn.example.com",
+    ]);
+    assert_eq!(conf.validate(), Ok(()));
+
+    let conf = RtcConfig::new(&["stun:stun.l.google.com:193020"]);
+    assert!(matches!(
+        conf.validate(),
+        Err(ConfigError::IceServer { .. })
+    ));
+
+    let conf = RtcConfig::new(&["ftp://example.com"]);
+    assert!(matches!(
+        conf.validate(),
+        Err(ConfigError::IceServer { .. })
+    ));
+
+    let conf = RtcConfig::new(&["stun:[::1]:3478", "turn:user:pass@[2001:db8::1]"])
+        .proxy(&ProxyServer::socks5("::1", 1080).credentials("user", "secret"));
+    assert_eq!(conf.validate(), Ok(()));
+
+    let conf = RtcConfig::new(&["stun:[::1]:65536"]);
+    assert!(matches!(
+        conf.validate(),
+        Err(ConfigError::IceServer { .. })
+    ));
+
+    let conf = RtcConfig::new(&["stun:[::1"]);
+    assert!(matches!(
+        conf.validate(),
+        Err(ConfigError::IceServer { .. })
+    ));
+
+    let conf = RtcConfig::new::<&str>(&[])
+        .port_range_begin(5000)
+        .port_range_end(4000);
+    assert_eq!(
+        conf.validate(),
+        Err(ConfigError::PortRange {
+            begin: 5000,
+            end: 4000
+        })
+    );
+
+    let conf = RtcConfig::new(&["stun:stun.l.google.com:19302"])
+        .ice_transport_policy(TransportPolicy::Relay);
+    assert_eq!(conf.validate(), Err(ConfigError::RelayWithoutTurn));
+}
+
+#[test]
+fn test_scheme_case() {
+    // Schemes are case-insensitive, libdatachannel only knows all-lowercase or all-uppercase
+    let conf = RtcConfig::new(&["Stun:127.0.0.1:3478", "tUrNs:user:pass@127.0.0.1"])
+        .proxy_server(&"Http://127.0.0.1:3128");
+    assert_eq!(conf.validate(), Ok(()));
+    RtcPeerConnection::new(&conf, Handler).unwrap();
+}
+
+#[test]
+fn test_error_policy() {
+    let conf = RtcConfig::new::<&str>(&[]);
+    assert!(matches!(conf.error_policy, ErrorPolicy::Log));
+
+    let reported = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&reported);
+    let conf = conf.on_internal_error(move |op, err| {
+        assert_eq!(op, "local_address");
+        assert!(matches!(err, Error::Runtime));
+        counter.fetch_add(1, Ordering::SeqCst);
+    });
+    assert!(format!("{:?}", conf).contains("error_policy: Callback(..)"));
+
+    // Cloned configurations share the callback
+    match conf.clone().error_policy {
+        ErrorPolicy::Callback(callback) => callback("local_address", &Error::Runtime),
+        ErrorPolicy::Log => panic!("Expected a callback"),
+    }
+    assert_eq!(reported.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_global_sctp_settings() {
+    let settings = SctpSettings::new()
+        .buffer_size(256 * 1024)
+        .congestion_control(CongestionControl::Htcp);
+    assert_eq!(settings.recv_buffer_size, Some(256 * 1024));
+    assert_eq!(settings.send_buffer_size, Some(256 * 1024));
+    set_global_sctp_settings(&settings).unwrap();
+
+    // Restores libdatachannel's defaults for the connections of the other tests
+    set_global_sctp_settings(&SctpSettings::default()).unwrap();
+}
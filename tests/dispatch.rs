@@ -0,0 +1,78 @@
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel as chan;
+
+use datachannel::{DataChannelHandler, WorkerPool};
+
+struct Collect {
+    id: usize,
+    output: chan::Sender<(usize, u8, thread::ThreadId)>,
+}
+
+impl DataChannelHandler for Collect {
+    fn on_message(&mut self, msg: &[u8]) {
+        if msg[0].is_multiple_of(16) {
+            thread::sleep(Duration::from_millis(1));
+        }
+        self.output
+            .send((self.id, msg[0], thread::current().id()))
+            .ok();
+    }
+}
+
+#[test]
+fn test_ordering() {
+    let (tx, rx) = chan::unbounded();
+    let pool = WorkerPool::new(2);
+    let mut handlers = (0..4)
+        .map(|id| {
+            pool.dispatch(Collect {
+                id,
+                output: tx.clone(),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for i in 0..64u8 {
+        for handler in &mut handlers {
+            handler.on_message(&[i]);
+        }
+    }
+
+    let mut received = vec![vec![]; 4];
+    let mut threads = [None; 4];
+    for _ in 0..4 * 64 {
+        let (id, i, thread) = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        received[id].push(i);
+        // Each handler always runs on the same worker
+        assert_eq!(*threads[id].get_or_insert(thread), thread);
+        assert_ne!(thread, thread::current().id());
+    }
+    for messages in received {
+        assert_eq!(messages, (0..64).collect::<Vec<_>>());
+    }
+}
+
+struct Faulty {
+    output: chan::Sender<u8>,
+}
+
+impl DataChannelHandler for Faulty {
+    fn on_message(&mut self, msg: &[u8]) {
+        assert_ne!(msg[0], 0, "faulty message");
+        self.output.send(msg[0]).ok();
+    }
+}
+
+#[test]
+fn test_panicking_job() {
+    let (tx, rx) = chan::unbounded();
+    let pool = WorkerPool::new(1);
+    let mut handler = pool.dispatch(Faulty { output: tx });
+
+    // The worker survives the panic and runs the following callbacks
+    handler.on_message(&[0]);
+    handler.on_message(&[1]);
+    assert_eq!(rx.recv_timeout(Duration::from_secs(5)).unwrap(), 1);
+}
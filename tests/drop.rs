@@ -0,0 +1,210 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{self as chan, select};
+
+use datachannel::{
+    DataChannelHandler, DataChannelInfo, IceCandidate, PeerConnectionHandler, RtcConfig,
+    RtcDataChannel, RtcPeerConnection, SessionDescription,
+};
+
+enum ConnectionMsg {
+    RemoteDescription { sess_desc: Box<SessionDescription> },
+    RemoteCandidate { cand: IceCandidate },
+}
+
+#[derive(Clone)]
+struct Counter {
+    received: Arc<AtomicUsize>,
+    ready: Option<chan::Sender<()>>,
+}
+
+impl DataChannelHandler for Counter {
+    fn on_open(&mut self) {
+        if let Some(ready) = &self.ready {
+            ready.send(()).ok();
+        }
+    }
+
+    fn on_message(&mut self, _msg: &[u8]) {
+        self.received.fetch_add(1, Ordering::Relaxed);
+        // Widen the window in which a callback is running during drop
+        thread::yield_now();
+    }
+}
+
+struct LocalConn {
+    signaling: chan::Sender<ConnectionMsg>,
+    counter: Counter,
+    dc: Option<Box<RtcDataChannel<Counter>>>,
+}
+
+impl PeerConnectionHandler for LocalConn {
+    type DCH = Counter;
+
+    fn data_channel_handler(&mut self, _info: DataChannelInfo) -> Counter {
+        self.counter.clone()
+    }
+
+    fn on_description(&mut self, sess_desc: SessionDescription) {
+        self.signaling
+            .send(ConnectionMsg::RemoteDescription {
+                sess_desc: Box::new(sess_desc),
+            })
+            .ok();
+    }
+
+    fn on_candidate(&mut self, cand: IceCandidate) {
+        self.signaling
+            .send(ConnectionMsg::RemoteCandidate { cand })
+            .ok();
+    }
+
+    fn on_data_channel(&mut self, dc: Box<RtcDataChannel<Counter>>) {
+        self.dc.replace(dc);
+    }
+}
+
+fn apply(pc: &mut RtcPeerConnection<LocalConn>, msg: ConnectionMsg) {
+    match msg {
+        ConnectionMsg::RemoteDescription { sess_desc } => {
+            pc.set_remote_description(&sess_desc).ok();
+        }
+        ConnectionMsg::RemoteCandidate { cand } => {
+            pc.add_remote_candidate(&cand).ok();
+        }
+    }
+}
+
+#[test]
+fn test_drop_during_traffic() {
+    for _ in 0..10 {
+        let (tx_peer1, rx_peer1) = chan::unbounded();
+        let (tx_peer2, rx_peer2) = chan::unbounded();
+        let (tx_ready, rx_ready) = chan::unbounded();
+        let received = Arc::new(AtomicUsize::new(0));
+
+        let conn = |signaling| LocalConn {
+            signaling,
+            counter: Counter {
+                received: Arc::clone(&received),
+                ready: None,
+            },
+            dc: None,
+        };
+
+        let conf = RtcConfig::new::<&str>(&[]);
+        let mut pc1 = RtcPeerConnection::new(&conf, conn(tx_peer2)).unwrap();
+        let mut pc2 = RtcPeerConnection::new(&conf, conn(tx_peer1)).unwrap();
+
+        let sender = Counter {
+            received: Arc::new(AtomicUsize::new(0)),
+            ready: Some(tx_ready),
+        };
+        let mut dc = pc1.create_data_channel("drop", sender).unwrap();
+
+        loop {
+            select! {
+                recv(rx_peer1) -> msg => apply(&mut pc1, msg.unwrap()),
+                recv(rx_peer2) -> msg => apply(&mut pc2, msg.unwrap()),
+                recv(rx_ready) -> _ => break,
+                default(Duration::from_secs(10)) => panic!("Connection timed out"),
+            }
+        }
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let spammer = {
+            let stop = Arc::clone(&stop);
+            thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    if dc.send(b"traffic").is_err() {
+                        break;
+                    }
+                }
+                dc
+            })
+        };
+
+        // Drop the receiving side, along with its data channel, while messages are flowing
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while received.load(Ordering::Relaxed) < 100 {
+            assert!(Instant::now() < deadline, "No traffic received");
+            thread::yield_now();
+        }
+        drop(pc2);
+
+        stop.store(true, Ordering::Relaxed);
+        let dc = spammer.join().unwrap();
+        drop(dc);
+        drop(pc1);
+    }
+}
+
+#[test]
+fn test_move_out_of_box() {
+    let (tx_peer1, rx_peer1) = chan::unbounded();
+    let (tx_peer2, rx_peer2) = chan::unbounded();
+    let (tx_ready, rx_ready) = chan::unbounded();
+    let received = Arc::new(AtomicUsize::new(0));
+
+    let conn = |signaling| LocalConn {
+        signaling,
+        counter: Counter {
+            received: Arc::clone(&received),
+            ready: None,
+        },
+        dc: None,
+    };
+
+    // Move everything out of the boxes returned by the constructors
+    let conf = RtcConfig::new::<&str>(&[]);
+    let mut pc1 = *RtcPeerConnection::new(&conf, conn(tx_peer2)).unwrap();
+    let mut pc2 = *RtcPeerConnection::new(&conf, conn(tx_peer1)).unwrap();
+
+    let sender = Counter {
+        received: Arc::new(AtomicUsize::new(0)),
+        ready: Some(tx_ready),
+    };
+    let mut dc = *pc1.create_data_channel("move", sender).unwrap();
+
+    loop {
+        select! {
+            recv(rx_peer1) -> msg => apply(&mut pc1, msg.unwrap()),
+            recv(rx_peer2) -> msg => apply(&mut pc2, msg.unwrap()),
+            recv(rx_ready) -> _ => break,
+            default(Duration::from_secs(10)) => panic!("Connection timed out"),
+        }
+    }
+
+    dc.send(b"moved").unwrap();
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while received.load(Ordering::Relaxed) < 1 {
+        assert!(Instant::now() < deadline, "No message received");
+        thread::yield_now();
+    }
+}
+
+#[test]
+fn test_explicit_delete() {
+    let (tx_peer, _rx_peer) = chan::unbounded();
+    let counter = Counter {
+        received: Arc::new(AtomicUsize::new(0)),
+        ready: None,
+    };
+    let conn = LocalConn {
+        signaling: tx_peer,
+        counter: counter.clone(),
+        dc: None,
+    };
+
+    let conf = RtcConfig::new::<&str>(&[]);
+    let mut pc = RtcPeerConnection::new(&conf, conn).unwrap();
+    let mut dc = pc.create_data_channel("delete", counter).unwrap();
+
+    // Closing then deleting must not delete the id twice once the boxes are dropped
+    dc.close().ok();
+    dc.delete().unwrap();
+    pc.delete().unwrap();
+}
@@ -0,0 +1,33 @@
+use datachannel::{ChannelError, ChannelErrorKind};
+
+#[test]
+fn test_channel_error_kind() {
+    let kind = |message: &str| ChannelError::from_message(message).kind();
+
+    // Reported by libdatachannel
+    assert_eq!(
+        kind("The connection has no media transport"),
+        ChannelErrorKind::Transport
+    );
+    assert_eq!(kind("TLS connection failed"), ChannelErrorKind::Transport);
+    assert_eq!(kind("Connection timed out"), ChannelErrorKind::Transport);
+
+    // Reported by browsers
+    assert_eq!(
+        kind("Transport channel closed"),
+        ChannelErrorKind::Transport
+    );
+    assert_eq!(kind("sctp-failure"), ChannelErrorKind::Transport);
+    assert_eq!(
+        kind("User-Initiated Abort, reason=Close called"),
+        ChannelErrorKind::Internal
+    );
+
+    assert_eq!(kind("Invalid message size"), ChannelErrorKind::Protocol);
+    assert_eq!(kind("Message too large"), ChannelErrorKind::Protocol);
+
+    // Only whole words count
+    assert_eq!(kind("No such device"), ChannelErrorKind::Internal);
+    assert_eq!(kind("Service unavailable"), ChannelErrorKind::Internal);
+    assert_eq!(kind("Connection reset"), ChannelErrorKind::Internal);
+}
@@ -0,0 +1,75 @@
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel as chan;
+
+use datachannel::test_util::local_pair;
+use datachannel::{
+    DataChannelInfo, ForwardChannel, PeerConnectionHandler, RtcConfig, RtcDataChannel, TcpEndpoint,
+    TcpForwarder,
+};
+
+struct Conn {
+    endpoint: TcpEndpoint,
+    /// The channel opened by `local_pair`, which doesn't carry a connection
+    bootstrap: Option<Box<RtcDataChannel<ForwardChannel>>>,
+}
+
+impl PeerConnectionHandler for Conn {
+    type DCH = ForwardChannel;
+
+    fn data_channel_handler(&mut self, info: DataChannelInfo) -> Self::DCH {
+        self.endpoint.channel_handler(&info)
+    }
+
+    fn on_data_channel(&mut self, dc: Box<RtcDataChannel<Self::DCH>>) {
+        if let Some(dc) = self.endpoint.accept(dc) {
+            self.bootstrap = Some(dc);
+        }
+    }
+}
+
+#[test]
+fn test_forward() {
+    // The remote endpoint echoes what it receives until the connection is closed
+    let echo = TcpListener::bind("127.0.0.1:0").unwrap();
+    let target = echo.local_addr().unwrap();
+    let (closed, rx_closed) = chan::bounded(1);
+    thread::spawn(move || {
+        let (mut tcp, _) = echo.accept().unwrap();
+        let mut buf = [0; 4096];
+        while let Ok(len @ 1..) = tcp.read(&mut buf) {
+            tcp.write_all(&buf[..len]).unwrap();
+        }
+        closed.send(()).ok();
+    });
+
+    let conn = || Conn {
+        endpoint: TcpEndpoint::new(target),
+        bootstrap: None,
+    };
+    let config = RtcConfig::new::<&str>(&[]);
+    let (mut offerer, _answerer) = local_pair(&config, conn(), conn()).unwrap();
+
+    let forwarder = TcpForwarder::bind("127.0.0.1:0").unwrap();
+    let addr = forwarder.local_addr().unwrap();
+    let payload = (0..512 * 1024).map(|i| i as u8).collect::<Vec<_>>();
+    let sent = payload.clone();
+    let client = thread::spawn(move || {
+        let mut tcp = TcpStream::connect(addr).unwrap();
+        tcp.set_read_timeout(Some(Duration::from_secs(10))).unwrap();
+        let mut writer = tcp.try_clone().unwrap();
+        thread::spawn(move || writer.write_all(&sent).unwrap());
+        let mut echoed = vec![0; payload.len()];
+        tcp.read_exact(&mut echoed).unwrap();
+        assert_eq!(echoed, payload);
+    });
+
+    forwarder.accept(&mut offerer).unwrap();
+    client.join().unwrap();
+
+    // Closing the local connection closes the remote one
+    rx_closed.recv_timeout(Duration::from_secs(10)).unwrap();
+}
@@ -0,0 +1,70 @@
+use datachannel::test_util::{ChannelCall, MockChannelHandler};
+use datachannel::{ChannelErrorKind, DataChannelHandler, Deframe, FrameWriter, Framing};
+
+fn frames() -> Vec<Vec<u8>> {
+    vec![
+        b"hello".to_vec(),
+        vec![],
+        (0..300u32).map(|i| i as u8).collect(),
+        vec![42; 1000],
+    ]
+}
+
+#[test]
+fn test_framing_roundtrip() {
+    for framing in [Framing::LengthPrefix, Framing::Varint] {
+        let writer = FrameWriter::new(framing).max_message_size(64);
+        let (handler, recording) = MockChannelHandler::new();
+        let mut deframe = Deframe::new(handler, framing);
+
+        for frame in frames() {
+            let messages = writer.encode(&frame);
+            assert!(messages.iter().all(|msg| msg.len() <= 64));
+            for msg in messages {
+                deframe.on_message(&msg);
+            }
+        }
+        assert_eq!(deframe.pending(), 0);
+        let expected = frames().into_iter().map(ChannelCall::Message);
+        assert_eq!(recording.events(), expected.collect::<Vec<_>>());
+    }
+}
+
+#[test]
+fn test_framing_coalesced() {
+    // Several frames in one message, the last one split with the next message
+    let writer = FrameWriter::new(Framing::Varint);
+    let stream = frames()
+        .iter()
+        .flat_map(|frame| writer.encode(frame).concat())
+        .collect::<Vec<_>>();
+    let (handler, recording) = MockChannelHandler::new();
+    let mut deframe = Deframe::new(handler, Framing::Varint);
+
+    let (first, second) = stream.split_at(stream.len() - 500);
+    deframe.on_message(first);
+    assert_eq!(recording.events().len(), 3);
+    assert!(deframe.pending() > 0);
+    deframe.on_message(second);
+    assert_eq!(recording.events().len(), 4);
+    assert_eq!(deframe.pending(), 0);
+}
+
+#[test]
+fn test_framing_too_large() {
+    let writer = FrameWriter::new(Framing::LengthPrefix);
+    let (handler, recording) = MockChannelHandler::new();
+    let mut deframe = Deframe::new(handler, Framing::LengthPrefix).max_frame_size(100);
+
+    deframe.on_message(&writer.encode(b"small").concat());
+    deframe.on_message(&writer.encode(&[0; 200]).concat());
+    deframe.on_message(&writer.encode(b"dropped").concat());
+
+    let events = recording.events();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0], ChannelCall::Message(b"small".to_vec()));
+    assert!(matches!(
+        &events[1],
+        ChannelCall::Error(err) if err.kind() == ChannelErrorKind::Protocol
+    ));
+}
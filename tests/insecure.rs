@@ -0,0 +1,113 @@
+#![cfg(feature = "insecure")]
+
+use std::time::{Duration, Instant};
+
+use crossbeam_channel as chan;
+
+use datachannel::sdp::attribute_type::{SdpAttribute, SdpAttributeType};
+use datachannel::{
+    ConnectionState, DataChannelHandler, DataChannelInfo, IceCandidate, PeerConnectionHandler,
+    RtcConfig, RtcPeerConnection, SessionDescription,
+};
+
+enum Signal {
+    Description(Box<SessionDescription>),
+    Candidate(IceCandidate),
+    Connected,
+}
+
+struct Handler(chan::Sender<Signal>);
+
+impl DataChannelHandler for Handler {}
+
+impl PeerConnectionHandler for Handler {
+    type DCH = Handler;
+
+    fn data_channel_handler(&mut self, _info: DataChannelInfo) -> Handler {
+        Handler(self.0.clone())
+    }
+
+    fn on_description(&mut self, mut sess_desc: SessionDescription) {
+        // The rig doesn't signal fingerprints
+        sess_desc
+            .sdp
+            .attribute
+            .retain(|attr| !matches!(attr, SdpAttribute::Fingerprint(_)));
+        for media in &mut sess_desc.sdp.media {
+            media.remove_attribute(SdpAttributeType::Fingerprint);
+        }
+        self.0.send(Signal::Description(Box::new(sess_desc))).ok();
+    }
+
+    fn on_candidate(&mut self, cand: IceCandidate) {
+        self.0.send(Signal::Candidate(cand)).ok();
+    }
+
+    fn on_connection_state_change(&mut self, state: ConnectionState) {
+        if state == ConnectionState::Connected {
+            self.0.send(Signal::Connected).ok();
+        }
+    }
+}
+
+fn offer(config: &RtcConfig) -> (Box<RtcPeerConnection<Handler>>, chan::Receiver<Signal>) {
+    let (tx, rx) = chan::unbounded();
+    let mut pc = RtcPeerConnection::new(config, Handler(tx)).unwrap();
+    pc.create_data_channel("insecure", Handler(chan::unbounded().0))
+        .unwrap();
+    (pc, rx)
+}
+
+fn description(rx: &chan::Receiver<Signal>) -> SessionDescription {
+    loop {
+        match rx.recv_timeout(Duration::from_secs(5)).unwrap() {
+            Signal::Description(sess_desc) => return *sess_desc,
+            _ => continue,
+        }
+    }
+}
+
+#[test]
+fn test_fingerprint_required() {
+    let insecure = RtcConfig::new::<&str>(&[]).insecure_local_fingerprints(true);
+    let (_pc_a, rx_a) = offer(&insecure);
+    let sess_desc = description(&rx_a);
+
+    // The remote peer must opt in as well
+    let (tx_b, _rx_b) = chan::unbounded();
+    let mut pc_b = RtcPeerConnection::new(&RtcConfig::new::<&str>(&[]), Handler(tx_b)).unwrap();
+    assert!(pc_b.set_remote_description(&sess_desc).is_err());
+}
+
+#[test]
+fn test_insecure_local_fingerprints() {
+    let config = RtcConfig::new::<&str>(&[]).insecure_local_fingerprints(true);
+    let (mut pc_a, rx_a) = offer(&config);
+    let (tx_b, rx_b) = chan::unbounded();
+    let mut pc_b = RtcPeerConnection::new(&config, Handler(tx_b)).unwrap();
+
+    let (mut a_connected, mut b_connected) = (false, false);
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while !(a_connected && b_connected) && Instant::now() < deadline {
+        chan::select! {
+            recv(rx_a) -> signal => match signal.unwrap() {
+                Signal::Description(sess_desc) => pc_b.set_remote_description(&sess_desc).unwrap(),
+                Signal::Candidate(cand) => pc_b.add_remote_candidate(&cand).unwrap(),
+                Signal::Connected => a_connected = true,
+            },
+            recv(rx_b) -> signal => match signal.unwrap() {
+                Signal::Description(sess_desc) => pc_a.set_remote_description(&sess_desc).unwrap(),
+                Signal::Candidate(cand) => pc_a.add_remote_candidate(&cand).unwrap(),
+                Signal::Connected => b_connected = true,
+            },
+            default(Duration::from_millis(50)) => (),
+        }
+    }
+    assert!(a_connected && b_connected);
+
+    // Once dropped, its fingerprint isn't known anymore
+    let sess_desc = description(&offer(&config).1);
+    let (tx_c, _rx_c) = chan::unbounded();
+    let mut pc_c = RtcPeerConnection::new(&config, Handler(tx_c)).unwrap();
+    assert!(pc_c.set_remote_description(&sess_desc).is_err());
+}
@@ -0,0 +1,41 @@
+use std::time::Duration;
+
+use crossbeam_channel as chan;
+
+use datachannel::{JitterBuffer, TrackHandler};
+
+struct Collect {
+    output: chan::Sender<u16>,
+}
+
+impl TrackHandler for Collect {
+    fn on_message(&mut self, msg: &[u8]) {
+        self.output.send(u16::from_be_bytes([msg[2], msg[3]])).ok();
+    }
+}
+
+fn rtp(seq: u16) -> Vec<u8> {
+    let mut packet = vec![0x80, 96, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1];
+    packet[2..4].copy_from_slice(&seq.to_be_bytes());
+    packet
+}
+
+#[test]
+fn test_reordering() {
+    let (tx, rx) = chan::unbounded();
+    let mut jb = JitterBuffer::new(Collect { output: tx }, Duration::from_millis(50));
+
+    for seq in [65534, 0, 65535, 2, 1] {
+        jb.on_message(&rtp(seq));
+    }
+
+    let received = (0..5)
+        .map(|_| rx.recv_timeout(Duration::from_secs(1)).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(received, vec![65534, 65535, 0, 1, 2]);
+
+    // Packets older than the ones already emitted are dropped
+    jb.on_message(&rtp(1));
+    jb.on_message(&rtp(3));
+    assert_eq!(rx.recv_timeout(Duration::from_secs(1)).unwrap(), 3);
+}
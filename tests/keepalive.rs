@@ -0,0 +1,104 @@
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use datachannel::test_util::{
+    impaired_pair, local_pair, ChannelCall, Impairment, MockChannelHandler,
+    MockPeerConnectionHandler,
+};
+use datachannel::{DataChannelHandler, Keepalive, RtcConfig};
+
+#[test]
+fn test_keepalive_timeout() {
+    let (channels, rx_channels) = mpsc::channel();
+    let answerer = MockPeerConnectionHandler::new()
+        .0
+        .forward_channels("heartbeat", channels);
+    let config = RtcConfig::new::<&str>(&[]);
+    let (mut offerer, _answerer, link) = impaired_pair(
+        &config,
+        Impairment::new(),
+        MockPeerConnectionHandler::new().0,
+        answerer,
+    )
+    .unwrap();
+
+    let (handler, local) = MockChannelHandler::new();
+    let mut dc = offerer.create_data_channel("heartbeat", handler).unwrap();
+    let (mut remote_dc, remote) = rx_channels.recv_timeout(Duration::from_secs(10)).unwrap();
+    local.assert_recorded(|call| *call == ChannelCall::Open, Duration::from_secs(10));
+
+    let keepalive = Keepalive::new(Duration::from_millis(50), Duration::from_millis(500));
+    dc.set_keepalive(Some(keepalive));
+    remote_dc.set_keepalive(Some(keepalive));
+    assert_eq!(dc.keepalive(), Some(keepalive));
+
+    // Pings are answered and never handed to the handlers
+    thread::sleep(Duration::from_secs(1));
+    let is_message = |call: &ChannelCall| matches!(call, ChannelCall::Message(_));
+    for recording in [&local, &remote] {
+        recording.assert_not_recorded(|call| *call == ChannelCall::KeepaliveTimeout);
+        recording.assert_not_recorded(is_message);
+    }
+    let rtt = dc.round_trip_time().unwrap();
+    assert!(rtt.samples > 5);
+    assert!(rtt.smoothed < Duration::from_millis(500));
+    assert!(remote_dc.round_trip_time().is_some());
+    dc.send(b"data").unwrap();
+    remote.assert_recorded(is_message, Duration::from_secs(5));
+
+    // The peer goes silent
+    drop(link);
+    local.assert_recorded(
+        |call| *call == ChannelCall::KeepaliveTimeout,
+        Duration::from_secs(5),
+    );
+    assert_eq!(
+        local.count(|call| *call == ChannelCall::KeepaliveTimeout),
+        1
+    );
+
+    dc.set_keepalive(None);
+    assert_eq!(dc.keepalive(), None);
+}
+
+struct Silent;
+
+impl DataChannelHandler for Silent {}
+
+#[test]
+fn test_keepalive_dedicated_channel() {
+    let config = RtcConfig::new::<&str>(&[]);
+    let (mut offerer, mut answerer) = local_pair(
+        &config,
+        MockPeerConnectionHandler::new().0,
+        MockPeerConnectionHandler::new().0,
+    )
+    .unwrap();
+
+    let init = Keepalive::channel_init(42);
+    let keepalive = Keepalive::new(Duration::from_millis(20), Duration::from_secs(5));
+    let mut local = offerer
+        .create_data_channel_ex("rtt", Silent, &init)
+        .unwrap();
+    let mut remote = answerer
+        .create_data_channel_ex("rtt", Silent, &init)
+        .unwrap();
+    local.set_keepalive(Some(keepalive));
+    remote.set_keepalive(Some(keepalive));
+
+    // Late pings are useless, they must not be retransmitted
+    let reliability = local.reliability().unwrap();
+    assert!(reliability.unordered && reliability.unreliable);
+    assert_eq!(reliability.max_retransmits, 0);
+
+    for _ in 0..250 {
+        if local.round_trip_time().is_some_and(|rtt| rtt.samples >= 3) {
+            break;
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+    let rtt = local.round_trip_time().unwrap();
+    assert!(rtt.samples >= 3);
+    assert!(rtt.latest < Duration::from_secs(1));
+}
@@ -114,7 +114,7 @@ impl PeerConnectionHandler for LocalConn {
         logger::info!(
             "PeerConnection {}: Received DataChannel with label={}, protocol={:?}, reliability={:?}",
             self.id,
-            dc.label(),
+            dc.label().unwrap(),
             dc.protocol(),
             dc.reliability()
         );
@@ -173,6 +173,7 @@ fn test_connectivity() {
                 ConnectionMsg::Stop => break,
             }
         }
+        pc2.stats()
     });
 
     let t1 = thread::spawn(move || {
@@ -198,6 +199,7 @@ fn test_connectivity() {
                 }
             }
         }
+        (pc1.stats(), dc.stats())
     });
 
     let mut expected = HashSet::new();
@@ -213,6 +215,20 @@ fn test_connectivity() {
     tx_peer1.send(ConnectionMsg::Stop).unwrap();
     tx_peer2.send(ConnectionMsg::Stop).unwrap();
 
-    t2.join().unwrap();
-    t1.join().unwrap();
+    let stats2 = t2.join().unwrap();
+    let (stats1, dc_stats) = t1.join().unwrap();
+
+    assert_eq!(stats1.connection_state, ConnectionState::Connected);
+    assert!(stats1.connected_at.is_some());
+    assert!(stats1.selected_candidate_pair.is_some());
+    assert_eq!(stats1.messages_sent, 1);
+    assert_eq!(stats1.bytes_sent, "PING from 1".len() as u64);
+    assert_eq!(stats2.messages_received, 1);
+    assert_eq!(stats2.bytes_received, "PING from 1".len() as u64);
+    assert_eq!(stats2.bytes_sent, "PONG from 2".len() as u64);
+
+    assert_eq!(dc_stats.messages_sent, 1);
+    assert_eq!(dc_stats.messages_received, 1);
+    assert_eq!(dc_stats.bytes_received, "PONG from 2".len() as u64);
+    assert_eq!(dc_stats.send_failures, 0);
 }
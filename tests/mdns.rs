@@ -0,0 +1,140 @@
+#![cfg(feature = "mdns")]
+
+use std::net::{IpAddr, Ipv4Addr, UdpSocket};
+use std::time::{Duration, Instant};
+
+use crossbeam_channel as chan;
+
+use datachannel::sdp::address::{Address, ExplicitlyTypedAddress};
+use datachannel::sdp::attribute_type::SdpAttributeCandidateType;
+use datachannel::{
+    AddressFamily, ConnectionState, DataChannelHandler, DataChannelInfo, IceCandidate,
+    PeerConnectionHandler, RtcConfig, RtcPeerConnection, SessionDescription,
+};
+
+enum Signal {
+    Description(Box<SessionDescription>),
+    Candidate(IceCandidate),
+    Connected,
+}
+
+struct Handler(chan::Sender<Signal>);
+
+impl DataChannelHandler for Handler {}
+
+impl PeerConnectionHandler for Handler {
+    type DCH = Handler;
+
+    fn data_channel_handler(&mut self, _info: DataChannelInfo) -> Handler {
+        Handler(self.0.clone())
+    }
+
+    fn on_description(&mut self, sess_desc: SessionDescription) {
+        self.0.send(Signal::Description(Box::new(sess_desc))).ok();
+    }
+
+    fn on_candidate(&mut self, cand: IceCandidate) {
+        self.0.send(Signal::Candidate(cand)).ok();
+    }
+
+    fn on_connection_state_change(&mut self, state: ConnectionState) {
+        if state == ConnectionState::Connected {
+            self.0.send(Signal::Connected).ok();
+        }
+    }
+}
+
+/// Builds a legacy unicast query, sent from another port than 5353, for the A record of `name`.
+fn query(id: u16, name: &str) -> Vec<u8> {
+    let mut query = id.to_be_bytes().to_vec();
+    query.extend_from_slice(&[0, 0, 0, 1, 0, 0, 0, 0, 0, 0]);
+    for label in name.split('.') {
+        query.push(label.len() as u8);
+        query.extend_from_slice(label.as_bytes());
+    }
+    query.extend_from_slice(&[0, 0, 1, 0, 1]);
+    query
+}
+
+#[test]
+fn test_mdns_host_candidates() {
+    let (tx_a, rx_a) = chan::unbounded();
+    let (tx_b, rx_b) = chan::unbounded();
+    let config = RtcConfig::new::<&str>(&[]).address_family(AddressFamily::Ipv4);
+    let mut pc_a =
+        RtcPeerConnection::new(&config.clone().mdns_host_candidates(true), Handler(tx_a)).unwrap();
+    let mut pc_b = RtcPeerConnection::new(&config, Handler(tx_b)).unwrap();
+    let _dc = pc_a.create_data_channel("mdns", Handler(chan::unbounded().0));
+
+    let mut description = None;
+    let (mut a_connected, mut b_connected) = (false, false);
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while !(a_connected && b_connected) && Instant::now() < deadline {
+        chan::select! {
+            recv(rx_a) -> signal => match signal.unwrap() {
+                Signal::Description(sess_desc) => {
+                    pc_b.set_remote_description(&sess_desc).unwrap();
+                    description.get_or_insert(*sess_desc);
+                }
+                Signal::Candidate(cand) => {
+                    // The remote peer may fail to resolve it, A reaches it anyway
+                    pc_b.add_remote_candidate(&cand).ok();
+                }
+                Signal::Connected => a_connected = true,
+            },
+            recv(rx_b) -> signal => match signal.unwrap() {
+                Signal::Description(sess_desc) => pc_a.set_remote_description(&sess_desc).unwrap(),
+                Signal::Candidate(cand) => pc_a.add_remote_candidate(&cand).unwrap(),
+                Signal::Connected => b_connected = true,
+            },
+            default(Duration::from_millis(50)) => (),
+        }
+    }
+    assert!(a_connected && b_connected);
+
+    // The description hides the addresses too
+    let description = description.unwrap();
+    assert!(!description.sdp.media.is_empty());
+    for media in description
+        .sdp
+        .media
+        .iter()
+        .chain(&pc_a.local_description().unwrap().sdp.media)
+    {
+        let connection = media.get_connection().as_ref().unwrap();
+        assert_eq!(
+            connection.address,
+            ExplicitlyTypedAddress::Ip(IpAddr::V4(Ipv4Addr::UNSPECIFIED))
+        );
+    }
+
+    // Host candidates are only signaled by name, which the responder answers
+    let names = pc_a
+        .local_candidates()
+        .into_iter()
+        .filter_map(|cand| cand.attribute)
+        .filter(|attr| attr.c_type == SdpAttributeCandidateType::Host)
+        .map(|attr| attr.address)
+        .collect::<Vec<_>>();
+    assert!(!names.is_empty());
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    socket
+        .set_read_timeout(Some(Duration::from_secs(2)))
+        .unwrap();
+    for (id, name) in names.into_iter().enumerate() {
+        let Address::Fqdn(name) = name else {
+            panic!("host candidate signaled with its address: {}", name);
+        };
+        assert!(name.ends_with(".local"), "{}", name);
+
+        let id = id as u16 + 1;
+        socket.send_to(&query(id, &name), "127.0.0.1:5353").unwrap();
+        let mut buf = [0; 512];
+        let len = socket.recv(&mut buf).unwrap();
+        assert_eq!(buf[..2], id.to_be_bytes());
+        // One answer, the A record ending the response
+        assert_eq!(buf[6..8], [0, 1]);
+        let addr = Ipv4Addr::new(buf[len - 4], buf[len - 3], buf[len - 2], buf[len - 1]);
+        assert!(!addr.is_unspecified());
+    }
+}
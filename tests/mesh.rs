@@ -0,0 +1,223 @@
+use std::borrow::Borrow;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crossbeam_channel as chan;
+
+use datachannel::{MeshHandler, MeshSignal, MeshTransport, PeerManager, RtcConfig};
+
+type Queue = Arc<Mutex<VecDeque<(u32, Option<u32>, MeshSignal)>>>;
+
+/// Queues signals for the test to hand them to their recipients.
+struct Hub {
+    local: u32,
+    queue: Queue,
+}
+
+impl MeshTransport<u32> for Hub {
+    fn send(&self, to: &u32, signal: MeshSignal) {
+        let mut queue = self.queue.lock().unwrap();
+        queue.push_back((self.local, Some(*to), signal));
+    }
+
+    fn broadcast(&self, signal: MeshSignal) {
+        let mut queue = self.queue.lock().unwrap();
+        queue.push_back((self.local, None, signal));
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Event {
+    Joined(u32, u32),
+    Left(u32, u32),
+    Message(u32, u32, Vec<u8>),
+}
+
+struct Recorder {
+    local: u32,
+    events: chan::Sender<Event>,
+}
+
+impl MeshHandler<u32> for Recorder {
+    fn on_peer_joined(&mut self, peer: &u32) {
+        self.events.send(Event::Joined(self.local, *peer)).ok();
+    }
+
+    fn on_peer_left(&mut self, peer: &u32) {
+        self.events.send(Event::Left(self.local, *peer)).ok();
+    }
+
+    fn on_message(&mut self, peer: &u32, msg: &[u8]) {
+        let event = Event::Message(self.local, *peer, msg.to_vec());
+        self.events.send(event).ok();
+    }
+}
+
+type Manager = PeerManager<u32, Recorder, Hub>;
+
+/// Delivers the queued signals until `done` holds.
+fn pump<H, M>(managers: &[M], queue: &Queue, done: impl Fn() -> bool)
+where
+    H: MeshHandler<u32> + Send + 'static,
+    M: Borrow<PeerManager<u32, H, Hub>>,
+{
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while !done() {
+        assert!(Instant::now() < deadline, "mesh not settled in time");
+        let next = queue.lock().unwrap().pop_front();
+        let Some((from, to, signal)) = next else {
+            std::thread::sleep(Duration::from_millis(10));
+            continue;
+        };
+        for manager in managers.iter().map(M::borrow) {
+            let recipient = *manager.local_id();
+            match (&signal, to) {
+                (MeshSignal::Join, None) if recipient != from => {
+                    manager.handle_signal(from, MeshSignal::Join).unwrap()
+                }
+                (MeshSignal::Leave, None) if recipient != from => {
+                    manager.handle_signal(from, MeshSignal::Leave).unwrap()
+                }
+                _ => {}
+            }
+        }
+        if let Some(to) = to {
+            let mut managers = managers.iter().map(M::borrow);
+            let manager = managers.find(|m| *m.local_id() == to).unwrap();
+            manager.handle_signal(from, signal).ok();
+        }
+    }
+}
+
+#[test]
+fn test_mesh() {
+    let queue = Queue::default();
+    let (tx, rx) = chan::unbounded();
+    let config = RtcConfig::new::<&str>(&[]);
+    let managers = (1..=3)
+        .map(|local| {
+            let recorder = Recorder {
+                local,
+                events: tx.clone(),
+            };
+            let hub = Hub {
+                local,
+                queue: Arc::clone(&queue),
+            };
+            PeerManager::new(local, config.clone(), recorder, hub)
+        })
+        .collect::<Vec<_>>();
+
+    for manager in &managers {
+        manager.join();
+    }
+    let connected = |manager: &Manager| manager.peers().len() == 2;
+    pump(&managers, &queue, || managers.iter().all(connected));
+
+    let mut peers = managers[0].peers();
+    peers.sort();
+    assert_eq!(peers, [2, 3]);
+
+    managers[0].send(&2, b"unicast").unwrap();
+    let results = managers[1].broadcast(b"broadcast");
+    assert!(results.iter().all(|(_, res)| res.is_ok()));
+    assert!(managers[0].send(&4, b"nobody").is_err());
+
+    let mut expected = vec![
+        Event::Message(2, 1, b"unicast".to_vec()),
+        Event::Message(1, 2, b"broadcast".to_vec()),
+        Event::Message(3, 2, b"broadcast".to_vec()),
+    ];
+    while !expected.is_empty() {
+        let event = rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        expected.retain(|expected| *expected != event);
+    }
+
+    managers[2].leave();
+    assert!(managers[2].peers().is_empty());
+    pump(&managers, &queue, || {
+        managers[..2].iter().all(|m| m.peers().len() == 1)
+    });
+    let left = rx
+        .try_iter()
+        .filter(|event| matches!(event, Event::Left(..)))
+        .count();
+    assert!(left >= 2);
+}
+
+/// Sends through its own manager from within the callbacks.
+struct Replier {
+    recorder: Recorder,
+    manager: Arc<OnceLock<PeerManager<u32, Replier, Hub>>>,
+}
+
+impl MeshHandler<u32> for Replier {
+    fn on_peer_left(&mut self, peer: &u32) {
+        if let Some(manager) = self.manager.get() {
+            manager.broadcast(b"left");
+        }
+        self.recorder.on_peer_left(peer);
+    }
+
+    fn on_message(&mut self, peer: &u32, msg: &[u8]) {
+        if let Some(manager) = self.manager.get() {
+            if msg == b"ping" {
+                manager.send(peer, b"pong").unwrap();
+            }
+        }
+        self.recorder.on_message(peer, msg);
+    }
+}
+
+#[test]
+fn test_mesh_send_from_callbacks() {
+    let queue = Queue::default();
+    let (tx, rx) = chan::unbounded();
+    let config = RtcConfig::new::<&str>(&[]);
+    let slots = (1..=3)
+        .map(|local| {
+            let slot = Arc::new(OnceLock::new());
+            let replier = Replier {
+                recorder: Recorder {
+                    local,
+                    events: tx.clone(),
+                },
+                manager: Arc::clone(&slot),
+            };
+            let hub = Hub {
+                local,
+                queue: Arc::clone(&queue),
+            };
+            let manager = PeerManager::new(local, config.clone(), replier, hub);
+            slot.set(manager).ok().unwrap();
+            slot
+        })
+        .collect::<Vec<_>>();
+    let managers = slots
+        .iter()
+        .map(|slot| slot.get().unwrap())
+        .collect::<Vec<_>>();
+
+    for manager in &managers {
+        manager.join();
+    }
+    pump(&managers, &queue, || {
+        managers.iter().all(|m| m.peers().len() == 2)
+    });
+
+    managers[0].send(&2, b"ping").unwrap();
+    let expected = Event::Message(1, 2, b"pong".to_vec());
+    while rx.recv_timeout(Duration::from_secs(10)).unwrap() != expected {}
+
+    // The remaining peers broadcast from `on_peer_left`, which drops the peer that left
+    managers[2].leave();
+    pump(&managers, &queue, || {
+        managers[..2].iter().all(|m| m.peers().len() == 1)
+    });
+    let mut expected = vec![Event::Left(1, 3), Event::Left(2, 3)];
+    while !expected.is_empty() {
+        let event = rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        expected.retain(|expected| *expected != event);
+    }
+}
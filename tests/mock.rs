@@ -0,0 +1,79 @@
+use std::thread;
+use std::time::Duration;
+
+use datachannel::test_util::{
+    ChannelCall, MockChannelHandler, MockPeerConnectionHandler, PeerConnectionCall,
+};
+use datachannel::{
+    ChannelError, ChannelErrorKind, ConnectionState, DataChannelHandler, PeerConnectionHandler,
+    TrackHandler,
+};
+
+#[test]
+fn test_mock_channel_handler() {
+    let (mut handler, recording) = MockChannelHandler::new();
+    DataChannelHandler::on_open(&mut handler);
+    DataChannelHandler::on_message(&mut handler, b"hello");
+    let err = ChannelError::new(ChannelErrorKind::Internal, "boom");
+    DataChannelHandler::on_error(&mut handler, &err);
+    TrackHandler::on_bitrate(&mut handler, 1000);
+
+    assert_eq!(
+        recording.events(),
+        [
+            ChannelCall::Open,
+            ChannelCall::Message(b"hello".to_vec()),
+            ChannelCall::Error(err),
+            ChannelCall::Bitrate(1000),
+        ]
+    );
+    let entries = recording.entries();
+    assert!(entries
+        .windows(2)
+        .all(|pair| pair[0].timestamp <= pair[1].timestamp));
+    assert_eq!(
+        recording.count(|call| matches!(call, ChannelCall::Message(_))),
+        1
+    );
+    recording.assert_not_recorded(|call| *call == ChannelCall::Closed);
+
+    recording.clear();
+    assert!(recording.events().is_empty());
+}
+
+#[test]
+fn test_mock_wait_for() {
+    let (mut handler, recording) = MockPeerConnectionHandler::new();
+    assert!(recording
+        .wait_for(|_| true, Duration::from_millis(10))
+        .is_none());
+
+    let waiter = thread::spawn(move || {
+        recording.assert_recorded(
+            |call| {
+                matches!(
+                    call,
+                    PeerConnectionCall::ConnectionState(ConnectionState::Connected)
+                )
+            },
+            Duration::from_secs(5),
+        )
+    });
+    handler.on_connection_state_change(ConnectionState::Connecting);
+    handler.on_connection_state_change(ConnectionState::Connected);
+    assert!(matches!(
+        waiter.join().unwrap(),
+        PeerConnectionCall::ConnectionState(ConnectionState::Connected)
+    ));
+}
+
+#[test]
+#[should_panic(expected = "no matching callback recorded")]
+fn test_mock_assert_recorded() {
+    let (mut handler, recording) = MockChannelHandler::new();
+    DataChannelHandler::on_open(&mut handler);
+    recording.assert_recorded(
+        |call| *call == ChannelCall::Closed,
+        Duration::from_millis(10),
+    );
+}
@@ -0,0 +1,81 @@
+use datachannel::test_util::{ChannelCall, MockChannelHandler};
+use datachannel::{ChannelErrorKind, DataChannelHandler, Mux};
+
+#[test]
+fn test_mux_routing() {
+    let (mut mux, subscriptions) = Mux::new();
+    let (chat, chat_rec) = MockChannelHandler::new();
+    let (state, state_rec) = MockChannelHandler::new();
+    let (fallback, fallback_rec) = MockChannelHandler::new();
+    subscriptions.subscribe("chat", chat);
+    subscriptions.subscribe("state", state);
+    subscriptions.set_fallback(fallback);
+
+    mux.on_open();
+    mux.on_message(&Mux::encode("chat", b"hello").unwrap());
+    mux.on_message(&Mux::encode("state", b"").unwrap());
+    mux.on_message(&Mux::encode("other", b"lost").unwrap());
+
+    assert_eq!(
+        chat_rec.events(),
+        [ChannelCall::Open, ChannelCall::Message(b"hello".to_vec())]
+    );
+    assert_eq!(
+        state_rec.events(),
+        [ChannelCall::Open, ChannelCall::Message(vec![])]
+    );
+    assert_eq!(
+        fallback_rec.events(),
+        [
+            ChannelCall::Open,
+            ChannelCall::Message(Mux::encode("other", b"lost").unwrap())
+        ]
+    );
+
+    assert!(subscriptions.unsubscribe("chat"));
+    assert!(!subscriptions.unsubscribe("chat"));
+    assert!(!subscriptions.is_subscribed("chat"));
+    assert_eq!(subscriptions.topics(), ["state"]);
+    mux.on_message(&Mux::encode("chat", b"again").unwrap());
+    assert_eq!(chat_rec.events().len(), 2);
+    assert_eq!(fallback_rec.events().len(), 3);
+
+    subscriptions.remove_fallback();
+    mux.on_message(&Mux::encode("chat", b"dropped").unwrap());
+    assert_eq!(fallback_rec.events().len(), 3);
+}
+
+#[test]
+fn test_mux_malformed() {
+    let (mut mux, subscriptions) = Mux::new();
+    let (handler, recording) = MockChannelHandler::new();
+    subscriptions.subscribe("a", handler);
+
+    mux.on_message(b"");
+    mux.on_message(b"\x05ab");
+    mux.on_message(b"\x01\xff");
+    assert_eq!(recording.events().len(), 3);
+    assert!(recording.events().iter().all(|call| matches!(
+        call,
+        ChannelCall::Error(err) if err.kind() == ChannelErrorKind::Protocol
+    )));
+
+    assert!(Mux::encode(&"t".repeat(256), b"").is_err());
+}
+
+#[test]
+fn test_mux_subscribe_from_handler() {
+    struct Subscriber(datachannel::Subscriptions);
+
+    impl DataChannelHandler for Subscriber {
+        fn on_message(&mut self, msg: &[u8]) {
+            let (handler, _) = MockChannelHandler::new();
+            self.0.subscribe(std::str::from_utf8(msg).unwrap(), handler);
+        }
+    }
+
+    let (mut mux, subscriptions) = Mux::new();
+    subscriptions.subscribe("control", Subscriber(subscriptions.clone()));
+    mux.on_message(&Mux::encode("control", b"news").unwrap());
+    assert!(subscriptions.is_subscribed("news"));
+}
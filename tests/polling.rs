@@ -0,0 +1,48 @@
+use std::ops::ControlFlow;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use datachannel::test_util::{
+    local_pair, ChannelCall, MockChannelHandler, MockPeerConnectionHandler,
+};
+use datachannel::{DataChannelInit, Error, RtcConfig};
+
+#[test]
+fn test_busy_poll() {
+    let (channels, rx_channels) = mpsc::channel();
+    let answerer = MockPeerConnectionHandler::new()
+        .0
+        .forward_channels("polling", channels);
+    let config = RtcConfig::new::<&str>(&[]);
+    let (mut offerer, _answerer) =
+        local_pair(&config, MockPeerConnectionHandler::new().0, answerer).unwrap();
+
+    let (handler, local) = MockChannelHandler::new();
+    let init = DataChannelInit::default().graceful_shutdown();
+    let mut dc = offerer
+        .create_data_channel_ex("polling", handler, &init)
+        .unwrap();
+    let (mut remote_dc, _) = rx_channels.recv_timeout(Duration::from_secs(10)).unwrap();
+    local.assert_recorded(|call| *call == ChannelCall::Open, Duration::from_secs(10));
+
+    // Switched before sending, busy_poll only does it once running
+    remote_dc.set_polling(true).unwrap();
+    let poller = thread::spawn(move || {
+        let mut received = Vec::new();
+        let res = remote_dc.busy_poll(|msg| {
+            received.push(msg.to_vec());
+            ControlFlow::Continue(())
+        });
+        (received, res)
+    });
+    dc.send(b"first").unwrap();
+    dc.send(b"second").unwrap();
+
+    // The close marker is answered by the poller without being handed over, which then
+    // stops once the channel is closed
+    dc.shutdown(Duration::from_secs(10)).unwrap();
+    let (received, res) = poller.join().unwrap();
+    assert_eq!(received, [b"first".to_vec(), b"second".to_vec()]);
+    assert!(matches!(res, Err(Error::NotOpen)));
+}
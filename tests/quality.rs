@@ -0,0 +1,40 @@
+use std::time::{Duration, SystemTime};
+
+use datachannel::{CandidatePair, ConnectionState, IceState, PeerConnectionStats, QualityLevel};
+
+fn stats(packet_loss: Option<f64>, rtt: Option<Duration>, typ: &str) -> PeerConnectionStats {
+    PeerConnectionStats {
+        timestamp: SystemTime::now(),
+        connected_at: Some(SystemTime::now()),
+        connection_state: ConnectionState::Connected,
+        ice_state: IceState::Connected,
+        selected_candidate_pair: Some(CandidatePair {
+            local: format!("candidate:1 1 UDP 2122317823 10.0.0.1 50000 typ {}", typ),
+            remote: "candidate:1 1 UDP 2122317823 10.0.0.2 50000 typ host".to_string(),
+        }),
+        bytes_sent: 0,
+        bytes_received: 0,
+        messages_sent: 0,
+        messages_received: 0,
+        round_trip_time: rtt,
+        packet_loss,
+    }
+}
+
+#[test]
+fn test_quality_levels() {
+    let good = stats(None, None, "host").quality();
+    assert_eq!(good.score, 100);
+    assert_eq!(good.level(), QualityLevel::Good);
+
+    let relayed = stats(Some(0.02), Some(Duration::from_millis(100)), "relay").quality();
+    assert_eq!(relayed.level(), QualityLevel::Good);
+    assert!(relayed < good);
+
+    let lossy = stats(Some(0.1), Some(Duration::from_millis(250)), "host").quality();
+    assert_eq!(lossy.level(), QualityLevel::Poor);
+
+    let mut disconnected = stats(None, None, "host");
+    disconnected.connection_state = ConnectionState::Disconnected;
+    assert_eq!(disconnected.quality().score, 0);
+}
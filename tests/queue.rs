@@ -0,0 +1,77 @@
+use std::thread;
+
+use datachannel::{DataChannelHandler, QueueOverflow, ReceiveQueue};
+
+#[derive(Default)]
+struct Events {
+    opened: bool,
+    messages: usize,
+}
+
+impl DataChannelHandler for Events {
+    fn on_open(&mut self) {
+        self.opened = true;
+    }
+
+    fn on_message(&mut self, _msg: &[u8]) {
+        self.messages += 1;
+    }
+}
+
+#[test]
+fn test_drop_newest() {
+    let (mut queue, reader) = ReceiveQueue::new(Events::default(), 2, QueueOverflow::DropNewest);
+    queue.on_open();
+    for msg in [b"a", b"b", b"c"] {
+        queue.on_message(msg);
+    }
+
+    assert!(queue.handler().opened);
+    assert_eq!(queue.handler().messages, 0);
+    assert_eq!(reader.len(), 2);
+    assert_eq!(reader.dropped(), 1);
+    assert_eq!(reader.drain().collect::<Vec<_>>(), vec![b"a", b"b"]);
+    assert!(reader.is_empty());
+}
+
+#[test]
+fn test_drop_oldest() {
+    let (mut queue, reader) = ReceiveQueue::new(Events::default(), 2, QueueOverflow::DropOldest);
+    for msg in [b"a", b"b", b"c"] {
+        queue.on_message(msg);
+    }
+
+    assert_eq!(reader.dropped(), 1);
+    assert_eq!(reader.pop().as_deref(), Some(&b"b"[..]));
+    assert_eq!(reader.pop().as_deref(), Some(&b"c"[..]));
+    assert_eq!(reader.pop(), None);
+
+    assert!(!reader.is_closed());
+    queue.on_closed();
+    assert!(reader.is_closed());
+}
+
+#[test]
+fn test_concurrent_reader() {
+    let (mut queue, reader) = ReceiveQueue::new(Events::default(), 16, QueueOverflow::DropNewest);
+
+    let consumer = thread::spawn(move || {
+        let mut received = vec![];
+        while !reader.is_closed() || !reader.is_empty() {
+            match reader.pop() {
+                Some(msg) => received.push(msg[0]),
+                None => thread::yield_now(),
+            }
+        }
+        (received, reader.dropped())
+    });
+
+    for i in 0..=255u8 {
+        queue.on_message(&[i]);
+    }
+    queue.on_closed();
+
+    let (received, dropped) = consumer.join().unwrap();
+    assert_eq!(received.len() as u64 + dropped, 256);
+    assert!(received.windows(2).all(|w| w[0] < w[1]));
+}
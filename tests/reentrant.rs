@@ -0,0 +1,169 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use crossbeam_channel as chan;
+use parking_lot::Mutex;
+
+use datachannel::{
+    ConnectionState, DataChannelHandler, DataChannelInfo, IceCandidate, PeerConnectionHandler,
+    RtcConfig, RtcDataChannel, RtcPeerConnection, SessionDescription,
+};
+
+type SharedConn = Arc<Mutex<Option<Box<RtcPeerConnection<DirectConn>>>>>;
+type SelfSlot = Arc<Mutex<Option<Box<RtcPeerConnection<SelfDrop>>>>>;
+
+#[derive(Clone)]
+struct Echo {
+    output: chan::Sender<Vec<u8>>,
+}
+
+impl DataChannelHandler for Echo {
+    fn on_message(&mut self, msg: &[u8]) {
+        self.output.send(msg.to_vec()).ok();
+    }
+}
+
+/// Signals straight into the remote connection from within the callbacks.
+struct DirectConn {
+    remote: SharedConn,
+    echo: Echo,
+    dc: Option<Box<RtcDataChannel<Echo>>>,
+}
+
+impl PeerConnectionHandler for DirectConn {
+    type DCH = Echo;
+
+    fn data_channel_handler(&mut self, _info: DataChannelInfo) -> Echo {
+        self.echo.clone()
+    }
+
+    fn on_description(&mut self, sess_desc: SessionDescription) {
+        if let Some(remote) = self.remote.lock().as_mut() {
+            remote.set_remote_description(&sess_desc).unwrap();
+        }
+    }
+
+    fn on_candidate(&mut self, cand: IceCandidate) {
+        if let Some(remote) = self.remote.lock().as_mut() {
+            remote.add_remote_candidate(&cand).unwrap();
+        }
+    }
+
+    fn on_data_channel(&mut self, mut dc: Box<RtcDataChannel<Echo>>) {
+        dc.send(b"hello from callback").unwrap();
+        self.dc.replace(dc);
+    }
+}
+
+#[test]
+fn test_signal_from_callbacks() {
+    let (tx, rx) = chan::unbounded();
+    let slot1: SharedConn = Arc::new(Mutex::new(None));
+    let slot2: SharedConn = Arc::new(Mutex::new(None));
+
+    let conn = |remote| DirectConn {
+        remote,
+        echo: Echo { output: tx.clone() },
+        dc: None,
+    };
+
+    let conf = RtcConfig::new::<&str>(&[]);
+    let pc1 = RtcPeerConnection::new(&conf, conn(Arc::clone(&slot2))).unwrap();
+    let pc2 = RtcPeerConnection::new(&conf, conn(Arc::clone(&slot1))).unwrap();
+    slot2.lock().replace(pc2);
+    slot1.lock().replace(pc1);
+
+    let echo = Echo { output: tx.clone() };
+    let _dc = slot1
+        .lock()
+        .as_mut()
+        .unwrap()
+        .create_data_channel("reentrant", echo)
+        .unwrap();
+
+    let msg = rx.recv_timeout(Duration::from_secs(10)).unwrap();
+    assert_eq!(msg, b"hello from callback");
+
+    // Each handler holds the other connection, break the cycle before dropping
+    let pc1 = slot1.lock().take();
+    let pc2 = slot2.lock().take();
+    drop(pc1);
+    drop(pc2);
+}
+
+/// Drops its own connection once connected.
+struct SelfDrop {
+    remote: SelfSlot,
+    own: SelfSlot,
+    dropped: chan::Sender<()>,
+}
+
+impl PeerConnectionHandler for SelfDrop {
+    type DCH = Echo;
+
+    fn data_channel_handler(&mut self, _info: DataChannelInfo) -> Echo {
+        let (output, _) = chan::unbounded();
+        Echo { output }
+    }
+
+    fn on_description(&mut self, sess_desc: SessionDescription) {
+        if let Some(remote) = self.remote.lock().as_mut() {
+            remote.set_remote_description(&sess_desc).unwrap();
+        }
+    }
+
+    fn on_candidate(&mut self, cand: IceCandidate) {
+        if let Some(remote) = self.remote.lock().as_mut() {
+            remote.add_remote_candidate(&cand).unwrap();
+        }
+    }
+
+    fn on_connection_state_change(&mut self, state: ConnectionState) {
+        // The first peer connected drops itself, then the other one once disconnected
+        if matches!(
+            state,
+            ConnectionState::Connected | ConnectionState::Disconnected | ConnectionState::Closed
+        ) {
+            let pc = self.own.lock().take();
+            if pc.is_some() {
+                drop(pc);
+                self.dropped.send(()).ok();
+            }
+        }
+    }
+}
+
+#[test]
+fn test_drop_from_callback() {
+    let (tx, rx) = chan::unbounded();
+    let slot1: SelfSlot = Arc::new(Mutex::new(None));
+    let slot2: SelfSlot = Arc::new(Mutex::new(None));
+
+    let conn = |remote, own| SelfDrop {
+        remote,
+        own,
+        dropped: tx.clone(),
+    };
+
+    let conf = RtcConfig::new::<&str>(&[]);
+    let pc1 = RtcPeerConnection::new(&conf, conn(Arc::clone(&slot2), Arc::clone(&slot1))).unwrap();
+    let pc2 = RtcPeerConnection::new(&conf, conn(Arc::clone(&slot1), Arc::clone(&slot2))).unwrap();
+    slot2.lock().replace(pc2);
+    slot1.lock().replace(pc1);
+
+    let (output, _) = chan::unbounded();
+    let dc = slot1
+        .lock()
+        .as_mut()
+        .unwrap()
+        .create_data_channel("self-drop", Echo { output })
+        .unwrap();
+
+    // Both connections get dropped from their own dispatcher thread
+    for _ in 0..2 {
+        rx.recv_timeout(Duration::from_secs(10)).unwrap();
+    }
+    drop(dc);
+    assert!(slot1.lock().is_none());
+    assert!(slot2.lock().is_none());
+}
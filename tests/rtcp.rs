@@ -0,0 +1,58 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use datachannel::ReceptionReport;
+
+#[test]
+fn test_receiver_report() {
+    let sdes = [0x81, 202, 0, 1, 0, 0, 0, 1];
+    let rr = [
+        0x81, 201, 0, 7, // header
+        0, 0, 0, 1, // sender SSRC
+        0, 0, 0, 2, // reported SSRC
+        64, 0xff, 0xff, 0xfe, // fraction lost and cumulative lost
+        0, 1, 0, 10, // highest sequence
+        0, 0, 0, 42, // jitter
+        0, 0, 0, 0, // LSR
+        0, 0, 0, 0, // DLSR
+    ];
+    let compound = [&sdes[..], &rr[..]].concat();
+
+    let report = ReceptionReport::parse(&compound).unwrap();
+    assert_eq!(report.ssrc, 2);
+    assert_eq!(report.fraction_lost, 64);
+    assert_eq!(report.packets_lost, -2);
+    assert_eq!(report.highest_sequence, 65546);
+    assert_eq!(report.jitter, 42);
+    assert_eq!(report.round_trip_time(SystemTime::now()), None);
+
+    // Truncated
+    assert_eq!(ReceptionReport::parse(&rr[..20]), None);
+    assert_eq!(ReceptionReport::parse(&sdes), None);
+}
+
+#[test]
+fn test_round_trip_time() {
+    // Middle 32 bits of the NTP timestamp
+    let ntp = |time: SystemTime| {
+        let time = time.duration_since(UNIX_EPOCH).unwrap();
+        let seconds = (time.as_secs() + 2_208_988_800) as u32;
+        let fraction = ((time.subsec_nanos() as u64) << 32) / 1_000_000_000;
+        (seconds << 16) | (fraction >> 16) as u32
+    };
+    let now = SystemTime::now();
+    let report = ReceptionReport {
+        ssrc: 1,
+        fraction_lost: 0,
+        packets_lost: 0,
+        highest_sequence: 0,
+        jitter: 0,
+        last_sender_report: ntp(now - Duration::from_millis(300)),
+        delay_since_last_sender_report: 65536 / 10,
+    };
+
+    let rtt = report.round_trip_time(now).unwrap();
+    assert!(rtt > Duration::from_millis(195) && rtt < Duration::from_millis(205));
+
+    // Received before the sender report was sent, the clocks don't match
+    assert_eq!(report.round_trip_time(now - Duration::from_secs(1)), None);
+}
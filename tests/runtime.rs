@@ -0,0 +1,40 @@
+use datachannel::{
+    DataChannelHandler, DataChannelInfo, PeerConnectionHandler, RtcConfig, RtcPeerConnection,
+    RtcRuntime,
+};
+
+struct Handler;
+
+impl DataChannelHandler for Handler {}
+
+impl PeerConnectionHandler for Handler {
+    type DCH = Handler;
+
+    fn data_channel_handler(&mut self, _info: DataChannelInfo) -> Handler {
+        Handler
+    }
+}
+
+#[test]
+fn test_cleanup() {
+    let mut runtime = RtcRuntime::new();
+
+    let conf = RtcConfig::new::<&str>(&[]);
+    let mut pc = RtcPeerConnection::new(&conf, Handler).unwrap();
+    let dc = pc.create_data_channel("runtime", Handler).unwrap();
+    assert_eq!(runtime.live_objects(), 2);
+    assert!(runtime.try_cleanup().is_err());
+
+    drop(dc);
+    drop(pc);
+    assert_eq!(runtime.live_objects(), 0);
+    runtime.try_cleanup().unwrap();
+
+    // Objects are counted globally, both cases are checked in the same test
+    let runtime = RtcRuntime::new();
+    let pc = RtcPeerConnection::new(&conf, Handler).unwrap();
+
+    // Cleanup only happens once the connection is gone
+    drop(runtime);
+    drop(pc);
+}
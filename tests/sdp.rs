@@ -0,0 +1,150 @@
+use datachannel::sdp::{parse_sdp, SdpBandwidth};
+use datachannel::{check_compatibility, Direction, Mismatch, SdpType, SessionDescription};
+
+const OFFER: &str = "v=0\r
+o=- 1234 0 IN IP4 127.0.0.1\r
+s=-\r
+t=0 0\r
+a=group:BUNDLE 0\r
+a=ice-ufrag:Fg3b\r
+a=ice-pwd:3aL7VsKhvQhJtNsA3jLS8Z\r
+m=video 9 UDP/TLS/RTP/SAVPF 96\r
+c=IN IP4 0.0.0.0\r
+a=mid:0\r
+a=sendrecv\r
+a=rtpmap:96 VP8/90000\r
+";
+
+fn offer() -> SessionDescription {
+    SessionDescription {
+        sdp: parse_sdp(OFFER, false).unwrap(),
+        sdp_type: SdpType::Offer,
+    }
+}
+
+#[test]
+fn test_ice_credentials() {
+    let offer = offer();
+    assert_eq!(offer.ice_ufrag(), Some("Fg3b"));
+    assert_eq!(offer.ice_pwd(), Some("3aL7VsKhvQhJtNsA3jLS8Z"));
+}
+
+#[test]
+fn test_bandwidth() {
+    let mut offer = offer();
+    assert_eq!(offer.bandwidth("0").map(|bw| bw.len()), Some(0));
+    assert!(offer.bandwidth("1").is_none());
+
+    offer
+        .set_bandwidth("0", &[SdpBandwidth::As(500), SdpBandwidth::Tias(500000)])
+        .unwrap();
+    offer.set_bandwidth("0", &[SdpBandwidth::As(300)]).unwrap();
+    let bandwidth = offer.bandwidth("0").unwrap();
+    assert_eq!(bandwidth.len(), 1);
+    assert!(matches!(bandwidth[0], SdpBandwidth::As(300)));
+    assert!(offer.sdp.to_string().contains("b=AS:300\r\n"));
+}
+
+#[test]
+fn test_compatibility() {
+    let answer = SessionDescription {
+        sdp: parse_sdp(
+            &OFFER
+                .replace("VP8", "H264")
+                .replace("sendrecv", "inactive")
+                .replace("a=mid:0", "a=mid:1"),
+            false,
+        )
+        .unwrap(),
+        sdp_type: SdpType::Answer,
+    };
+    assert_eq!(
+        check_compatibility(&offer(), &answer),
+        vec![
+            Mismatch::MissingMid {
+                mid: "0".to_string()
+            },
+            Mismatch::UnexpectedMid {
+                mid: "1".to_string()
+            },
+        ]
+    );
+
+    let mut answer = answer;
+    answer.sdp = parse_sdp(
+        &OFFER.replace("VP8", "H264").replace("sendrecv", "sendonly"),
+        false,
+    )
+    .unwrap();
+    let mut offer = offer();
+    offer.sdp = parse_sdp(&OFFER.replace("sendrecv", "sendonly"), false).unwrap();
+    assert_eq!(
+        check_compatibility(&offer, &answer),
+        vec![
+            Mismatch::NoCommonCodec {
+                mid: "0".to_string()
+            },
+            Mismatch::Direction {
+                mid: "0".to_string(),
+                offer: Direction::SendOnly,
+                answer: Direction::SendOnly,
+            },
+        ]
+    );
+}
+
+#[cfg(feature = "compact")]
+#[test]
+fn test_compact() {
+    let compact = offer().to_compact();
+    assert!(compact.starts_with('o'));
+
+    let decoded = SessionDescription::from_compact(&compact).unwrap();
+    assert!(matches!(decoded.sdp_type, SdpType::Offer));
+    assert_eq!(decoded.sdp.to_string(), offer().sdp.to_string());
+}
+
+#[cfg(feature = "compact")]
+#[test]
+fn test_compact_too_large() {
+    use std::io::Write;
+
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use base64::Engine;
+    use datachannel::Error;
+    use flate2::write::DeflateEncoder;
+    use flate2::Compression;
+
+    // A few kilobytes inflating to 2 MiB
+    let mut encoder = DeflateEncoder::new(vec![], Compression::best());
+    encoder.write_all(&vec![b'a'; 2 << 20]).unwrap();
+    let compact = format!("o{}", URL_SAFE_NO_PAD.encode(encoder.finish().unwrap()));
+    assert!(compact.len() < 16 << 10);
+    let res = SessionDescription::from_compact(&compact);
+    assert!(matches!(res, Err(Error::BadString(msg)) if msg.contains("exceeds")));
+}
+
+#[test]
+fn test_minimize_for_data_channels() {
+    let sdp = OFFER.replace("BUNDLE 0", "BUNDLE 0 1").to_string()
+        + "m=application 9 UDP/DTLS/SCTP webrtc-datachannel\r
+c=IN IP4 0.0.0.0\r
+a=mid:1\r
+a=sendrecv\r
+a=setup:actpass\r
+a=sctp-port:5000\r
+a=max-message-size:262144\r
+";
+    let mut offer = SessionDescription {
+        sdp: parse_sdp(&sdp, false).unwrap(),
+        sdp_type: SdpType::Offer,
+    };
+    offer.minimize_for_data_channels();
+
+    let minimized = offer.sdp.to_string();
+    assert_eq!(offer.sdp.media.len(), 1);
+    assert!(minimized.contains("a=group:BUNDLE 1\r\n"));
+    assert!(minimized.contains("a=sctp-port:5000\r\n"));
+    assert!(!minimized.contains("a=sendrecv"));
+    assert!(parse_sdp(&minimized, false).is_ok());
+}
@@ -0,0 +1,122 @@
+use std::sync::mpsc;
+use std::time::Duration;
+
+use datachannel::test_util::{
+    local_pair, ChannelCall, ForwardedChannel, LocalPeer, MockChannelHandler,
+    MockPeerConnectionHandler,
+};
+use datachannel::{DataChannelInit, Error, RtcConfig};
+
+type Peer = LocalPeer<MockPeerConnectionHandler>;
+
+/// Connects two peers, the channels labeled "shutdown" the answerer receives are handed out.
+fn shutdown_pair() -> (Peer, Peer, mpsc::Receiver<ForwardedChannel>) {
+    let (channels, rx_channels) = mpsc::channel();
+    let answerer = MockPeerConnectionHandler::new()
+        .0
+        .forward_channels("shutdown", channels);
+    let config = RtcConfig::new::<&str>(&[]);
+    let (offerer, answerer) =
+        local_pair(&config, MockPeerConnectionHandler::new().0, answerer).unwrap();
+    (offerer, answerer, rx_channels)
+}
+
+#[test]
+fn test_shutdown() {
+    let (mut offerer, _answerer, rx_channels) = shutdown_pair();
+
+    let (handler, local) = MockChannelHandler::new();
+    let init = DataChannelInit::default().graceful_shutdown();
+    let mut dc = offerer
+        .create_data_channel_ex("shutdown", handler, &init)
+        .unwrap();
+    let (mut remote_dc, remote) = rx_channels.recv_timeout(Duration::from_secs(10)).unwrap();
+    local.assert_recorded(|call| *call == ChannelCall::Open, Duration::from_secs(10));
+
+    let msg = vec![7; 16 * 1024];
+    for _ in 0..64 {
+        dc.send(&msg).unwrap();
+    }
+    remote_dc.send(b"last words").unwrap();
+    dc.shutdown(Duration::from_secs(10)).unwrap();
+
+    // Everything sent before the close marker is delivered, the marker itself isn't
+    let is_message = |call: &ChannelCall| matches!(call, ChannelCall::Message(_));
+    assert_eq!(remote.count(is_message), 64);
+    assert_eq!(
+        local.count(|call| *call == ChannelCall::Message(b"last words".to_vec())),
+        1
+    );
+    assert_eq!(local.count(is_message), 1);
+    remote.assert_recorded(|call| *call == ChannelCall::Closed, Duration::from_secs(5));
+    assert!(remote_dc.send(b"late").is_err());
+}
+
+#[test]
+fn test_shutdown_timeout() {
+    let (mut offerer, _answerer, rx_channels) = shutdown_pair();
+
+    let (handler, local) = MockChannelHandler::new();
+    let init = DataChannelInit::default().graceful_shutdown();
+    let dc = offerer
+        .create_data_channel_ex("shutdown", handler, &init)
+        .unwrap();
+    let (mut remote_dc, _) = rx_channels.recv_timeout(Duration::from_secs(10)).unwrap();
+    local.assert_recorded(|call| *call == ChannelCall::Open, Duration::from_secs(10));
+
+    // The marker is left queued by a polling peer, so it's never answered
+    remote_dc.set_polling(true).unwrap();
+    let res = offerer.shutdown(Some(dc), Duration::from_millis(500));
+    assert!(matches!(res, Err(Error::Timeout)));
+}
+
+#[test]
+fn test_shutdown_not_negotiated() {
+    let (mut offerer, _answerer, rx_channels) = shutdown_pair();
+
+    let (handler, local) = MockChannelHandler::new();
+    let mut dc = offerer.create_data_channel("shutdown", handler).unwrap();
+    let (_remote_dc, remote) = rx_channels.recv_timeout(Duration::from_secs(10)).unwrap();
+    local.assert_recorded(|call| *call == ChannelCall::Open, Duration::from_secs(10));
+
+    // Without the shutdown protocol the marker is a regular message, and isn't exchanged
+    let marker = b"\0datachannel-shutdown:fin".to_vec();
+    dc.send(&marker).unwrap();
+    remote.assert_recorded(
+        |call| *call == ChannelCall::Message(marker.clone()),
+        Duration::from_secs(10),
+    );
+    dc.shutdown(Duration::from_secs(10)).unwrap();
+    remote.assert_recorded(|call| *call == ChannelCall::Closed, Duration::from_secs(5));
+}
+
+#[test]
+fn test_close_and_wait() {
+    let (mut offerer, _answerer, rx_channels) = shutdown_pair();
+
+    let (handler, local) = MockChannelHandler::new();
+    let dc = offerer.create_data_channel("shutdown", handler).unwrap();
+    let (_remote_dc, remote) = rx_channels.recv_timeout(Duration::from_secs(10)).unwrap();
+    local.assert_recorded(|call| *call == ChannelCall::Open, Duration::from_secs(10));
+
+    // The closed event was delivered once it returns
+    dc.close_and_wait(Duration::from_secs(10)).unwrap();
+    assert_eq!(local.count(|call| *call == ChannelCall::Closed), 1);
+    remote.assert_recorded(|call| *call == ChannelCall::Closed, Duration::from_secs(5));
+}
+
+#[test]
+fn test_drop_waits_for_close() {
+    let (mut offerer, _answerer, rx_channels) = shutdown_pair();
+
+    let (handler, local) = MockChannelHandler::new();
+    let mut dc = offerer.create_data_channel("shutdown", handler).unwrap();
+    let (_remote_dc, remote) = rx_channels.recv_timeout(Duration::from_secs(10)).unwrap();
+    local.assert_recorded(|call| *call == ChannelCall::Open, Duration::from_secs(10));
+
+    // The closed event was delivered to its handler once dropped
+    dc.set_close_timeout(None);
+    drop(dc);
+    assert_eq!(local.count(|call| *call == ChannelCall::Closed), 1);
+    remote.assert_recorded(|call| *call == ChannelCall::Closed, Duration::from_secs(5));
+}
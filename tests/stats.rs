@@ -0,0 +1,50 @@
+use std::time::Duration;
+
+use crossbeam_channel as chan;
+
+use datachannel::{
+    DataChannelHandler, DataChannelInfo, PeerConnectionHandler, RtcConfig, RtcPeerConnection,
+    StatsReport,
+};
+
+struct Handler;
+
+impl DataChannelHandler for Handler {}
+
+struct Monitor {
+    reports: chan::Sender<StatsReport>,
+}
+
+impl PeerConnectionHandler for Monitor {
+    type DCH = Handler;
+
+    fn data_channel_handler(&mut self, _info: DataChannelInfo) -> Handler {
+        Handler
+    }
+
+    fn on_stats(&mut self, report: StatsReport) {
+        self.reports.send(report).ok();
+    }
+}
+
+#[test]
+fn test_stats_poller() {
+    let (tx, rx) = chan::unbounded();
+    let conf = RtcConfig::new::<&str>(&[]).stats_interval(Duration::from_millis(50));
+    let mut pc = RtcPeerConnection::new(&conf, Monitor { reports: tx }).unwrap();
+    let dc = pc.create_data_channel("stats", Handler).unwrap();
+
+    // The first report may have been sampled before the data channel was created
+    rx.recv_timeout(Duration::from_secs(1)).unwrap();
+    let report = rx.recv_timeout(Duration::from_secs(1)).unwrap();
+    assert_eq!(report.connection.bytes_sent, 0);
+    assert_eq!(report.data_channels, vec![(dc.id(), dc.stats())]);
+
+    drop(dc);
+    rx.recv_timeout(Duration::from_secs(1)).unwrap();
+    let report = rx.recv_timeout(Duration::from_secs(1)).unwrap();
+    assert!(report.data_channels.is_empty());
+
+    // Stops the poller along with the dispatcher
+    drop(pc);
+}
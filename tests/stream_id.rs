@@ -0,0 +1,29 @@
+use datachannel::{
+    DataChannelHandler, DataChannelInfo, DataChannelInit, Error, PeerConnectionHandler, RtcConfig,
+    RtcPeerConnection,
+};
+
+struct Handler;
+
+impl DataChannelHandler for Handler {}
+
+impl PeerConnectionHandler for Handler {
+    type DCH = Handler;
+
+    fn data_channel_handler(&mut self, _info: DataChannelInfo) -> Handler {
+        Handler
+    }
+}
+
+#[test]
+fn test_manual_stream_range() {
+    let conf = RtcConfig::new::<&str>(&[]);
+    let mut pc = RtcPeerConnection::new(&conf, Handler).unwrap();
+
+    let init = DataChannelInit::default().manual_stream().stream(65535);
+    let res = pc.create_data_channel_ex("invalid", Handler, &init);
+    assert!(matches!(res, Err(Error::InvalidArg)));
+
+    let init = DataChannelInit::default().manual_stream().stream(42);
+    pc.create_data_channel_ex("valid", Handler, &init).unwrap();
+}
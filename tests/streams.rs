@@ -0,0 +1,188 @@
+use std::time::Duration;
+
+use crossbeam_channel as chan;
+
+use datachannel::test_util::{local_pair, LocalPeer};
+use datachannel::{
+    DataChannelInfo, Error, PeerConnectionHandler, RtcConfig, RtcDataChannel, StreamAcceptor,
+    StreamChannel, StreamHandler, StreamId, StreamMux, StreamMuxConfig,
+};
+
+#[derive(Debug, PartialEq)]
+enum Event {
+    Stream(StreamId),
+    Writable(StreamId),
+    Message(StreamId, Vec<u8>),
+    Finished(StreamId),
+    Reset(StreamId),
+}
+
+struct Recorder {
+    events: chan::Sender<Event>,
+}
+
+impl StreamHandler for Recorder {
+    fn on_stream(&mut self, stream: StreamId) {
+        self.events.send(Event::Stream(stream)).ok();
+    }
+
+    fn on_writable(&mut self, stream: StreamId) {
+        self.events.send(Event::Writable(stream)).ok();
+    }
+
+    fn on_message(&mut self, stream: StreamId, msg: &[u8]) {
+        self.events.send(Event::Message(stream, msg.to_vec())).ok();
+    }
+
+    fn on_finished(&mut self, stream: StreamId) {
+        self.events.send(Event::Finished(stream)).ok();
+    }
+
+    fn on_reset(&mut self, stream: StreamId) {
+        self.events.send(Event::Reset(stream)).ok();
+    }
+}
+
+struct Conn {
+    acceptor: StreamAcceptor<Recorder>,
+    /// The channel opened by `local_pair`, which isn't part of the mux
+    bootstrap: Option<Box<RtcDataChannel<StreamChannel<Recorder>>>>,
+}
+
+impl PeerConnectionHandler for Conn {
+    type DCH = StreamChannel<Recorder>;
+
+    fn data_channel_handler(&mut self, info: DataChannelInfo) -> Self::DCH {
+        self.acceptor.channel_handler(&info)
+    }
+
+    fn on_data_channel(&mut self, dc: Box<RtcDataChannel<Self::DCH>>) {
+        if let Some(dc) = self.acceptor.accept(dc) {
+            self.bootstrap = Some(dc);
+        }
+    }
+}
+
+struct Peer {
+    mux: StreamMux<Recorder>,
+    events: chan::Receiver<Event>,
+}
+
+fn peers(config: StreamMuxConfig) -> (Peer, Peer, LocalPeer<Conn>, LocalPeer<Conn>) {
+    let mut peers = Vec::new();
+    let mut conns = Vec::new();
+    for _ in 0..2 {
+        let (tx, events) = chan::unbounded();
+        let (mux, acceptor) = StreamMux::new(config, Recorder { events: tx });
+        peers.push(Peer { mux, events });
+        conns.push(Conn {
+            acceptor,
+            bootstrap: None,
+        });
+    }
+    let answerer = conns.pop().unwrap();
+    let offerer = conns.pop().unwrap();
+    let config = RtcConfig::new::<&str>(&[]);
+    let (offerer, answerer) = local_pair(&config, offerer, answerer).unwrap();
+    let remote = peers.pop().unwrap();
+    let local = peers.pop().unwrap();
+    (local, remote, offerer, answerer)
+}
+
+/// Waits for an event matching `f`, skipping the others.
+fn wait_for<T>(events: &chan::Receiver<Event>, f: impl Fn(&Event) -> Option<T>) -> T {
+    loop {
+        let event = events.recv_timeout(Duration::from_secs(10)).unwrap();
+        if let Some(found) = f(&event) {
+            return found;
+        }
+    }
+}
+
+#[test]
+fn test_streams() {
+    let (local, remote, mut offerer, _answerer) = peers(StreamMuxConfig::default());
+
+    let stream = local.mux.open(&mut offerer).unwrap();
+    wait_for(&local.events, |event| {
+        (*event == Event::Writable(stream)).then_some(())
+    });
+    let accepted = wait_for(&remote.events, |event| match event {
+        Event::Stream(id) => Some(*id),
+        _ => None,
+    });
+
+    local.mux.send(stream, b"ping").unwrap();
+    let expected = Event::Message(accepted, b"ping".to_vec());
+    wait_for(&remote.events, |event| (*event == expected).then_some(()));
+    remote.mux.send(accepted, b"pong").unwrap();
+    let expected = Event::Message(stream, b"pong".to_vec());
+    wait_for(&local.events, |event| (*event == expected).then_some(()));
+
+    // Half-closed streams still carry messages the other way
+    local.mux.close(stream).unwrap();
+    let expected = Event::Finished(accepted);
+    wait_for(&remote.events, |event| (*event == expected).then_some(()));
+    assert!(matches!(
+        local.mux.send(stream, b"late"),
+        Err(Error::NotOpen)
+    ));
+    remote.mux.send(accepted, b"bye").unwrap();
+    remote.mux.close(accepted).unwrap();
+    let expected = Event::Finished(stream);
+    wait_for(&local.events, |event| (*event == expected).then_some(()));
+    assert!(local.mux.streams().is_empty());
+    assert!(remote.mux.streams().is_empty());
+    assert_eq!(local.mux.idle_channels(), 1);
+
+    // The next stream reuses the idle channel right away
+    let next = local.mux.open(&mut offerer).unwrap();
+    assert_ne!(next, stream);
+    local.mux.send(next, b"again").unwrap();
+    let accepted = wait_for(&remote.events, |event| match event {
+        Event::Stream(id) => Some(*id),
+        _ => None,
+    });
+    let expected = Event::Message(accepted, b"again".to_vec());
+    wait_for(&remote.events, |event| (*event == expected).then_some(()));
+    assert_eq!(local.mux.channels(), 1);
+    assert_eq!(local.mux.idle_channels(), 0);
+
+    // Streams opened meanwhile get their own channel
+    let other = local.mux.open(&mut offerer).unwrap();
+    wait_for(&local.events, |event| {
+        (*event == Event::Writable(other)).then_some(())
+    });
+    assert_eq!(local.mux.channels(), 2);
+}
+
+#[test]
+fn test_streams_window() {
+    let config = StreamMuxConfig::default().window(1024);
+    let (local, remote, mut offerer, _answerer) = peers(config);
+
+    let stream = local.mux.open(&mut offerer).unwrap();
+    wait_for(&local.events, |event| {
+        (*event == Event::Writable(stream)).then_some(())
+    });
+    assert!(matches!(
+        local.mux.send(stream, &[0; 2048]),
+        Err(Error::InvalidArg)
+    ));
+
+    local.mux.send(stream, &[0; 1024]).unwrap();
+    assert!(matches!(
+        local.mux.send(stream, &[1]),
+        Err(Error::WouldBlock)
+    ));
+    wait_for(&remote.events, |event| match event {
+        Event::Message(_, msg) if msg.len() == 1024 => Some(()),
+        _ => None,
+    });
+
+    // The window is granted back once the message is consumed
+    wait_for(&local.events, |event| {
+        (*event == Event::Writable(stream)).then_some(())
+    });
+    local.mux.send(stream, &[1]).unwrap();
+}
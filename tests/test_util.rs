@@ -0,0 +1,269 @@
+use std::time::Duration;
+
+use crossbeam_channel as chan;
+
+use datachannel::test_util::{
+    impaired_pair, local_pair, Impairment, Signaler, SignalingMessage, Soak, TestSignaling,
+    BOOTSTRAP_LABEL,
+};
+use datachannel::{
+    ConnectionState, DataChannelHandler, DataChannelInfo, IceCandidate, PeerConnectionHandler,
+    Reliability, RtcConfig, RtcDataChannel, RtcPeerConnection, SessionDescription,
+};
+
+#[derive(Clone)]
+struct Echo {
+    output: chan::Sender<String>,
+}
+
+impl DataChannelHandler for Echo {
+    fn on_message(&mut self, msg: &[u8]) {
+        self.output
+            .send(String::from_utf8_lossy(msg).to_string())
+            .ok();
+    }
+}
+
+struct Conn {
+    echo: Echo,
+    labels: chan::Sender<String>,
+    channels: Vec<RtcDataChannel<Echo>>,
+}
+
+impl PeerConnectionHandler for Conn {
+    type DCH = Echo;
+
+    fn data_channel_handler(&mut self, _info: DataChannelInfo) -> Echo {
+        self.echo.clone()
+    }
+
+    fn on_data_channel(&mut self, dc: Box<RtcDataChannel<Echo>>) {
+        self.labels.send(dc.label().unwrap()).ok();
+        self.channels.push(*dc);
+    }
+}
+
+#[test]
+fn test_local_pair() {
+    let (tx_msg, rx_msg) = chan::unbounded();
+    let (tx_label, rx_label) = chan::unbounded();
+    let conn = || Conn {
+        echo: Echo {
+            output: tx_msg.clone(),
+        },
+        labels: tx_label.clone(),
+        channels: vec![],
+    };
+
+    let config = RtcConfig::new::<&str>(&[]);
+    let (mut offerer, answerer) = local_pair(&config, conn(), conn()).unwrap();
+    assert_eq!(offerer.stats().connection_state, ConnectionState::Connected);
+    assert_eq!(
+        answerer.stats().connection_state,
+        ConnectionState::Connected
+    );
+    assert_eq!(
+        rx_label.recv_timeout(Duration::from_secs(5)).unwrap(),
+        BOOTSTRAP_LABEL
+    );
+
+    // More channels can be opened once connected
+    let (tx_open, rx_open) = chan::bounded(1);
+    struct Opener(chan::Sender<()>);
+    impl DataChannelHandler for Opener {
+        fn on_open(&mut self) {
+            self.0.send(()).ok();
+        }
+    }
+    let mut dc = offerer
+        .create_data_channel("ping", Opener(tx_open))
+        .unwrap();
+    rx_open.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(
+        rx_label.recv_timeout(Duration::from_secs(5)).unwrap(),
+        "ping"
+    );
+    dc.send(b"hello").unwrap();
+    assert_eq!(
+        rx_msg.recv_timeout(Duration::from_secs(5)).unwrap(),
+        "hello"
+    );
+}
+
+#[test]
+fn test_signaling_routing() {
+    let signaling = TestSignaling::new();
+    let a_to_b = signaling.signaler("a", "b");
+    let c_to_b = signaling.signaler("c", "b");
+    let b_to_a = signaling.signaler("b", "a");
+    assert!(!signaling.wait(&"b", Duration::from_millis(10)));
+
+    let cand = |candidate: &str| IceCandidate {
+        candidate: candidate.to_string(),
+        mid: "0".to_string(),
+    };
+    a_to_b.send_candidate(cand("1"));
+    b_to_a.send_candidate(cand("2"));
+    c_to_b.send_candidate(cand("3"));
+    assert_eq!(signaling.pending(), 3);
+    assert!(signaling.wait(&"b", Duration::from_millis(10)));
+
+    let to_b = signaling.take(&"b");
+    let received = to_b
+        .iter()
+        .map(|envelope| match &envelope.message {
+            SignalingMessage::Candidate(cand) => (envelope.from, cand.candidate.as_str()),
+            SignalingMessage::Description(_) => unreachable!(),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(received, [("a", "1"), ("c", "3")]);
+    assert_eq!(signaling.pending(), 1);
+    assert!(signaling.take(&"b").is_empty());
+}
+
+struct Signaled {
+    signaler: Signaler<&'static str>,
+    states: chan::Sender<ConnectionState>,
+}
+
+impl PeerConnectionHandler for Signaled {
+    type DCH = Echo;
+
+    fn data_channel_handler(&mut self, _info: DataChannelInfo) -> Echo {
+        Echo {
+            output: chan::unbounded().0,
+        }
+    }
+
+    fn on_description(&mut self, sess_desc: SessionDescription) {
+        self.signaler.send_description(sess_desc);
+    }
+
+    fn on_candidate(&mut self, cand: IceCandidate) {
+        self.signaler.send_candidate(cand);
+    }
+
+    fn on_connection_state_change(&mut self, state: ConnectionState) {
+        self.states.send(state).ok();
+    }
+}
+
+#[test]
+fn test_signaling_connect() {
+    let signaling = TestSignaling::new();
+    let (tx_a, rx_a) = chan::unbounded();
+    let (tx_b, rx_b) = chan::unbounded();
+    let config = RtcConfig::new::<&str>(&[]);
+    let mut pc_a = RtcPeerConnection::new(
+        &config,
+        Signaled {
+            signaler: signaling.signaler("a", "b"),
+            states: tx_a,
+        },
+    )
+    .unwrap();
+    let mut pc_b = RtcPeerConnection::new(
+        &config,
+        Signaled {
+            signaler: signaling.signaler("b", "a"),
+            states: tx_b,
+        },
+    )
+    .unwrap();
+
+    let _dc = pc_a.create_data_channel(
+        "test",
+        Echo {
+            output: chan::unbounded().0,
+        },
+    );
+    let connected = |rx: &chan::Receiver<ConnectionState>| {
+        rx.try_iter()
+            .any(|state| state == ConnectionState::Connected)
+    };
+    let (mut a_connected, mut b_connected) = (false, false);
+    for _ in 0..100 {
+        signaling.wait(&"b", Duration::from_millis(50));
+        signaling.deliver(&"b", &mut pc_b).unwrap();
+        signaling.deliver(&"a", &mut pc_a).unwrap();
+        a_connected |= connected(&rx_a);
+        b_connected |= connected(&rx_b);
+        if a_connected && b_connected {
+            break;
+        }
+    }
+    assert!(a_connected && b_connected);
+}
+
+#[test]
+fn test_soak() {
+    let config = RtcConfig::new::<&str>(&[]);
+    let report = Soak::new()
+        .rounds(2)
+        .connections(3)
+        .channels(2)
+        .messages(50)
+        .run(&config)
+        .unwrap();
+    assert_eq!(report.sent, 2 * 3 * 2 * 50);
+    assert_eq!(report.received, report.sent);
+    assert!(report.is_lossless(), "{:?}", report);
+}
+
+#[test]
+fn test_impaired_pair() {
+    let (tx_msg, _rx_msg) = chan::unbounded();
+    let (tx_label, _rx_label) = chan::unbounded();
+    let conn = || Conn {
+        echo: Echo {
+            output: tx_msg.clone(),
+        },
+        labels: tx_label.clone(),
+        channels: vec![],
+    };
+
+    let config = RtcConfig::new::<&str>(&[]);
+    let impairment = Impairment::new().delay(Duration::from_millis(20));
+    let (offerer, _answerer, link) = impaired_pair(&config, impairment, conn(), conn()).unwrap();
+    let pair = offerer.selected_candidate_pair().unwrap();
+    assert!(pair.remote.contains("host"));
+    assert!(link.stats().forwarded > 0);
+    assert_eq!(link.stats().dropped, 0);
+}
+
+#[test]
+fn test_soak_impaired() {
+    let config = RtcConfig::new::<&str>(&[]);
+    let impairment = Impairment::new()
+        .loss(0.05)
+        .delay(Duration::from_millis(5))
+        .jitter(Duration::from_millis(5));
+
+    // Reliable channels recover from the loss
+    let report = Soak::new()
+        .connections(1)
+        .channels(2)
+        .messages(200)
+        .impairment(impairment)
+        .run(&config)
+        .unwrap();
+    assert!(report.is_lossless(), "{:?}", report);
+
+    // Unreliable ones without retransmissions don't, the handshake being impaired too the
+    // loss is kept low enough for it to complete in time
+    let report = Soak::new()
+        .connections(1)
+        .channels(2)
+        .messages(200)
+        .reliability(Reliability {
+            unordered: true,
+            unreliable: true,
+            max_retransmits: 0,
+            ..Default::default()
+        })
+        .impairment(impairment.loss(0.15))
+        .timeout(Duration::from_secs(5))
+        .run(&config)
+        .unwrap();
+    assert!(report.lost > 0, "{:?}", report);
+}
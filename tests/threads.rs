@@ -0,0 +1,55 @@
+use std::sync::Arc;
+use std::thread;
+
+use datachannel::{
+    DataChannelHandler, DataChannelInfo, PeerConnectionHandler, RtcConfig, RtcDataChannel,
+    RtcPeerConnection, RtcTrack, TrackHandler,
+};
+
+struct Handler;
+
+impl DataChannelHandler for Handler {}
+
+impl TrackHandler for Handler {}
+
+impl PeerConnectionHandler for Handler {
+    type DCH = Handler;
+
+    fn data_channel_handler(&mut self, _info: DataChannelInfo) -> Handler {
+        Handler
+    }
+}
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn test_send_sync() {
+    assert_send_sync::<RtcConfig>();
+    assert_send_sync::<RtcPeerConnection<Handler>>();
+    assert_send_sync::<RtcDataChannel<Handler>>();
+    assert_send_sync::<RtcTrack<Handler>>();
+}
+
+#[test]
+fn test_shared_getters() {
+    let conf = RtcConfig::new::<&str>(&[]);
+    let mut pc = RtcPeerConnection::new(&conf, Handler).unwrap();
+    let _dc = pc.create_data_channel("shared", Handler).unwrap();
+
+    let pc = Arc::new(*pc);
+    let readers = (0..4)
+        .map(|_| {
+            let pc = Arc::clone(&pc);
+            thread::spawn(move || {
+                for _ in 0..100 {
+                    pc.local_description();
+                    pc.local_address();
+                    pc.selected_candidate_pair();
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for reader in readers {
+        reader.join().unwrap();
+    }
+}
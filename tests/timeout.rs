@@ -0,0 +1,80 @@
+use std::time::{Duration, Instant};
+
+use crossbeam_channel as chan;
+
+use datachannel::{
+    ConnectionState, DataChannelHandler, DataChannelInfo, PeerConnectionHandler, RtcConfig,
+    RtcPeerConnection, SessionDescription,
+};
+
+struct Nothing;
+
+impl DataChannelHandler for Nothing {}
+
+/// Hands its descriptions over, but never its candidates.
+struct Unreachable {
+    descriptions: chan::Sender<SessionDescription>,
+    states: chan::Sender<ConnectionState>,
+}
+
+impl PeerConnectionHandler for Unreachable {
+    type DCH = Nothing;
+
+    fn data_channel_handler(&mut self, _info: DataChannelInfo) -> Nothing {
+        Nothing
+    }
+
+    fn on_description(&mut self, sess_desc: SessionDescription) {
+        self.descriptions.send(sess_desc).ok();
+    }
+
+    fn on_connection_state_change(&mut self, state: ConnectionState) {
+        self.states.send(state).ok();
+    }
+}
+
+#[test]
+fn test_connection_timeout() {
+    let (tx_offer, rx_offer) = chan::unbounded();
+    let (tx_answer, rx_answer) = chan::unbounded();
+    let (states, rx_states) = chan::unbounded();
+
+    let conf = RtcConfig::new::<&str>(&[]).connection_timeout(Duration::from_millis(500));
+    let mut offerer = RtcPeerConnection::new(
+        &conf,
+        Unreachable {
+            descriptions: tx_offer,
+            states,
+        },
+    )
+    .unwrap();
+    let (states, _) = chan::unbounded();
+    let mut answerer = RtcPeerConnection::new(
+        &RtcConfig::new::<&str>(&[]),
+        Unreachable {
+            descriptions: tx_answer,
+            states,
+        },
+    )
+    .unwrap();
+
+    let _dc = offerer.create_data_channel("timeout", Nothing).unwrap();
+    let offer = rx_offer.recv_timeout(Duration::from_secs(10)).unwrap();
+    answerer.set_remote_description(&offer).unwrap();
+    let answer = rx_answer.recv_timeout(Duration::from_secs(10)).unwrap();
+    offerer.set_remote_description(&answer).unwrap();
+
+    // Stuck connecting without candidates, until closed by the timeout
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let mut connecting = None;
+    loop {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        match rx_states.recv_timeout(timeout).unwrap() {
+            ConnectionState::Connecting => connecting = Some(Instant::now()),
+            ConnectionState::Closed => break,
+            state => panic!("Unexpected state {:?}", state),
+        }
+    }
+    let connecting = connecting.expect("Closed without connecting first");
+    assert!(connecting.elapsed() >= Duration::from_millis(400));
+}
@@ -0,0 +1,130 @@
+#![cfg(feature = "transfer")]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crossbeam_channel as chan;
+
+use datachannel::test_util::local_pair;
+use datachannel::{
+    DataChannelInfo, FileInfo, FileTransfer, PeerConnectionHandler, Progress, RtcConfig,
+    RtcDataChannel, TransferHandler, TransferInbox,
+};
+
+type Channel = (Box<RtcDataChannel<TransferHandler>>, TransferInbox);
+
+struct Conn {
+    inbox: Option<TransferInbox>,
+    channels: chan::Sender<Channel>,
+}
+
+impl PeerConnectionHandler for Conn {
+    type DCH = TransferHandler;
+
+    fn data_channel_handler(&mut self, _info: DataChannelInfo) -> TransferHandler {
+        let (handler, inbox) = TransferHandler::new();
+        self.inbox = Some(inbox);
+        handler
+    }
+
+    fn on_data_channel(&mut self, dc: Box<RtcDataChannel<TransferHandler>>) {
+        if dc.label().unwrap() == "file" {
+            self.channels.send((dc, self.inbox.take().unwrap())).ok();
+        }
+    }
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("datachannel-{}-{}", name, std::process::id()));
+    fs::remove_dir_all(&dir).ok();
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Transfers `src` into `dir`, returning the progress reported on both sides.
+fn transfer(src: &Path, dir: &Path) -> (Vec<Progress>, Vec<Progress>) {
+    let (tx, rx) = chan::unbounded();
+    let conn = || Conn {
+        inbox: None,
+        channels: tx.clone(),
+    };
+    let config = RtcConfig::new::<&str>(&[]);
+    let (mut offerer, _answerer) = local_pair(&config, conn(), conn()).unwrap();
+
+    let (handler, inbox) = TransferHandler::new();
+    let mut dc = offerer.create_data_channel("file", handler).unwrap();
+    let sent = Arc::new(Mutex::new(vec![]));
+    let received = Arc::new(Mutex::new(vec![]));
+
+    let receiver = {
+        let dir = dir.to_path_buf();
+        let received = Arc::clone(&received);
+        thread::spawn(move || {
+            let (mut dc, inbox) = rx.recv().unwrap();
+            FileTransfer::new()
+                .on_progress(move |progress| received.lock().unwrap().push(progress))
+                .receive(&mut dc, &inbox, dir)
+                .unwrap()
+        })
+    };
+    let sent_progress = Arc::clone(&sent);
+    let info = FileTransfer::new()
+        .chunk_size(4096)
+        .on_progress(move |progress| sent_progress.lock().unwrap().push(progress))
+        .send(&mut dc, &inbox, src)
+        .unwrap();
+
+    let file = receiver.join().unwrap();
+    assert_eq!(file.info, info);
+    assert_eq!(file.path, dir.join("data.bin"));
+    assert_eq!(fs::read(&file.path).unwrap(), fs::read(src).unwrap());
+
+    let sent = sent.lock().unwrap().clone();
+    let received = received.lock().unwrap().clone();
+    (sent, received)
+}
+
+fn source(dir: &Path) -> PathBuf {
+    let src = dir.join("data.bin");
+    let data = (0..100_000u32)
+        .map(|i| (i * 7 % 251) as u8)
+        .collect::<Vec<_>>();
+    fs::write(&src, data).unwrap();
+    src
+}
+
+#[test]
+fn test_file_transfer() {
+    let src_dir = temp_dir("transfer-src");
+    let dst_dir = temp_dir("transfer-dst");
+    let src = source(&src_dir);
+
+    let (sent, received) = transfer(&src, &dst_dir);
+    let last = *sent.last().unwrap();
+    assert_eq!(last.transferred, 100_000);
+    assert_eq!(last.fraction(), 1.0);
+    assert_eq!(received.last().unwrap().transferred, 100_000);
+    assert!(sent.iter().all(|progress| progress.resumed_from == 0));
+
+    let info = FileInfo::from_path(&src).unwrap();
+    assert!(!FileTransfer::partial_path(&dst_dir, &info).exists());
+}
+
+#[test]
+fn test_file_transfer_resume() {
+    let src_dir = temp_dir("resume-src");
+    let dst_dir = temp_dir("resume-dst");
+    let src = source(&src_dir);
+
+    // As left by a transfer interrupted halfway
+    let info = FileInfo::from_path(&src).unwrap();
+    let data = fs::read(&src).unwrap();
+    fs::write(FileTransfer::partial_path(&dst_dir, &info), &data[..40_000]).unwrap();
+
+    let (sent, received) = transfer(&src, &dst_dir);
+    assert!(sent.iter().all(|progress| progress.resumed_from == 40_000));
+    assert_eq!(sent.first().unwrap().transferred, 40_000 + 4096);
+    assert_eq!(received.last().unwrap().transferred, 100_000);
+}
@@ -0,0 +1,135 @@
+#![cfg(feature = "json")]
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crossbeam_channel as chan;
+use serde::{Deserialize, Serialize};
+
+use datachannel::test_util::local_pair;
+use datachannel::{
+    ChannelError, ChannelErrorKind, DataChannelHandler, DataChannelInfo, Json, MessageCodec,
+    PeerConnectionHandler, RtcConfig, RtcDataChannel, Typed, TypedDataChannel, TypedHandler,
+};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum Rpc {
+    Ping(u32),
+    Pong(u32),
+    Echo { text: String, data: Vec<u8> },
+}
+
+#[derive(Default, Clone)]
+struct Recorder {
+    opened: Arc<AtomicBool>,
+    messages: Arc<Mutex<Vec<Rpc>>>,
+    errors: Arc<Mutex<Vec<ChannelError>>>,
+}
+
+impl TypedHandler<Rpc> for Recorder {
+    fn on_open(&mut self) {
+        self.opened.store(true, Ordering::SeqCst);
+    }
+
+    fn on_error(&mut self, err: &ChannelError) {
+        self.errors.lock().unwrap().push(err.clone());
+    }
+
+    fn on_message(&mut self, msg: Rpc) {
+        self.messages.lock().unwrap().push(msg);
+    }
+}
+
+fn messages() -> Vec<Rpc> {
+    vec![
+        Rpc::Ping(1),
+        Rpc::Pong(1),
+        Rpc::Echo {
+            text: "héllo".to_string(),
+            data: vec![0, 1, 255],
+        },
+    ]
+}
+
+fn check_codec<C: MessageCodec>() {
+    let recorder = Recorder::default();
+    let mut typed = Typed::<_, Rpc, C>::new(recorder.clone());
+    for msg in messages() {
+        typed.on_message(&C::encode(&msg).unwrap());
+    }
+    typed.on_message(&[0xff; 3]);
+
+    assert_eq!(*recorder.messages.lock().unwrap(), messages());
+    let errors = recorder.errors.lock().unwrap();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].kind(), ChannelErrorKind::Protocol);
+}
+
+#[test]
+fn test_typed_codecs() {
+    check_codec::<Json>();
+    #[cfg(feature = "bincode")]
+    check_codec::<datachannel::Bincode>();
+    #[cfg(feature = "cbor")]
+    check_codec::<datachannel::Cbor>();
+}
+
+fn wait_until(cond: impl Fn() -> bool) {
+    for _ in 0..200 {
+        if cond() {
+            return;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+type RpcHandler = Typed<Recorder, Rpc, Json>;
+
+struct Conn {
+    recorder: Recorder,
+    channels: chan::Sender<Box<RtcDataChannel<RpcHandler>>>,
+}
+
+impl PeerConnectionHandler for Conn {
+    type DCH = RpcHandler;
+
+    fn data_channel_handler(&mut self, _info: DataChannelInfo) -> RpcHandler {
+        Typed::new(self.recorder.clone())
+    }
+
+    fn on_data_channel(&mut self, dc: Box<RtcDataChannel<RpcHandler>>) {
+        self.channels.send(dc).ok();
+    }
+}
+
+#[test]
+fn test_typed_data_channel() {
+    let (tx, rx) = chan::unbounded();
+    let received = Recorder::default();
+    let offerer = Conn {
+        recorder: Recorder::default(),
+        channels: tx.clone(),
+    };
+    let answerer = Conn {
+        recorder: received.clone(),
+        channels: tx,
+    };
+    let config = RtcConfig::new::<&str>(&[]);
+    let (mut offerer, _answerer) = local_pair(&config, offerer, answerer).unwrap();
+
+    let sender = Recorder::default();
+    let dc = offerer
+        .create_data_channel("rpc", Typed::new(sender.clone()))
+        .unwrap();
+    let mut dc: TypedDataChannel<_, Rpc, Json> = dc.into();
+    let _remote = rx.recv_timeout(Duration::from_secs(10)).unwrap();
+    wait_until(|| sender.opened.load(Ordering::SeqCst));
+
+    for msg in messages() {
+        dc.send(&msg).unwrap();
+    }
+    wait_until(|| received.messages.lock().unwrap().len() == messages().len());
+    assert_eq!(*received.messages.lock().unwrap(), messages());
+    assert!(received.errors.lock().unwrap().is_empty());
+}
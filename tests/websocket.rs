@@ -214,7 +214,7 @@ impl PeerConnectionHandler for WsConn {
     fn on_data_channel(&mut self, mut dc: Box<RtcDataChannel<DataPipe>>) {
         logger::info!(
             "Received Datachannel with: label={}, protocol={:?}, reliability={:?}",
-            dc.label(),
+            dc.label().unwrap(),
             dc.protocol(),
             dc.reliability()
         );